@@ -8,7 +8,6 @@
 use metrics::histogram;
 use orka_persist::Store;
 use serde::{Deserialize, Serialize};
-use std::sync::atomic::{AtomicU64, Ordering};
 use std::time::Instant;
 use tracing::info;
 // Delegate kube client management to kubehub so GUI context switches are honored.
@@ -18,16 +17,23 @@ async fn get_kube_client() -> OrkaResult<kube::Client> {
         .map_err(|e| OrkaError::Internal(e.to_string()))
 }
 
+pub use orka_apply::RollbackResult; // Re-export rollback outcome
+pub use orka_apply::{filter_ownership, FieldOwner, FieldOwnership}; // Re-export managed-fields ownership rows
+pub use orka_apply::{render_kustomization, Manifest}; // Re-export offline kustomize rendering
+pub use orka_apply::{set_pod_snapshot_source, PodSnapshotSource}; // Re-export policy pod-snapshot hook
+pub use orka_apply::{ThreeWayEntry, ThreeWayReport, ThreeWayState}; // Re-export three-way diff rows
 pub use orka_ops::CancelHandle as OpsCancelHandle;
 pub use orka_ops::ExecChunk as OpsExecChunk;
 pub use orka_ops::ForwardEvent as OpsForwardEvent;
 pub use orka_ops::LogChunk as OpsLogChunk;
 pub use orka_ops::LogOptions as OpsLogOptions; // Re-export ops types for frontends
+pub use orka_ops::LogTarget as OpsLogTarget;
 pub use orka_ops::OpsCaps;
 pub use orka_ops::OrkaOps; // Re-export imperative ops trait
 pub use orka_ops::ScaleCaps as OpsScaleCaps;
 pub use orka_ops::StreamHandle as OpsStreamHandle;
 pub use orka_persist::LastApplied; // Re-export last-applied row
+pub use orka_persist::{ObjectHistory, ObjectKey}; // Re-export history by object identity
 pub use orka_schema::CrdSchema; // Re-export schema type
 use std::collections::HashMap;
 
@@ -99,6 +105,29 @@ pub struct Stats {
     pub traffic_snapshot_bytes: Option<u64>,
     pub traffic_watch_bytes: Option<u64>,
     pub traffic_details_bytes: Option<u64>,
+    /// Per context/GVK/verb request and byte counts, largest receivers first.
+    #[serde(default)]
+    pub traffic: Vec<TrafficStat>,
+    /// Client-side rate limit (None when disabled via `ORKA_KUBE_QPS=0`).
+    pub throttle_qps: Option<u32>,
+    pub throttle_burst: Option<u32>,
+    /// Effective rate as a percentage of `throttle_qps`; below 100 after 429s.
+    pub throttle_effective_pct: Option<u32>,
+    pub throttle_429_total: Option<u64>,
+    /// Remaining server-requested pause (Retry-After), in milliseconds.
+    pub throttle_backoff_ms: Option<u64>,
+    pub throttle_wait_ms_total: Option<u64>,
+}
+
+/// Kube API traffic for one (context, GVK, verb), measured at the HTTP layer.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Default)]
+pub struct TrafficStat {
+    pub context: String,
+    pub gvk: String,
+    pub verb: String,
+    pub requests: u64,
+    pub rx_bytes: u64,
+    pub tx_bytes: u64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -134,8 +163,13 @@ pub enum OrkaError {
     Capability(String),
     #[error("validation: {0}")]
     Validation(String),
-    #[error("conflict: {0}")]
-    Conflict(String),
+    /// Field ownership conflict on server-side apply; `conflicts` names the
+    /// owning manager of each contested field.
+    #[error("conflict: {message}")]
+    Conflict {
+        message: String,
+        conflicts: Vec<orka_apply::FieldConflict>,
+    },
     #[error("not_found: {0}")]
     NotFound(String),
     #[error("internal: {0}")]
@@ -156,6 +190,11 @@ pub enum LiteEvent {
 pub trait OrkaApi: Send + Sync {
     async fn discover(&self) -> OrkaResult<Vec<ResourceKind>>;
 
+    /// Resolve a strict GVK key or a kubectl-style name (`pods`, `deploy`,
+    /// `deployments.apps`) to a served kind. Ambiguous names yield a
+    /// `Validation` error listing the candidates.
+    async fn resolve_kind(&self, input: &str) -> OrkaResult<ResourceKind>;
+
     /// Return a consistent snapshot for the given selector (single-GVK, optional ns),
     /// along with runtime metadata for UI (partial/pressure/explain).
     async fn snapshot(&self, selector: Selector) -> OrkaResult<SnapshotResponse>;
@@ -182,8 +221,26 @@ pub trait OrkaApi: Send + Sync {
         ns_override: Option<&str>,
     ) -> OrkaResult<(orka_apply::DiffSummary, Option<orka_apply::DiffSummary>)>;
 
-    /// Server-side apply (SSA) for a YAML payload.
-    async fn apply(&self, yaml: &str) -> OrkaResult<orka_apply::ApplyResult>;
+    /// Like `diff` but with per-field changes and a unified YAML rendering.
+    async fn diff_report(
+        &self,
+        yaml: &str,
+        ns_override: Option<&str>,
+    ) -> OrkaResult<(orka_apply::DiffReport, Option<orka_apply::DiffReport>)>;
+
+    /// Three-way comparison of a YAML payload against live, using the newest
+    /// last-applied snapshot as base: paths changed by the payload, drifted
+    /// in the cluster since the last apply, or both.
+    async fn diff_three_way(
+        &self,
+        yaml: &str,
+        ns_override: Option<&str>,
+    ) -> OrkaResult<orka_apply::ThreeWayReport>;
+
+    /// Server-side apply (SSA) for a YAML payload. With `force`, fields owned
+    /// by other managers are taken over (listed in `warnings`); without it
+    /// such conflicts fail with `OrkaError::Conflict`.
+    async fn apply(&self, yaml: &str, force: bool) -> OrkaResult<orka_apply::ApplyResult>;
 
     /// Runtime stats and limits.
     async fn stats(&self) -> OrkaResult<Stats>;
@@ -207,6 +264,40 @@ pub trait OrkaApi: Send + Sync {
         limit: Option<usize>,
     ) -> OrkaResult<Vec<LastApplied>>;
 
+    /// Last-applied entries for an object by identity (active context,
+    /// kind, namespace, name) rather than by its live UID: newest first
+    /// across every UID the name had, so it also works once the object was
+    /// deleted or recreated.
+    async fn history(
+        &self,
+        gvk_key: &str,
+        name: &str,
+        namespace: Option<&str>,
+        limit: Option<usize>,
+    ) -> OrkaResult<Vec<LastApplied>>;
+
+    /// Every object with recorded last-applied history, deleted ones
+    /// included, sorted by cluster, kind, namespace and name.
+    async fn history_objects(&self) -> OrkaResult<Vec<ObjectHistory>>;
+
+    /// Roll the object with `uid` back to last-applied revision `revision`
+    /// (0 = newest, in `last_applied` order). Always returns the revision's
+    /// diff against live; with `apply` it is re-applied via SSA (subject to
+    /// `force`, like `apply`) and recorded as the newest revision.
+    /// `ns_override` is used when the stored YAML omits the namespace.
+    async fn rollback(
+        &self,
+        uid: orka_core::Uid,
+        revision: usize,
+        ns_override: Option<&str>,
+        apply: bool,
+        force: bool,
+    ) -> OrkaResult<orka_apply::RollbackResult>;
+
+    /// Decode the live object's `metadata.managedFields` into per-field
+    /// ownership (manager, operation, time), sorted by field path.
+    async fn managed_fields(&self, reference: ResourceRef) -> OrkaResult<Vec<FieldOwnership>>;
+
     /// Access to imperative ops provider (in-proc wraps KubeOps; remote later).
     fn ops(&self) -> std::sync::Arc<dyn OrkaOps>;
 }
@@ -272,6 +363,28 @@ impl InProcApi {
         OrkaError::Internal(e.to_string())
     }
 
+    fn map_apply_err(e: anyhow::Error) -> OrkaError {
+        match e.downcast::<orka_apply::ApplyConflict>() {
+            Ok(c) => OrkaError::Conflict {
+                message: c.to_string(),
+                conflicts: c.conflicts,
+            },
+            Err(e) if e.is::<orka_apply::PolicyViolation>() => OrkaError::Validation(e.to_string()),
+            Err(e) if e.is::<orka_apply::RevisionNotFound>() => OrkaError::NotFound(e.to_string()),
+            Err(e) => OrkaError::Internal(e.to_string()),
+        }
+    }
+
+    fn map_resolve_err(e: anyhow::Error) -> OrkaError {
+        match e.downcast_ref::<orka_kubehub::ResolveError>() {
+            Some(orka_kubehub::ResolveError::Ambiguous { .. }) => {
+                OrkaError::Validation(e.to_string())
+            }
+            Some(orka_kubehub::ResolveError::NotFound(_)) => OrkaError::NotFound(e.to_string()),
+            None => Self::map_err(e),
+        }
+    }
+
     fn gvk_key(gvk: &ResourceKind) -> String {
         if gvk.group.is_empty() {
             format!("{}/{}", gvk.version, gvk.kind)
@@ -287,8 +400,6 @@ impl Default for InProcApi {
     }
 }
 
-static TRAFFIC_DETAILS_BYTES: AtomicU64 = AtomicU64::new(0);
-
 #[async_trait::async_trait]
 impl OrkaApi for InProcApi {
     async fn discover(&self) -> OrkaResult<Vec<ResourceKind>> {
@@ -300,6 +411,19 @@ impl OrkaApi for InProcApi {
         Ok(kinds)
     }
 
+    async fn resolve_kind(&self, input: &str) -> OrkaResult<ResourceKind> {
+        let r = orka_kubehub::resolve_resource(input)
+            .await
+            .map_err(Self::map_resolve_err)?;
+        info!(input = %input, gvk = %r.gvk_key(), "api: resolve_kind ok");
+        Ok(ResourceKind {
+            group: r.group,
+            version: r.version,
+            kind: r.kind,
+            namespaced: r.namespaced,
+        })
+    }
+
     async fn snapshot(&self, selector: Selector) -> OrkaResult<SnapshotResponse> {
         let t0 = Instant::now();
         info!(gvk = %Self::gvk_key(&selector.gvk), ns = %selector.namespace.as_deref().unwrap_or("(all)"), "api: snapshot start");
@@ -553,7 +677,6 @@ impl OrkaApi for InProcApi {
         let bytes = serde_json::to_vec(&obj).map_err(|e| OrkaError::Internal(e.to_string()))?;
         let ser_ms = s0.elapsed().as_millis() as f64;
         histogram!("api_get_raw_serialize_ms", ser_ms);
-        TRAFFIC_DETAILS_BYTES.fetch_add(bytes.len() as u64, Ordering::Relaxed);
         let took = t0.elapsed().as_millis() as f64;
         histogram!("api_get_raw_total_ms", took);
         let overhead_ms = took - http_ms;
@@ -585,12 +708,40 @@ impl OrkaApi for InProcApi {
         res
     }
 
-    async fn apply(&self, yaml: &str) -> OrkaResult<orka_apply::ApplyResult> {
+    async fn diff_report(
+        &self,
+        yaml: &str,
+        ns_override: Option<&str>,
+    ) -> OrkaResult<(orka_apply::DiffReport, Option<orka_apply::DiffReport>)> {
         let t0 = Instant::now();
-        info!("api: apply start");
-        let res = orka_apply::edit_from_yaml(yaml, None, false, true)
+        info!(ns = %ns_override.unwrap_or("(none)"), "api: diff_report start");
+        let res = orka_apply::diff_report_from_yaml(yaml, ns_override)
             .await
-            .map_err(|e| OrkaError::Internal(e.to_string()))?;
+            .map_err(|e| OrkaError::Internal(e.to_string()));
+        info!(took_ms = %t0.elapsed().as_millis(), ok = res.is_ok(), "api: diff_report done");
+        res
+    }
+
+    async fn diff_three_way(
+        &self,
+        yaml: &str,
+        ns_override: Option<&str>,
+    ) -> OrkaResult<orka_apply::ThreeWayReport> {
+        let t0 = Instant::now();
+        info!(ns = %ns_override.unwrap_or("(none)"), "api: diff_three_way start");
+        let res = orka_apply::three_way_from_yaml(yaml, ns_override)
+            .await
+            .map_err(|e| OrkaError::Internal(e.to_string()));
+        info!(took_ms = %t0.elapsed().as_millis(), ok = res.is_ok(), "api: diff_three_way done");
+        res
+    }
+
+    async fn apply(&self, yaml: &str, force: bool) -> OrkaResult<orka_apply::ApplyResult> {
+        let t0 = Instant::now();
+        info!(force, "api: apply start");
+        let res = orka_apply::edit_from_yaml_with(yaml, None, false, true, force)
+            .await
+            .map_err(Self::map_apply_err)?;
         info!(took_ms = %t0.elapsed().as_millis(), "api: apply ok");
         Ok(res)
     }
@@ -623,8 +774,26 @@ impl OrkaApi for InProcApi {
             .ok()
             .and_then(|s| s.parse().ok());
         let metrics_addr = std::env::var("ORKA_METRICS_ADDR").ok();
-        let (snap_b, watch_b) = orka_kubehub::traffic_bytes();
-        let details_b = TRAFFIC_DETAILS_BYTES.load(Ordering::Relaxed);
+        let traffic: Vec<TrafficStat> = orka_kubehub::traffic_breakdown()
+            .into_iter()
+            .map(|t| TrafficStat {
+                context: t.context,
+                gvk: t.gvk,
+                verb: t.verb,
+                requests: t.requests,
+                rx_bytes: t.rx_bytes,
+                tx_bytes: t.tx_bytes,
+            })
+            .collect();
+        let rx_for = |verb: &str| -> u64 {
+            traffic
+                .iter()
+                .filter(|t| t.verb == verb)
+                .map(|t| t.rx_bytes)
+                .sum()
+        };
+        let (snap_b, watch_b, details_b) = (rx_for("list"), rx_for("watch"), rx_for("get"));
+        let throttle = orka_kubehub::throttle_state();
         let stats = Stats {
             shards,
             relist_secs,
@@ -638,6 +807,13 @@ impl OrkaApi for InProcApi {
             traffic_snapshot_bytes: Some(snap_b),
             traffic_watch_bytes: Some(watch_b),
             traffic_details_bytes: Some(details_b),
+            traffic,
+            throttle_qps: throttle.map(|t| t.qps),
+            throttle_burst: throttle.map(|t| t.burst),
+            throttle_effective_pct: throttle.map(|t| t.effective_pct),
+            throttle_429_total: throttle.map(|t| t.throttled_total),
+            throttle_backoff_ms: throttle.map(|t| t.backoff_ms),
+            throttle_wait_ms_total: throttle.map(|t| t.wait_ms_total),
         };
         info!(took_ms = %t0.elapsed().as_millis(), "api: stats ready");
         Ok(stats)
@@ -739,6 +915,14 @@ impl OrkaApi for InProcApi {
             info!("api: schema offline-only; skipping live fetch");
             return Ok(None);
         }
+        let resolved;
+        let (gvk_key, is_builtin) = if gvk_key.contains('/') {
+            (gvk_key, is_builtin)
+        } else {
+            let rk = self.resolve_kind(gvk_key).await?;
+            resolved = Self::gvk_key(&rk);
+            (resolved.as_str(), rk.group.is_empty())
+        };
         if is_builtin && skip_builtins {
             info!("api: schema builtin skip");
             return Ok(None);
@@ -761,35 +945,13 @@ impl OrkaApi for InProcApi {
         namespace: Option<&str>,
         limit: Option<usize>,
     ) -> OrkaResult<Vec<LastApplied>> {
-        use kube::{
-            api::Api,
-            core::{DynamicObject, GroupVersionKind},
-        };
+        use kube::{api::Api, core::DynamicObject};
         let t0 = Instant::now();
         info!(gvk = %gvk_key, name = %name, ns = %namespace.unwrap_or("-"), limit = ?limit, "api: last_applied start");
         // Resolve UID via live object fetch
         let client = get_kube_client().await?;
-        // Parse GVK key -> GroupVersionKind
-        let parts: Vec<&str> = gvk_key.split('/').collect();
-        let gvk = match parts.as_slice() {
-            [version, kind] => GroupVersionKind {
-                group: String::new(),
-                version: (*version).to_string(),
-                kind: (*kind).to_string(),
-            },
-            [group, version, kind] => GroupVersionKind {
-                group: (*group).to_string(),
-                version: (*version).to_string(),
-                kind: (*kind).to_string(),
-            },
-            _ => return Err(OrkaError::Validation(format!("invalid gvk: {}", gvk_key))),
-        };
-        // Find ApiResource via kubehub cache
-        let key = if gvk.group.is_empty() {
-            format!("{}/{}", gvk.version, gvk.kind)
-        } else {
-            format!("{}/{}/{}", gvk.group, gvk.version, gvk.kind)
-        };
+        // Resolve GVK key or kubectl-style name, then find ApiResource via kubehub cache
+        let key = Self::gvk_key(&self.resolve_kind(gvk_key).await?);
         let (ar, namespaced) = orka_kubehub::get_api_resource(&key)
             .await
             .map_err(Self::map_err)?;
@@ -826,6 +988,78 @@ impl OrkaApi for InProcApi {
         Ok(rows)
     }
 
+    async fn history(
+        &self,
+        gvk_key: &str,
+        name: &str,
+        namespace: Option<&str>,
+        limit: Option<usize>,
+    ) -> OrkaResult<Vec<LastApplied>> {
+        let t0 = Instant::now();
+        info!(gvk = %gvk_key, name = %name, ns = %namespace.unwrap_or("-"), limit = ?limit, "api: history start");
+        // A kind that is no longer served (e.g. its CRD was deleted) can
+        // still be looked up by its exact key.
+        let (key, namespaced) = match self.resolve_kind(gvk_key).await {
+            Ok(kind) => (Self::gvk_key(&kind), kind.namespaced),
+            Err(_) if gvk_key.contains('/') => (gvk_key.to_string(), namespace.is_some()),
+            Err(e) => return Err(e),
+        };
+        if namespaced && namespace.is_none() {
+            return Err(OrkaError::Validation(
+                "namespace required for namespaced kind".into(),
+            ));
+        }
+        let key = ObjectKey {
+            cluster: orka_kubehub::active_context(),
+            gvk: key,
+            namespace: namespace.filter(|_| namespaced).map(|s| s.to_string()),
+            name: name.to_string(),
+        };
+        let store = orka_persist::LogStore::open_default()
+            .map_err(|e| OrkaError::Internal(e.to_string()))?;
+        let rows = store
+            .history(&key, limit)
+            .map_err(|e| OrkaError::Internal(e.to_string()))?;
+        info!(rows = rows.len(), took_ms = %t0.elapsed().as_millis(), "api: history ok");
+        Ok(rows)
+    }
+
+    async fn history_objects(&self) -> OrkaResult<Vec<ObjectHistory>> {
+        let t0 = Instant::now();
+        let store = orka_persist::LogStore::open_default()
+            .map_err(|e| OrkaError::Internal(e.to_string()))?;
+        let objects = store.objects();
+        info!(objects = objects.len(), took_ms = %t0.elapsed().as_millis(), "api: history_objects ok");
+        Ok(objects)
+    }
+
+    async fn rollback(
+        &self,
+        uid: orka_core::Uid,
+        revision: usize,
+        ns_override: Option<&str>,
+        apply: bool,
+        force: bool,
+    ) -> OrkaResult<orka_apply::RollbackResult> {
+        let t0 = Instant::now();
+        info!(uid = %uuid::Uuid::from_bytes(uid), revision, apply, force, "api: rollback start");
+        let res = orka_apply::rollback(uid, revision, ns_override, apply, force)
+            .await
+            .map_err(Self::map_apply_err);
+        info!(took_ms = %t0.elapsed().as_millis(), ok = res.is_ok(), "api: rollback done");
+        res
+    }
+
+    async fn managed_fields(&self, reference: ResourceRef) -> OrkaResult<Vec<FieldOwnership>> {
+        let t0 = Instant::now();
+        let bytes = self.get_raw(reference).await?;
+        let v: serde_json::Value =
+            serde_json::from_slice(&bytes).map_err(|e| OrkaError::Internal(e.to_string()))?;
+        let rows = orka_apply::field_ownership(&v);
+        info!(fields = rows.len(), took_ms = %t0.elapsed().as_millis(), "api: managed_fields ok");
+        Ok(rows)
+    }
+
     fn ops(&self) -> std::sync::Arc<dyn OrkaOps> {
         std::sync::Arc::new(orka_ops::KubeOps::new())
     }
@@ -925,6 +1159,61 @@ impl ApiOps {
         })
     }
 
+    /// Stream logs from every pod matching `target`, following pods that
+    /// appear later. Chunks keep their pod/container tags.
+    pub async fn logs_aggregate(
+        &self,
+        namespace: &str,
+        target: &OpsLogTarget,
+        container: Option<&str>,
+        opts: OpsLogOptions,
+    ) -> OrkaResult<StreamHandle<OpsLogChunk>> {
+        let cap = std::env::var("ORKA_OPS_QUEUE_CAP")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(1024);
+        let (tx, rx) = tokio::sync::mpsc::channel::<OpsLogChunk>(cap);
+        let res = self
+            .inner
+            .logs_aggregate(namespace, target, container, opts)
+            .await
+            .map_err(|e| OrkaError::Internal(e.to_string()))?;
+        struct OpsCancelGuard {
+            inner: Option<OpsCancelHandle>,
+        }
+        impl Drop for OpsCancelGuard {
+            fn drop(&mut self) {
+                if let Some(c) = self.inner.take() {
+                    c.cancel();
+                }
+            }
+        }
+        let mut rx_ops = res.rx;
+        let guard = OpsCancelGuard {
+            inner: Some(res.cancel),
+        };
+        let task = tokio::spawn(async move {
+            // Dropping the guard cancels every pod stream; stop once the
+            // receiver is gone rather than waiting for the next line.
+            let _g = guard;
+            loop {
+                tokio::select! {
+                    _ = tx.closed() => break,
+                    maybe = rx_ops.recv() => match maybe {
+                        Some(chunk) => {
+                            let _ = tx.try_send(chunk);
+                        }
+                        None => break,
+                    },
+                }
+            }
+        });
+        Ok(StreamHandle {
+            rx,
+            cancel: CancelHandle { task: Some(task) },
+        })
+    }
+
     /// Execute a command in a pod. For now this is a one-shot operation using
     /// the underlying ops provider and does not return a streaming handle.
     pub async fn exec(
@@ -1126,6 +1415,30 @@ impl OrkaApi for MockApi {
         Ok(self.kinds.clone())
     }
 
+    async fn resolve_kind(&self, input: &str) -> OrkaResult<ResourceKind> {
+        // Mock: strict keys or case-insensitive Kind names only
+        let matches: Vec<&ResourceKind> = self
+            .kinds
+            .iter()
+            .filter(|k| InProcApi::gvk_key(k) == input || k.kind.eq_ignore_ascii_case(input))
+            .collect();
+        match matches.as_slice() {
+            [] => Err(OrkaError::NotFound(format!(
+                "unknown resource type: {}",
+                input
+            ))),
+            [k] => Ok((*k).clone()),
+            many => Err(OrkaError::Validation(format!(
+                "ambiguous resource type '{}'; candidates: {}",
+                input,
+                many.iter()
+                    .map(|k| InProcApi::gvk_key(k))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ))),
+        }
+    }
+
     async fn snapshot(&self, _selector: Selector) -> OrkaResult<SnapshotResponse> {
         let snap = self
             .snapshot
@@ -1172,7 +1485,31 @@ impl OrkaApi for MockApi {
             .ok_or_else(|| OrkaError::NotFound("no diff configured".into()))
     }
 
-    async fn apply(&self, _yaml: &str) -> OrkaResult<orka_apply::ApplyResult> {
+    async fn diff_report(
+        &self,
+        _yaml: &str,
+        _ns_override: Option<&str>,
+    ) -> OrkaResult<(orka_apply::DiffReport, Option<orka_apply::DiffReport>)> {
+        // Counts only; the mock has no documents to render.
+        let report = |summary| orka_apply::DiffReport {
+            summary,
+            ..Default::default()
+        };
+        self.diff_pair
+            .clone()
+            .map(|(live, last)| (report(live), last.map(report)))
+            .ok_or_else(|| OrkaError::NotFound("no diff configured".into()))
+    }
+
+    async fn diff_three_way(
+        &self,
+        _yaml: &str,
+        _ns_override: Option<&str>,
+    ) -> OrkaResult<orka_apply::ThreeWayReport> {
+        Err(OrkaError::NotFound("no three-way diff configured".into()))
+    }
+
+    async fn apply(&self, _yaml: &str, _force: bool) -> OrkaResult<orka_apply::ApplyResult> {
         self.apply
             .clone()
             .ok_or_else(|| OrkaError::Internal("no apply configured".into()))
@@ -1215,6 +1552,40 @@ impl OrkaApi for MockApi {
         Ok(Vec::new())
     }
 
+    async fn history(
+        &self,
+        _gvk_key: &str,
+        _name: &str,
+        _namespace: Option<&str>,
+        _limit: Option<usize>,
+    ) -> OrkaResult<Vec<LastApplied>> {
+        Ok(Vec::new())
+    }
+
+    async fn history_objects(&self) -> OrkaResult<Vec<ObjectHistory>> {
+        Ok(Vec::new())
+    }
+
+    async fn rollback(
+        &self,
+        _uid: orka_core::Uid,
+        _revision: usize,
+        _ns_override: Option<&str>,
+        _apply: bool,
+        _force: bool,
+    ) -> OrkaResult<orka_apply::RollbackResult> {
+        Err(OrkaError::NotFound(
+            "no last-applied history in mock".into(),
+        ))
+    }
+
+    async fn managed_fields(&self, reference: ResourceRef) -> OrkaResult<Vec<FieldOwnership>> {
+        let bytes = self.get_raw(reference).await?;
+        let v: serde_json::Value =
+            serde_json::from_slice(&bytes).map_err(|e| OrkaError::Internal(e.to_string()))?;
+        Ok(orka_apply::field_ownership(&v))
+    }
+
     fn ops(&self) -> std::sync::Arc<dyn OrkaOps> {
         std::sync::Arc::new(orka_ops::KubeOps::new())
     }
@@ -0,0 +1,616 @@
+//! Apply sets with pruning, following the kubectl ApplySet convention
+//! (KEP-3659) so sets stay readable by other tooling.
+//!
+//! Every member carries the `applyset.kubernetes.io/part-of=<id>` label. A
+//! parent Secret records which group/kinds and namespaces the set spans, so
+//! a later apply knows where to look for members that left the manifests.
+//! Before members are applied the parent is widened to the union of the old
+//! and new scope; once pruning succeeds it is narrowed to the new scope. An
+//! interrupted run therefore never loses track of objects it may own.
+
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+use anyhow::{anyhow, Result};
+use base64::Engine as _;
+use k8s_openapi::api::core::v1::Secret;
+use kube::{
+    api::{Api, DeleteParams, ListParams},
+    core::{ApiResource, DynamicObject},
+};
+use metrics::counter;
+use serde::{Deserialize, Serialize};
+use serde_json::Value as Json;
+use sha2::{Digest, Sha256};
+
+use crate::{BatchResult, Manifest};
+
+pub const PART_OF_LABEL: &str = "applyset.kubernetes.io/part-of";
+pub const ID_LABEL: &str = "applyset.kubernetes.io/id";
+pub const TOOLING_ANNOTATION: &str = "applyset.kubernetes.io/tooling";
+pub const GROUP_KINDS_ANNOTATION: &str = "applyset.kubernetes.io/contains-group-kinds";
+pub const NAMESPACES_ANNOTATION: &str = "applyset.kubernetes.io/additional-namespaces";
+const TOOLING: &str = "orka/v1";
+
+/// An apply set whose parent is the Secret `name` in `namespace`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ApplySet {
+    pub name: String,
+    pub namespace: String,
+}
+
+impl ApplySet {
+    pub fn new(name: &str, namespace: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            namespace: namespace.to_string(),
+        }
+    }
+
+    /// `applyset-<base64url(sha256(name.namespace.Secret.))>-v1`, as kubectl
+    /// computes it for a Secret parent.
+    pub fn id(&self) -> String {
+        let digest = Sha256::digest(format!("{}.{}.Secret.", self.name, self.namespace));
+        format!(
+            "applyset-{}-v1",
+            base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(digest)
+        )
+    }
+
+    fn parent_yaml(&self, scope: &SetScope) -> Result<String> {
+        let extra_ns: Vec<&str> = scope
+            .namespaces
+            .iter()
+            .map(|s| s.as_str())
+            .filter(|ns| *ns != self.namespace)
+            .collect();
+        let parent = serde_json::json!({
+            "apiVersion": "v1",
+            "kind": "Secret",
+            "metadata": {
+                "name": self.name,
+                "namespace": self.namespace,
+                "labels": { ID_LABEL: self.id() },
+                "annotations": {
+                    TOOLING_ANNOTATION: TOOLING,
+                    GROUP_KINDS_ANNOTATION: join(&scope.group_kinds),
+                    NAMESPACES_ANNOTATION: extra_ns.join(","),
+                },
+            },
+        });
+        Ok(serde_yaml::to_string(&parent)?)
+    }
+}
+
+fn join(set: &BTreeSet<String>) -> String {
+    set.iter().cloned().collect::<Vec<_>>().join(",")
+}
+
+fn split(s: &str) -> BTreeSet<String> {
+    s.split(',')
+        .map(|p| p.trim())
+        .filter(|p| !p.is_empty())
+        .map(|p| p.to_string())
+        .collect()
+}
+
+/// Group/kinds (`Deployment.apps`, `ConfigMap`) and namespaces a set spans.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct SetScope {
+    group_kinds: BTreeSet<String>,
+    namespaces: BTreeSet<String>,
+}
+
+impl SetScope {
+    fn from_parent(parent: &Secret) -> Self {
+        let anno = |k: &str| {
+            parent
+                .metadata
+                .annotations
+                .as_ref()
+                .and_then(|a| a.get(k))
+                .map(|s| split(s))
+                .unwrap_or_default()
+        };
+        Self {
+            group_kinds: anno(GROUP_KINDS_ANNOTATION),
+            namespaces: anno(NAMESPACES_ANNOTATION),
+        }
+    }
+
+    fn union(&self, other: &SetScope) -> SetScope {
+        SetScope {
+            group_kinds: self
+                .group_kinds
+                .union(&other.group_kinds)
+                .cloned()
+                .collect(),
+            namespaces: self.namespaces.union(&other.namespaces).cloned().collect(),
+        }
+    }
+}
+
+/// `Kind.group`, or just `Kind` for the core group.
+fn group_kind(api_version: &str, kind: &str) -> String {
+    match api_version.split_once('/') {
+        Some((group, _)) => format!("{}.{}", kind, group),
+        None => kind.to_string(),
+    }
+}
+
+/// Scope (`true` = namespaced) of the kinds defined by CustomResourceDefinitions
+/// in `manifests`, keyed by `Kind.group`. Lets a CRD and its first objects go
+/// into one run before the API server serves the new kind.
+fn batch_crd_scopes(manifests: &[Manifest]) -> HashMap<String, bool> {
+    manifests
+        .iter()
+        .filter(|m| {
+            m.kind == "CustomResourceDefinition"
+                && m.api_version.starts_with("apiextensions.k8s.io/")
+        })
+        .filter_map(|m| {
+            let v: Json = serde_yaml::from_str(&m.yaml).ok()?;
+            let spec = v.get("spec")?;
+            let group = spec.get("group")?.as_str()?;
+            let kind = spec.get("names")?.get("kind")?.as_str()?;
+            let namespaced = spec.get("scope")?.as_str()? == "Namespaced";
+            Some((format!("{}.{}", kind, group), namespaced))
+        })
+        .collect()
+}
+
+/// Served resource for a versionless `Kind.group`, as recorded on the parent.
+async fn served_group_kind(gk: &str) -> Result<Option<(ApiResource, bool)>> {
+    let (kind, group) = gk.split_once('.').unwrap_or((gk, ""));
+    orka_kubehub::find_group_kind(group, kind).await
+}
+
+/// Identity of an object within a set: group/kind, namespace, name.
+type ObjKey = (String, Option<String>, String);
+
+/// An object labelled into the set but missing from the manifests.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PruneItem {
+    pub api_version: String,
+    pub kind: String,
+    pub name: String,
+    pub namespace: Option<String>,
+    pub deleted: bool,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ApplySetResult {
+    pub id: String,
+    pub batch: BatchResult,
+    /// Objects that would be (dry-run) or were pruned.
+    pub prune: Vec<PruneItem>,
+    /// Why pruning did not run even though candidates may exist.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prune_skipped: Option<String>,
+}
+
+/// Add the part-of label to a member manifest.
+pub fn stamp_member(m: &Manifest, id: &str) -> Result<Manifest> {
+    let mut v: Json = serde_yaml::from_str(&m.yaml)?;
+    let meta = v
+        .as_object_mut()
+        .ok_or_else(|| anyhow!("{}: not an object", m.source))?
+        .entry("metadata")
+        .or_insert_with(|| Json::Object(Default::default()));
+    let labels = meta
+        .as_object_mut()
+        .ok_or_else(|| anyhow!("{}: metadata is not an object", m.source))?
+        .entry("labels")
+        .or_insert_with(|| Json::Object(Default::default()));
+    match labels.as_object_mut() {
+        Some(l) => {
+            l.insert(PART_OF_LABEL.into(), Json::String(id.to_string()));
+        }
+        None => return Err(anyhow!("{}: metadata.labels is not a map", m.source)),
+    }
+    Ok(Manifest {
+        yaml: serde_yaml::to_string(&v)?,
+        ..m.clone()
+    })
+}
+
+/// Labelled objects not among `keep`, in listing order.
+fn prune_candidates(
+    listed: Vec<(String, DynamicObject)>,
+    keep: &HashSet<ObjKey>,
+) -> Vec<PruneItem> {
+    listed
+        .into_iter()
+        .filter_map(|(gk, obj)| {
+            let name = obj.metadata.name.clone().unwrap_or_default();
+            let ns = obj.metadata.namespace.clone();
+            if keep.contains(&(gk, ns.clone(), name.clone())) {
+                return None;
+            }
+            let (api_version, kind) = obj
+                .types
+                .map(|t| (t.api_version, t.kind))
+                .unwrap_or_default();
+            Some(PruneItem {
+                api_version,
+                kind,
+                name,
+                namespace: ns,
+                deleted: false,
+                error: None,
+            })
+        })
+        .collect()
+}
+
+/// Apply `manifests` as members of `set`, then prune members that are no
+/// longer in the manifests. With `do_apply == false` nothing is written and
+/// `prune` lists what an apply would delete. Pruning is skipped when any
+/// member fails to apply.
+pub async fn apply_set(
+    set: &ApplySet,
+    manifests: Vec<Manifest>,
+    ns_override: Option<&str>,
+    validate: bool,
+    do_apply: bool,
+    force: bool,
+) -> Result<ApplySetResult> {
+    let id = set.id();
+    let client = orka_kubehub::get_kube_client().await?;
+    let parents: Api<Secret> = Api::namespaced(client.clone(), &set.namespace);
+    let previous = match parents.get_opt(&set.name).await? {
+        Some(p) => {
+            let labels = p.metadata.labels.clone().unwrap_or_default();
+            if labels.get(ID_LABEL).map(|s| s.as_str()) != Some(id.as_str()) {
+                return Err(anyhow!(
+                    "secret {}/{} exists but is not the parent of apply set {}",
+                    set.namespace,
+                    set.name,
+                    id
+                ));
+            }
+            let tooling = p
+                .metadata
+                .annotations
+                .as_ref()
+                .and_then(|a| a.get(TOOLING_ANNOTATION))
+                .cloned()
+                .unwrap_or_default();
+            if !tooling.is_empty() && !tooling.starts_with("orka/") {
+                return Err(anyhow!(
+                    "apply set {} is managed by {}; refusing to take it over",
+                    id,
+                    tooling
+                ));
+            }
+            SetScope::from_parent(&p)
+        }
+        None => SetScope::default(),
+    };
+
+    // Kinds are resolved one at a time through the kubehub discovery cache.
+    // Kinds whose CRD is part of this batch are not served yet; their scope
+    // comes from the CRD and they are looked up again after the apply.
+    let batch_crds = batch_crd_scopes(&manifests);
+    let mut served: HashMap<String, (ApiResource, bool)> = HashMap::new();
+    let mut members = Vec::with_capacity(manifests.len());
+    let mut current = SetScope::default();
+    let mut keep: HashSet<ObjKey> = HashSet::new();
+    for m in &manifests {
+        let gk = group_kind(&m.api_version, &m.kind);
+        let namespaced = match served.get(&gk) {
+            Some((_, namespaced)) => *namespaced,
+            None => {
+                let key = format!("{}/{}", m.api_version, m.kind);
+                match orka_kubehub::get_api_resource(&key).await {
+                    Ok((ar, namespaced)) => {
+                        served.insert(gk.clone(), (ar, namespaced));
+                        namespaced
+                    }
+                    Err(e) => match batch_crds.get(&gk) {
+                        Some(namespaced) => *namespaced,
+                        None => return Err(anyhow!("{}: unknown kind {}: {}", m.source, gk, e)),
+                    },
+                }
+            }
+        };
+        let ns = if namespaced {
+            // Same precedence as `edit_from_yaml`: the override wins.
+            let ns = ns_override
+                .map(|s| s.to_string())
+                .or_else(|| m.namespace.clone())
+                .ok_or_else(|| anyhow!("{}: namespace required for {}", m.source, gk))?;
+            current.namespaces.insert(ns.clone());
+            Some(ns)
+        } else {
+            None
+        };
+        current.group_kinds.insert(gk.clone());
+        keep.insert((gk, ns, m.name.clone()));
+        members.push(stamp_member(m, &id)?);
+    }
+    let search = previous.union(&current);
+
+    if do_apply {
+        crate::edit_from_yaml_with(&set.parent_yaml(&search)?, None, false, true, true)
+            .await
+            .map_err(|e| anyhow!("updating apply set parent: {}", e))?;
+    }
+    let batch = crate::edit_from_manifests(members, ns_override, validate, do_apply, force).await;
+
+    let selector = ListParams::default().labels(&format!("{}={}", PART_OF_LABEL, id));
+    let mut listed = Vec::new();
+    for gk in &search.group_kinds {
+        if !served.contains_key(gk) {
+            match served_group_kind(gk).await? {
+                Some(found) => {
+                    served.insert(gk.clone(), found);
+                }
+                // Kind not served (CRD removed, or not established yet);
+                // nothing to prune.
+                None => continue,
+            }
+        }
+        let (ar, namespaced) = served[gk].clone();
+        let apis: Vec<Api<DynamicObject>> = if namespaced {
+            search
+                .namespaces
+                .iter()
+                .chain(std::iter::once(&set.namespace))
+                .collect::<BTreeSet<_>>()
+                .into_iter()
+                .map(|ns| Api::namespaced_with(client.clone(), ns, &ar))
+                .collect()
+        } else {
+            vec![Api::all_with(client.clone(), &ar)]
+        };
+        for api in apis {
+            for mut obj in api.list(&selector).await?.items {
+                if obj.types.is_none() {
+                    obj.types = Some(kube::core::TypeMeta {
+                        api_version: ar.api_version.clone(),
+                        kind: ar.kind.clone(),
+                    });
+                }
+                listed.push((gk.clone(), obj));
+            }
+        }
+    }
+    let mut prune = prune_candidates(listed, &keep);
+
+    let mut prune_skipped = None;
+    if do_apply {
+        if batch.summary.failed > 0 {
+            prune_skipped = Some(format!(
+                "{} object(s) failed to apply; not pruning",
+                batch.summary.failed
+            ));
+        } else {
+            for item in &mut prune {
+                let gk = group_kind(&item.api_version, &item.kind);
+                let Some((ar, _)) = served.get(&gk) else {
+                    continue;
+                };
+                let api: Api<DynamicObject> = match item.namespace.as_deref() {
+                    Some(ns) => Api::namespaced_with(client.clone(), ns, ar),
+                    None => Api::all_with(client.clone(), ar),
+                };
+                match api.delete(&item.name, &DeleteParams::background()).await {
+                    Ok(_) => {
+                        item.deleted = true;
+                        counter!("applyset_pruned_total", 1u64);
+                    }
+                    Err(e) => item.error = Some(e.to_string()),
+                }
+            }
+            if prune.iter().all(|p| p.deleted) {
+                crate::edit_from_yaml_with(&set.parent_yaml(&current)?, None, false, true, true)
+                    .await
+                    .map_err(|e| anyhow!("updating apply set parent: {}", e))?;
+            }
+        }
+    }
+    Ok(ApplySetResult {
+        id,
+        batch,
+        prune,
+        prune_skipped,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn manifest(yaml: &str) -> Manifest {
+        crate::manifest::split_manifests(yaml, "test.yaml")
+            .unwrap()
+            .remove(0)
+    }
+
+    #[test]
+    fn id_is_stable_and_kubectl_shaped() {
+        let a = ApplySet::new("shop", "prod").id();
+        assert_eq!(a, ApplySet::new("shop", "prod").id());
+        assert_ne!(a, ApplySet::new("shop", "staging").id());
+        assert!(a.starts_with("applyset-") && a.ends_with("-v1"), "{}", a);
+        // 32-byte digest, unpadded base64url
+        assert_eq!(a.len(), "applyset-".len() + 43 + "-v1".len());
+        assert!(!a.contains('+') && !a.contains('/') && !a.contains('='));
+    }
+
+    #[test]
+    fn stamps_part_of_label_and_keeps_existing_labels() {
+        let m = manifest(
+            "apiVersion: v1\nkind: ConfigMap\nmetadata:\n  name: cfg\n  labels: {app: web}\n",
+        );
+        let stamped = stamp_member(&m, "applyset-x-v1").unwrap();
+        let v: Json = serde_yaml::from_str(&stamped.yaml).unwrap();
+        assert_eq!(v["metadata"]["labels"]["app"], "web");
+        assert_eq!(v["metadata"]["labels"][PART_OF_LABEL], "applyset-x-v1");
+        assert_eq!(stamped.name, "cfg");
+    }
+
+    #[test]
+    fn parent_records_scope_and_candidates_exclude_kept_objects() {
+        let set = ApplySet::new("shop", "prod");
+        let scope = SetScope {
+            group_kinds: ["Deployment.apps".to_string(), "ConfigMap".to_string()].into(),
+            namespaces: ["prod".to_string(), "shared".to_string()].into(),
+        };
+        let parent: Secret = serde_yaml::from_str(&set.parent_yaml(&scope).unwrap()).unwrap();
+        let anno = parent.metadata.annotations.clone().unwrap();
+        assert_eq!(anno[GROUP_KINDS_ANNOTATION], "ConfigMap,Deployment.apps");
+        assert_eq!(anno[NAMESPACES_ANNOTATION], "shared");
+        assert_eq!(
+            SetScope::from_parent(&parent).group_kinds,
+            scope.group_kinds
+        );
+
+        let obj = |name: &str| -> DynamicObject {
+            serde_json::from_value(serde_json::json!({
+                "apiVersion": "v1",
+                "kind": "ConfigMap",
+                "metadata": {"name": name, "namespace": "prod"},
+            }))
+            .unwrap()
+        };
+        let keep: HashSet<ObjKey> = [(
+            "ConfigMap".to_string(),
+            Some("prod".to_string()),
+            "a".to_string(),
+        )]
+        .into();
+        let got = prune_candidates(
+            vec![
+                ("ConfigMap".into(), obj("a")),
+                ("ConfigMap".into(), obj("b")),
+            ],
+            &keep,
+        );
+        assert_eq!(got.len(), 1);
+        assert_eq!(got[0].name, "b");
+        assert_eq!(got[0].kind, "ConfigMap");
+        assert_eq!(group_kind("apps/v1", "Deployment"), "Deployment.apps");
+    }
+
+    #[tokio::test]
+    async fn apply_set_prunes_members_dropped_from_manifests() {
+        use orka_kubehub::fake::FakeApiServer;
+        let srv = FakeApiServer::new();
+        let _guard = srv.install();
+        let set = ApplySet::new("shop", "default");
+        let both = crate::manifest::split_manifests(
+            "apiVersion: v1\nkind: ConfigMap\nmetadata: {name: a, namespace: default}\n\
+             ---\n\
+             apiVersion: v1\nkind: ConfigMap\nmetadata: {name: b, namespace: default}\n",
+            "set.yaml",
+        )
+        .unwrap();
+        let res = apply_set(&set, both.clone(), None, false, true, false)
+            .await
+            .unwrap();
+        assert_eq!(res.batch.summary.applied, 2);
+        assert!(res.prune.is_empty());
+        let b = srv.get("v1/ConfigMap", Some("default"), "b").unwrap();
+        assert_eq!(b["metadata"]["labels"][PART_OF_LABEL], set.id().as_str());
+
+        // Dropping `b` from the manifests: dry-run lists it, apply deletes it.
+        let only_a = vec![both[0].clone()];
+        let dry = apply_set(&set, only_a.clone(), None, false, false, false)
+            .await
+            .unwrap();
+        assert_eq!(dry.prune.len(), 1);
+        assert!(!dry.prune[0].deleted);
+        assert!(srv.get("v1/ConfigMap", Some("default"), "b").is_some());
+
+        let res = apply_set(&set, only_a, None, false, true, false)
+            .await
+            .unwrap();
+        assert_eq!(res.prune.len(), 1);
+        assert!(res.prune[0].deleted, "{:?}", res.prune);
+        assert!(srv.get("v1/ConfigMap", Some("default"), "b").is_none());
+        assert!(srv.get("v1/ConfigMap", Some("default"), "a").is_some());
+        let parent = srv.get("v1/Secret", Some("default"), "shop").unwrap();
+        assert_eq!(
+            parent["metadata"]["annotations"][GROUP_KINDS_ANNOTATION],
+            "ConfigMap"
+        );
+
+        // A Secret of the same name that is not an apply set parent is left alone.
+        let other = ApplySet::new("shop", "default");
+        srv.update(serde_json::json!({
+            "apiVersion": "v1",
+            "kind": "Secret",
+            "metadata": {"name": "shop", "namespace": "default", "labels": {ID_LABEL: "other"}},
+        }));
+        assert!(apply_set(&other, vec![], None, false, false, false)
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn apply_set_takes_scope_of_kinds_from_crds_in_the_batch() {
+        use orka_kubehub::fake::{FakeApiServer, FakeResource};
+        let srv = FakeApiServer::new();
+        let _guard = srv.install();
+        let set = ApplySet::new("widgets", "default");
+        let manifests = crate::manifest::split_manifests(
+            "apiVersion: apiextensions.k8s.io/v1\n\
+             kind: CustomResourceDefinition\n\
+             metadata: {name: widgets.example.com}\n\
+             spec:\n  group: example.com\n  scope: Namespaced\n\
+             \x20 names: {kind: Widget, plural: widgets}\n\
+             \x20 versions: [{name: v1, served: true, storage: true}]\n\
+             ---\n\
+             apiVersion: example.com/v1\nkind: Widget\nmetadata: {name: w, namespace: default}\n",
+            "set.yaml",
+        )
+        .unwrap();
+
+        // The fake has no controller to establish the CRD, so the Widget
+        // itself fails; the run still gets past scope resolution.
+        let res = apply_set(&set, manifests.clone(), None, false, true, false)
+            .await
+            .unwrap();
+        assert_eq!(res.batch.summary.applied, 1);
+        assert_eq!(res.batch.summary.failed, 1);
+        assert!(res.prune_skipped.is_some());
+        let parent = srv.get("v1/Secret", Some("default"), "widgets").unwrap();
+        assert_eq!(
+            parent["metadata"]["annotations"][GROUP_KINDS_ANNOTATION],
+            "CustomResourceDefinition.apiextensions.k8s.io,Widget.example.com"
+        );
+
+        srv.register(FakeResource::new(
+            "example.com",
+            "v1",
+            "Widget",
+            "widgets",
+            true,
+        ));
+        let res = apply_set(&set, manifests, None, false, true, false)
+            .await
+            .unwrap();
+        assert_eq!(res.batch.summary.applied, 2);
+        assert!(res.prune.is_empty());
+        assert!(srv
+            .get("example.com/v1/Widget", Some("default"), "w")
+            .is_some());
+
+        // Without its CRD in the batch an unserved kind is still an error.
+        let gadget = crate::manifest::split_manifests(
+            "apiVersion: example.com/v1\nkind: Gadget\nmetadata: {name: g, namespace: default}\n",
+            "set.yaml",
+        )
+        .unwrap();
+        let err = apply_set(&set, gadget, None, false, false, false)
+            .await
+            .unwrap_err();
+        assert!(
+            err.to_string().contains("unknown kind Gadget.example.com"),
+            "{}",
+            err
+        );
+    }
+}
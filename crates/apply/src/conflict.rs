@@ -0,0 +1,182 @@
+//! Server-side apply conflicts: which manager owns which field.
+//!
+//! kube surfaces only the Status message of a 409, so conflicts are parsed
+//! from the apiserver's text, which comes in two shapes:
+//!
+//! ```text
+//! Apply failed with 1 conflict: conflict with "helm" using apps/v1: .spec.replicas
+//! Apply failed with 2 conflicts: conflicts with "argocd-controller" using apps/v1:
+//! - .spec.replicas
+//! - .spec.template.spec.containers[name="app"].image
+//! ```
+
+use serde::{Deserialize, Serialize};
+
+/// One field another manager owns with a different value.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FieldConflict {
+    /// Field path as reported by the apiserver, e.g. `.spec.replicas`.
+    pub field: String,
+    /// Owning field manager, e.g. `helm` or `argocd-controller`.
+    pub manager: String,
+    /// API version the manager last wrote with, when reported.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub api_version: Option<String>,
+}
+
+/// A rejected apply; returned (inside `anyhow::Error`) by
+/// [`crate::edit_from_yaml`] so callers can downcast for the details.
+#[derive(Debug, Clone, thiserror::Error)]
+#[error("{context}: {message}")]
+pub struct ApplyConflict {
+    /// `server-side apply failed` or `dry-run failed`.
+    pub context: String,
+    pub message: String,
+    pub conflicts: Vec<FieldConflict>,
+}
+
+impl ApplyConflict {
+    /// Build from a kube API error if it is an apply conflict.
+    pub fn from_kube(context: &str, err: &kube::Error) -> Option<Self> {
+        match err {
+            kube::Error::Api(ae) if ae.code == 409 && ae.reason == "Conflict" => {
+                let conflicts = parse_conflicts(&ae.message);
+                if conflicts.is_empty() {
+                    // Plain optimistic-concurrency conflict, not field ownership.
+                    return None;
+                }
+                Some(Self {
+                    context: context.to_string(),
+                    message: ae.message.clone(),
+                    conflicts,
+                })
+            }
+            _ => None,
+        }
+    }
+
+    /// Distinct managers involved, in first-seen order.
+    pub fn managers(&self) -> Vec<&str> {
+        let mut out: Vec<&str> = Vec::new();
+        for c in &self.conflicts {
+            if !out.contains(&c.manager.as_str()) {
+                out.push(&c.manager);
+            }
+        }
+        out
+    }
+}
+
+/// `"manager" using group/version` -> (manager, api version)
+fn parse_owner(s: &str) -> Option<(String, Option<String>)> {
+    let s = s.trim();
+    let rest = s.strip_prefix('"')?;
+    let end = rest.find('"')?;
+    let manager = rest[..end].to_string();
+    let api_version = rest[end + 1..]
+        .trim()
+        .strip_prefix("using ")
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty());
+    Some((manager, api_version))
+}
+
+/// Parse the conflict list out of an apply failure message; empty when the
+/// message is not an SSA conflict.
+pub fn parse_conflicts(message: &str) -> Vec<FieldConflict> {
+    let body = match message.find("Apply failed with") {
+        Some(i) => {
+            let tail = &message[i..];
+            match tail.find(": ") {
+                Some(j) => &tail[j + 2..],
+                None => return Vec::new(),
+            }
+        }
+        None => message,
+    };
+    let mut out = Vec::new();
+    let mut owner: Option<(String, Option<String>)> = None;
+    for raw in body.lines() {
+        let line = raw.trim();
+        if let Some(rest) = line.strip_prefix("conflicts with ") {
+            owner = parse_owner(rest.trim_end_matches(':'));
+        } else if let Some(rest) = line
+            .strip_prefix("- conflict with ")
+            .or_else(|| line.strip_prefix("conflict with "))
+        {
+            // Single form: `"m" using v: .path` (the path itself may contain `: `
+            // only inside quoted keys, which follow the owner).
+            let Some(close) = rest.get(1..).and_then(|r| r.find('"')).map(|i| i + 2) else {
+                continue;
+            };
+            let (head, tail) = rest.split_at(close);
+            let (version, field) = match tail.split_once(": ") {
+                Some((v, f)) => (v, f),
+                None => continue,
+            };
+            if let Some((manager, api_version)) = parse_owner(&format!("{}{}", head, version)) {
+                out.push(FieldConflict {
+                    field: field.trim().to_string(),
+                    manager,
+                    api_version,
+                });
+            }
+        } else if let Some(field) = line.strip_prefix("- ") {
+            if let Some((manager, api_version)) = &owner {
+                out.push(FieldConflict {
+                    field: field.trim().to_string(),
+                    manager: manager.clone(),
+                    api_version: api_version.clone(),
+                });
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_single_conflict() {
+        let c = parse_conflicts(
+            "Apply failed with 1 conflict: conflict with \"helm\" using apps/v1: .spec.replicas",
+        );
+        assert_eq!(
+            c,
+            vec![FieldConflict {
+                field: ".spec.replicas".into(),
+                manager: "helm".into(),
+                api_version: Some("apps/v1".into()),
+            }]
+        );
+    }
+
+    #[test]
+    fn parses_grouped_conflicts_across_managers() {
+        let msg =
+            "Apply failed with 3 conflicts: conflicts with \"argocd-controller\" using apps/v1:\n\
+                   - .spec.replicas\n\
+                   - .spec.template.spec.containers[name=\"app\"].image\n\
+                   conflicts with \"kubectl-edit\":\n\
+                   - .metadata.labels.tier";
+        let c = parse_conflicts(msg);
+        assert_eq!(c.len(), 3);
+        assert_eq!(
+            c[1].field,
+            ".spec.template.spec.containers[name=\"app\"].image"
+        );
+        assert_eq!(c[1].manager, "argocd-controller");
+        assert_eq!(c[2].manager, "kubectl-edit");
+        assert_eq!(c[2].api_version, None);
+    }
+
+    #[test]
+    fn ignores_optimistic_lock_conflicts() {
+        assert!(parse_conflicts(
+            "Operation cannot be fulfilled on deployments.apps \"web\": the object has been modified"
+        )
+        .is_empty());
+    }
+}
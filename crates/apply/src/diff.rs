@@ -0,0 +1,614 @@
+//! Structured diffs: per-path changes with old/new values, rendered as a
+//! unified YAML diff or an RFC 6902 JSON Patch.
+//!
+//! Lists of objects are matched by merge key (containers and env by `name`,
+//! volume mounts by `mountPath`, ports by `containerPort`/`port`) so that a
+//! reordered or inserted item shows up as one change rather than a cascade
+//! of index updates.
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value as Json};
+
+use crate::DiffSummary;
+
+/// Candidate merge keys, tried in order; a list is keyed by the first one
+/// present and unique on every item of both sides.
+pub(crate) const MERGE_KEYS: &[&str] = &[
+    "name",
+    "mountPath",
+    "containerPort",
+    "port",
+    "devicePath",
+    "ip",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChangeOp {
+    Add,
+    Remove,
+    Replace,
+}
+
+/// One changed field.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FieldChange {
+    /// Human path, e.g. `.spec.template.spec.containers[name=app].image`.
+    pub path: String,
+    /// JSON Pointer into the base document (`-` appends for list adds).
+    pub pointer: String,
+    pub op: ChangeOp,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub old: Option<Json>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub new: Option<Json>,
+}
+
+/// Structured diff of `target` against `base` plus its renderings.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DiffReport {
+    pub summary: DiffSummary,
+    pub changes: Vec<FieldChange>,
+    /// Unified diff of the YAML renderings (base `---`, target `+++`).
+    pub unified: String,
+}
+
+impl DiffReport {
+    pub fn new(target: &Json, base: &Json, base_label: &str) -> Self {
+        let changes = structured_diff(target, base);
+        let summary = summarize(&changes);
+        let unified = unified_yaml_diff(target, base, base_label, "target", 3);
+        Self {
+            summary,
+            changes,
+            unified,
+        }
+    }
+
+    pub fn json_patch(&self) -> Json {
+        to_json_patch(&self.changes)
+    }
+}
+
+fn summarize(changes: &[FieldChange]) -> DiffSummary {
+    let mut s = DiffSummary::default();
+    for c in changes {
+        match c.op {
+            ChangeOp::Add => s.adds += 1,
+            ChangeOp::Remove => s.removes += 1,
+            ChangeOp::Replace => s.updates += 1,
+        }
+    }
+    s
+}
+
+fn escape_pointer(seg: &str) -> String {
+    seg.replace('~', "~0").replace('/', "~1")
+}
+
+fn scalar_key(v: &Json) -> Option<String> {
+    match v {
+        Json::String(s) => Some(s.clone()),
+        Json::Number(n) => Some(n.to_string()),
+        _ => None,
+    }
+}
+
+/// The merge key shared by every item of both lists, if any.
+fn merge_key(a: &[Json], b: &[Json]) -> Option<&'static str> {
+    if a.is_empty() && b.is_empty() {
+        return None;
+    }
+    MERGE_KEYS.iter().copied().find(|k| {
+        [a, b].iter().all(|items| {
+            let mut seen = std::collections::HashSet::new();
+            items.iter().all(|i| {
+                i.get(*k)
+                    .and_then(scalar_key)
+                    .map(|v| seen.insert(v))
+                    .unwrap_or(false)
+            })
+        })
+    })
+}
+
+/// Changes that turn `base` into `target`, in an order that applies cleanly
+/// as a JSON Patch.
+pub fn structured_diff(target: &Json, base: &Json) -> Vec<FieldChange> {
+    let mut out = Vec::new();
+    walk(target, base, "", "", &mut out);
+    out
+}
+
+fn walk(t: &Json, b: &Json, path: &str, ptr: &str, out: &mut Vec<FieldChange>) {
+    match (t, b) {
+        (Json::Object(to), Json::Object(bo)) => {
+            for (k, bv) in bo {
+                let p = format!("{}.{}", path, k);
+                let q = format!("{}/{}", ptr, escape_pointer(k));
+                match to.get(k) {
+                    Some(tv) if tv == bv => {}
+                    Some(tv) => walk(tv, bv, &p, &q, out),
+                    None => out.push(FieldChange {
+                        path: p,
+                        pointer: q,
+                        op: ChangeOp::Remove,
+                        old: Some(bv.clone()),
+                        new: None,
+                    }),
+                }
+            }
+            for (k, tv) in to {
+                if !bo.contains_key(k) {
+                    out.push(FieldChange {
+                        path: format!("{}.{}", path, k),
+                        pointer: format!("{}/{}", ptr, escape_pointer(k)),
+                        op: ChangeOp::Add,
+                        old: None,
+                        new: Some(tv.clone()),
+                    });
+                }
+            }
+        }
+        (Json::Array(ta), Json::Array(ba)) => match merge_key(ta, ba) {
+            Some(key) => walk_keyed(ta, ba, key, path, ptr, out),
+            None if ta.len() == ba.len() => {
+                for (i, (tv, bv)) in ta.iter().zip(ba).enumerate() {
+                    if tv != bv {
+                        walk(
+                            tv,
+                            bv,
+                            &format!("{}[{}]", path, i),
+                            &format!("{}/{}", ptr, i),
+                            out,
+                        );
+                    }
+                }
+            }
+            None => out.push(replace(t, b, path, ptr)),
+        },
+        _ if t != b => out.push(replace(t, b, path, ptr)),
+        _ => {}
+    }
+}
+
+fn replace(t: &Json, b: &Json, path: &str, ptr: &str) -> FieldChange {
+    FieldChange {
+        path: if path.is_empty() {
+            ".".into()
+        } else {
+            path.into()
+        },
+        pointer: ptr.into(),
+        op: ChangeOp::Replace,
+        old: Some(b.clone()),
+        new: Some(t.clone()),
+    }
+}
+
+fn walk_keyed(
+    ta: &[Json],
+    ba: &[Json],
+    key: &str,
+    path: &str,
+    ptr: &str,
+    out: &mut Vec<FieldChange>,
+) {
+    let kv = |v: &Json| v.get(key).and_then(scalar_key).unwrap_or_default();
+    let label = |v: &Json| format!("{}[{}={}]", path, key, kv(v));
+    // Matched items first, at their base index.
+    for (i, bv) in ba.iter().enumerate() {
+        if let Some(tv) = ta.iter().find(|tv| kv(tv) == kv(bv)) {
+            if tv != bv {
+                walk(tv, bv, &label(bv), &format!("{}/{}", ptr, i), out);
+            }
+        }
+    }
+    // Removals from the back so earlier indices stay valid.
+    for (i, bv) in ba.iter().enumerate().rev() {
+        if !ta.iter().any(|tv| kv(tv) == kv(bv)) {
+            out.push(FieldChange {
+                path: label(bv),
+                pointer: format!("{}/{}", ptr, i),
+                op: ChangeOp::Remove,
+                old: Some(bv.clone()),
+                new: None,
+            });
+        }
+    }
+    for tv in ta {
+        if !ba.iter().any(|bv| kv(bv) == kv(tv)) {
+            out.push(FieldChange {
+                path: label(tv),
+                pointer: format!("{}/-", ptr),
+                op: ChangeOp::Add,
+                old: None,
+                new: Some(tv.clone()),
+            });
+        }
+    }
+}
+
+/// RFC 6902 operations for a change list from [`structured_diff`].
+pub fn to_json_patch(changes: &[FieldChange]) -> Json {
+    Json::Array(
+        changes
+            .iter()
+            .map(|c| match c.op {
+                ChangeOp::Remove => json!({"op": "remove", "path": c.pointer}),
+                ChangeOp::Add => json!({"op": "add", "path": c.pointer, "value": c.new}),
+                ChangeOp::Replace => json!({"op": "replace", "path": c.pointer, "value": c.new}),
+            })
+            .collect(),
+    )
+}
+
+/// Reorder keyed lists in `target` to follow `base`, so the text diff pairs
+/// up the same items; new items keep their relative order at the end.
+fn align_to_base(target: &Json, base: &Json) -> Json {
+    match (target, base) {
+        (Json::Object(to), Json::Object(bo)) => Json::Object(
+            to.iter()
+                .map(|(k, tv)| {
+                    let v = match bo.get(k) {
+                        Some(bv) => align_to_base(tv, bv),
+                        None => tv.clone(),
+                    };
+                    (k.clone(), v)
+                })
+                .collect(),
+        ),
+        (Json::Array(ta), Json::Array(ba)) => match merge_key(ta, ba) {
+            Some(key) => {
+                let kv = |v: &Json| v.get(key).and_then(scalar_key).unwrap_or_default();
+                let mut out: Vec<Json> = Vec::with_capacity(ta.len());
+                for bv in ba {
+                    if let Some(tv) = ta.iter().find(|tv| kv(tv) == kv(bv)) {
+                        out.push(align_to_base(tv, bv));
+                    }
+                }
+                out.extend(
+                    ta.iter()
+                        .filter(|tv| !ba.iter().any(|bv| kv(bv) == kv(tv)))
+                        .cloned(),
+                );
+                Json::Array(out)
+            }
+            None => target.clone(),
+        },
+        _ => target.clone(),
+    }
+}
+
+fn to_yaml_lines(v: &Json) -> Vec<String> {
+    if v.is_null() {
+        return Vec::new();
+    }
+    serde_yaml::to_string(v)
+        .unwrap_or_default()
+        .lines()
+        .map(|l| l.to_string())
+        .collect()
+}
+
+/// Line diff operations: a shortest edit script (Myers), found by
+/// splitting at middle snakes so time is O((n+m)·D) and memory O(n+m) even
+/// for large CRDs.
+fn line_ops<'a>(a: &'a [String], b: &'a [String]) -> Vec<(char, &'a str)> {
+    let mut ops = Vec::with_capacity(a.len() + b.len());
+    diff_range(a, b, &mut ops);
+    ops
+}
+
+fn diff_range<'a>(a: &'a [String], b: &'a [String], ops: &mut Vec<(char, &'a str)>) {
+    let pre = a.iter().zip(b).take_while(|(x, y)| x == y).count();
+    let (a, b, same) = (&a[pre..], &b[pre..], &a[..pre]);
+    ops.extend(same.iter().map(|l| (' ', l.as_str())));
+    let suf = a
+        .iter()
+        .rev()
+        .zip(b.iter().rev())
+        .take_while(|(x, y)| x == y)
+        .count();
+    let (a, b, same) = (
+        &a[..a.len() - suf],
+        &b[..b.len() - suf],
+        &a[a.len() - suf..],
+    );
+    if a.is_empty() || b.is_empty() {
+        ops.extend(a.iter().map(|l| ('-', l.as_str())));
+        ops.extend(b.iter().map(|l| ('+', l.as_str())));
+    } else {
+        // Both ends differ, so each side of the snake holds at least one edit.
+        let (x, y, u, v) = middle_snake(a, b);
+        diff_range(&a[..x], &b[..y], ops);
+        ops.extend(a[x..u].iter().map(|l| (' ', l.as_str())));
+        diff_range(&a[u..], &b[v..], ops);
+    }
+    ops.extend(same.iter().map(|l| (' ', l.as_str())));
+}
+
+/// Start and end `(x, y, u, v)` of the diagonal run in the middle of a
+/// shortest edit script from `a` to `b` (Myers 1986, section 4b).
+fn middle_snake(a: &[String], b: &[String]) -> (usize, usize, usize, usize) {
+    let (n, m) = (a.len() as isize, b.len() as isize);
+    let max = (n + m + 1) / 2 + 1;
+    let delta = n - m;
+    let odd = delta % 2 != 0;
+    let at = |k: isize| (k + max) as usize;
+    // Furthest x reached on each diagonal k = x - y, from the start
+    // (`fwd`) and from the end in reversed coordinates (`bwd`).
+    let mut fwd = vec![0isize; 2 * max as usize + 1];
+    let mut bwd = vec![0isize; 2 * max as usize + 1];
+    for d in 0..max {
+        for k in (-d..=d).step_by(2) {
+            let mut x = if k == -d || (k != d && fwd[at(k - 1)] < fwd[at(k + 1)]) {
+                fwd[at(k + 1)]
+            } else {
+                fwd[at(k - 1)] + 1
+            };
+            let (x0, y0) = (x, x - k);
+            let mut y = y0;
+            while x < n && y < m && a[x as usize] == b[y as usize] {
+                x += 1;
+                y += 1;
+            }
+            fwd[at(k)] = x;
+            let kb = delta - k;
+            if odd && (-(d - 1)..=d - 1).contains(&kb) && x + bwd[at(kb)] >= n {
+                return (x0 as usize, y0 as usize, x as usize, y as usize);
+            }
+        }
+        for k in (-d..=d).step_by(2) {
+            let mut x = if k == -d || (k != d && bwd[at(k - 1)] < bwd[at(k + 1)]) {
+                bwd[at(k + 1)]
+            } else {
+                bwd[at(k - 1)] + 1
+            };
+            let (x0, y0) = (x, x - k);
+            let mut y = y0;
+            while x < n && y < m && a[(n - 1 - x) as usize] == b[(m - 1 - y) as usize] {
+                x += 1;
+                y += 1;
+            }
+            bwd[at(k)] = x;
+            let kf = delta - k;
+            if !odd && (-d..=d).contains(&kf) && x + fwd[at(kf)] >= n {
+                return (
+                    (n - x) as usize,
+                    (m - y) as usize,
+                    (n - x0) as usize,
+                    (m - y0) as usize,
+                );
+            }
+        }
+    }
+    unreachable!("an edit script is at most n + m long")
+}
+
+/// Unified diff of the YAML renderings of `base` and `target` with
+/// `context` lines around each hunk. Empty when they are equal.
+pub fn unified_yaml_diff(
+    target: &Json,
+    base: &Json,
+    base_label: &str,
+    target_label: &str,
+    context: usize,
+) -> String {
+    let a = to_yaml_lines(base);
+    let b = to_yaml_lines(&align_to_base(target, base));
+    let ops = line_ops(&a, &b);
+    if ops.iter().all(|(c, _)| *c == ' ') {
+        return String::new();
+    }
+    // Group changed op indices into hunks separated by > 2*context equal lines.
+    let changed: Vec<usize> = ops
+        .iter()
+        .enumerate()
+        .filter(|(_, (c, _))| *c != ' ')
+        .map(|(i, _)| i)
+        .collect();
+    let mut hunks: Vec<(usize, usize)> = Vec::new();
+    for &i in &changed {
+        let start = i.saturating_sub(context);
+        let end = (i + context + 1).min(ops.len());
+        match hunks.last_mut() {
+            Some(h) if start <= h.1 => h.1 = end,
+            _ => hunks.push((start, end)),
+        }
+    }
+    let mut out = format!("--- {}\n+++ {}\n", base_label, target_label);
+    for (start, end) in hunks {
+        // Line numbers at the hunk start.
+        let (mut la, mut lb) = (1, 1);
+        for (c, _) in &ops[..start] {
+            if *c != '+' {
+                la += 1;
+            }
+            if *c != '-' {
+                lb += 1;
+            }
+        }
+        let slice = &ops[start..end];
+        let na = slice.iter().filter(|(c, _)| *c != '+').count();
+        let nb = slice.iter().filter(|(c, _)| *c != '-').count();
+        let la = if na == 0 { la - 1 } else { la };
+        let lb = if nb == 0 { lb - 1 } else { lb };
+        out.push_str(&format!("@@ -{},{} +{},{} @@\n", la, na, lb, nb));
+        for (c, line) in slice {
+            out.push(*c);
+            out.push_str(line);
+            out.push('\n');
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn deploy(containers: Json, replicas: i64) -> Json {
+        json!({
+            "apiVersion": "apps/v1",
+            "kind": "Deployment",
+            "metadata": {"name": "web"},
+            "spec": {"replicas": replicas, "template": {"spec": {"containers": containers}}}
+        })
+    }
+
+    #[test]
+    fn matches_list_items_by_merge_key() {
+        let base = deploy(
+            json!([{"name": "app", "image": "app:1"}, {"name": "sidecar", "image": "envoy:1"}]),
+            2,
+        );
+        // Sidecar moved first and app bumped: one image change, no index noise.
+        let target = deploy(
+            json!([{"name": "sidecar", "image": "envoy:1"}, {"name": "app", "image": "app:2"}]),
+            2,
+        );
+        let changes = structured_diff(&target, &base);
+        assert_eq!(changes.len(), 1);
+        assert_eq!(
+            changes[0].path,
+            ".spec.template.spec.containers[name=app].image"
+        );
+        assert_eq!(changes[0].pointer, "/spec/template/spec/containers/0/image");
+        assert_eq!(changes[0].old, Some(json!("app:1")));
+        assert_eq!(changes[0].new, Some(json!("app:2")));
+    }
+
+    #[test]
+    fn json_patch_applies_back_to_target() {
+        let base = deploy(
+            json!([{"name": "a", "image": "a"}, {"name": "b", "image": "b"}, {"name": "c", "image": "c"}]),
+            1,
+        );
+        let mut target = deploy(
+            json!([{"name": "b", "image": "b2"}, {"name": "d", "image": "d"}]),
+            3,
+        );
+        target["metadata"]["labels"] = json!({"app/name": "web"});
+        let report = DiffReport::new(&target, &base, "live");
+        assert_eq!(report.summary.adds, 2);
+        assert_eq!(report.summary.removes, 2);
+        assert_eq!(report.summary.updates, 2);
+
+        let mut doc = base.clone();
+        for op in report.json_patch().as_array().unwrap() {
+            let ptr = op["path"].as_str().unwrap();
+            let (parent, last) = ptr.rsplit_once('/').unwrap();
+            let last = last.replace("~1", "/").replace("~0", "~");
+            let container = doc.pointer_mut(parent).unwrap();
+            match (op["op"].as_str().unwrap(), container) {
+                ("remove", Json::Array(a)) => {
+                    a.remove(last.parse().unwrap());
+                }
+                ("remove", Json::Object(m)) => {
+                    m.remove(&last);
+                }
+                ("add", Json::Array(a)) => a.push(op["value"].clone()),
+                (_, Json::Array(a)) => a[last.parse::<usize>().unwrap()] = op["value"].clone(),
+                (_, Json::Object(m)) => {
+                    m.insert(last, op["value"].clone());
+                }
+                _ => unreachable!(),
+            }
+        }
+        assert_eq!(doc, target);
+    }
+
+    #[test]
+    fn unified_diff_shows_only_changed_lines_with_context() {
+        let base = deploy(json!([{"name": "app", "image": "app:1"}]), 2);
+        let target = deploy(json!([{"name": "app", "image": "app:2"}]), 2);
+        let d = unified_yaml_diff(&target, &base, "live", "target", 1);
+        assert!(d.starts_with("--- live\n+++ target\n@@ "), "{}", d);
+        let removed: Vec<&str> = d
+            .lines()
+            .filter(|l| l.starts_with("-") && !l.starts_with("---"))
+            .collect();
+        let added: Vec<&str> = d
+            .lines()
+            .filter(|l| l.starts_with("+") && !l.starts_with("+++"))
+            .collect();
+        assert_eq!(removed.len(), 1, "{}", d);
+        assert!(removed[0].ends_with("image: app:1"), "{}", d);
+        assert!(
+            added.len() == 1 && added[0].ends_with("image: app:2"),
+            "{}",
+            d
+        );
+        assert!(
+            !d.contains("apiVersion"),
+            "context should be trimmed: {}",
+            d
+        );
+        assert_eq!(unified_yaml_diff(&base, &base, "live", "target", 3), "");
+    }
+
+    #[test]
+    fn line_ops_is_a_shortest_edit_script() {
+        fn lcs(a: &[String], b: &[String]) -> usize {
+            let mut t = vec![vec![0; b.len() + 1]; a.len() + 1];
+            for i in (0..a.len()).rev() {
+                for j in (0..b.len()).rev() {
+                    t[i][j] = if a[i] == b[j] {
+                        t[i + 1][j + 1] + 1
+                    } else {
+                        t[i + 1][j].max(t[i][j + 1])
+                    };
+                }
+            }
+            t[0][0]
+        }
+        let lines = |s: &str| s.chars().map(|c| c.to_string()).collect::<Vec<_>>();
+        let cases = [
+            ("", ""),
+            ("abc", ""),
+            ("", "abc"),
+            ("abcabba", "cbabac"),
+            ("xaxbxc", "abc"),
+            ("abcdefg", "gfedcba"),
+            ("aaaabbbb", "bbbbaaaa"),
+            ("kubernetes", "kustomize"),
+        ];
+        for (a, b) in cases {
+            let (a, b) = (lines(a), lines(b));
+            let ops = line_ops(&a, &b);
+            let side = |skip: char| -> Vec<&str> {
+                ops.iter()
+                    .filter(|(c, _)| *c != skip)
+                    .map(|(_, l)| *l)
+                    .collect()
+            };
+            assert_eq!(side('+'), a, "{:?} -> {:?}", a, b);
+            assert_eq!(side('-'), b, "{:?} -> {:?}", a, b);
+            let kept = ops.iter().filter(|(c, _)| *c == ' ').count();
+            assert_eq!(kept, lcs(&a, &b), "{:?} -> {:?}", a, b);
+        }
+    }
+
+    #[test]
+    fn line_ops_handles_large_documents() {
+        // ~1.6 GB as a full LCS table.
+        let a: Vec<String> = (0..20_000).map(|i| format!("line {}", i)).collect();
+        let mut b = a.clone();
+        b[100] = "changed".into();
+        b.insert(15_000, "added".into());
+        let ops = line_ops(&a, &b);
+        let count = |c: char| ops.iter().filter(|(o, _)| *o == c).count();
+        assert_eq!((count('-'), count('+'), count(' ')), (1, 2, 19_999));
+    }
+
+    #[test]
+    fn new_object_diffs_against_null() {
+        let target = json!({"kind": "ConfigMap", "data": {"k": "v"}});
+        let r = DiffReport::new(&target, &Json::Null, "live");
+        assert_eq!(r.changes.len(), 1);
+        assert_eq!(r.changes[0].op, ChangeOp::Replace);
+        assert!(r.unified.contains("+kind: ConfigMap"));
+        assert!(r.unified.contains("@@ -0,0 +1,"), "{}", r.unified);
+    }
+}
@@ -0,0 +1,734 @@
+//! Drift detection: compare the newest last-applied snapshot of every object
+//! in the history store with its live state.
+//!
+//! Only fields the snapshot sets are compared (status, server defaults and
+//! fields added by other managers are ignored), so a report lists what a
+//! `kubectl edit` or controller changed since Orka last applied it. Both
+//! sides are first put in the form the API server stores, so `cpu: 1000m`
+//! against a live `1`, or `stringData` against base64 `data`, is not drift. A scan
+//! runs once; [`watch_drift`] keeps the result current from watch events.
+
+use std::collections::HashMap;
+
+use anyhow::{anyhow, Result};
+use base64::Engine as _;
+use kube::{
+    api::{Api, DynamicObject},
+    core::{ApiResource, GroupVersionKind},
+    Client,
+};
+use orka_persist::{LastApplied, LogStore, Store};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value as Json};
+use tokio::sync::mpsc;
+use tracing::{debug, warn};
+use uuid::Uuid;
+
+use crate::threeway::{ThreeWayEntry, ThreeWayReport};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DriftStatus {
+    InSync,
+    Drifted,
+    /// The object no longer exists.
+    Missing,
+    /// An object with the same name exists but with another UID; changes are
+    /// against the replacement.
+    Replaced,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DriftItem {
+    pub uid: String,
+    pub gvk: String,
+    pub namespace: Option<String>,
+    pub name: String,
+    pub status: DriftStatus,
+    /// When the compared snapshot was applied, and the resulting rv.
+    pub applied_ts: i64,
+    pub applied_rv: String,
+    /// Drifted paths with last-applied (`base`) and `live` values.
+    pub changes: Vec<ThreeWayEntry>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DriftReport {
+    pub scanned: usize,
+    pub in_sync: usize,
+    pub drifted: usize,
+    pub missing: usize,
+    pub replaced: usize,
+    /// Objects not in sync.
+    pub items: Vec<DriftItem>,
+    /// Snapshots that could not be checked (unparseable, unknown kind, …).
+    pub errors: Vec<String>,
+    /// Objects last applied through another kube context; not checked.
+    pub other_context: usize,
+    /// Snapshots recorded before object keys (and so their cluster) were
+    /// tracked; not checked, as they may belong to any context.
+    pub legacy: Vec<String>,
+}
+
+impl DriftReport {
+    fn push(&mut self, item: DriftItem) {
+        self.scanned += 1;
+        match item.status {
+            DriftStatus::InSync => self.in_sync += 1,
+            DriftStatus::Drifted => self.drifted += 1,
+            DriftStatus::Missing => self.missing += 1,
+            DriftStatus::Replaced => self.replaced += 1,
+        }
+        if item.status != DriftStatus::InSync {
+            self.items.push(item);
+        }
+    }
+}
+
+/// Paths where `live` no longer matches the last-applied document.
+pub fn drift_changes(last_applied: &Json, live: &Json) -> Vec<ThreeWayEntry> {
+    let base = normalize(crate::strip_identity(crate::strip_noisy(
+        last_applied.clone(),
+    )));
+    let live = normalize(crate::strip_identity(crate::strip_noisy(live.clone())));
+    ThreeWayReport::new(Some(&base), &live, &base).entries
+}
+
+/// Maps whose values are resource quantities: container and PVC
+/// `resources.requests`/`limits`, ResourceQuota `hard`.
+const QUANTITY_MAPS: &[&str] = &["requests", "limits", "hard"];
+
+/// Rewrite what the API server rewrites on write: quantities to canonical
+/// form and a Secret's `stringData` into base64 `data`.
+fn normalize(mut doc: Json) -> Json {
+    fold_string_data(&mut doc);
+    canonical_quantities(&mut doc);
+    doc
+}
+
+fn fold_string_data(doc: &mut Json) {
+    if doc.get("kind").and_then(|k| k.as_str()) != Some("Secret") {
+        return;
+    }
+    let Some(obj) = doc.as_object_mut() else {
+        return;
+    };
+    let Some(Json::Object(string_data)) = obj.remove("stringData") else {
+        return;
+    };
+    let data = obj
+        .entry("data")
+        .or_insert_with(|| Json::Object(Map::new()));
+    if !data.is_object() {
+        *data = Json::Object(Map::new());
+    }
+    if let Some(data) = data.as_object_mut() {
+        for (k, v) in string_data {
+            if let Some(text) = v.as_str() {
+                let encoded = base64::engine::general_purpose::STANDARD.encode(text);
+                data.insert(k, Json::String(encoded));
+            }
+        }
+    }
+}
+
+fn canonical_quantities(v: &mut Json) {
+    match v {
+        Json::Object(m) => {
+            for (k, child) in m.iter_mut() {
+                match child {
+                    Json::Object(q) if QUANTITY_MAPS.contains(&k.as_str()) => {
+                        for qv in q.values_mut() {
+                            if let Some(c) = canonical_quantity(qv) {
+                                *qv = Json::String(c);
+                            }
+                        }
+                    }
+                    _ => canonical_quantities(child),
+                }
+            }
+        }
+        Json::Array(items) => items.iter_mut().for_each(canonical_quantities),
+        _ => {}
+    }
+}
+
+/// Canonical spelling of a quantity, as the API server returns it: the
+/// largest unit that keeps the number whole (`1000m` → `1`, `0.5` → `500m`,
+/// `2048Mi` → `2Gi`). Binary suffixes stay binary. `None` when `v` is not a
+/// quantity.
+fn canonical_quantity(v: &Json) -> Option<String> {
+    let text = match v {
+        Json::String(s) => s.trim().to_string(),
+        Json::Number(n) => n.to_string(),
+        _ => return None,
+    };
+    let (nanos, binary) = parse_quantity(&text)?;
+    Some(format_quantity(nanos, binary))
+}
+
+const NANO: i128 = 1_000_000_000;
+
+/// Value in billionths (rounded up, like the API server) and whether a
+/// binary suffix was used.
+fn parse_quantity(s: &str) -> Option<(i128, bool)> {
+    let end = s
+        .find(|c: char| !(c.is_ascii_digit() || c == '.' || c == '+' || c == '-'))
+        .unwrap_or(s.len());
+    let (num, suffix) = s.split_at(end);
+    let (pow2, pow10): (u32, i32) = match suffix {
+        "" => (0, 0),
+        "n" => (0, -9),
+        "u" => (0, -6),
+        "m" => (0, -3),
+        "k" => (0, 3),
+        "M" => (0, 6),
+        "G" => (0, 9),
+        "T" => (0, 12),
+        "P" => (0, 15),
+        "E" => (0, 18),
+        "Ki" => (10, 0),
+        "Mi" => (20, 0),
+        "Gi" => (30, 0),
+        "Ti" => (40, 0),
+        "Pi" => (50, 0),
+        "Ei" => (60, 0),
+        _ => (0, suffix.strip_prefix(['e', 'E'])?.parse().ok()?),
+    };
+    let (neg, num) = match num.strip_prefix('-') {
+        Some(n) => (true, n),
+        None => (false, num.strip_prefix('+').unwrap_or(num)),
+    };
+    let (int, frac) = num.split_once('.').unwrap_or((num, ""));
+    if (int.is_empty() && frac.is_empty())
+        || !int.chars().chain(frac.chars()).all(|c| c.is_ascii_digit())
+    {
+        return None;
+    }
+    let mut n: i128 = format!("{}{}", int, frac).parse().ok()?;
+    n = n.checked_mul(1 << pow2)?;
+    let exp = pow10 + 9 - frac.len() as i32;
+    if exp >= 0 {
+        n = n.checked_mul(10i128.checked_pow(exp as u32)?)?;
+    } else {
+        let d = 10i128.checked_pow(exp.unsigned_abs())?;
+        n = (n + d - 1) / d;
+    }
+    Some((if neg { -n } else { n }, pow2 > 0))
+}
+
+fn format_quantity(nanos: i128, binary: bool) -> String {
+    if nanos == 0 {
+        return "0".to_string();
+    }
+    if binary && nanos % NANO == 0 && nanos.abs() >= 1024 * NANO {
+        let mut v = nanos / NANO;
+        let mut unit = 0;
+        while unit < 6 && v % 1024 == 0 {
+            v /= 1024;
+            unit += 1;
+        }
+        return format!("{}{}", v, ["", "Ki", "Mi", "Gi", "Ti", "Pi", "Ei"][unit]);
+    }
+    for (suffix, pow) in [
+        ("E", 27),
+        ("P", 24),
+        ("T", 21),
+        ("G", 18),
+        ("M", 15),
+        ("k", 12),
+        ("", 9),
+        ("m", 6),
+        ("u", 3),
+    ] {
+        let d = 10i128.pow(pow);
+        if nanos % d == 0 {
+            return format!("{}{}", nanos / d, suffix);
+        }
+    }
+    format!("{}n", nanos)
+}
+
+/// A history entry resolved to its object coordinates.
+#[derive(Clone)]
+struct Tracked {
+    uid: [u8; 16],
+    gvk: GroupVersionKind,
+    gvk_key: String,
+    namespace: Option<String>,
+    name: String,
+    base: Json,
+    ts: i64,
+    rv: String,
+}
+
+fn tracked(uid: [u8; 16], la: &LastApplied) -> Result<Tracked> {
+    let yaml = orka_persist::maybe_decompress(&la.yaml_zstd);
+    // The recorded key knows the namespace even when the YAML omitted it.
+    let ns = la.key.as_ref().and_then(|k| k.namespace.as_deref());
+    let (base, gvk, name, namespace) = crate::parse_yaml_for_target(&yaml, ns)?;
+    Ok(Tracked {
+        uid,
+        gvk_key: crate::gvk_key(&gvk),
+        gvk,
+        namespace,
+        name,
+        base,
+        ts: la.ts,
+        rv: la.rv.clone(),
+    })
+}
+
+/// Newest snapshot of every object in the store applied through `context`.
+/// Unreadable records land in `report.errors`, records of other contexts and
+/// keyless legacy records in `report.other_context` / `report.legacy`.
+fn load_tracked(store: &LogStore, context: &str, report: &mut DriftReport) -> Vec<Tracked> {
+    let mut out = Vec::new();
+    for uid in store.uids() {
+        let la = match store.get_last(uid, Some(1)) {
+            Ok(rows) => match rows.into_iter().next() {
+                Some(la) => la,
+                None => continue,
+            },
+            Err(e) => {
+                report
+                    .errors
+                    .push(format!("{}: {}", Uuid::from_bytes(uid), e));
+                continue;
+            }
+        };
+        match &la.key {
+            Some(key) if key.cluster != context => {
+                report.other_context += 1;
+                continue;
+            }
+            Some(_) => {}
+            None => {
+                report.legacy.push(Uuid::from_bytes(uid).to_string());
+                continue;
+            }
+        }
+        match tracked(uid, &la) {
+            Ok(t) => out.push(t),
+            Err(e) => report
+                .errors
+                .push(format!("{}: {}", Uuid::from_bytes(uid), e)),
+        }
+    }
+    out
+}
+
+fn item_for(t: &Tracked, live: Option<&Json>) -> DriftItem {
+    let (status, changes) = match live {
+        None => (DriftStatus::Missing, Vec::new()),
+        Some(live) => {
+            let changes = drift_changes(&t.base, live);
+            let live_uid = live
+                .pointer("/metadata/uid")
+                .and_then(|u| u.as_str())
+                .and_then(|u| Uuid::parse_str(u).ok());
+            let status = if live_uid.is_some_and(|u| *u.as_bytes() != t.uid) {
+                DriftStatus::Replaced
+            } else if changes.is_empty() {
+                DriftStatus::InSync
+            } else {
+                DriftStatus::Drifted
+            };
+            (status, changes)
+        }
+    };
+    DriftItem {
+        uid: Uuid::from_bytes(t.uid).to_string(),
+        gvk: t.gvk_key.clone(),
+        namespace: t.namespace.clone(),
+        name: t.name.clone(),
+        status,
+        applied_ts: t.ts,
+        applied_rv: t.rv.clone(),
+        changes,
+    }
+}
+
+/// API resources resolved once per kind for a scan.
+struct Resources {
+    client: Client,
+    cache: HashMap<String, (ApiResource, bool)>,
+}
+
+impl Resources {
+    async fn api(&mut self, t: &Tracked) -> Result<Api<DynamicObject>> {
+        if !self.cache.contains_key(&t.gvk_key) {
+            let found = crate::find_api_resource(self.client.clone(), &t.gvk).await?;
+            self.cache.insert(t.gvk_key.clone(), found);
+        }
+        let (ar, namespaced) = &self.cache[&t.gvk_key];
+        Ok(if *namespaced {
+            let ns = t.namespace.as_deref().unwrap_or("default");
+            Api::namespaced_with(self.client.clone(), ns, ar)
+        } else {
+            Api::all_with(self.client.clone(), ar)
+        })
+    }
+
+    async fn check(&mut self, t: &Tracked) -> Result<DriftItem> {
+        let api = self.api(t).await?;
+        let live = match api.get_opt(&t.name).await? {
+            Some(obj) => Some(serde_json::to_value(&obj)?),
+            None => None,
+        };
+        Ok(item_for(t, live.as_ref()))
+    }
+}
+
+/// Compare every object with history in the active context against the
+/// cluster once.
+pub async fn scan_drift(store: &LogStore) -> Result<DriftReport> {
+    let mut report = DriftReport::default();
+    let tracked = load_tracked(store, &orka_kubehub::active_context(), &mut report);
+    let client = orka_kubehub::get_kube_client().await?;
+    let mut resources = Resources {
+        client,
+        cache: HashMap::new(),
+    };
+    for t in &tracked {
+        match resources.check(t).await {
+            Ok(item) => report.push(item),
+            Err(e) => report.errors.push(format!(
+                "{} {}/{}: {}",
+                t.gvk_key,
+                t.namespace.as_deref().unwrap_or("-"),
+                t.name,
+                e
+            )),
+        }
+    }
+    metrics::gauge!("drift_objects", report.drifted as f64);
+    Ok(report)
+}
+
+fn refresh_secs() -> u64 {
+    std::env::var("ORKA_DRIFT_REFRESH_SECS")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(60)
+        .max(1)
+}
+
+/// What a consumer has last been told about an object.
+fn signature(item: &DriftItem) -> String {
+    serde_json::to_string(&(&item.status, &item.changes, &item.applied_rv)).unwrap_or_default()
+}
+
+/// Scan once, then keep watching every kind with history and send an item
+/// whenever an object's drift state changes (including back to in-sync).
+/// The history store is re-read every `ORKA_DRIFT_REFRESH_SECS` (default
+/// 60) so new applies become the new baseline. Runs until `tx` is closed.
+pub async fn watch_drift(tx: mpsc::Sender<DriftItem>) -> Result<()> {
+    let mut by_uid: HashMap<[u8; 16], Tracked> = HashMap::new();
+    let mut sent: HashMap<[u8; 16], String> = HashMap::new();
+    let mut watched: HashMap<String, tokio::task::JoinHandle<()>> = HashMap::new();
+    let (dtx, mut drx) = mpsc::channel::<orka_core::Delta>(1024);
+
+    let store = LogStore::open_default()?;
+    let initial = scan_drift(&store).await?;
+    for e in &initial.errors {
+        warn!(error = %e, "drift: snapshot skipped");
+    }
+    if !initial.legacy.is_empty() {
+        warn!(
+            count = initial.legacy.len(),
+            "drift: snapshots without object key skipped"
+        );
+    }
+    for item in initial.items {
+        if let Ok(u) = Uuid::parse_str(&item.uid) {
+            sent.insert(*u.as_bytes(), signature(&item));
+        }
+        if tx.send(item).await.is_err() {
+            return Ok(());
+        }
+    }
+
+    let mut refresh = tokio::time::interval(std::time::Duration::from_secs(refresh_secs()));
+    let result = loop {
+        tokio::select! {
+            _ = refresh.tick() => {
+                if let Err(e) = store.refresh() {
+                    warn!(error = %e, "drift: history refresh failed");
+                    continue;
+                }
+                let mut skipped = DriftReport::default();
+                let tracked = load_tracked(&store, &orka_kubehub::active_context(), &mut skipped);
+                for e in &skipped.errors {
+                    debug!(error = %e, "drift: snapshot skipped");
+                }
+                by_uid = tracked.into_iter().map(|t| (t.uid, t)).collect();
+                for t in by_uid.values() {
+                    if watched.contains_key(&t.gvk_key) {
+                        continue;
+                    }
+                    let (key, dtx) = (t.gvk_key.clone(), dtx.clone());
+                    debug!(gvk = %key, "drift: watching");
+                    let task = tokio::spawn(async move {
+                        if let Err(e) = orka_kubehub::start_watcher(&key, None, dtx).await {
+                            warn!(gvk = %key, error = %e, "drift: watcher ended");
+                        }
+                    });
+                    watched.insert(t.gvk_key.clone(), task);
+                }
+            }
+            delta = drx.recv() => {
+                let Some(delta) = delta else {
+                    break Err(anyhow!("drift watchers stopped"));
+                };
+                let Some(t) = by_uid.get(&delta.uid) else {
+                    continue;
+                };
+                let item = match delta.kind {
+                    orka_core::DeltaKind::Applied => item_for(t, Some(&delta.raw)),
+                    orka_core::DeltaKind::Deleted => item_for(t, None),
+                };
+                let sig = signature(&item);
+                let first_in_sync = item.status == DriftStatus::InSync && !sent.contains_key(&t.uid);
+                if first_in_sync || sent.get(&t.uid) == Some(&sig) {
+                    continue;
+                }
+                sent.insert(t.uid, sig);
+                if tx.send(item).await.is_err() {
+                    break Ok(());
+                }
+            }
+        }
+    };
+    for (_, task) in watched {
+        task.abort();
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use orka_persist::ObjectKey;
+    use serde_json::json;
+
+    fn config_map(name: &str, mode: &str) -> Json {
+        json!({
+            "apiVersion": "v1",
+            "kind": "ConfigMap",
+            "metadata": {"name": name, "namespace": "shop"},
+            "data": {"mode": mode},
+        })
+    }
+
+    fn record(store: &LogStore, uid: [u8; 16], cluster: Option<&str>, doc: &Json) {
+        let name = doc["metadata"]["name"].as_str().unwrap();
+        store
+            .put_last(LastApplied {
+                uid,
+                rv: "1".into(),
+                ts: 1,
+                yaml_zstd: orka_persist::maybe_compress(&serde_yaml::to_string(doc).unwrap()),
+                key: cluster.map(|c| ObjectKey {
+                    cluster: c.into(),
+                    gvk: "v1/ConfigMap".into(),
+                    namespace: Some("shop".into()),
+                    name: name.into(),
+                }),
+            })
+            .unwrap();
+    }
+
+    fn live_uid(obj: &Json) -> [u8; 16] {
+        *Uuid::parse_str(obj["metadata"]["uid"].as_str().unwrap())
+            .unwrap()
+            .as_bytes()
+    }
+
+    #[tokio::test]
+    async fn scan_drift_classifies_objects_of_the_active_context() {
+        use orka_kubehub::fake::FakeApiServer;
+        let srv = FakeApiServer::new();
+        let _guard = srv.install();
+        let ctx = orka_kubehub::active_context();
+        let dir = crate::testutil::temp_dir("drift");
+        let store = LogStore::open(dir.to_str().unwrap()).unwrap();
+
+        let same = srv.create(config_map("same", "a"));
+        record(
+            &store,
+            live_uid(&same),
+            Some(ctx.as_str()),
+            &config_map("same", "a"),
+        );
+        let edited = srv.create(config_map("edited", "hotfix"));
+        record(
+            &store,
+            live_uid(&edited),
+            Some(ctx.as_str()),
+            &config_map("edited", "a"),
+        );
+        record(
+            &store,
+            [1u8; 16],
+            Some(ctx.as_str()),
+            &config_map("gone", "a"),
+        );
+        srv.create(config_map("again", "a"));
+        record(
+            &store,
+            [2u8; 16],
+            Some(ctx.as_str()),
+            &config_map("again", "a"),
+        );
+        // Would be `missing` here; it belongs to another cluster.
+        record(
+            &store,
+            [3u8; 16],
+            Some("elsewhere"),
+            &config_map("far", "a"),
+        );
+        record(&store, [4u8; 16], None, &config_map("old", "a"));
+
+        let report = scan_drift(&store).await.unwrap();
+        assert!(report.errors.is_empty(), "{:?}", report.errors);
+        assert_eq!(
+            (
+                report.scanned,
+                report.in_sync,
+                report.drifted,
+                report.missing,
+                report.replaced
+            ),
+            (4, 1, 1, 1, 1)
+        );
+        assert_eq!(report.other_context, 1);
+        assert_eq!(report.legacy, vec![Uuid::from_bytes([4u8; 16]).to_string()]);
+        let mut got: Vec<_> = report
+            .items
+            .iter()
+            .map(|i| (i.name.as_str(), i.status))
+            .collect();
+        got.sort_by_key(|(name, _)| *name);
+        assert_eq!(
+            got,
+            vec![
+                ("again", DriftStatus::Replaced),
+                ("edited", DriftStatus::Drifted),
+                ("gone", DriftStatus::Missing),
+            ]
+        );
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn ignores_status_defaults_and_foreign_fields() {
+        let last = json!({
+            "apiVersion": "v1",
+            "kind": "ConfigMap",
+            "metadata": {"name": "cfg", "namespace": "shop", "labels": {"app": "shop"}},
+            "data": {"mode": "a", "level": "info"},
+        });
+        let mut live = last.clone();
+        live["metadata"]["uid"] = json!("0b8f5f49-2f5b-4c36-9a2c-6a2ae8c9e3a1");
+        live["metadata"]["resourceVersion"] = json!("42");
+        live["metadata"]["labels"]["added-by"] = json!("controller");
+        live["metadata"]["annotations"] = json!({"note": "x"});
+        assert!(drift_changes(&last, &live).is_empty());
+
+        live["data"]["mode"] = json!("hotfix");
+        live["data"].as_object_mut().unwrap().remove("level");
+        let changes = drift_changes(&last, &live);
+        let got: Vec<_> = changes
+            .iter()
+            .map(|c| (c.path.as_str(), c.base.clone(), c.live.clone()))
+            .collect();
+        assert_eq!(
+            got,
+            vec![
+                (".data.level", Some(json!("info")), None),
+                (".data.mode", Some(json!("a")), Some(json!("hotfix"))),
+            ]
+        );
+    }
+
+    #[test]
+    fn quantities_compare_in_canonical_form() {
+        let pod = |cpu: Json, memory: &str, limit: Json| {
+            json!({
+                "apiVersion": "v1",
+                "kind": "Pod",
+                "metadata": {"name": "web", "namespace": "shop"},
+                "spec": {"containers": [{
+                    "name": "app",
+                    "resources": {
+                        "requests": {"cpu": cpu, "memory": memory},
+                        "limits": {"cpu": limit},
+                    },
+                }]},
+            })
+        };
+        let last = pod(json!("1000m"), "1024Mi", json!(0.5));
+        assert!(drift_changes(&last, &pod(json!("1"), "1Gi", json!("500m"))).is_empty());
+        assert!(drift_changes(
+            &pod(json!(2), "1.5Gi", json!("1.5")),
+            &pod(json!("2"), "1536Mi", json!("1500m"))
+        )
+        .is_empty());
+
+        let changes = drift_changes(&last, &pod(json!("2"), "1Gi", json!("500m")));
+        assert_eq!(changes.len(), 1, "{:?}", changes);
+        assert_eq!(
+            changes[0].path,
+            ".spec.containers[name=app].resources.requests.cpu"
+        );
+        assert_eq!(
+            (changes[0].base.clone(), changes[0].live.clone()),
+            (Some(json!("1")), Some(json!("2")))
+        );
+
+        let canon = |q: &str| canonical_quantity(&json!(q)).unwrap();
+        assert_eq!(canon("0.1"), "100m");
+        assert_eq!(canon("2000"), "2k");
+        assert_eq!(canon("1500"), "1500");
+        assert_eq!(canon("0.5Ki"), "512");
+        assert_eq!(canon("1e3"), "1k");
+        assert_eq!(canon("0"), "0");
+        assert_eq!(canon("-250m"), "-250m");
+        assert_eq!(canonical_quantity(&json!("lots")), None);
+    }
+
+    #[test]
+    fn string_data_compares_as_base64_data() {
+        let last = json!({
+            "apiVersion": "v1",
+            "kind": "Secret",
+            "metadata": {"name": "db", "namespace": "shop"},
+            "data": {"user": "YWRtaW4="},
+            "stringData": {"password": "hunter2"},
+        });
+        let mut live = json!({
+            "apiVersion": "v1",
+            "kind": "Secret",
+            "metadata": {"name": "db", "namespace": "shop"},
+            "type": "Opaque",
+            "data": {"user": "YWRtaW4=", "password": "aHVudGVyMg=="},
+        });
+        assert!(drift_changes(&last, &live).is_empty());
+
+        live["data"]["password"] = json!("b3RoZXI=");
+        let changes = drift_changes(&last, &live);
+        let got: Vec<_> = changes
+            .iter()
+            .map(|c| (c.path.as_str(), c.base.clone(), c.live.clone()))
+            .collect();
+        assert_eq!(
+            got,
+            vec![(
+                ".data.password",
+                Some(json!("aHVudGVyMg==")),
+                Some(json!("b3RoZXI="))
+            )]
+        );
+    }
+}
@@ -0,0 +1,1790 @@
+//! A Go `text/template` subset, enough for common Helm charts.
+//!
+//! Supported: `{{ }}` actions with `{{-`/`-}}` trimming and `/* */`
+//! comments; pipelines with variables (`$x := ...`, `$x = ...`), field
+//! chains on `.`, `$` and parenthesised pipelines; `if`/`else if`/`else`,
+//! `with`, `range` (lists, maps in key order, integers; `break`/`continue`),
+//! `define`, `template` and `block`. Functions cover Go's builtins plus the
+//! Sprig/Helm ones charts lean on (`include`, `tpl`, `toYaml`, `indent`,
+//! `nindent`, `default`, `required`, `quote`, `printf`, `dict`, `list`, ...).
+//! Data is plain JSON; a missing map key evaluates to nil and prints as an
+//! empty string, as Helm does.
+
+use std::collections::HashMap;
+use std::fmt::Write as _;
+
+use anyhow::{anyhow, bail, Context, Result};
+use base64::Engine as _;
+use serde_json::{Map, Number, Value as Json};
+use sha2::{Digest, Sha256};
+
+const MAX_DEPTH: usize = 200;
+
+/// A set of parsed templates sharing one namespace of `define`d names.
+#[derive(Debug, Default)]
+pub struct Templates {
+    trees: HashMap<String, Vec<Node>>,
+}
+
+impl Templates {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parse `text` as the template `name`; its `define` blocks become
+    /// templates of their own.
+    pub fn add(&mut self, name: &str, text: &str) -> Result<()> {
+        let items = lex(text).with_context(|| name.to_string())?;
+        let mut p = Parser {
+            items,
+            pos: 0,
+            defs: Vec::new(),
+        };
+        let (nodes, term) = p.parse_list().with_context(|| name.to_string())?;
+        if let Some(t) = term {
+            bail!("{}:{}: unexpected {{{{{}}}}}", name, t.line, t.keyword);
+        }
+        for (def, body) in p.defs {
+            self.trees.insert(def, body);
+        }
+        self.trees.insert(name.to_string(), nodes);
+        Ok(())
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.trees.contains_key(name)
+    }
+
+    /// Execute the template `name` with `data` as both `.` and `$`.
+    pub fn render(&self, name: &str, data: &Json) -> Result<String> {
+        let mut ex = Exec {
+            tpl: self,
+            vars: Vec::new(),
+            depth: 0,
+            name: name.to_string(),
+        };
+        let mut out = String::new();
+        ex.call(name, data, &mut out)?;
+        Ok(out)
+    }
+}
+
+// ---------------- lexing ----------------
+
+#[derive(Debug)]
+enum Item {
+    Text(String),
+    Action { body: String, line: usize },
+}
+
+fn lex(src: &str) -> Result<Vec<Item>> {
+    let mut items = Vec::new();
+    let mut pos = 0;
+    let mut line = 1;
+    let mut trim_next = false;
+    while let Some(off) = src[pos..].find("{{") {
+        let start = pos + off;
+        let mut text = &src[pos..start];
+        if trim_next {
+            text = text.trim_start();
+        }
+        let mut body_start = start + 2;
+        let bytes = src.as_bytes();
+        if bytes.get(body_start) == Some(&b'-')
+            && bytes
+                .get(body_start + 1)
+                .map(|b| b.is_ascii_whitespace())
+                .unwrap_or(false)
+        {
+            text = text.trim_end();
+            body_start += 1;
+        }
+        if !text.is_empty() {
+            items.push(Item::Text(text.to_string()));
+        }
+        line += src[pos..start].matches('\n').count();
+        let end = find_action_end(src, body_start)
+            .ok_or_else(|| anyhow!("line {}: unclosed action", line))?;
+        let mut body = &src[body_start..end];
+        trim_next = false;
+        if body.ends_with('-')
+            && body[..body.len() - 1]
+                .chars()
+                .last()
+                .map(|c| c.is_whitespace())
+                .unwrap_or(false)
+        {
+            body = &body[..body.len() - 1];
+            trim_next = true;
+        }
+        let trimmed = body.trim();
+        if !(trimmed.starts_with("/*") && trimmed.ends_with("*/")) {
+            items.push(Item::Action {
+                body: trimmed.to_string(),
+                line,
+            });
+        }
+        line += src[start..end + 2].matches('\n').count();
+        pos = end + 2;
+    }
+    let mut text = &src[pos..];
+    if trim_next {
+        text = text.trim_start();
+    }
+    if !text.is_empty() {
+        items.push(Item::Text(text.to_string()));
+    }
+    Ok(items)
+}
+
+/// Offset of the `}}` closing the action starting at `from`, skipping string
+/// literals and comments.
+fn find_action_end(src: &str, from: usize) -> Option<usize> {
+    let b = src.as_bytes();
+    let mut i = from;
+    while i + 1 < b.len() {
+        match b[i] {
+            b'"' | b'\'' => {
+                let q = b[i];
+                i += 1;
+                while i < b.len() && b[i] != q {
+                    if b[i] == b'\\' {
+                        i += 1;
+                    }
+                    i += 1;
+                }
+            }
+            b'`' => {
+                i += 1;
+                while i < b.len() && b[i] != b'`' {
+                    i += 1;
+                }
+            }
+            b'/' if b[i + 1] == b'*' => {
+                i += src[i..].find("*/")? + 1;
+            }
+            b'}' if b[i + 1] == b'}' => return Some(i),
+            _ => {}
+        }
+        i += 1;
+    }
+    None
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Tok {
+    LParen,
+    RParen,
+    Pipe,
+    Declare,
+    Assign,
+    Comma,
+    Lit(Json),
+    Dot,
+    /// `.Name`; `attached` when it directly follows the previous token.
+    Field(String, bool),
+    Var(String),
+    Ident(String),
+}
+
+fn tokenize(s: &str) -> Result<Vec<Tok>> {
+    let c: Vec<char> = s.chars().collect();
+    let mut out = Vec::new();
+    let mut i = 0;
+    let ident = |ch: char| ch.is_alphanumeric() || ch == '_';
+    while i < c.len() {
+        let ch = c[i];
+        let attached = i > 0 && !c[i - 1].is_whitespace();
+        match ch {
+            _ if ch.is_whitespace() => i += 1,
+            '(' => {
+                out.push(Tok::LParen);
+                i += 1;
+            }
+            ')' => {
+                out.push(Tok::RParen);
+                i += 1;
+            }
+            '|' => {
+                out.push(Tok::Pipe);
+                i += 1;
+            }
+            ',' => {
+                out.push(Tok::Comma);
+                i += 1;
+            }
+            ':' if c.get(i + 1) == Some(&'=') => {
+                out.push(Tok::Declare);
+                i += 2;
+            }
+            '=' => {
+                out.push(Tok::Assign);
+                i += 1;
+            }
+            '"' => {
+                let mut v = String::new();
+                i += 1;
+                while i < c.len() && c[i] != '"' {
+                    if c[i] == '\\' && i + 1 < c.len() {
+                        i += 1;
+                        v.push(match c[i] {
+                            'n' => '\n',
+                            't' => '\t',
+                            'r' => '\r',
+                            '0' => '\0',
+                            other => other,
+                        });
+                    } else {
+                        v.push(c[i]);
+                    }
+                    i += 1;
+                }
+                if i >= c.len() {
+                    bail!("unterminated string");
+                }
+                out.push(Tok::Lit(Json::String(v)));
+                i += 1;
+            }
+            '`' => {
+                let start = i + 1;
+                i = start;
+                while i < c.len() && c[i] != '`' {
+                    i += 1;
+                }
+                if i >= c.len() {
+                    bail!("unterminated raw string");
+                }
+                out.push(Tok::Lit(Json::String(c[start..i].iter().collect())));
+                i += 1;
+            }
+            '\'' => {
+                // Character constants are integers in Go.
+                let v = c.get(i + 1).copied().unwrap_or('\0');
+                out.push(Tok::Lit(Json::from(v as u32)));
+                i += 3;
+            }
+            '.' => {
+                let start = i + 1;
+                i = start;
+                while i < c.len() && ident(c[i]) {
+                    i += 1;
+                }
+                if i == start {
+                    if c.get(i).map(|d| d.is_ascii_digit()).unwrap_or(false) {
+                        bail!("unexpected number");
+                    }
+                    out.push(Tok::Dot);
+                } else {
+                    let name: String = c[start..i].iter().collect();
+                    let attached = attached
+                        && !matches!(
+                            out.last(),
+                            Some(Tok::LParen | Tok::Pipe | Tok::Declare | Tok::Assign | Tok::Comma)
+                        );
+                    out.push(Tok::Field(name, attached));
+                }
+            }
+            '$' => {
+                let start = i;
+                i += 1;
+                while i < c.len() && ident(c[i]) {
+                    i += 1;
+                }
+                out.push(Tok::Var(c[start..i].iter().collect()));
+            }
+            _ if ch.is_ascii_digit()
+                || ((ch == '-' || ch == '+')
+                    && c.get(i + 1).map(|d| d.is_ascii_digit()).unwrap_or(false)) =>
+            {
+                let start = i;
+                i += 1;
+                while i < c.len() && (c[i].is_ascii_alphanumeric() || c[i] == '.' || c[i] == '_') {
+                    i += 1;
+                }
+                let text: String = c[start..i].iter().filter(|d| **d != '_').collect();
+                out.push(Tok::Lit(parse_number(&text)?));
+            }
+            _ if ident(ch) => {
+                let start = i;
+                while i < c.len() && ident(c[i]) {
+                    i += 1;
+                }
+                let word: String = c[start..i].iter().collect();
+                out.push(match word.as_str() {
+                    "true" => Tok::Lit(Json::Bool(true)),
+                    "false" => Tok::Lit(Json::Bool(false)),
+                    "nil" => Tok::Lit(Json::Null),
+                    _ => Tok::Ident(word),
+                });
+            }
+            other => bail!("unexpected {:?}", other),
+        }
+    }
+    Ok(out)
+}
+
+fn parse_number(text: &str) -> Result<Json> {
+    let (neg, digits) = match text.strip_prefix('-') {
+        Some(d) => (true, d),
+        None => (false, text.trim_start_matches('+')),
+    };
+    let int = if let Some(h) = digits.strip_prefix("0x").or(digits.strip_prefix("0X")) {
+        i64::from_str_radix(h, 16).ok()
+    } else if let Some(o) = digits.strip_prefix("0o") {
+        i64::from_str_radix(o, 8).ok()
+    } else {
+        digits.parse::<i64>().ok()
+    };
+    if let Some(n) = int {
+        return Ok(Json::from(if neg { -n } else { n }));
+    }
+    let f: f64 = text
+        .parse()
+        .map_err(|_| anyhow!("bad number syntax: {:?}", text))?;
+    Number::from_f64(f)
+        .map(Json::Number)
+        .ok_or_else(|| anyhow!("bad number syntax: {:?}", text))
+}
+
+// ---------------- parsing ----------------
+
+#[derive(Debug, Clone)]
+enum Operand {
+    Dot,
+    /// Field chain on `.`.
+    Field(Vec<String>),
+    Var(String, Vec<String>),
+    Lit(Json),
+    Func(String),
+    Sub(Box<Pipe>, Vec<String>),
+}
+
+#[derive(Debug, Clone, Default)]
+struct Pipe {
+    decl: Vec<String>,
+    /// `=` rather than `:=`.
+    assign: bool,
+    cmds: Vec<Vec<Operand>>,
+}
+
+#[derive(Debug)]
+enum Node {
+    Text(String),
+    Output {
+        pipe: Pipe,
+        line: usize,
+    },
+    If {
+        branches: Vec<(Pipe, Vec<Node>)>,
+        otherwise: Vec<Node>,
+        line: usize,
+    },
+    With {
+        branches: Vec<(Pipe, Vec<Node>)>,
+        otherwise: Vec<Node>,
+        line: usize,
+    },
+    Range {
+        pipe: Pipe,
+        body: Vec<Node>,
+        otherwise: Vec<Node>,
+        line: usize,
+    },
+    Template {
+        name: String,
+        pipe: Option<Pipe>,
+        line: usize,
+    },
+    Break,
+    Continue,
+}
+
+struct Terminator {
+    keyword: String,
+    rest: String,
+    line: usize,
+}
+
+struct Parser {
+    items: Vec<Item>,
+    pos: usize,
+    defs: Vec<(String, Vec<Node>)>,
+}
+
+fn split_keyword(body: &str) -> (&str, &str) {
+    let end = body
+        .find(|c: char| c.is_whitespace() || c == '(')
+        .unwrap_or(body.len());
+    let word = &body[..end];
+    match word {
+        "if" | "else" | "end" | "range" | "with" | "define" | "template" | "block" | "break"
+        | "continue" => (word, body[end..].trim()),
+        _ => ("", body),
+    }
+}
+
+impl Parser {
+    /// Parse nodes until `{{end}}`/`{{else ...}}` (returned) or end of input.
+    fn parse_list(&mut self) -> Result<(Vec<Node>, Option<Terminator>)> {
+        let mut nodes = Vec::new();
+        while self.pos < self.items.len() {
+            let (body, line) = match &self.items[self.pos] {
+                Item::Text(t) => {
+                    nodes.push(Node::Text(t.clone()));
+                    self.pos += 1;
+                    continue;
+                }
+                Item::Action { body, line } => (body.clone(), *line),
+            };
+            self.pos += 1;
+            let at = |e: anyhow::Error| e.context(format!("line {}", line));
+            let (kw, rest) = split_keyword(&body);
+            match kw {
+                "end" | "else" => {
+                    return Ok((
+                        nodes,
+                        Some(Terminator {
+                            keyword: kw.to_string(),
+                            rest: rest.to_string(),
+                            line,
+                        }),
+                    ))
+                }
+                "if" | "with" => {
+                    let mut branches = vec![(parse_pipe(rest).map_err(at)?, Vec::new())];
+                    let otherwise = loop {
+                        let (body, term) = self.parse_list()?;
+                        branches.last_mut().unwrap().1 = body;
+                        let term =
+                            term.ok_or_else(|| anyhow!("line {}: missing {{{{end}}}}", line))?;
+                        if term.keyword == "end" {
+                            break Vec::new();
+                        }
+                        let (kw2, rest2) = split_keyword(&term.rest);
+                        if kw2 == kw {
+                            branches.push((parse_pipe(rest2).map_err(at)?, Vec::new()));
+                            continue;
+                        }
+                        let (body, term) = self.parse_list()?;
+                        match term {
+                            Some(t) if t.keyword == "end" => break body,
+                            _ => bail!("line {}: expected {{{{end}}}} after {{{{else}}}}", line),
+                        }
+                    };
+                    nodes.push(if kw == "if" {
+                        Node::If {
+                            branches,
+                            otherwise,
+                            line,
+                        }
+                    } else {
+                        Node::With {
+                            branches,
+                            otherwise,
+                            line,
+                        }
+                    });
+                }
+                "range" => {
+                    let pipe = parse_pipe(rest).map_err(at)?;
+                    let (body, term) = self.parse_list()?;
+                    let term = term.ok_or_else(|| anyhow!("line {}: missing {{{{end}}}}", line))?;
+                    let otherwise = if term.keyword == "else" {
+                        match self.parse_list()? {
+                            (b, Some(t)) if t.keyword == "end" => b,
+                            _ => bail!("line {}: expected {{{{end}}}} after {{{{else}}}}", line),
+                        }
+                    } else {
+                        Vec::new()
+                    };
+                    nodes.push(Node::Range {
+                        pipe,
+                        body,
+                        otherwise,
+                        line,
+                    });
+                }
+                "define" | "block" => {
+                    let (name, pipe_src) = parse_template_name(rest).map_err(at)?;
+                    let (body, term) = self.parse_list()?;
+                    match term {
+                        Some(t) if t.keyword == "end" => {}
+                        _ => bail!("line {}: missing {{{{end}}}} for {} {:?}", line, kw, name),
+                    }
+                    self.defs.push((name.clone(), body));
+                    if kw == "block" {
+                        nodes.push(Node::Template {
+                            name,
+                            pipe: Some(parse_pipe(pipe_src).map_err(at)?),
+                            line,
+                        });
+                    }
+                }
+                "template" => {
+                    let (name, pipe_src) = parse_template_name(rest).map_err(at)?;
+                    let pipe = if pipe_src.trim().is_empty() {
+                        None
+                    } else {
+                        Some(parse_pipe(pipe_src).map_err(at)?)
+                    };
+                    nodes.push(Node::Template { name, pipe, line });
+                }
+                "break" => nodes.push(Node::Break),
+                "continue" => nodes.push(Node::Continue),
+                _ => nodes.push(Node::Output {
+                    pipe: parse_pipe(rest).map_err(at)?,
+                    line,
+                }),
+            }
+        }
+        Ok((nodes, None))
+    }
+}
+
+fn parse_template_name(rest: &str) -> Result<(String, &str)> {
+    let toks = tokenize(rest)?;
+    match toks.first() {
+        Some(Tok::Lit(Json::String(name))) => {
+            // Everything after the closing quote is the pipeline.
+            let q = rest.trim_start().chars().next().unwrap_or('"');
+            let after = rest.trim_start()[1..]
+                .find(q)
+                .map(|i| i + 2)
+                .unwrap_or(rest.len());
+            Ok((name.clone(), &rest.trim_start()[after..]))
+        }
+        _ => bail!("template name must be a string literal"),
+    }
+}
+
+fn parse_pipe(src: &str) -> Result<Pipe> {
+    let toks = tokenize(src)?;
+    let mut pos = 0;
+    let pipe = parse_pipe_toks(&toks, &mut pos)?;
+    if pos != toks.len() {
+        bail!("unexpected {:?} in {:?}", toks[pos], src);
+    }
+    Ok(pipe)
+}
+
+fn parse_pipe_toks(toks: &[Tok], pos: &mut usize) -> Result<Pipe> {
+    let mut pipe = Pipe::default();
+    // Variable declarations: `$x :=`, `$k, $v :=`, `$x =`.
+    let mut look = *pos;
+    let mut decl = Vec::new();
+    while let Some(Tok::Var(v)) = toks.get(look) {
+        decl.push(v.clone());
+        look += 1;
+        match toks.get(look) {
+            Some(Tok::Comma) => look += 1,
+            Some(Tok::Declare) | Some(Tok::Assign) => break,
+            _ => {
+                decl.clear();
+                break;
+            }
+        }
+    }
+    if !decl.is_empty() {
+        if let Some(t @ (Tok::Declare | Tok::Assign)) = toks.get(look) {
+            pipe.assign = *t == Tok::Assign;
+            pipe.decl = decl;
+            *pos = look + 1;
+        }
+    }
+    let mut cmd = Vec::new();
+    while let Some(t) = toks.get(*pos) {
+        match t {
+            Tok::RParen => break,
+            Tok::Pipe => {
+                if cmd.is_empty() {
+                    bail!("missing command before |");
+                }
+                pipe.cmds.push(std::mem::take(&mut cmd));
+                *pos += 1;
+            }
+            _ => cmd.push(parse_operand(toks, pos)?),
+        }
+    }
+    if cmd.is_empty() {
+        bail!("missing value for command");
+    }
+    pipe.cmds.push(cmd);
+    Ok(pipe)
+}
+
+fn parse_operand(toks: &[Tok], pos: &mut usize) -> Result<Operand> {
+    let tok = toks[*pos].clone();
+    *pos += 1;
+    let mut op = match tok {
+        Tok::Dot => Operand::Dot,
+        Tok::Field(name, _) => Operand::Field(vec![name]),
+        Tok::Var(v) => Operand::Var(v, Vec::new()),
+        Tok::Lit(v) => Operand::Lit(v),
+        Tok::Ident(f) => return Ok(Operand::Func(f)),
+        Tok::LParen => {
+            let inner = parse_pipe_toks(toks, pos)?;
+            if toks.get(*pos) != Some(&Tok::RParen) {
+                bail!("unclosed left paren");
+            }
+            *pos += 1;
+            Operand::Sub(Box::new(inner), Vec::new())
+        }
+        other => bail!("unexpected {:?}", other),
+    };
+    while let Some(Tok::Field(name, true)) = toks.get(*pos) {
+        match &mut op {
+            Operand::Field(chain) | Operand::Var(_, chain) | Operand::Sub(_, chain) => {
+                chain.push(name.clone())
+            }
+            _ => bail!("can't evaluate field {} of a literal", name),
+        }
+        *pos += 1;
+    }
+    Ok(op)
+}
+
+// ---------------- execution ----------------
+
+enum Flow {
+    Normal,
+    Break,
+    Continue,
+}
+
+struct Exec<'a> {
+    tpl: &'a Templates,
+    vars: Vec<(String, Json)>,
+    depth: usize,
+    name: String,
+}
+
+impl Exec<'_> {
+    fn call(&mut self, name: &str, data: &Json, out: &mut String) -> Result<()> {
+        let tpl = self.tpl;
+        let tree = tpl
+            .trees
+            .get(name)
+            .ok_or_else(|| anyhow!("no template {:?}", name))?;
+        if self.depth >= MAX_DEPTH {
+            bail!("template {:?}: nesting too deep", name);
+        }
+        self.depth += 1;
+        let saved_vars = std::mem::replace(&mut self.vars, vec![("$".into(), data.clone())]);
+        let saved_name = std::mem::replace(&mut self.name, name.to_string());
+        let res = self.exec_list(tree, data, out);
+        self.vars = saved_vars;
+        self.name = saved_name;
+        self.depth -= 1;
+        res.map(|_| ())
+    }
+
+    fn at(&self, line: usize) -> String {
+        format!("{}:{}", self.name, line)
+    }
+
+    fn exec_list(&mut self, nodes: &[Node], dot: &Json, out: &mut String) -> Result<Flow> {
+        let mark = self.vars.len();
+        let res = self.exec_nodes(nodes, dot, out);
+        self.vars.truncate(mark);
+        res
+    }
+
+    fn exec_nodes(&mut self, nodes: &[Node], dot: &Json, out: &mut String) -> Result<Flow> {
+        for node in nodes {
+            match node {
+                Node::Text(t) => out.push_str(t),
+                Node::Output { pipe, line } => {
+                    let v = self.eval_pipe(pipe, dot).with_context(|| self.at(*line))?;
+                    if pipe.decl.is_empty() {
+                        out.push_str(&to_text(&v));
+                    }
+                }
+                Node::If {
+                    branches,
+                    otherwise,
+                    line,
+                }
+                | Node::With {
+                    branches,
+                    otherwise,
+                    line,
+                } => {
+                    let with = matches!(node, Node::With { .. });
+                    let mark = self.vars.len();
+                    let mut taken = None;
+                    for (cond, body) in branches {
+                        let v = self.eval_pipe(cond, dot).with_context(|| self.at(*line))?;
+                        if truthy(&v) {
+                            taken = Some((body, v));
+                            break;
+                        }
+                    }
+                    let flow = match taken {
+                        Some((body, v)) if with => self.exec_list(body, &v, out)?,
+                        Some((body, _)) => self.exec_list(body, dot, out)?,
+                        None => self.exec_list(otherwise, dot, out)?,
+                    };
+                    self.vars.truncate(mark);
+                    if !matches!(flow, Flow::Normal) {
+                        return Ok(flow);
+                    }
+                }
+                Node::Range {
+                    pipe,
+                    body,
+                    otherwise,
+                    line,
+                } => {
+                    let mut bare = pipe.clone();
+                    bare.decl.clear();
+                    let v = self.eval_pipe(&bare, dot).with_context(|| self.at(*line))?;
+                    let items: Vec<(Json, Json)> = match &v {
+                        Json::Array(a) => a
+                            .iter()
+                            .enumerate()
+                            .map(|(i, e)| (Json::from(i), e.clone()))
+                            .collect(),
+                        Json::Object(m) => {
+                            let mut keys: Vec<&String> = m.keys().collect();
+                            keys.sort();
+                            keys.into_iter()
+                                .map(|k| (Json::String(k.clone()), m[k].clone()))
+                                .collect()
+                        }
+                        Json::Number(n) if n.as_i64().is_some() => (0..n.as_i64().unwrap())
+                            .map(|i| (Json::from(i), Json::from(i)))
+                            .collect(),
+                        Json::Null => Vec::new(),
+                        other => bail!("{}: range can't iterate over {}", self.at(*line), other),
+                    };
+                    if items.is_empty() {
+                        self.exec_list(otherwise, dot, out)?;
+                        continue;
+                    }
+                    for (k, e) in items {
+                        let mark = self.vars.len();
+                        match pipe.decl.as_slice() {
+                            [] => {}
+                            [v] => self.vars.push((v.clone(), e.clone())),
+                            [kv, vv, ..] => {
+                                self.vars.push((kv.clone(), k));
+                                self.vars.push((vv.clone(), e.clone()));
+                            }
+                        }
+                        let flow = self.exec_list(body, &e, out)?;
+                        self.vars.truncate(mark);
+                        if matches!(flow, Flow::Break) {
+                            break;
+                        }
+                    }
+                }
+                Node::Template { name, pipe, line } => {
+                    let data = match pipe {
+                        Some(p) => self.eval_pipe(p, dot).with_context(|| self.at(*line))?,
+                        None => Json::Null,
+                    };
+                    self.call(name, &data, out)
+                        .with_context(|| self.at(*line))?;
+                }
+                Node::Break => return Ok(Flow::Break),
+                Node::Continue => return Ok(Flow::Continue),
+            }
+        }
+        Ok(Flow::Normal)
+    }
+
+    fn lookup_var(&self, name: &str) -> Result<Json> {
+        self.vars
+            .iter()
+            .rev()
+            .find(|(n, _)| n == name)
+            .map(|(_, v)| v.clone())
+            .ok_or_else(|| anyhow!("undefined variable {}", name))
+    }
+
+    fn eval_pipe(&mut self, pipe: &Pipe, dot: &Json) -> Result<Json> {
+        let mut val: Option<Json> = None;
+        for cmd in &pipe.cmds {
+            val = Some(self.eval_cmd(cmd, dot, val)?);
+        }
+        let val = val.unwrap_or(Json::Null);
+        if let Some(name) = pipe.decl.first() {
+            if pipe.assign {
+                let slot = self
+                    .vars
+                    .iter_mut()
+                    .rev()
+                    .find(|(n, _)| n == name)
+                    .ok_or_else(|| anyhow!("undefined variable {}", name))?;
+                slot.1 = val.clone();
+            } else {
+                self.vars.push((name.clone(), val.clone()));
+            }
+        }
+        Ok(val)
+    }
+
+    fn eval_cmd(&mut self, cmd: &[Operand], dot: &Json, piped: Option<Json>) -> Result<Json> {
+        if let Operand::Func(name) = &cmd[0] {
+            return self.call_func(name, &cmd[1..], dot, piped);
+        }
+        if cmd.len() > 1 || piped.is_some() {
+            // Method calls on Helm's built-in objects: `.Capabilities.APIVersions.Has "v1"`
+            // and `.Files.Get "path"`.
+            if let Some((base, method)) = split_method(&cmd[0]) {
+                let recv = self.eval_operand(&base, dot)?;
+                let mut args = cmd[1..]
+                    .iter()
+                    .map(|a| self.eval_operand(a, dot))
+                    .collect::<Result<Vec<_>>>()?;
+                args.extend(piped);
+                return match (method.as_str(), &recv, args.as_slice()) {
+                    ("Has", Json::Array(a), [v]) => Ok(Json::Bool(a.contains(v))),
+                    ("Get", Json::Object(m), [Json::String(k)]) => {
+                        Ok(m.get(k).cloned().unwrap_or(Json::String(String::new())))
+                    }
+                    _ => bail!("can't call method {} here", method),
+                };
+            }
+            bail!("can't give argument to non-function");
+        }
+        self.eval_operand(&cmd[0], dot)
+    }
+
+    fn eval_operand(&mut self, op: &Operand, dot: &Json) -> Result<Json> {
+        Ok(match op {
+            Operand::Dot => dot.clone(),
+            Operand::Field(chain) => walk_fields(dot, chain),
+            Operand::Var(v, chain) => walk_fields(&self.lookup_var(v)?, chain),
+            Operand::Lit(v) => v.clone(),
+            Operand::Func(name) => self.call_func(name, &[], dot, None)?,
+            Operand::Sub(pipe, chain) => {
+                let v = self.eval_pipe(pipe, dot)?;
+                walk_fields(&v, chain)
+            }
+        })
+    }
+
+    fn call_func(
+        &mut self,
+        name: &str,
+        arg_ops: &[Operand],
+        dot: &Json,
+        piped: Option<Json>,
+    ) -> Result<Json> {
+        // Short-circuiting boolean operators return the deciding operand.
+        if name == "and" || name == "or" {
+            let mut last = Json::Null;
+            for op in arg_ops {
+                last = self.eval_operand(op, dot)?;
+                if truthy(&last) != (name == "and") {
+                    return Ok(last);
+                }
+            }
+            if let Some(p) = piped {
+                last = p;
+            }
+            return Ok(last);
+        }
+        let mut args = arg_ops
+            .iter()
+            .map(|a| self.eval_operand(a, dot))
+            .collect::<Result<Vec<_>>>()?;
+        args.extend(piped);
+        match name {
+            "include" => {
+                let (tname, data) = match args.as_slice() {
+                    [Json::String(n)] => (n.clone(), Json::Null),
+                    [Json::String(n), d] => (n.clone(), d.clone()),
+                    _ => bail!("include expects a template name and data"),
+                };
+                let mut out = String::new();
+                self.call(&tname, &data, &mut out)?;
+                Ok(Json::String(out))
+            }
+            "tpl" => {
+                let (text, data) = match args.as_slice() {
+                    [t, d] => (to_text(t), d.clone()),
+                    _ => bail!("tpl expects a template string and data"),
+                };
+                let items = lex(&text)?;
+                let mut p = Parser {
+                    items,
+                    pos: 0,
+                    defs: Vec::new(),
+                };
+                let (nodes, _) = p.parse_list()?;
+                let saved = std::mem::replace(&mut self.vars, vec![("$".into(), data.clone())]);
+                let mut out = String::new();
+                let res = self.exec_list(&nodes, &data, &mut out);
+                self.vars = saved;
+                res?;
+                Ok(Json::String(out))
+            }
+            _ => builtin(name, args),
+        }
+    }
+}
+
+fn split_method(op: &Operand) -> Option<(Operand, String)> {
+    match op {
+        Operand::Field(chain) if chain.len() > 1 => {
+            let (last, base) = chain.split_last()?;
+            Some((Operand::Field(base.to_vec()), last.clone()))
+        }
+        Operand::Var(v, chain) if !chain.is_empty() => {
+            let (last, base) = chain.split_last()?;
+            Some((Operand::Var(v.clone(), base.to_vec()), last.clone()))
+        }
+        _ => None,
+    }
+}
+
+fn walk_fields(v: &Json, chain: &[String]) -> Json {
+    let mut cur = v;
+    for f in chain {
+        match cur.get(f) {
+            Some(next) => cur = next,
+            None => return Json::Null,
+        }
+    }
+    cur.clone()
+}
+
+// ---------------- values ----------------
+
+pub fn truthy(v: &Json) -> bool {
+    match v {
+        Json::Null => false,
+        Json::Bool(b) => *b,
+        Json::Number(n) => n.as_f64().map(|f| f != 0.0).unwrap_or(true),
+        Json::String(s) => !s.is_empty(),
+        Json::Array(a) => !a.is_empty(),
+        Json::Object(m) => !m.is_empty(),
+    }
+}
+
+fn fmt_number(n: &Number) -> String {
+    match (n.as_i64(), n.as_u64(), n.as_f64()) {
+        (Some(i), _, _) => i.to_string(),
+        (None, Some(u), _) => u.to_string(),
+        (None, None, Some(f)) if f.fract() == 0.0 && f.abs() < 1e21 => format!("{}", f as i64),
+        (None, None, Some(f)) => f.to_string(),
+        _ => n.to_string(),
+    }
+}
+
+/// Go's `%v` rendering; nil prints as the empty string (Helm strips
+/// `<no value>`).
+pub fn to_text(v: &Json) -> String {
+    match v {
+        Json::Null => String::new(),
+        Json::Bool(b) => b.to_string(),
+        Json::Number(n) => fmt_number(n),
+        Json::String(s) => s.clone(),
+        Json::Array(a) => format!("[{}]", a.iter().map(go_value).collect::<Vec<_>>().join(" ")),
+        Json::Object(m) => {
+            let mut keys: Vec<&String> = m.keys().collect();
+            keys.sort();
+            let parts: Vec<String> = keys
+                .into_iter()
+                .map(|k| format!("{}:{}", k, go_value(&m[k])))
+                .collect();
+            format!("map[{}]", parts.join(" "))
+        }
+    }
+}
+
+fn go_value(v: &Json) -> String {
+    match v {
+        Json::Null => "<nil>".into(),
+        other => to_text(other),
+    }
+}
+
+fn as_f64(v: &Json) -> Option<f64> {
+    match v {
+        Json::Number(n) => n.as_f64(),
+        Json::String(s) => s.trim().parse().ok(),
+        Json::Bool(b) => Some(if *b { 1.0 } else { 0.0 }),
+        Json::Null => Some(0.0),
+        _ => None,
+    }
+}
+
+fn as_i64(v: &Json) -> Result<i64> {
+    match v {
+        Json::Number(n) => Ok(n
+            .as_i64()
+            .unwrap_or_else(|| n.as_f64().unwrap_or(0.0) as i64)),
+        other => as_f64(other)
+            .map(|f| f as i64)
+            .ok_or_else(|| anyhow!("expected a number, got {}", other)),
+    }
+}
+
+fn num(f: f64) -> Json {
+    if f.fract() == 0.0 && f.abs() < 9.0e15 {
+        Json::from(f as i64)
+    } else {
+        Number::from_f64(f).map(Json::Number).unwrap_or(Json::Null)
+    }
+}
+
+fn values_eq(a: &Json, b: &Json) -> bool {
+    match (a, b) {
+        (Json::Number(x), Json::Number(y)) => x.as_f64() == y.as_f64(),
+        _ => a == b,
+    }
+}
+
+fn compare(a: &Json, b: &Json) -> Result<std::cmp::Ordering> {
+    match (a, b) {
+        (Json::String(x), Json::String(y)) => Ok(x.cmp(y)),
+        _ => {
+            let (x, y) = (as_f64(a), as_f64(b));
+            match (x, y) {
+                (Some(x), Some(y)) => x
+                    .partial_cmp(&y)
+                    .ok_or_else(|| anyhow!("incomparable values")),
+                _ => bail!("incompatible types for comparison"),
+            }
+        }
+    }
+}
+
+fn quote_go(s: &str) -> String {
+    let mut out = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\t' => out.push_str("\\t"),
+            '\r' => out.push_str("\\r"),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+pub fn to_yaml(v: &Json) -> Result<String> {
+    if v.is_null() {
+        return Ok("null".into());
+    }
+    let s = serde_yaml::to_string(v)?;
+    Ok(s.strip_suffix('\n').unwrap_or(&s).to_string())
+}
+
+fn indent(n: i64, s: &str) -> String {
+    let pad = " ".repeat(n.max(0) as usize);
+    s.split('\n')
+        .map(|l| format!("{}{}", pad, l))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Go's `fmt.Sprintf` for the verbs templates use.
+fn sprintf(format: &str, args: &[Json]) -> String {
+    let mut out = String::new();
+    let mut args = args.iter();
+    let mut chars = format.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            out.push(c);
+            continue;
+        }
+        let mut spec = String::new();
+        while let Some(&n) = chars.peek() {
+            if n.is_ascii_digit() || "-+ #.".contains(n) {
+                spec.push(n);
+                chars.next();
+            } else {
+                break;
+            }
+        }
+        let Some(verb) = chars.next() else {
+            out.push_str("%!(NOVERB)");
+            break;
+        };
+        if verb == '%' {
+            out.push('%');
+            continue;
+        }
+        let Some(arg) = args.next() else {
+            let _ = write!(out, "%!{}(MISSING)", verb);
+            continue;
+        };
+        let left = spec.starts_with('-');
+        let zero = spec.starts_with('0');
+        let spec = spec.trim_start_matches(['-', '+', ' ', '#', '0']);
+        let (width, prec) = match spec.split_once('.') {
+            Some((w, p)) => (w.parse::<usize>().ok(), p.parse::<usize>().ok()),
+            None => (spec.parse::<usize>().ok(), None),
+        };
+        let body = match verb {
+            's' | 'v' => match (arg, prec) {
+                (Json::Null, _) if verb == 's' => "%!s(<nil>)".to_string(),
+                (Json::Null, _) => "<nil>".to_string(),
+                (a, Some(p)) if verb == 's' => to_text(a).chars().take(p).collect(),
+                (a, _) => to_text(a),
+            },
+            'd' => as_i64(arg)
+                .map(|i| i.to_string())
+                .unwrap_or_else(|_| format!("%!d({})", to_text(arg))),
+            'f' | 'F' => format!("{:.*}", prec.unwrap_or(6), as_f64(arg).unwrap_or(0.0)),
+            'g' => fmt_number(&Number::from_f64(as_f64(arg).unwrap_or(0.0)).unwrap_or(0.into())),
+            'q' => quote_go(&to_text(arg)),
+            't' => to_text(arg),
+            'x' => match arg {
+                Json::String(s) => s.bytes().map(|b| format!("{:02x}", b)).collect(),
+                a => format!("{:x}", as_i64(a).unwrap_or(0)),
+            },
+            other => format!("%!{}({})", other, to_text(arg)),
+        };
+        match width {
+            Some(w) if body.chars().count() < w => {
+                let pad = w - body.chars().count();
+                if left {
+                    out.push_str(&body);
+                    out.push_str(&" ".repeat(pad));
+                } else {
+                    out.push_str(&(if zero { "0" } else { " " }).repeat(pad));
+                    out.push_str(&body);
+                }
+            }
+            _ => out.push_str(&body),
+        }
+    }
+    for extra in args {
+        let _ = write!(out, "%!(EXTRA {})", to_text(extra));
+    }
+    out
+}
+
+/// Go's `fmt.Sprint`: spaces between operands when neither is a string.
+fn sprint(args: &[Json]) -> String {
+    let mut out = String::new();
+    for (i, a) in args.iter().enumerate() {
+        if i > 0 && !a.is_string() && !args[i - 1].is_string() {
+            out.push(' ');
+        }
+        out.push_str(&go_value(a));
+    }
+    out
+}
+
+fn str_arg(v: &Json) -> String {
+    to_text(v)
+}
+
+fn list_arg(v: &Json) -> Vec<Json> {
+    match v {
+        Json::Array(a) => a.clone(),
+        Json::Null => Vec::new(),
+        other => vec![other.clone()],
+    }
+}
+
+/// Deep merge `src` into `dst`; with `overwrite` src wins, otherwise only
+/// missing keys are filled (Sprig's `merge` vs `mergeOverwrite`).
+pub fn merge_values(dst: &mut Json, src: &Json, overwrite: bool) {
+    match (dst, src) {
+        (Json::Object(d), Json::Object(s)) => {
+            for (k, v) in s {
+                match d.get_mut(k) {
+                    Some(existing) if existing.is_object() && v.is_object() => {
+                        merge_values(existing, v, overwrite)
+                    }
+                    Some(existing) => {
+                        if overwrite {
+                            *existing = v.clone();
+                        }
+                    }
+                    None => {
+                        d.insert(k.clone(), v.clone());
+                    }
+                }
+            }
+        }
+        (d, s) => {
+            if overwrite {
+                *d = s.clone();
+            }
+        }
+    }
+}
+
+/// `>=1.19-0`, `<1.22.0`, `~1.2`, `^1.2`, `1.x`; several constraints may be
+/// joined with `,` (and) or `||` (or). Pre-release tags are ignored.
+fn semver_compare(constraint: &str, version: &str) -> Result<bool> {
+    fn parse(v: &str) -> Option<[u64; 3]> {
+        let v = v.trim().trim_start_matches('v');
+        let v = v.split(['-', '+']).next()?;
+        let mut parts = [0u64; 3];
+        for (i, p) in v.split('.').take(3).enumerate() {
+            parts[i] = match p {
+                "x" | "X" | "*" => 0,
+                p => p.parse().ok()?,
+            };
+        }
+        Some(parts)
+    }
+    let have = parse(version).ok_or_else(|| anyhow!("invalid semantic version {:?}", version))?;
+    let one = |c: &str| -> Result<bool> {
+        let c = c.trim();
+        let ops = [">=", "<=", "!=", "=>", "=<", ">", "<", "=", "~", "^"];
+        let op = ops
+            .iter()
+            .find(|o| c.starts_with(*o))
+            .copied()
+            .unwrap_or("=");
+        let want_s = c[if c.starts_with(op) { op.len() } else { 0 }..].trim();
+        let want = parse(want_s).ok_or_else(|| anyhow!("invalid constraint {:?}", constraint))?;
+        let depth = want_s
+            .split(['-', '+'])
+            .next()
+            .unwrap_or("")
+            .split('.')
+            .count();
+        Ok(match op {
+            ">=" | "=>" => have >= want,
+            "<=" | "=<" => have <= want,
+            ">" => have > want,
+            "<" => have < want,
+            "!=" => have != want,
+            "~" => have >= want && have[0] == want[0] && (depth < 2 || have[1] == want[1]),
+            "^" => have >= want && have[0] == want[0],
+            _ => (0..depth.min(3)).all(|i| {
+                want_s
+                    .split('.')
+                    .nth(i)
+                    .map(|p| p == "x" || p == "*")
+                    .unwrap_or(false)
+                    || have[i] == want[i]
+            }),
+        })
+    };
+    for alt in constraint.split("||") {
+        let mut ok = true;
+        for c in alt.split(',').flat_map(|p| p.split_whitespace()) {
+            ok &= one(c)?;
+        }
+        if ok {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+fn builtin(name: &str, args: Vec<Json>) -> Result<Json> {
+    let arity = |n: usize| -> Result<()> {
+        if args.len() != n {
+            bail!("{}: expected {} arguments, got {}", name, n, args.len());
+        }
+        Ok(())
+    };
+    let s = |i: usize| args.get(i).map(str_arg).unwrap_or_default();
+    Ok(match name {
+        // Go builtins
+        "not" => {
+            arity(1)?;
+            Json::Bool(!truthy(&args[0]))
+        }
+        "eq" => {
+            if args.len() < 2 {
+                bail!("eq: missing argument for comparison");
+            }
+            Json::Bool(args[1..].iter().any(|b| values_eq(&args[0], b)))
+        }
+        "ne" => {
+            arity(2)?;
+            Json::Bool(!values_eq(&args[0], &args[1]))
+        }
+        "lt" | "le" | "gt" | "ge" => {
+            arity(2)?;
+            let o = compare(&args[0], &args[1])?;
+            Json::Bool(match name {
+                "lt" => o.is_lt(),
+                "le" => o.is_le(),
+                "gt" => o.is_gt(),
+                _ => o.is_ge(),
+            })
+        }
+        "len" => {
+            arity(1)?;
+            Json::from(match &args[0] {
+                Json::String(s) => s.len(),
+                Json::Array(a) => a.len(),
+                Json::Object(m) => m.len(),
+                Json::Null => 0,
+                other => bail!("len of {}", other),
+            })
+        }
+        "index" => {
+            let mut cur = args.first().cloned().unwrap_or(Json::Null);
+            for k in args.iter().skip(1) {
+                cur = match (&cur, k) {
+                    (Json::Array(a), k) => {
+                        let i = as_i64(k)?;
+                        a.get(i as usize)
+                            .cloned()
+                            .ok_or_else(|| anyhow!("index out of range: {}", i))?
+                    }
+                    (Json::Object(m), k) => m.get(&str_arg(k)).cloned().unwrap_or(Json::Null),
+                    (Json::Null, _) => Json::Null,
+                    (other, _) => bail!("can't index item of type {}", other),
+                };
+            }
+            cur
+        }
+        "print" => Json::String(sprint(&args)),
+        "println" => Json::String(format!(
+            "{}\n",
+            args.iter().map(go_value).collect::<Vec<_>>().join(" ")
+        )),
+        "printf" => {
+            if args.is_empty() {
+                bail!("printf: missing format");
+            }
+            Json::String(sprintf(&s(0), &args[1..]))
+        }
+        // Sprig / Helm
+        "default" => match args.as_slice() {
+            [d] => d.clone(),
+            [d, v] => {
+                if truthy(v) {
+                    v.clone()
+                } else {
+                    d.clone()
+                }
+            }
+            _ => bail!("default: expected 1 or 2 arguments"),
+        },
+        "empty" => {
+            arity(1)?;
+            Json::Bool(!truthy(&args[0]))
+        }
+        "coalesce" => args
+            .iter()
+            .find(|v| truthy(v))
+            .cloned()
+            .unwrap_or(Json::Null),
+        "ternary" => {
+            arity(3)?;
+            if truthy(&args[2]) {
+                args[0].clone()
+            } else {
+                args[1].clone()
+            }
+        }
+        "required" => {
+            arity(2)?;
+            match &args[1] {
+                Json::Null => bail!("{}", s(0)),
+                Json::String(v) if v.is_empty() => bail!("{}", s(0)),
+                v => v.clone(),
+            }
+        }
+        "fail" => bail!("{}", s(0)),
+        "toYaml" => {
+            arity(1)?;
+            Json::String(to_yaml(&args[0])?)
+        }
+        "fromYaml" => {
+            arity(1)?;
+            let v: serde_yaml::Value = serde_yaml::from_str(&s(0))?;
+            serde_json::to_value(v)?
+        }
+        "toJson" => {
+            arity(1)?;
+            Json::String(serde_json::to_string(&args[0])?)
+        }
+        "toPrettyJson" => {
+            arity(1)?;
+            Json::String(serde_json::to_string_pretty(&args[0])?)
+        }
+        "fromJson" => {
+            arity(1)?;
+            serde_json::from_str(&s(0))?
+        }
+        "indent" => {
+            arity(2)?;
+            Json::String(indent(as_i64(&args[0])?, &s(1)))
+        }
+        "nindent" => {
+            arity(2)?;
+            Json::String(format!("\n{}", indent(as_i64(&args[0])?, &s(1))))
+        }
+        "quote" => Json::String(
+            args.iter()
+                .filter(|a| !a.is_null())
+                .map(|a| quote_go(&to_text(a)))
+                .collect::<Vec<_>>()
+                .join(" "),
+        ),
+        "squote" => Json::String(
+            args.iter()
+                .filter(|a| !a.is_null())
+                .map(|a| format!("'{}'", to_text(a)))
+                .collect::<Vec<_>>()
+                .join(" "),
+        ),
+        "toString" => {
+            arity(1)?;
+            Json::String(s(0))
+        }
+        "upper" => Json::String(s(0).to_uppercase()),
+        "lower" => Json::String(s(0).to_lowercase()),
+        "title" => Json::String(
+            s(0).split(' ')
+                .map(|w| {
+                    let mut c = w.chars();
+                    match c.next() {
+                        Some(f) => f.to_uppercase().collect::<String>() + c.as_str(),
+                        None => String::new(),
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join(" "),
+        ),
+        "trim" => Json::String(s(0).trim().to_string()),
+        "trimAll" => {
+            arity(2)?;
+            let cut = s(0);
+            Json::String(s(1).trim_matches(|c| cut.contains(c)).to_string())
+        }
+        "trimSuffix" => {
+            arity(2)?;
+            let v = s(1);
+            Json::String(v.strip_suffix(s(0).as_str()).unwrap_or(&v).to_string())
+        }
+        "trimPrefix" => {
+            arity(2)?;
+            let v = s(1);
+            Json::String(v.strip_prefix(s(0).as_str()).unwrap_or(&v).to_string())
+        }
+        "replace" => {
+            arity(3)?;
+            Json::String(s(2).replace(&s(0), &s(1)))
+        }
+        "contains" => {
+            arity(2)?;
+            Json::Bool(s(1).contains(&s(0)))
+        }
+        "hasPrefix" => {
+            arity(2)?;
+            Json::Bool(s(1).starts_with(&s(0)))
+        }
+        "hasSuffix" => {
+            arity(2)?;
+            Json::Bool(s(1).ends_with(&s(0)))
+        }
+        "trunc" => {
+            arity(2)?;
+            let n = as_i64(&args[0])?;
+            let v: Vec<char> = s(1).chars().collect();
+            let out: String = if n >= 0 {
+                v.iter().take(n as usize).collect()
+            } else {
+                v.iter()
+                    .skip(v.len().saturating_sub((-n) as usize))
+                    .collect()
+            };
+            Json::String(out)
+        }
+        "repeat" => {
+            arity(2)?;
+            Json::String(s(1).repeat(as_i64(&args[0])?.max(0) as usize))
+        }
+        "join" => {
+            arity(2)?;
+            Json::String(
+                list_arg(&args[1])
+                    .iter()
+                    .map(to_text)
+                    .collect::<Vec<_>>()
+                    .join(&s(0)),
+            )
+        }
+        "splitList" => {
+            arity(2)?;
+            Json::Array(
+                s(1).split(s(0).as_str())
+                    .map(|p| Json::String(p.to_string()))
+                    .collect(),
+            )
+        }
+        "regexMatch" => {
+            arity(2)?;
+            Json::Bool(regex::Regex::new(&s(0))?.is_match(&s(1)))
+        }
+        "regexReplaceAll" => {
+            arity(3)?;
+            Json::String(
+                regex::Regex::new(&s(0))?
+                    .replace_all(&s(1), s(2).as_str())
+                    .into_owned(),
+            )
+        }
+        "b64enc" => Json::String(base64::engine::general_purpose::STANDARD.encode(s(0))),
+        "b64dec" => Json::String(
+            String::from_utf8_lossy(&base64::engine::general_purpose::STANDARD.decode(s(0))?)
+                .into_owned(),
+        ),
+        "sha256sum" => Json::String(
+            Sha256::digest(s(0).as_bytes())
+                .iter()
+                .map(|b| format!("{:02x}", b))
+                .collect(),
+        ),
+        "int" | "int64" | "atoi" => {
+            arity(1)?;
+            Json::from(as_i64(&args[0]).unwrap_or(0))
+        }
+        "float64" => {
+            arity(1)?;
+            num(as_f64(&args[0]).unwrap_or(0.0))
+        }
+        "add" => num(args.iter().filter_map(as_f64).sum()),
+        "add1" => {
+            arity(1)?;
+            num(as_f64(&args[0]).unwrap_or(0.0) + 1.0)
+        }
+        "sub" | "mul" | "div" | "mod" => {
+            arity(2)?;
+            let (a, b) = (as_i64(&args[0])?, as_i64(&args[1])?);
+            let v = match name {
+                "sub" => a.checked_sub(b),
+                "mul" => a.checked_mul(b),
+                _ if b == 0 => bail!("{}: division by zero", name),
+                "div" => a.checked_div(b),
+                _ => a.checked_rem(b),
+            };
+            let Some(v) = v else {
+                bail!("{}: integer overflow", name)
+            };
+            Json::from(v)
+        }
+        "max" | "min" => {
+            let mut it = args.iter().map(as_i64);
+            let mut acc = it
+                .next()
+                .ok_or_else(|| anyhow!("{}: no arguments", name))??;
+            for v in it {
+                let v = v?;
+                acc = if name == "max" {
+                    acc.max(v)
+                } else {
+                    acc.min(v)
+                };
+            }
+            Json::from(acc)
+        }
+        "until" => {
+            arity(1)?;
+            Json::Array((0..as_i64(&args[0])?).map(Json::from).collect())
+        }
+        "list" => Json::Array(args),
+        "first" | "last" | "rest" => {
+            arity(1)?;
+            let l = list_arg(&args[0]);
+            match name {
+                "first" => l.first().cloned().unwrap_or(Json::Null),
+                "last" => l.last().cloned().unwrap_or(Json::Null),
+                _ => Json::Array(l.into_iter().skip(1).collect()),
+            }
+        }
+        "append" => {
+            arity(2)?;
+            let mut l = list_arg(&args[0]);
+            l.push(args[1].clone());
+            Json::Array(l)
+        }
+        "has" => {
+            arity(2)?;
+            Json::Bool(list_arg(&args[1]).iter().any(|v| values_eq(v, &args[0])))
+        }
+        "uniq" => {
+            arity(1)?;
+            let mut out: Vec<Json> = Vec::new();
+            for v in list_arg(&args[0]) {
+                if !out.contains(&v) {
+                    out.push(v);
+                }
+            }
+            Json::Array(out)
+        }
+        "compact" => {
+            arity(1)?;
+            Json::Array(list_arg(&args[0]).into_iter().filter(truthy).collect())
+        }
+        "dict" => {
+            let mut m = Map::new();
+            for pair in args.chunks(2) {
+                m.insert(
+                    str_arg(&pair[0]),
+                    pair.get(1).cloned().unwrap_or(Json::String(String::new())),
+                );
+            }
+            Json::Object(m)
+        }
+        "hasKey" => {
+            arity(2)?;
+            Json::Bool(
+                args[0]
+                    .as_object()
+                    .map(|m| m.contains_key(&s(1)))
+                    .unwrap_or(false),
+            )
+        }
+        "get" => {
+            arity(2)?;
+            args[0]
+                .get(s(1))
+                .cloned()
+                .unwrap_or(Json::String(String::new()))
+        }
+        "keys" => {
+            let mut keys: Vec<String> = args
+                .iter()
+                .filter_map(|a| a.as_object())
+                .flat_map(|m| m.keys().cloned())
+                .collect();
+            keys.sort();
+            Json::Array(keys.into_iter().map(Json::String).collect())
+        }
+        "values" => Json::Array(
+            args.first()
+                .and_then(|a| a.as_object())
+                .map(|m| m.values().cloned().collect())
+                .unwrap_or_default(),
+        ),
+        "pick" | "omit" => {
+            let Some(Json::Object(m)) = args.first() else {
+                bail!("{}: expected a dict", name);
+            };
+            let names: Vec<String> = args[1..].iter().map(str_arg).collect();
+            Json::Object(
+                m.iter()
+                    .filter(|(k, _)| names.contains(k) == (name == "pick"))
+                    .map(|(k, v)| (k.clone(), v.clone()))
+                    .collect(),
+            )
+        }
+        "merge" | "mergeOverwrite" => {
+            let mut it = args.into_iter();
+            let mut dst = it.next().unwrap_or(Json::Object(Map::new()));
+            for src in it {
+                merge_values(&mut dst, &src, name == "mergeOverwrite");
+            }
+            dst
+        }
+        "deepCopy" => args.first().cloned().unwrap_or(Json::Null),
+        "semverCompare" => {
+            arity(2)?;
+            Json::Bool(semver_compare(&s(0), &s(1))?)
+        }
+        // `helm template` has no cluster to look objects up in.
+        "lookup" => Json::Object(Map::new()),
+        other => bail!("function {:?} not defined", other),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn render(text: &str, data: Json) -> String {
+        let mut t = Templates::new();
+        t.add("t", text).unwrap();
+        t.render("t", &data).unwrap()
+    }
+
+    #[test]
+    fn trims_and_evaluates_pipelines() {
+        let data = json!({"Values": {"name": "web", "port": 8080, "tag": null}});
+        assert_eq!(
+            render(
+                "name: {{ .Values.name | upper | quote }}\n{{- /* c */}}\nport: {{ .Values.port }}\ntag: {{ .Values.tag | default \"latest\" }}",
+                data
+            ),
+            "name: \"WEB\"\nport: 8080\ntag: latest"
+        );
+        assert_eq!(
+            render(
+                "{{ printf \"%s-%05d\" \"a\" 42 }} {{ trunc 3 \"abcdef\" }}",
+                json!({})
+            ),
+            "a-00042 abc"
+        );
+    }
+
+    #[test]
+    fn control_flow_and_variables() {
+        let data = json!({"items": ["a", "b", "c"], "m": {"z": 1, "y": 2}, "on": true});
+        let out = render(
+            "{{- range $i, $v := .items }}{{ if eq $i 1 }}{{ continue }}{{ end }}{{ $v }}{{ end }}|\
+             {{- range $k, $v := .m }}{{ $k }}={{ $v }};{{ end }}|\
+             {{- with .missing }}x{{ else with .on }}{{ . }}{{ end }}|\
+             {{- $n := 0 }}{{ range .items }}{{ $n = add1 $n }}{{ end }}{{ $n }}|\
+             {{- if and .on (not .off) }}yes{{ else }}no{{ end }}",
+            data,
+        );
+        assert_eq!(out, "ac|y=2;z=1;|true|3|yes");
+    }
+
+    #[test]
+    fn define_include_and_to_yaml() {
+        let mut t = Templates::new();
+        t.add(
+            "_helpers.tpl",
+            "{{- define \"labels\" -}}\napp: {{ .name }}\ntier: {{ .tier | default \"web\" }}\n{{- end }}",
+        )
+        .unwrap();
+        t.add(
+            "deploy.yaml",
+            "metadata:\n  labels:\n    {{- include \"labels\" . | nindent 4 }}\nspec:\n  {{- toYaml .spec | nindent 2 }}",
+        )
+        .unwrap();
+        let out = t
+            .render(
+                "deploy.yaml",
+                &json!({"name": "api", "spec": {"replicas": 2}}),
+            )
+            .unwrap();
+        assert_eq!(
+            out,
+            "metadata:\n  labels:\n    app: api\n    tier: web\nspec:\n  replicas: 2"
+        );
+    }
+
+    #[test]
+    fn errors_name_template_and_line() {
+        let mut t = Templates::new();
+        t.add("x.yaml", "a\nb: {{ required \"b is required\" .b }}")
+            .unwrap();
+        let err = format!("{:#}", t.render("x.yaml", &json!({})).unwrap_err());
+        assert!(err.contains("x.yaml:2"), "{}", err);
+        assert!(err.contains("b is required"), "{}", err);
+        // Integer overflow in user values is an error, not a panic.
+        for (f, a, b) in [
+            ("sub", i64::MIN, 1),
+            ("mul", i64::MAX, 2),
+            ("div", i64::MIN, -1),
+            ("mod", i64::MIN, -1),
+        ] {
+            let mut t = Templates::new();
+            t.add("o.yaml", &format!("{{{{ {} .a .b }}}}", f)).unwrap();
+            let err = format!(
+                "{:#}",
+                t.render("o.yaml", &json!({"a": a, "b": b})).unwrap_err()
+            );
+            assert!(err.contains(&format!("{}: integer overflow", f)), "{}", err);
+        }
+        assert!(semver_compare(">=1.19-0", "v1.29.3").unwrap());
+        assert!(!semver_compare("<1.22", "v1.29.0").unwrap());
+    }
+}
@@ -0,0 +1,603 @@
+//! Local Helm chart rendering (`helm template` without leaving Orka).
+//!
+//! Loads `Chart.yaml`, `values.yaml`, `templates/` and `crds/` from a chart
+//! directory, layers values files and `--set` pairs over the defaults, and
+//! renders every template except `_*` partials and `NOTES.txt` with the Go
+//! template subset in [`crate::gotmpl`]. Unpacked subcharts under `charts/`
+//! render with their own scoped `.Values` (plus `global`), honouring
+//! dependency `condition`s and `alias`es from `Chart.yaml`. Hook resources
+//! (`helm.sh/hook`) are not part of a release's manifest and are dropped.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, bail, Context, Result};
+use serde_json::{json, Map, Value as Json};
+
+use crate::gotmpl::{merge_values, Templates};
+use crate::Manifest;
+
+const HOOK_ANNOTATION: &str = "helm.sh/hook";
+
+/// Group-versions `.Capabilities.APIVersions.Has` reports when rendering
+/// offline.
+const DEFAULT_API_VERSIONS: &[&str] = &[
+    "v1",
+    "admissionregistration.k8s.io/v1",
+    "apiextensions.k8s.io/v1",
+    "apps/v1",
+    "autoscaling/v1",
+    "autoscaling/v2",
+    "batch/v1",
+    "certificates.k8s.io/v1",
+    "coordination.k8s.io/v1",
+    "discovery.k8s.io/v1",
+    "events.k8s.io/v1",
+    "networking.k8s.io/v1",
+    "policy/v1",
+    "rbac.authorization.k8s.io/v1",
+    "scheduling.k8s.io/v1",
+    "storage.k8s.io/v1",
+];
+
+/// Release settings for [`render_chart`].
+#[derive(Debug, Clone)]
+pub struct ChartOptions {
+    /// `.Release.Name`.
+    pub release: String,
+    /// `.Release.Namespace`.
+    pub namespace: String,
+    /// Values files applied in order over the chart's `values.yaml`.
+    pub values_files: Vec<PathBuf>,
+    /// `key.path=value` overrides applied last.
+    pub set: Vec<String>,
+    /// `.Capabilities.KubeVersion`.
+    pub kube_version: String,
+}
+
+impl Default for ChartOptions {
+    fn default() -> Self {
+        Self {
+            release: "release-name".into(),
+            namespace: "default".into(),
+            values_files: Vec::new(),
+            set: Vec::new(),
+            kube_version: "v1.29.0".into(),
+        }
+    }
+}
+
+struct Chart {
+    /// Template path prefix, e.g. `web` or `web/charts/redis`.
+    path: String,
+    meta: Json,
+    defaults: Json,
+    templates: Vec<(String, String)>,
+    crds: Vec<(String, String)>,
+    files: Map<String, Json>,
+    subcharts: Vec<Chart>,
+}
+
+/// Render the chart in `dir` into manifests (`source` names the template).
+pub fn render_chart(dir: impl AsRef<Path>, opts: &ChartOptions) -> Result<Vec<Manifest>> {
+    let dir = dir.as_ref();
+    let chart = load_chart(dir, None)?;
+    let mut values = chart.defaults.clone();
+    for f in &opts.values_files {
+        let text =
+            std::fs::read_to_string(f).with_context(|| format!("reading {}", f.display()))?;
+        merge_values(
+            &mut values,
+            &yaml_to_json(&text, &f.display().to_string())?,
+            true,
+        );
+    }
+    for pair in &opts.set {
+        set_value(&mut values, pair)?;
+    }
+
+    let mut tpl = Templates::new();
+    let mut jobs = Vec::new();
+    let mut crds = Vec::new();
+    collect(
+        &chart,
+        &chart.meta,
+        values,
+        opts,
+        &mut tpl,
+        &mut jobs,
+        &mut crds,
+    )?;
+
+    let mut out = Vec::new();
+    for (name, text) in crds {
+        out.extend(crate::manifest::split_manifests(&text, &name)?);
+    }
+    for (name, data) in jobs {
+        let text = tpl.render(&name, &data)?;
+        for m in crate::manifest::split_manifests(&text, &name)? {
+            let obj: Json = serde_yaml::from_str(&m.yaml)?;
+            if obj
+                .pointer("/metadata/annotations")
+                .and_then(|a| a.get(HOOK_ANNOTATION))
+                .is_none()
+            {
+                out.push(m);
+            }
+        }
+    }
+    Ok(out)
+}
+
+/// Parse every template of `chart` and its enabled subcharts into `tpl` and
+/// queue the renderable ones with their data. `meta` is the chart's
+/// Chart.yaml, renamed for aliased subcharts.
+fn collect(
+    chart: &Chart,
+    meta: &Json,
+    values: Json,
+    opts: &ChartOptions,
+    tpl: &mut Templates,
+    jobs: &mut Vec<(String, Json)>,
+    crds: &mut Vec<(String, String)>,
+) -> Result<()> {
+    let mut values = values;
+    let deps = meta
+        .get("dependencies")
+        .and_then(|d| d.as_array())
+        .cloned()
+        .unwrap_or_default();
+    for sub in &chart.subcharts {
+        let sub_name = sub.meta["name"].as_str().unwrap_or_default();
+        let dep = deps
+            .iter()
+            .find(|d| d.get("name").and_then(|n| n.as_str()) == Some(sub_name));
+        let key = dep
+            .and_then(|d| d.get("alias"))
+            .and_then(|a| a.as_str())
+            .unwrap_or(sub_name)
+            .to_string();
+        if let Some(cond) = dep
+            .and_then(|d| d.get("condition"))
+            .and_then(|c| c.as_str())
+        {
+            let decided = cond
+                .split(',')
+                .map(|p| lookup_path(&values, p.trim()))
+                .find(|v| v.is_some())
+                .flatten();
+            if let Some(false) = decided.and_then(|v| v.as_bool()) {
+                continue;
+            }
+        }
+        // Parent values for the subchart override its defaults; globals flow down.
+        let mut sub_values = sub.defaults.clone();
+        if let Some(over) = values.get(&key) {
+            merge_values(&mut sub_values, over, true);
+        }
+        if let Some(global) = values.get("global") {
+            let obj = ensure_object(&mut sub_values);
+            let g = obj.entry("global").or_insert_with(|| json!({}));
+            merge_values(g, global, true);
+        }
+        let mut sub_meta = sub.meta.clone();
+        sub_meta["name"] = Json::String(key.clone());
+        ensure_object(&mut values).insert(key, sub_values.clone());
+        collect(sub, &sub_meta, sub_values, opts, tpl, jobs, crds)?;
+    }
+
+    let chart_obj = chart_object(meta);
+    for (name, text) in &chart.templates {
+        tpl.add(name, text)?;
+    }
+    for (name, text) in &chart.crds {
+        crds.push((name.clone(), text.clone()));
+    }
+    for (name, _) in &chart.templates {
+        let base = Path::new(name)
+            .file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or("");
+        if base.starts_with('_') || base == "NOTES.txt" {
+            continue;
+        }
+        let data = json!({
+            "Values": values,
+            "Release": {
+                "Name": opts.release,
+                "Namespace": opts.namespace,
+                "Service": "Helm",
+                "IsInstall": true,
+                "IsUpgrade": false,
+                "Revision": 1,
+            },
+            "Chart": chart_obj,
+            "Capabilities": capabilities(&opts.kube_version),
+            "Template": {
+                "Name": name,
+                "BasePath": format!("{}/templates", chart.path),
+            },
+            "Files": chart.files,
+        });
+        jobs.push((name.clone(), data));
+    }
+    Ok(())
+}
+
+fn load_chart(dir: &Path, parent: Option<&str>) -> Result<Chart> {
+    let chart_yaml = dir.join("Chart.yaml");
+    let text = std::fs::read_to_string(&chart_yaml)
+        .with_context(|| format!("reading {}", chart_yaml.display()))?;
+    let meta = yaml_to_json(&text, &chart_yaml.display().to_string())?;
+    let name = meta
+        .get("name")
+        .and_then(|n| n.as_str())
+        .ok_or_else(|| anyhow!("{}: missing name", chart_yaml.display()))?
+        .to_string();
+    let path = match parent {
+        Some(p) => format!("{}/charts/{}", p, name),
+        None => name.clone(),
+    };
+    let values_path = dir.join("values.yaml");
+    let defaults = if values_path.is_file() {
+        let text = std::fs::read_to_string(&values_path)?;
+        match yaml_to_json(&text, &values_path.display().to_string())? {
+            Json::Null => json!({}),
+            v => v,
+        }
+    } else {
+        json!({})
+    };
+
+    let mut templates = Vec::new();
+    let mut crds = Vec::new();
+    let mut files = Map::new();
+    for file in walk(dir)? {
+        let rel = file
+            .strip_prefix(dir)
+            .unwrap_or(&file)
+            .to_string_lossy()
+            .replace('\\', "/");
+        let top = rel.split('/').next().unwrap_or("");
+        if top == "charts" {
+            continue;
+        }
+        let named = format!("{}/{}", path, rel);
+        match top {
+            "templates" => templates.push((named, std::fs::read_to_string(&file)?)),
+            "crds" if is_yaml(&rel) => crds.push((named, std::fs::read_to_string(&file)?)),
+            _ if rel == "Chart.yaml" || rel == "values.yaml" || rel == ".helmignore" => {}
+            _ => {
+                // `.Files.Get`; binary files are left out.
+                if let Ok(content) = std::fs::read_to_string(&file) {
+                    files.insert(rel, Json::String(content));
+                }
+            }
+        }
+    }
+
+    let mut subcharts = Vec::new();
+    let charts_dir = dir.join("charts");
+    if charts_dir.is_dir() {
+        let mut entries: Vec<PathBuf> = std::fs::read_dir(&charts_dir)?
+            .filter_map(|e| e.ok().map(|e| e.path()))
+            .collect();
+        entries.sort();
+        for entry in entries {
+            if entry.is_dir() {
+                subcharts.push(load_chart(&entry, Some(&path))?);
+            } else if entry.extension().and_then(|e| e.to_str()) == Some("tgz") {
+                bail!(
+                    "{}: packaged subcharts are not supported; unpack it into {}",
+                    entry.display(),
+                    charts_dir.display()
+                );
+            }
+        }
+    }
+    Ok(Chart {
+        path,
+        meta,
+        defaults,
+        templates,
+        crds,
+        files,
+        subcharts,
+    })
+}
+
+fn walk(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut out = Vec::new();
+    let mut stack = vec![dir.to_path_buf()];
+    while let Some(d) = stack.pop() {
+        for entry in std::fs::read_dir(&d).with_context(|| format!("reading {}", d.display()))? {
+            let p = entry?.path();
+            if p.is_dir() {
+                stack.push(p);
+            } else {
+                out.push(p);
+            }
+        }
+    }
+    out.sort();
+    Ok(out)
+}
+
+fn is_yaml(path: &str) -> bool {
+    path.ends_with(".yaml") || path.ends_with(".yml") || path.ends_with(".json")
+}
+
+fn yaml_to_json(text: &str, src: &str) -> Result<Json> {
+    let v: serde_yaml::Value =
+        serde_yaml::from_str(text).with_context(|| format!("{}: parsing YAML", src))?;
+    Ok(serde_json::to_value(v)?)
+}
+
+fn ensure_object(v: &mut Json) -> &mut Map<String, Json> {
+    if !v.is_object() {
+        *v = json!({});
+    }
+    v.as_object_mut().unwrap()
+}
+
+fn lookup_path<'a>(v: &'a Json, path: &str) -> Option<&'a Json> {
+    path.split('.').try_fold(v, |cur, k| cur.get(k))
+}
+
+/// `a.b.c=value`; `true`/`false`/`null` and integers are typed like
+/// `helm --set`, everything else is a string.
+fn set_value(values: &mut Json, pair: &str) -> Result<()> {
+    let (path, raw) = pair
+        .split_once('=')
+        .ok_or_else(|| anyhow!("--set {:?}: expected key=value", pair))?;
+    let value = match raw {
+        "true" => Json::Bool(true),
+        "false" => Json::Bool(false),
+        "null" => Json::Null,
+        _ => raw
+            .parse::<i64>()
+            .map(Json::from)
+            .unwrap_or_else(|_| Json::String(raw.to_string())),
+    };
+    let mut cur = values;
+    let keys: Vec<&str> = path.split('.').collect();
+    for (i, k) in keys.iter().enumerate() {
+        let obj = ensure_object(cur);
+        if i == keys.len() - 1 {
+            obj.insert(k.to_string(), value);
+            return Ok(());
+        }
+        cur = obj.entry(k.to_string()).or_insert_with(|| json!({}));
+    }
+    Ok(())
+}
+
+/// `.Chart`: Chart.yaml with Helm's capitalised field names.
+fn chart_object(meta: &Json) -> Json {
+    let mut out = Map::new();
+    if let Some(m) = meta.as_object() {
+        for (k, v) in m {
+            let key = match k.as_str() {
+                "apiVersion" => "APIVersion".to_string(),
+                other => {
+                    let mut c = other.chars();
+                    match c.next() {
+                        Some(f) => f.to_uppercase().collect::<String>() + c.as_str(),
+                        None => continue,
+                    }
+                }
+            };
+            out.insert(key, v.clone());
+        }
+    }
+    Json::Object(out)
+}
+
+fn capabilities(kube_version: &str) -> Json {
+    let v = kube_version.trim_start_matches('v');
+    let mut parts = v.split('.');
+    let major = parts.next().unwrap_or("1");
+    let minor = parts.next().unwrap_or("0");
+    json!({
+        "KubeVersion": {
+            "Version": format!("v{}", v),
+            "GitVersion": format!("v{}", v),
+            "Major": major,
+            "Minor": minor,
+        },
+        "APIVersions": DEFAULT_API_VERSIONS,
+        "HelmVersion": {"Version": "v3.14.0"},
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::{temp_dir, write};
+
+    /// The skeleton `helm create` generates, trimmed to a Deployment, a
+    /// Service and a test hook.
+    fn chart() -> PathBuf {
+        let root = temp_dir("helm");
+        write(
+            &root,
+            "Chart.yaml",
+            "apiVersion: v2\nname: web\nversion: 0.1.0\nappVersion: \"1.16.0\"\n\
+             dependencies:\n- name: cache\n  version: 0.1.0\n  condition: cache.enabled\n",
+        );
+        write(
+            &root,
+            "values.yaml",
+            "replicaCount: 1\nimage:\n  repository: nginx\n  tag: \"\"\n\
+             service:\n  port: 80\npodAnnotations: {}\nresources: {}\n\
+             cache:\n  enabled: false\n",
+        );
+        write(
+            &root,
+            "templates/_helpers.tpl",
+            r#"{{/* Expand the name of the chart. */}}
+{{- define "web.name" -}}
+{{- default .Chart.Name .Values.nameOverride | trunc 63 | trimSuffix "-" }}
+{{- end }}
+
+{{- define "web.fullname" -}}
+{{- if .Values.fullnameOverride }}
+{{- .Values.fullnameOverride | trunc 63 | trimSuffix "-" }}
+{{- else }}
+{{- $name := default .Chart.Name .Values.nameOverride }}
+{{- if contains $name .Release.Name }}
+{{- .Release.Name | trunc 63 | trimSuffix "-" }}
+{{- else }}
+{{- printf "%s-%s" .Release.Name $name | trunc 63 | trimSuffix "-" }}
+{{- end }}
+{{- end }}
+{{- end }}
+
+{{- define "web.selectorLabels" -}}
+app.kubernetes.io/name: {{ include "web.name" . }}
+app.kubernetes.io/instance: {{ .Release.Name }}
+{{- end }}
+
+{{- define "web.labels" -}}
+helm.sh/chart: {{ printf "%s-%s" .Chart.Name .Chart.Version | replace "+" "_" | trunc 63 | trimSuffix "-" }}
+{{ include "web.selectorLabels" . }}
+{{- if .Chart.AppVersion }}
+app.kubernetes.io/version: {{ .Chart.AppVersion | quote }}
+{{- end }}
+app.kubernetes.io/managed-by: {{ .Release.Service }}
+{{- end }}
+"#,
+        );
+        write(
+            &root,
+            "templates/deployment.yaml",
+            r#"apiVersion: apps/v1
+kind: Deployment
+metadata:
+  name: {{ include "web.fullname" . }}
+  labels:
+    {{- include "web.labels" . | nindent 4 }}
+spec:
+  replicas: {{ .Values.replicaCount }}
+  selector:
+    matchLabels:
+      {{- include "web.selectorLabels" . | nindent 6 }}
+  template:
+    metadata:
+      {{- with .Values.podAnnotations }}
+      annotations:
+        {{- toYaml . | nindent 8 }}
+      {{- end }}
+      labels:
+        {{- include "web.selectorLabels" . | nindent 8 }}
+    spec:
+      containers:
+        - name: {{ .Chart.Name }}
+          image: "{{ .Values.image.repository }}:{{ .Values.image.tag | default .Chart.AppVersion }}"
+          ports:
+            - name: http
+              containerPort: {{ .Values.service.port }}
+          resources:
+            {{- toYaml .Values.resources | nindent 12 }}
+"#,
+        );
+        write(
+            &root,
+            "templates/service.yaml",
+            r#"apiVersion: v1
+kind: Service
+metadata:
+  name: {{ include "web.fullname" . }}
+spec:
+  ports:
+    - port: {{ .Values.service.port }}
+  selector:
+    {{- include "web.selectorLabels" . | nindent 4 }}
+"#,
+        );
+        write(
+            &root,
+            "templates/tests/test-connection.yaml",
+            "apiVersion: v1\nkind: Pod\nmetadata:\n  name: test\n  annotations:\n    \"helm.sh/hook\": test\n",
+        );
+        write(&root, "templates/NOTES.txt", "Visit {{ .Release.Name }}\n");
+        write(
+            &root,
+            "charts/cache/Chart.yaml",
+            "apiVersion: v2\nname: cache\nversion: 0.1.0\n",
+        );
+        write(&root, "charts/cache/values.yaml", "size: 64\n");
+        write(
+            &root,
+            "charts/cache/templates/cm.yaml",
+            "apiVersion: v1\nkind: ConfigMap\nmetadata:\n  name: {{ .Release.Name }}-cache\n\
+             data:\n  size: {{ .Values.size | quote }}\n  region: {{ .Values.global.region | default \"none\" }}\n",
+        );
+        root
+    }
+
+    #[test]
+    fn renders_helm_create_skeleton() {
+        let root = chart();
+        let values = root.join("prod-values.yaml");
+        write(
+            &root,
+            "prod-values.yaml",
+            "replicaCount: 3\npodAnnotations:\n  team: shop\nresources:\n  limits:\n    cpu: 500m\n",
+        );
+        let opts = ChartOptions {
+            release: "shop".into(),
+            values_files: vec![values],
+            set: vec!["image.tag=1.25".into()],
+            ..Default::default()
+        };
+        let out = render_chart(&root, &opts).unwrap();
+        let kinds: Vec<&str> = out.iter().map(|m| m.kind.as_str()).collect();
+        assert_eq!(
+            kinds,
+            vec!["Deployment", "Service"],
+            "hook and NOTES dropped"
+        );
+        assert_eq!(out[0].name, "shop-web");
+        assert_eq!(out[0].source, "web/templates/deployment.yaml#0");
+        let deploy: Json = serde_yaml::from_str(&out[0].yaml).unwrap();
+        assert_eq!(deploy["spec"]["replicas"], 3);
+        assert_eq!(deploy["metadata"]["labels"]["helm.sh/chart"], "web-0.1.0");
+        assert_eq!(
+            deploy["metadata"]["labels"]["app.kubernetes.io/version"],
+            "1.16.0"
+        );
+        assert_eq!(
+            deploy["spec"]["template"]["metadata"]["annotations"]["team"],
+            "shop"
+        );
+        let c = &deploy["spec"]["template"]["spec"]["containers"][0];
+        assert_eq!(c["image"], "nginx:1.25");
+        assert_eq!(c["resources"]["limits"]["cpu"], "500m");
+        assert_eq!(
+            deploy["spec"]["selector"]["matchLabels"]["app.kubernetes.io/instance"],
+            "shop"
+        );
+        std::fs::remove_dir_all(root).ok();
+    }
+
+    #[test]
+    fn subchart_values_are_scoped_and_conditional() {
+        let root = chart();
+        let opts = ChartOptions {
+            set: vec![
+                "cache.enabled=true".into(),
+                "cache.size=128".into(),
+                "global.region=eu".into(),
+            ],
+            ..Default::default()
+        };
+        let out = render_chart(&root, &opts).unwrap();
+        let cm = out.iter().find(|m| m.kind == "ConfigMap").unwrap();
+        assert_eq!(cm.name, "release-name-cache");
+        assert_eq!(cm.source, "web/charts/cache/templates/cm.yaml#0");
+        let cm: Json = serde_yaml::from_str(&cm.yaml).unwrap();
+        assert_eq!(cm["data"]["size"], "128");
+        assert_eq!(cm["data"]["region"], "eu");
+        std::fs::remove_dir_all(root).ok();
+    }
+}
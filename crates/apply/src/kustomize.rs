@@ -0,0 +1,1195 @@
+//! Offline kustomization rendering.
+//!
+//! Covers the parts of kustomize our overlays use: `resources` (files and
+//! nested kustomizations), `namePrefix`/`nameSuffix`, `namespace`,
+//! `commonLabels`/`commonAnnotations`, strategic-merge and JSON 6902 patches
+//! (`patchesStrategicMerge`, `patchesJson6902` and `patches`), `images`, and
+//! `configMapGenerator`/`secretGenerator`. Each kustomization is rendered
+//! bottom-up: its resources and generators are collected, patched, then
+//! namespaced, renamed and labelled. Generated objects get kustomize's
+//! content-hash name suffix once the whole tree is rendered, and references
+//! to renamed ConfigMaps and Secrets in pod specs follow along. Remote bases
+//! and fields outside that list are rejected rather than silently ignored.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, bail, Context, Result};
+use base64::Engine as _;
+use serde::Deserialize;
+use serde_json::{Map, Value as Json};
+use sha2::{Digest, Sha256};
+
+use crate::Manifest;
+
+const KUSTOMIZATION_FILES: &[&str] = &["kustomization.yaml", "kustomization.yml", "Kustomization"];
+
+/// Fields we accept besides the ones rendered; anything else is an error.
+const IGNORED_FIELDS: &[&str] = &["apiVersion", "kind", "metadata", "sortOptions"];
+
+/// Kinds left alone by the `namespace` transform.
+const CLUSTER_SCOPED: &[&str] = &[
+    "Namespace",
+    "Node",
+    "PersistentVolume",
+    "StorageClass",
+    "PriorityClass",
+    "ClusterRole",
+    "ClusterRoleBinding",
+    "CustomResourceDefinition",
+    "APIService",
+    "MutatingWebhookConfiguration",
+    "ValidatingWebhookConfiguration",
+    "ValidatingAdmissionPolicy",
+    "ValidatingAdmissionPolicyBinding",
+    "IngressClass",
+    "RuntimeClass",
+    "CSIDriver",
+    "CSINode",
+    "VolumeAttachment",
+    "VolumeSnapshotClass",
+    "FlowSchema",
+    "PriorityLevelConfiguration",
+];
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+struct Kustomization {
+    resources: Vec<String>,
+    bases: Vec<String>,
+    name_prefix: Option<String>,
+    name_suffix: Option<String>,
+    namespace: Option<String>,
+    common_labels: BTreeMap<String, String>,
+    common_annotations: BTreeMap<String, String>,
+    patches_strategic_merge: Vec<String>,
+    patches_json6902: Vec<PatchEntry>,
+    patches: Vec<PatchEntry>,
+    images: Vec<ImageEntry>,
+    config_map_generator: Vec<GeneratorArgs>,
+    secret_generator: Vec<GeneratorArgs>,
+    generator_options: GeneratorOptions,
+    #[serde(flatten)]
+    other: BTreeMap<String, serde_yaml::Value>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct PatchEntry {
+    path: Option<String>,
+    patch: Option<String>,
+    target: Option<Target>,
+}
+
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+struct Target {
+    group: Option<String>,
+    version: Option<String>,
+    kind: Option<String>,
+    name: Option<String>,
+    namespace: Option<String>,
+    label_selector: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+struct ImageEntry {
+    name: String,
+    new_name: Option<String>,
+    new_tag: Option<String>,
+    digest: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+struct GeneratorArgs {
+    name: String,
+    namespace: Option<String>,
+    behavior: Option<String>,
+    literals: Vec<String>,
+    files: Vec<String>,
+    envs: Vec<String>,
+    env: Option<String>,
+    #[serde(rename = "type")]
+    secret_type: Option<String>,
+    options: Option<GeneratorOptions>,
+}
+
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+struct GeneratorOptions {
+    labels: BTreeMap<String, String>,
+    annotations: BTreeMap<String, String>,
+    disable_name_suffix_hash: bool,
+}
+
+/// A rendered object plus what patches and generators need to find it.
+#[derive(Debug, Clone)]
+struct Res {
+    obj: Json,
+    source: String,
+    /// Name as first declared, before any prefix/suffix.
+    orig_name: String,
+    /// Generated objects still waiting for their content-hash suffix.
+    hash_suffix: bool,
+}
+
+impl Res {
+    fn kind(&self) -> &str {
+        self.obj.get("kind").and_then(|v| v.as_str()).unwrap_or("")
+    }
+
+    fn name(&self) -> &str {
+        self.obj
+            .pointer("/metadata/name")
+            .and_then(|v| v.as_str())
+            .unwrap_or("")
+    }
+
+    fn namespace(&self) -> Option<&str> {
+        self.obj
+            .pointer("/metadata/namespace")
+            .and_then(|v| v.as_str())
+    }
+
+    fn set_name(&mut self, name: String) {
+        meta_mut(&mut self.obj).insert("name".into(), Json::String(name));
+    }
+}
+
+/// Render the kustomization in `dir` into manifests (`source` names the file
+/// or generator each object came from).
+pub fn render_kustomization(dir: impl AsRef<Path>) -> Result<Vec<Manifest>> {
+    let mut stack = Vec::new();
+    let mut resources = render_dir(dir.as_ref(), &mut stack)?;
+    // Hash suffixes go on last so they cover every transform, as in kustomize.
+    let mut renames = Vec::new();
+    for r in resources.iter_mut().filter(|r| r.hash_suffix) {
+        let old = r.name().to_string();
+        let new = format!("{}-{}", old, content_hash(&r.obj)?);
+        renames.push((r.kind().to_string(), old, new.clone()));
+        r.set_name(new);
+        r.hash_suffix = false;
+    }
+    fix_name_references(&mut resources, &renames);
+    let mut out = Vec::with_capacity(resources.len());
+    for r in resources {
+        crate::manifest::push_object(r.obj, &r.source, &mut out)?;
+    }
+    Ok(out)
+}
+
+fn render_dir(dir: &Path, stack: &mut Vec<PathBuf>) -> Result<Vec<Res>> {
+    let canon = dir
+        .canonicalize()
+        .with_context(|| format!("reading {}", dir.display()))?;
+    if stack.contains(&canon) {
+        bail!("kustomization cycle at {}", dir.display());
+    }
+    let file = KUSTOMIZATION_FILES
+        .iter()
+        .map(|f| dir.join(f))
+        .find(|p| p.is_file())
+        .ok_or_else(|| anyhow!("{}: no kustomization.yaml", dir.display()))?;
+    let text =
+        std::fs::read_to_string(&file).with_context(|| format!("reading {}", file.display()))?;
+    let k: Kustomization =
+        serde_yaml::from_str(&text).with_context(|| format!("parsing {}", file.display()))?;
+    if let Some(field) = k
+        .other
+        .keys()
+        .find(|f| !IGNORED_FIELDS.contains(&f.as_str()))
+    {
+        bail!("{}: `{}` is not supported", file.display(), field);
+    }
+    stack.push(canon);
+    let res = render(&k, dir, &file.display().to_string(), stack);
+    stack.pop();
+    res
+}
+
+fn render(k: &Kustomization, dir: &Path, file: &str, stack: &mut Vec<PathBuf>) -> Result<Vec<Res>> {
+    let mut resources = Vec::new();
+    for entry in k.resources.iter().chain(k.bases.iter()) {
+        if entry.contains("://") || entry.starts_with("github.com/") {
+            bail!("{}: remote resource {} is not supported", file, entry);
+        }
+        let path = dir.join(entry);
+        if path.is_dir() {
+            resources.extend(render_dir(&path, stack)?);
+        } else {
+            let text = std::fs::read_to_string(&path)
+                .with_context(|| format!("{}: reading {}", file, path.display()))?;
+            for m in crate::manifest::split_manifests(&text, &path.display().to_string())? {
+                let obj: Json = serde_yaml::from_str(&m.yaml)?;
+                resources.push(Res {
+                    obj,
+                    source: m.source,
+                    orig_name: m.name,
+                    hash_suffix: false,
+                });
+            }
+        }
+    }
+    for (i, g) in k.config_map_generator.iter().enumerate() {
+        let src = format!("{}:configMapGenerator[{}]", file, i);
+        let res = generate(g, "ConfigMap", &k.generator_options, dir, &src)?;
+        merge_generated(&mut resources, res, g.behavior.as_deref(), &src)?;
+    }
+    for (i, g) in k.secret_generator.iter().enumerate() {
+        let src = format!("{}:secretGenerator[{}]", file, i);
+        let res = generate(g, "Secret", &k.generator_options, dir, &src)?;
+        merge_generated(&mut resources, res, g.behavior.as_deref(), &src)?;
+    }
+
+    for p in &k.patches_strategic_merge {
+        let patch = load_patch(p.contains('\n').then_some(p.as_str()), Some(p), dir, file)?;
+        apply_patch(&mut resources, &patch, None, file)?;
+    }
+    for p in k.patches_json6902.iter().chain(k.patches.iter()) {
+        let patch = load_patch(p.patch.as_deref(), p.path.as_deref(), dir, file)?;
+        apply_patch(&mut resources, &patch, p.target.as_ref(), file)?;
+    }
+
+    if let Some(ns) = &k.namespace {
+        for r in resources
+            .iter_mut()
+            .filter(|r| !CLUSTER_SCOPED.contains(&r.kind()))
+        {
+            meta_mut(&mut r.obj).insert("namespace".into(), Json::String(ns.clone()));
+        }
+    }
+    if k.name_prefix.is_some() || k.name_suffix.is_some() {
+        let mut renames = Vec::new();
+        for r in resources
+            .iter_mut()
+            .filter(|r| !matches!(r.kind(), "Namespace" | "CustomResourceDefinition"))
+        {
+            let old = r.name().to_string();
+            let new = format!(
+                "{}{}{}",
+                k.name_prefix.as_deref().unwrap_or(""),
+                old,
+                k.name_suffix.as_deref().unwrap_or("")
+            );
+            renames.push((r.kind().to_string(), old, new.clone()));
+            r.set_name(new);
+        }
+        fix_name_references(&mut resources, &renames);
+    }
+    for r in resources.iter_mut() {
+        add_common_labels(&mut r.obj, &k.common_labels);
+        add_common_annotations(&mut r.obj, &k.common_annotations);
+        for img in &k.images {
+            set_images(&mut r.obj, img);
+        }
+    }
+    Ok(resources)
+}
+
+// ---------------- generators ----------------
+
+fn generate(
+    g: &GeneratorArgs,
+    kind: &str,
+    global: &GeneratorOptions,
+    dir: &Path,
+    src: &str,
+) -> Result<Res> {
+    if g.name.is_empty() {
+        bail!("{}: generator without a name", src);
+    }
+    let mut entries: BTreeMap<String, Vec<u8>> = BTreeMap::new();
+    for lit in &g.literals {
+        let (k, v) = lit
+            .split_once('=')
+            .ok_or_else(|| anyhow!("{}: literal {:?} is not key=value", src, lit))?;
+        entries.insert(k.trim().to_string(), unquote(v).as_bytes().to_vec());
+    }
+    for f in &g.files {
+        let (key, path) = match f.split_once('=') {
+            Some((k, p)) => (k.to_string(), p),
+            None => (
+                Path::new(f)
+                    .file_name()
+                    .and_then(|n| n.to_str())
+                    .unwrap_or(f)
+                    .to_string(),
+                f.as_str(),
+            ),
+        };
+        let bytes =
+            std::fs::read(dir.join(path)).with_context(|| format!("{}: reading {}", src, path))?;
+        entries.insert(key, bytes);
+    }
+    for env in g.envs.iter().chain(g.env.iter()) {
+        let text = std::fs::read_to_string(dir.join(env))
+            .with_context(|| format!("{}: reading {}", src, env))?;
+        for line in text.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            match line.split_once('=') {
+                Some((k, v)) => entries.insert(k.trim().to_string(), v.as_bytes().to_vec()),
+                // A bare key takes its value from the environment, like kustomize.
+                None => entries.insert(
+                    line.to_string(),
+                    std::env::var(line).unwrap_or_default().into_bytes(),
+                ),
+            };
+        }
+    }
+
+    let local = g.options.clone().unwrap_or_default();
+    let mut labels = global.labels.clone();
+    labels.extend(local.labels);
+    let mut annotations = global.annotations.clone();
+    annotations.extend(local.annotations);
+    let mut meta = Map::new();
+    meta.insert("name".into(), Json::String(g.name.clone()));
+    if let Some(ns) = &g.namespace {
+        meta.insert("namespace".into(), Json::String(ns.clone()));
+    }
+    if !labels.is_empty() {
+        meta.insert("labels".into(), serde_json::to_value(&labels)?);
+    }
+    if !annotations.is_empty() {
+        meta.insert("annotations".into(), serde_json::to_value(&annotations)?);
+    }
+    let mut obj = Map::new();
+    obj.insert("apiVersion".into(), Json::String("v1".into()));
+    obj.insert("kind".into(), Json::String(kind.into()));
+    obj.insert("metadata".into(), Json::Object(meta));
+    let b64 = |v: &[u8]| Json::String(base64::engine::general_purpose::STANDARD.encode(v));
+    if kind == "Secret" {
+        obj.insert(
+            "type".into(),
+            Json::String(g.secret_type.clone().unwrap_or_else(|| "Opaque".into())),
+        );
+        let data: Map<String, Json> = entries.iter().map(|(k, v)| (k.clone(), b64(v))).collect();
+        obj.insert("data".into(), Json::Object(data));
+    } else {
+        let mut data = Map::new();
+        let mut binary = Map::new();
+        for (k, v) in &entries {
+            match std::str::from_utf8(v) {
+                Ok(s) => data.insert(k.clone(), Json::String(s.to_string())),
+                Err(_) => binary.insert(k.clone(), b64(v)),
+            };
+        }
+        obj.insert("data".into(), Json::Object(data));
+        if !binary.is_empty() {
+            obj.insert("binaryData".into(), Json::Object(binary));
+        }
+    }
+    Ok(Res {
+        obj: Json::Object(obj),
+        source: src.to_string(),
+        orig_name: g.name.clone(),
+        hash_suffix: !(global.disable_name_suffix_hash || local.disable_name_suffix_hash),
+    })
+}
+
+fn unquote(v: &str) -> &str {
+    let v = v.trim();
+    for q in ['"', '\''] {
+        if let Some(inner) = v.strip_prefix(q).and_then(|s| s.strip_suffix(q)) {
+            return inner;
+        }
+    }
+    v
+}
+
+/// Add a generated object, or fold it into the same-named one from a base
+/// for `behavior: merge` / `replace`.
+fn merge_generated(
+    resources: &mut Vec<Res>,
+    generated: Res,
+    behavior: Option<&str>,
+    src: &str,
+) -> Result<()> {
+    let behavior = behavior.unwrap_or("create");
+    let existing = resources
+        .iter_mut()
+        .find(|r| r.kind() == generated.kind() && r.orig_name == generated.orig_name);
+    match (behavior, existing) {
+        ("create", None) => resources.push(generated),
+        ("create", Some(_)) => bail!(
+            "{}: {} {} already exists; use behavior: merge or replace",
+            src,
+            generated.kind(),
+            generated.orig_name
+        ),
+        ("merge", Some(base)) => {
+            for field in ["data", "binaryData"] {
+                if let Some(Json::Object(add)) = generated.obj.get(field) {
+                    let dst = base
+                        .obj
+                        .as_object_mut()
+                        .unwrap()
+                        .entry(field)
+                        .or_insert_with(|| Json::Object(Map::new()));
+                    if let Some(dst) = dst.as_object_mut() {
+                        dst.extend(add.clone());
+                    }
+                }
+            }
+            for field in ["labels", "annotations"] {
+                if let Some(Json::Object(add)) =
+                    generated.obj.pointer(&format!("/metadata/{}", field))
+                {
+                    let dst = meta_mut(&mut base.obj)
+                        .entry(field)
+                        .or_insert_with(|| Json::Object(Map::new()));
+                    if let Some(dst) = dst.as_object_mut() {
+                        dst.extend(add.clone());
+                    }
+                }
+            }
+            base.hash_suffix |= generated.hash_suffix;
+        }
+        ("replace", Some(base)) => {
+            let name = base.name().to_string();
+            let ns = base.namespace().map(|s| s.to_string());
+            base.obj = generated.obj;
+            base.set_name(name);
+            if let Some(ns) = ns {
+                meta_mut(&mut base.obj).insert("namespace".into(), Json::String(ns));
+            }
+            base.hash_suffix = generated.hash_suffix;
+            base.source = generated.source;
+        }
+        (b, None) if b == "merge" || b == "replace" => bail!(
+            "{}: behavior {} but no {} {} to {}",
+            src,
+            b,
+            generated.kind(),
+            generated.orig_name,
+            b
+        ),
+        (b, _) => bail!("{}: unknown generator behavior {:?}", src, b),
+    }
+    Ok(())
+}
+
+/// kustomize's name suffix: the first 10 hex chars of a SHA-256 over the
+/// kind, name and data, with vowel-ish characters swapped so the suffix can
+/// never spell words.
+fn content_hash(obj: &Json) -> Result<String> {
+    let mut m = BTreeMap::new();
+    for field in ["kind", "data", "binaryData", "type"] {
+        if let Some(v) = obj.get(field) {
+            let empty = v.as_object().map(|o| o.is_empty()).unwrap_or(false);
+            if !(field == "binaryData" && empty) {
+                m.insert(field, v.clone());
+            }
+        }
+    }
+    m.insert(
+        "name",
+        obj.pointer("/metadata/name").cloned().unwrap_or(Json::Null),
+    );
+    if !m.contains_key("data") {
+        m.insert("data", Json::Object(Map::new()));
+    }
+    // Match Go's encoding/json, which escapes HTML characters.
+    let encoded = serde_json::to_string(&m)?
+        .replace('<', "\\u003c")
+        .replace('>', "\\u003e")
+        .replace('&', "\\u0026")
+        .replace('\u{2028}', "\\u2028")
+        .replace('\u{2029}', "\\u2029");
+    let hex: String = Sha256::digest(encoded.as_bytes())
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect();
+    Ok(hex[..10]
+        .chars()
+        .map(|c| match c {
+            '0' => 'g',
+            '1' => 'h',
+            '3' => 'k',
+            'a' => 'm',
+            'e' => 't',
+            c => c,
+        })
+        .collect())
+}
+
+// ---------------- patches ----------------
+
+fn load_patch(inline: Option<&str>, path: Option<&str>, dir: &Path, file: &str) -> Result<Json> {
+    let text = match (inline, path) {
+        (Some(p), _) => p.to_string(),
+        (None, Some(path)) => std::fs::read_to_string(dir.join(path))
+            .with_context(|| format!("{}: reading patch {}", file, path))?,
+        (None, None) => bail!("{}: patch needs `path` or `patch`", file),
+    };
+    let val: serde_yaml::Value =
+        serde_yaml::from_str(&text).with_context(|| format!("{}: parsing patch", file))?;
+    Ok(serde_json::to_value(val)?)
+}
+
+/// JSON 6902 patches (a list of operations) need a target; strategic-merge
+/// patches target themselves unless a target is given.
+fn apply_patch(
+    resources: &mut [Res],
+    patch: &Json,
+    target: Option<&Target>,
+    file: &str,
+) -> Result<()> {
+    let own_target;
+    let target = match (target, patch) {
+        (Some(t), _) => t,
+        (None, Json::Array(_)) => bail!("{}: JSON 6902 patch without a target", file),
+        (None, _) => {
+            let api_version = patch
+                .get("apiVersion")
+                .and_then(|v| v.as_str())
+                .unwrap_or("");
+            let (group, version) = match api_version.split_once('/') {
+                Some((g, v)) => (Some(g.to_string()), Some(v.to_string())),
+                None if !api_version.is_empty() => {
+                    (Some(String::new()), Some(api_version.to_string()))
+                }
+                None => (None, None),
+            };
+            own_target = Target {
+                group,
+                version,
+                kind: patch
+                    .get("kind")
+                    .and_then(|v| v.as_str())
+                    .map(|s| s.to_string()),
+                name: patch
+                    .pointer("/metadata/name")
+                    .and_then(|v| v.as_str())
+                    .map(|s| s.to_string()),
+                namespace: patch
+                    .pointer("/metadata/namespace")
+                    .and_then(|v| v.as_str())
+                    .map(|s| s.to_string()),
+                label_selector: None,
+            };
+            &own_target
+        }
+    };
+    let mut hit = false;
+    for r in resources.iter_mut().filter(|r| target_matches(target, r)) {
+        hit = true;
+        match patch {
+            Json::Array(ops) => json6902(&mut r.obj, ops)
+                .with_context(|| format!("{}: patching {}/{}", file, r.kind(), r.name()))?,
+            _ => {
+                let name = r.name().to_string();
+                let mut p = patch.clone();
+                // The patch names its target by the original name; keep the current one.
+                if let Some(meta) = p.get_mut("metadata").and_then(|m| m.as_object_mut()) {
+                    meta.remove("name");
+                }
+                strategic_merge(&mut r.obj, &p);
+                r.set_name(name);
+            }
+        }
+    }
+    if !hit {
+        bail!(
+            "{}: patch target {}/{} not found",
+            file,
+            target.kind.as_deref().unwrap_or("*"),
+            target.name.as_deref().unwrap_or("*")
+        );
+    }
+    Ok(())
+}
+
+fn target_matches(t: &Target, r: &Res) -> bool {
+    let api_version = r
+        .obj
+        .get("apiVersion")
+        .and_then(|v| v.as_str())
+        .unwrap_or("");
+    let (group, version) = api_version.split_once('/').unwrap_or(("", api_version));
+    let eq = |want: &Option<String>, have: &str| want.as_deref().map(|w| w == have).unwrap_or(true);
+    let name_ok = match &t.name {
+        None => true,
+        Some(n) => [r.name(), r.orig_name.as_str()].iter().any(|have| {
+            *have == n
+                || regex::Regex::new(&format!("^(?:{})$", n))
+                    .map(|re| re.is_match(have))
+                    .unwrap_or(false)
+        }),
+    };
+    let labels_ok = match &t.label_selector {
+        None => true,
+        Some(sel) => sel
+            .split(',')
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .all(|term| {
+                let (k, v) = term.split_once('=').unwrap_or((term, ""));
+                let have = r
+                    .obj
+                    .pointer("/metadata/labels")
+                    .and_then(|l| l.get(k.trim_end_matches('=')));
+                match have.and_then(|h| h.as_str()) {
+                    Some(h) => v.is_empty() || h == v.trim_start_matches('='),
+                    None => false,
+                }
+            }),
+    };
+    eq(&t.group, group)
+        && eq(&t.version, version)
+        && eq(&t.kind, r.kind())
+        && t.namespace
+            .as_deref()
+            .map(|ns| r.namespace() == Some(ns))
+            .unwrap_or(true)
+        && name_ok
+        && labels_ok
+}
+
+/// Strategic merge: maps merge recursively, `null` deletes, lists of objects
+/// merge by key (`name`, `mountPath`, `containerPort`, ...), other lists are
+/// replaced. Honors `$patch: delete` and `$patch: replace`.
+fn strategic_merge(target: &mut Json, patch: &Json) {
+    let Json::Object(p) = patch else {
+        *target = patch.clone();
+        return;
+    };
+    if p.get("$patch").and_then(|v| v.as_str()) == Some("replace") {
+        let mut p = p.clone();
+        p.remove("$patch");
+        *target = Json::Object(p);
+        return;
+    }
+    if !target.is_object() {
+        *target = Json::Object(Map::new());
+    }
+    let t = target.as_object_mut().unwrap();
+    for (k, v) in p {
+        if k.starts_with('$') {
+            continue;
+        }
+        match v {
+            Json::Null => {
+                t.remove(k);
+            }
+            Json::Array(items) => {
+                let merged = match t.get(k) {
+                    Some(Json::Array(base)) => merge_list(base, items),
+                    _ => strip_directives(items),
+                };
+                t.insert(k.clone(), Json::Array(merged));
+            }
+            Json::Object(_) => strategic_merge(t.entry(k.clone()).or_insert(Json::Null), v),
+            _ => {
+                t.insert(k.clone(), v.clone());
+            }
+        }
+    }
+}
+
+fn strip_directives(items: &[Json]) -> Vec<Json> {
+    items
+        .iter()
+        .filter(|i| i.get("$patch").is_none())
+        .cloned()
+        .collect()
+}
+
+fn merge_list(base: &[Json], patch: &[Json]) -> Vec<Json> {
+    if patch
+        .iter()
+        .any(|i| i.get("$patch").and_then(|v| v.as_str()) == Some("replace"))
+    {
+        return strip_directives(patch);
+    }
+    let key = crate::diff::MERGE_KEYS.iter().copied().find(|k| {
+        !patch.is_empty()
+            && patch.iter().all(|i| i.get(*k).is_some())
+            && base.iter().all(|i| i.get(*k).is_some())
+    });
+    let Some(key) = key else {
+        return strip_directives(patch);
+    };
+    let mut out = base.to_vec();
+    for item in patch {
+        let pos = out.iter().position(|b| b.get(key) == item.get(key));
+        let delete = item.get("$patch").and_then(|v| v.as_str()) == Some("delete");
+        match (pos, delete) {
+            (Some(i), true) => {
+                out.remove(i);
+            }
+            (None, true) => {}
+            (Some(i), false) => strategic_merge(&mut out[i], item),
+            (None, false) => {
+                let mut fresh = Json::Null;
+                strategic_merge(&mut fresh, item);
+                out.push(fresh);
+            }
+        }
+    }
+    out
+}
+
+fn json6902(doc: &mut Json, ops: &[Json]) -> Result<()> {
+    for op in ops {
+        let field = |f: &str| op.get(f).and_then(|v| v.as_str());
+        let path = field("path").ok_or_else(|| anyhow!("operation without path"))?;
+        match field("op").unwrap_or("") {
+            "add" => add_at(doc, path, op.get("value").cloned().unwrap_or(Json::Null))?,
+            "remove" => {
+                remove_at(doc, path)?;
+            }
+            "replace" => {
+                remove_at(doc, path)?;
+                add_at(doc, path, op.get("value").cloned().unwrap_or(Json::Null))?;
+            }
+            "move" => {
+                let from = field("from").ok_or_else(|| anyhow!("move without from"))?;
+                let v = remove_at(doc, from)?;
+                add_at(doc, path, v)?;
+            }
+            "copy" => {
+                let from = field("from").ok_or_else(|| anyhow!("copy without from"))?;
+                let v = doc
+                    .pointer(from)
+                    .cloned()
+                    .ok_or_else(|| anyhow!("copy: {} not found", from))?;
+                add_at(doc, path, v)?;
+            }
+            "test" => {
+                if doc.pointer(path) != op.get("value") {
+                    bail!("test failed at {}", path);
+                }
+            }
+            other => bail!("unknown patch op {:?}", other),
+        }
+    }
+    Ok(())
+}
+
+fn split_pointer(path: &str) -> Result<(String, String)> {
+    let i = path
+        .rfind('/')
+        .ok_or_else(|| anyhow!("invalid JSON pointer {:?}", path))?;
+    let last = path[i + 1..].replace("~1", "/").replace("~0", "~");
+    Ok((path[..i].to_string(), last))
+}
+
+fn add_at(doc: &mut Json, path: &str, value: Json) -> Result<()> {
+    if path.is_empty() {
+        *doc = value;
+        return Ok(());
+    }
+    let (parent, last) = split_pointer(path)?;
+    match doc.pointer_mut(&parent) {
+        Some(Json::Object(m)) => {
+            m.insert(last, value);
+        }
+        Some(Json::Array(a)) => {
+            let idx = if last == "-" {
+                a.len()
+            } else {
+                last.parse::<usize>()
+                    .ok()
+                    .filter(|i| *i <= a.len())
+                    .ok_or_else(|| anyhow!("index {} out of range at {}", last, path))?
+            };
+            a.insert(idx, value);
+        }
+        _ => bail!("path {} not found", parent),
+    }
+    Ok(())
+}
+
+fn remove_at(doc: &mut Json, path: &str) -> Result<Json> {
+    let (parent, last) = split_pointer(path)?;
+    let removed = match doc.pointer_mut(&parent) {
+        Some(Json::Object(m)) => m.remove(&last),
+        Some(Json::Array(a)) => last
+            .parse::<usize>()
+            .ok()
+            .filter(|i| *i < a.len())
+            .map(|i| a.remove(i)),
+        _ => None,
+    };
+    removed.ok_or_else(|| anyhow!("path {} not found", path))
+}
+
+// ---------------- transforms ----------------
+
+fn meta_mut(obj: &mut Json) -> &mut Map<String, Json> {
+    if !obj.is_object() {
+        *obj = Json::Object(Map::new());
+    }
+    let meta = obj
+        .as_object_mut()
+        .unwrap()
+        .entry("metadata")
+        .or_insert_with(|| Json::Object(Map::new()));
+    if !meta.is_object() {
+        *meta = Json::Object(Map::new());
+    }
+    meta.as_object_mut().unwrap()
+}
+
+fn merge_into(obj: &mut Json, pointer: &str, add: &BTreeMap<String, String>, create: bool) {
+    if add.is_empty() {
+        return;
+    }
+    let (parent, last) = match pointer.rsplit_once('/') {
+        Some(p) => p,
+        None => return,
+    };
+    let Some(Json::Object(p)) = obj.pointer_mut(parent) else {
+        return;
+    };
+    if !create && !p.contains_key(last) {
+        return;
+    }
+    let map = p
+        .entry(last.to_string())
+        .or_insert_with(|| Json::Object(Map::new()));
+    if let Some(m) = map.as_object_mut() {
+        for (k, v) in add {
+            m.insert(k.clone(), Json::String(v.clone()));
+        }
+    }
+}
+
+/// Pod template metadata pointer for workload kinds.
+fn template_pointer(kind: &str) -> Option<&'static str> {
+    match kind {
+        "Deployment"
+        | "ReplicaSet"
+        | "StatefulSet"
+        | "DaemonSet"
+        | "Job"
+        | "ReplicationController" => Some("/spec/template"),
+        "CronJob" => Some("/spec/jobTemplate/spec/template"),
+        _ => None,
+    }
+}
+
+fn add_common_labels(obj: &mut Json, labels: &BTreeMap<String, String>) {
+    if labels.is_empty() {
+        return;
+    }
+    meta_mut(obj);
+    merge_into(obj, "/metadata/labels", labels, true);
+    let kind = obj
+        .get("kind")
+        .and_then(|v| v.as_str())
+        .unwrap_or("")
+        .to_string();
+    if let Some(tpl) = template_pointer(&kind) {
+        if obj.pointer(tpl).is_some() {
+            if obj.pointer(&format!("{}/metadata", tpl)).is_none() {
+                if let Some(Json::Object(t)) = obj.pointer_mut(tpl) {
+                    t.insert("metadata".into(), Json::Object(Map::new()));
+                }
+            }
+            merge_into(obj, &format!("{}/metadata/labels", tpl), labels, true);
+        }
+        // Job selectors are generated by the apiserver.
+        if kind != "Job" && kind != "CronJob" && obj.pointer("/spec").is_some() {
+            if obj.pointer("/spec/selector").is_none() {
+                if let Some(Json::Object(s)) = obj.pointer_mut("/spec") {
+                    s.insert("selector".into(), Json::Object(Map::new()));
+                }
+            }
+            merge_into(obj, "/spec/selector/matchLabels", labels, true);
+        }
+    } else if kind == "Service" {
+        merge_into(obj, "/spec/selector", labels, true);
+    }
+}
+
+fn add_common_annotations(obj: &mut Json, annotations: &BTreeMap<String, String>) {
+    if annotations.is_empty() {
+        return;
+    }
+    meta_mut(obj);
+    merge_into(obj, "/metadata/annotations", annotations, true);
+    let kind = obj
+        .get("kind")
+        .and_then(|v| v.as_str())
+        .unwrap_or("")
+        .to_string();
+    if let Some(tpl) = template_pointer(&kind) {
+        merge_into(
+            obj,
+            &format!("{}/metadata/annotations", tpl),
+            annotations,
+            true,
+        );
+    }
+}
+
+/// `name[:tag][@digest]` -> (name, rest including the separator)
+fn split_image(image: &str) -> (&str, &str) {
+    if let Some(at) = image.find('@') {
+        return (&image[..at], &image[at..]);
+    }
+    let slash = image.rfind('/').map(|i| i + 1).unwrap_or(0);
+    match image[slash..].rfind(':') {
+        Some(c) => (&image[..slash + c], &image[slash + c..]),
+        None => (image, ""),
+    }
+}
+
+fn set_images(obj: &mut Json, img: &ImageEntry) {
+    match obj {
+        Json::Object(m) => {
+            for (k, v) in m.iter_mut() {
+                if k == "containers" || k == "initContainers" || k == "ephemeralContainers" {
+                    if let Json::Array(cs) = v {
+                        for c in cs.iter_mut() {
+                            if let Some(Json::String(image)) = c.get_mut("image") {
+                                let (name, rest) = split_image(image);
+                                if name != img.name {
+                                    continue;
+                                }
+                                let new_name = img.new_name.as_deref().unwrap_or(name);
+                                *image = match (&img.digest, &img.new_tag) {
+                                    (Some(d), _) => format!("{}@{}", new_name, d),
+                                    (None, Some(t)) => format!("{}:{}", new_name, t),
+                                    (None, None) => format!("{}{}", new_name, rest),
+                                };
+                            }
+                        }
+                    }
+                } else {
+                    set_images(v, img);
+                }
+            }
+        }
+        Json::Array(a) => a.iter_mut().for_each(|v| set_images(v, img)),
+        _ => {}
+    }
+}
+
+/// Where pod specs reference ConfigMaps and Secrets by name.
+fn name_ref_pointers(kind: &str) -> &'static [&'static str] {
+    match kind {
+        "ConfigMap" => &[
+            "volumes/*/configMap/name",
+            "volumes/*/projected/sources/*/configMap/name",
+            "*containers/*/env/*/valueFrom/configMapKeyRef/name",
+            "*containers/*/envFrom/*/configMapRef/name",
+        ],
+        "Secret" => &[
+            "volumes/*/secret/secretName",
+            "volumes/*/projected/sources/*/secret/name",
+            "*containers/*/env/*/valueFrom/secretKeyRef/name",
+            "*containers/*/envFrom/*/secretRef/name",
+            "imagePullSecrets/*/name",
+        ],
+        _ => &[],
+    }
+}
+
+fn pod_spec_pointer(kind: &str) -> Option<String> {
+    match kind {
+        "Pod" => Some("/spec".into()),
+        k => template_pointer(k).map(|t| format!("{}/spec", t)),
+    }
+}
+
+/// Walk `parts` below `node`; `*` matches every list item and a leading `*`
+/// on a key matches `containers`, `initContainers` and `ephemeralContainers`.
+fn rename_at(node: &mut Json, parts: &[&str], from: &str, to: &str) {
+    let Some((head, rest)) = parts.split_first() else {
+        if node.as_str() == Some(from) {
+            *node = Json::String(to.to_string());
+        }
+        return;
+    };
+    match (*head, node) {
+        ("*", Json::Array(items)) => items.iter_mut().for_each(|i| rename_at(i, rest, from, to)),
+        (key, Json::Object(m)) => {
+            if let Some(suffix) = key.strip_prefix('*') {
+                for prefix in ["c", "initC", "ephemeralC"] {
+                    let k = format!("{}{}", prefix, &suffix[1..]);
+                    if let Some(v) = m.get_mut(&k) {
+                        rename_at(v, rest, from, to);
+                    }
+                }
+            } else if let Some(v) = m.get_mut(key) {
+                rename_at(v, rest, from, to);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn fix_name_references(resources: &mut [Res], renames: &[(String, String, String)]) {
+    for (kind, from, to) in renames {
+        let pointers = name_ref_pointers(kind);
+        if pointers.is_empty() || from == to {
+            continue;
+        }
+        for r in resources.iter_mut() {
+            let Some(spec) = pod_spec_pointer(r.kind()) else {
+                continue;
+            };
+            let Some(spec) = r.obj.pointer_mut(&spec) else {
+                continue;
+            };
+            for p in pointers {
+                let parts: Vec<&str> = p.split('/').collect();
+                rename_at(spec, &parts, from, to);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::{temp_dir, write};
+
+    fn tree() -> PathBuf {
+        let root = temp_dir("kustomize");
+        write(
+            &root,
+            "base/kustomization.yaml",
+            "resources: [deploy.yaml, svc.yaml]\n\
+             commonLabels: {app: web}\n\
+             configMapGenerator:\n\
+             - name: web-config\n  literals: [MODE=base, LEVEL=info]\n",
+        );
+        write(
+            &root,
+            "base/deploy.yaml",
+            "apiVersion: apps/v1\nkind: Deployment\nmetadata: {name: web}\n\
+             spec:\n  replicas: 1\n  template:\n    spec:\n      containers:\n\
+             \x20     - name: app\n        image: nginx:1.25\n        envFrom:\n\
+             \x20       - configMapRef: {name: web-config}\n\
+             \x20     - name: sidecar\n        image: busybox\n",
+        );
+        write(
+            &root,
+            "base/svc.yaml",
+            "apiVersion: v1\nkind: Service\nmetadata: {name: web}\nspec:\n  ports: [{port: 80}]\n",
+        );
+        write(
+            &root,
+            "overlays/prod/kustomization.yaml",
+            "resources: [../../base]\n\
+             namespace: prod\n\
+             namePrefix: prod-\n\
+             images:\n- name: nginx\n  newTag: \"1.27\"\n\
+             configMapGenerator:\n- name: web-config\n  behavior: merge\n  literals: [MODE=prod]\n\
+             secretGenerator:\n- name: creds\n  literals: [token=s3cr3t]\n\
+             patchesStrategicMerge:\n- replicas.yaml\n\
+             patches:\n\
+             - target: {kind: Deployment, name: web}\n  patch: |\n\
+             \x20   - op: add\n      path: /metadata/annotations\n      value: {team: shop}\n\
+             - patch: |\n\
+             \x20   apiVersion: apps/v1\n    kind: Deployment\n    metadata: {name: web}\n\
+             \x20   spec:\n      template:\n        spec:\n          containers:\n\
+             \x20         - name: sidecar\n            $patch: delete\n",
+        );
+        write(
+            &root,
+            "overlays/prod/replicas.yaml",
+            "apiVersion: apps/v1\nkind: Deployment\nmetadata: {name: web}\nspec: {replicas: 3}\n",
+        );
+        root
+    }
+
+    fn find(out: &[Manifest], kind: &str) -> Json {
+        let m = out.iter().find(|m| m.kind == kind).unwrap();
+        serde_yaml::from_str(&m.yaml).unwrap()
+    }
+
+    #[test]
+    fn renders_overlay_on_base() {
+        let root = tree();
+        let out = render_kustomization(root.join("overlays/prod")).unwrap();
+        assert_eq!(out.len(), 4);
+        let deploy = find(&out, "Deployment");
+        assert_eq!(deploy["metadata"]["name"], "prod-web");
+        assert_eq!(deploy["metadata"]["namespace"], "prod");
+        assert_eq!(deploy["metadata"]["labels"]["app"], "web");
+        assert_eq!(deploy["metadata"]["annotations"]["team"], "shop");
+        assert_eq!(deploy["spec"]["replicas"], 3);
+        assert_eq!(deploy["spec"]["selector"]["matchLabels"]["app"], "web");
+        assert_eq!(
+            deploy["spec"]["template"]["metadata"]["labels"]["app"],
+            "web"
+        );
+        let containers = deploy["spec"]["template"]["spec"]["containers"]
+            .as_array()
+            .unwrap();
+        assert_eq!(containers.len(), 1, "sidecar deleted by $patch");
+        assert_eq!(containers[0]["image"], "nginx:1.27");
+
+        let cm = find(&out, "ConfigMap");
+        let cm_name = cm["metadata"]["name"].as_str().unwrap();
+        assert!(cm_name.starts_with("prod-web-config-"), "{}", cm_name);
+        assert_eq!(cm_name.len(), "prod-web-config-".len() + 10);
+        assert_eq!(cm["data"]["MODE"], "prod");
+        assert_eq!(cm["data"]["LEVEL"], "info");
+        // The workload follows the prefixed, hashed name.
+        assert_eq!(containers[0]["envFrom"][0]["configMapRef"]["name"], cm_name);
+
+        let secret = find(&out, "Secret");
+        assert_eq!(secret["data"]["token"], "czNjcjN0");
+        assert_eq!(secret["type"], "Opaque");
+        let svc = find(&out, "Service");
+        assert_eq!(svc["spec"]["selector"]["app"], "web");
+        assert!(out.iter().all(|m| m.namespace.as_deref() == Some("prod")));
+        std::fs::remove_dir_all(root).ok();
+    }
+
+    #[test]
+    fn hash_suffix_is_stable_and_word_safe() {
+        let cm = serde_json::json!({
+            "apiVersion": "v1",
+            "kind": "ConfigMap",
+            "metadata": {"name": "cm"},
+            "data": {"a": "b"},
+        });
+        let h = content_hash(&cm).unwrap();
+        assert_eq!(h.len(), 10);
+        assert!(!h.contains(['0', '1', '3', 'a', 'e']));
+        assert_eq!(h, content_hash(&cm).unwrap());
+    }
+
+    #[test]
+    fn json6902_ops_and_unsupported_fields() {
+        let mut doc = serde_json::json!({"spec": {"args": ["a"], "x": 1}});
+        json6902(
+            &mut doc,
+            serde_json::json!([
+                {"op": "add", "path": "/spec/args/-", "value": "b"},
+                {"op": "replace", "path": "/spec/x", "value": 2},
+                {"op": "copy", "from": "/spec/x", "path": "/spec/y"},
+                {"op": "remove", "path": "/spec/args/0"},
+                {"op": "test", "path": "/spec/y", "value": 2},
+            ])
+            .as_array()
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(
+            doc,
+            serde_json::json!({"spec": {"args": ["b"], "x": 2, "y": 2}})
+        );
+
+        let root = temp_dir("kustomize");
+        write(
+            &root,
+            "kustomization.yaml",
+            "resources: []\nhelmCharts: []\n",
+        );
+        let err = render_kustomization(&root).unwrap_err().to_string();
+        assert!(err.contains("`helmCharts` is not supported"), "{}", err);
+        std::fs::remove_dir_all(root).ok();
+    }
+}
@@ -16,6 +16,38 @@ use serde_json::Value as Json;
 use tracing::warn;
 use uuid::Uuid;
 
+pub mod applyset;
+pub mod conflict;
+pub mod diff;
+pub mod drift;
+pub mod gotmpl;
+pub mod helm;
+pub mod kustomize;
+pub mod manifest;
+pub mod ownership;
+pub mod policy;
+pub mod rollback;
+#[cfg(test)]
+mod testutil;
+pub mod threeway;
+pub use applyset::{apply_set, ApplySet, ApplySetResult, PruneItem};
+pub use conflict::{ApplyConflict, FieldConflict};
+pub use diff::{DiffReport, FieldChange};
+pub use drift::{scan_drift, watch_drift, DriftItem, DriftReport, DriftStatus};
+pub use helm::{render_chart, ChartOptions};
+pub use kustomize::render_kustomization;
+pub use manifest::{
+    diff_from_manifests, edit_from_manifests, load_manifests, BatchResult, BatchSummary, Manifest,
+    ObjectResult,
+};
+pub use ownership::{field_ownership, filter_ownership, FieldOwner, FieldOwnership};
+pub use policy::{
+    set_override as set_policy_override, set_pod_snapshot_source, Finding, PodSnapshotSource,
+    Policy, PolicyViolation, Severity,
+};
+pub use rollback::{rollback, RevisionNotFound, RollbackResult};
+pub use threeway::{ThreeWayEntry, ThreeWayReport, ThreeWayState};
+
 fn max_yaml_bytes() -> usize {
     std::env::var("ORKA_MAX_YAML_BYTES")
         .ok()
@@ -83,6 +115,22 @@ pub async fn edit_from_yaml(
     ns_override: Option<&str>,
     validate: bool,
     do_apply: bool,
+) -> Result<ApplyResult> {
+    edit_from_yaml_with(yaml, ns_override, validate, do_apply, false).await
+}
+
+/// [`edit_from_yaml`] with SSA `force`: take ownership of fields other
+/// managers hold. Without force, ownership conflicts fail with an
+/// [`ApplyConflict`] (downcast the error for the field/manager list); with
+/// force, the fields taken over are listed in `ApplyResult::warnings`.
+/// Policy findings ([`policy`]) are listed there too; error-level ones fail
+/// a real apply with a [`PolicyViolation`].
+pub async fn edit_from_yaml_with(
+    yaml: &str,
+    ns_override: Option<&str>,
+    validate: bool,
+    do_apply: bool,
+    force: bool,
 ) -> Result<ApplyResult> {
     let t0 = std::time::Instant::now();
     counter!("apply_attempts", 1u64);
@@ -116,9 +164,48 @@ pub async fn edit_from_yaml(
     };
     let summary = diff_summary(&tgt_json, &live_json.clone().unwrap_or(Json::Null));
 
+    // Policy findings become warnings; error-level ones stop a real apply.
+    let mut warnings = Vec::new();
+    if policy::enabled() {
+        let ctx = policy::PolicyContext {
+            pods: policy::pods_for_service(&client, &json, ns.as_deref()).await,
+        };
+        let findings = policy::demote_annotated(Policy::load()?.lint(&json, &ctx), &json);
+        counter!("apply_policy_findings_total", findings.len() as u64);
+        let blocked = policy::blocking(&findings);
+        if do_apply && !blocked.is_empty() {
+            counter!("apply_policy_blocked_total", 1u64);
+            return Err(PolicyViolation { findings: blocked }.into());
+        }
+        warnings.extend(findings.iter().map(|f| f.to_string()));
+    }
+
+    // With force, learn who owned the fields first so the takeover is visible.
+    if force && live_json.is_some() {
+        let pp = PatchParams::apply("orka").dry_run();
+        if let Err(e) = api.patch(&name, &pp, &Patch::Apply(&json)).await {
+            if let Some(c) = ApplyConflict::from_kube("dry-run failed", &e) {
+                counter!("apply_conflicts_forced_total", c.conflicts.len() as u64);
+                warnings.extend(
+                    c.conflicts.iter().map(|fc| {
+                        format!("took ownership of {} from \"{}\"", fc.field, fc.manager)
+                    }),
+                );
+            }
+        }
+    }
+    let apply_params = || {
+        let pp = PatchParams::apply("orka");
+        if force {
+            pp.force()
+        } else {
+            pp
+        }
+    };
+
     if !do_apply {
         // Dry-run: ask server to validate the SSA patch but don't persist
-        let pp = PatchParams::apply("orka").dry_run();
+        let pp = apply_params().dry_run();
         let res = api.patch(&name, &pp, &Patch::Apply(&json)).await;
         match res {
             Ok(_) => {
@@ -128,12 +215,16 @@ pub async fn edit_from_yaml(
                     dry_run: true,
                     applied: false,
                     new_rv: None,
-                    warnings: vec![],
+                    warnings,
                     summary,
                 });
             }
             Err(e) => {
                 counter!("apply_err", 1u64);
+                if let Some(c) = ApplyConflict::from_kube("dry-run failed", &e) {
+                    counter!("apply_conflicts_total", 1u64);
+                    return Err(c.into());
+                }
                 return Err(anyhow!("dry-run failed: {}", e));
             }
         }
@@ -164,11 +255,15 @@ pub async fn edit_from_yaml(
     }
 
     // Real apply (SSA)
-    let pp = PatchParams::apply("orka");
+    let pp = apply_params();
     let obj = match api.patch(&name, &pp, &Patch::Apply(&json)).await {
         Ok(o) => o,
         Err(e) => {
             counter!("apply_err", 1u64);
+            if let Some(c) = ApplyConflict::from_kube("server-side apply failed", &e) {
+                counter!("apply_conflicts_total", 1u64);
+                return Err(c.into());
+            }
             return Err(anyhow!("server-side apply failed: {}", e));
         }
     };
@@ -182,7 +277,7 @@ pub async fn edit_from_yaml(
         .map(|v| v == "1" || v.eq_ignore_ascii_case("true"))
         .unwrap_or(false);
     let is_secret = gvk.group.is_empty() && gvk.kind == "Secret";
-    if !disable_persist && !is_secret {
+    if !disable_persist {
         let uid = obj
             .metadata
             .uid
@@ -195,22 +290,30 @@ pub async fn edit_from_yaml(
             rv,
             ts: orka_persist::now_ts(),
             yaml_zstd: orka_persist::maybe_compress(yaml),
+            key: Some(orka_persist::ObjectKey {
+                cluster: orka_kubehub::active_context(),
+                gvk: gvk_key(&gvk),
+                namespace: obj.metadata.namespace.clone(),
+                name: name.clone(),
+            }),
         };
         match orka_persist::LogStore::open_default() {
+            // Secrets are only kept when the store encrypts them.
+            Ok(store) if is_secret && !store.encrypted() => {
+                warn!("skipping last-applied persist for Secret kind (store is not encrypted)");
+            }
             Ok(store) => {
                 let _ = store.put_last(la);
             }
             Err(e) => warn!(error = %e, "persist open failed; skipping last-applied save"),
         }
-    } else if is_secret {
-        warn!("skipping last-applied persist for Secret kind");
     }
 
     Ok(ApplyResult {
         dry_run: false,
         applied: true,
         new_rv,
-        warnings: vec![],
+        warnings,
         summary,
     })
 }
@@ -219,6 +322,39 @@ pub async fn diff_from_yaml(
     yaml: &str,
     ns_override: Option<&str>,
 ) -> Result<(DiffSummary, Option<DiffSummary>)> {
+    let (tgt_json, live_json, last_json) = load_diff_inputs(yaml, ns_override).await?;
+    let live_summary = diff_summary(&tgt_json, &live_json.unwrap_or(Json::Null));
+    let last_summary = last_json.map(|prev| diff_summary(&tgt_json, &prev));
+    Ok((live_summary, last_summary))
+}
+
+/// Like [`diff_from_yaml`] but with per-field changes and a unified YAML diff
+/// against live and, when recorded, the last-applied snapshot.
+pub async fn diff_report_from_yaml(
+    yaml: &str,
+    ns_override: Option<&str>,
+) -> Result<(DiffReport, Option<DiffReport>)> {
+    let (tgt_json, live_json, last_json) = load_diff_inputs(yaml, ns_override).await?;
+    let live = live_json.map(strip_identity).unwrap_or(Json::Null);
+    let live_report = DiffReport::new(&tgt_json, &live, "live");
+    let last_report = last_json.map(|prev| DiffReport::new(&tgt_json, &prev, "last-applied"));
+    Ok((live_report, last_report))
+}
+
+/// Three-way comparison of the manifest against live with the newest
+/// last-applied snapshot as base: which paths you changed, which drifted in
+/// the cluster since, and which both touched.
+pub async fn three_way_from_yaml(yaml: &str, ns_override: Option<&str>) -> Result<ThreeWayReport> {
+    let (tgt_json, live_json, last_json) = load_diff_inputs(yaml, ns_override).await?;
+    let live = live_json.map(strip_identity).unwrap_or(Json::Null);
+    Ok(ThreeWayReport::new(last_json.as_ref(), &live, &tgt_json))
+}
+
+/// Target, live and last-applied documents (noise stripped) for diffing.
+async fn load_diff_inputs(
+    yaml: &str,
+    ns_override: Option<&str>,
+) -> Result<(Json, Option<Json>, Option<Json>)> {
     let (json, gvk, name, ns) = parse_yaml_for_target(yaml, ns_override)?;
     let client = orka_kubehub::get_kube_client().await?;
     let (ar, namespaced) = find_api_resource(client.clone(), &gvk).await?;
@@ -239,10 +375,9 @@ pub async fn diff_from_yaml(
         ensure_metadata(&mut v, &name, ns.as_deref());
         v
     };
-    let live_summary = diff_summary(&tgt_json, &live_json.clone().unwrap_or(Json::Null));
 
     // Diff against last-applied if present
-    let last_summary = if let Some(uid_str) = live_json
+    let last_json = if let Some(uid_str) = live_json
         .as_ref()
         .and_then(|v| v.get("metadata"))
         .and_then(|m| m.get("uid"))
@@ -255,9 +390,7 @@ pub async fn diff_from_yaml(
                     if let Ok(prev_val_yaml) = serde_yaml::from_str::<serde_yaml::Value>(&prev_yaml)
                     {
                         let prev_json = serde_json::to_value(prev_val_yaml).unwrap_or(Json::Null);
-                        let prev = strip_noisy(prev_json);
-                        let sum = diff_summary(&tgt_json, &prev);
-                        Some(sum)
+                        Some(strip_noisy(prev_json))
                     } else {
                         None
                     }
@@ -274,7 +407,7 @@ pub async fn diff_from_yaml(
         None
     };
 
-    Ok((live_summary, last_summary))
+    Ok((tgt_json, live_json, last_json))
 }
 
 fn parse_yaml_for_target(
@@ -371,6 +504,16 @@ fn strip_noisy(mut v: Json) -> Json {
     v
 }
 
+/// Server-assigned identity never appears in manifests; keep it out of
+/// field-level diffs.
+fn strip_identity(mut v: Json) -> Json {
+    if let Some(meta) = v.get_mut("metadata").and_then(|m| m.as_object_mut()) {
+        meta.remove("uid");
+        meta.remove("selfLink");
+    }
+    v
+}
+
 fn ensure_metadata(v: &mut Json, name: &str, ns: Option<&str>) {
     let meta = v
         .as_object_mut()
@@ -444,6 +587,15 @@ fn parse_uid(uid_str: &str) -> Result<[u8; 16]> {
     Ok(*u.as_bytes())
 }
 
+/// `group/version/Kind`, or `version/Kind` for the core group.
+fn gvk_key(gvk: &GroupVersionKind) -> String {
+    if gvk.group.is_empty() {
+        format!("{}/{}", gvk.version, gvk.kind)
+    } else {
+        format!("{}/{}/{}", gvk.group, gvk.version, gvk.kind)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -513,4 +665,94 @@ mod tests {
         let e3 = parse_yaml_for_target(y3, None).unwrap_err().to_string();
         assert!(e3.contains("missing metadata.name"), "e3={}", e3);
     }
+
+    const DEPLOY_YAML: &str = "apiVersion: apps/v1
+kind: Deployment
+metadata:
+  name: web
+  namespace: default
+spec:
+  replicas: 3
+  selector:
+    matchLabels: {app: web}
+  template:
+    metadata:
+      labels: {app: web}
+    spec:
+      containers:
+      - name: app
+        image: nginx:1.25
+";
+
+    #[tokio::test]
+    async fn edit_from_yaml_reports_field_manager_conflicts() {
+        use orka_kubehub::fake::FakeApiServer;
+        let srv = FakeApiServer::new();
+        let _guard = srv.install();
+
+        // First apply creates the object and makes orka the owner.
+        let res = edit_from_yaml(DEPLOY_YAML, None, false, true)
+            .await
+            .unwrap();
+        assert!(res.applied);
+
+        // Another manager changes replicas behind orka's back.
+        let client = orka_kubehub::get_kube_client().await.unwrap();
+        let api: Api<k8s_openapi::api::apps::v1::Deployment> = Api::namespaced(client, "default");
+        api.patch(
+            "web",
+            &PatchParams {
+                field_manager: Some("kubectl-edit".into()),
+                ..Default::default()
+            },
+            &Patch::Merge(serde_json::json!({"spec": {"replicas": 5}})),
+        )
+        .await
+        .unwrap();
+
+        // Dry-run and real apply both surface the conflict without writing.
+        let dry = edit_from_yaml(DEPLOY_YAML, None, false, false)
+            .await
+            .unwrap_err()
+            .to_string();
+        assert!(
+            dry.contains("dry-run failed") && dry.contains("kubectl-edit"),
+            "{}",
+            dry
+        );
+        let err = edit_from_yaml(DEPLOY_YAML, None, false, true)
+            .await
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("server-side apply failed"), "{}", err);
+        assert!(err.contains(".spec.replicas"), "{}", err);
+        let live = srv
+            .get("apps/v1/Deployment", Some("default"), "web")
+            .unwrap();
+        assert_eq!(live["spec"]["replicas"], 5);
+
+        let conflict = edit_from_yaml(DEPLOY_YAML, None, false, true)
+            .await
+            .unwrap_err();
+        let conflict = conflict.downcast_ref::<ApplyConflict>().unwrap();
+        assert_eq!(conflict.managers(), vec!["kubectl-edit"]);
+        assert_eq!(conflict.conflicts[0].field, ".spec.replicas");
+
+        // Forcing takes ownership and records who lost the field.
+        let res = edit_from_yaml_with(DEPLOY_YAML, None, false, true, true)
+            .await
+            .unwrap();
+        assert!(res.applied);
+        assert!(
+            res.warnings
+                .iter()
+                .any(|w| w.contains(".spec.replicas") && w.contains("kubectl-edit")),
+            "{:?}",
+            res.warnings
+        );
+        let live = srv
+            .get("apps/v1/Deployment", Some("default"), "web")
+            .unwrap();
+        assert_ne!(live["spec"]["replicas"], 5);
+    }
 }
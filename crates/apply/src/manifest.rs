@@ -0,0 +1,433 @@
+//! Multi-document and directory input for apply/diff.
+//!
+//! Manifests are split per YAML document, `List` kinds are unwrapped into
+//! their items, and the result is ordered so that objects other objects
+//! depend on (Namespaces, CRDs, RBAC, config) are applied first.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value as Json;
+
+use crate::{ApplyConflict, ApplyResult, DiffReport, DiffSummary, FieldConflict};
+
+/// One object taken from an input file.
+#[derive(Debug, Clone)]
+pub struct Manifest {
+    /// `path#index` of the YAML document the object came from (`-` for stdin).
+    pub source: String,
+    pub api_version: String,
+    pub kind: String,
+    pub name: String,
+    pub namespace: Option<String>,
+    /// The object re-serialized as a single YAML document.
+    pub yaml: String,
+}
+
+impl Manifest {
+    /// `kind/name` or `kind/ns/name`, for display.
+    pub fn display_name(&self) -> String {
+        match &self.namespace {
+            Some(ns) => format!("{}/{}/{}", self.kind, ns, self.name),
+            None => format!("{}/{}", self.kind, self.name),
+        }
+    }
+}
+
+/// Read manifests from a file, a directory (recursively; `.yaml`, `.yml`
+/// and `.json` files in path order) or `-` for stdin.
+pub fn load_manifests(path: &str) -> Result<Vec<Manifest>> {
+    if path == "-" {
+        use std::io::Read;
+        let mut s = String::new();
+        std::io::stdin().read_to_string(&mut s)?;
+        return split_manifests(&s, "-");
+    }
+    let p = Path::new(path);
+    let mut files = Vec::new();
+    if p.is_dir() {
+        collect_files(p, &mut files)?;
+        files.sort();
+    } else {
+        files.push(p.to_path_buf());
+    }
+    let mut out = Vec::new();
+    for f in files {
+        let text =
+            std::fs::read_to_string(&f).with_context(|| format!("reading {}", f.display()))?;
+        out.extend(split_manifests(&text, &f.display().to_string())?);
+    }
+    Ok(out)
+}
+
+fn collect_files(dir: &Path, out: &mut Vec<PathBuf>) -> Result<()> {
+    for entry in std::fs::read_dir(dir).with_context(|| format!("reading {}", dir.display()))? {
+        let path = entry?.path();
+        let hidden = path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .map(|n| n.starts_with('.'))
+            .unwrap_or(false);
+        if hidden {
+            continue;
+        }
+        if path.is_dir() {
+            collect_files(&path, out)?;
+        } else if matches!(
+            path.extension().and_then(|e| e.to_str()),
+            Some("yaml") | Some("yml") | Some("json")
+        ) {
+            out.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// Split a `---`-separated YAML stream into objects. Empty documents are
+/// skipped; `List` kinds (`v1/List`, `PodList`, ...) are unwrapped.
+pub fn split_manifests(text: &str, source: &str) -> Result<Vec<Manifest>> {
+    if text.len() > crate::max_yaml_bytes() * 16 {
+        return Err(anyhow!(
+            "{}: YAML input too large (>{} bytes)",
+            source,
+            crate::max_yaml_bytes() * 16
+        ));
+    }
+    let mut out = Vec::new();
+    for (idx, doc) in serde_yaml::Deserializer::from_str(text).enumerate() {
+        let src = format!("{}#{}", source, idx);
+        let val = serde_yaml::Value::deserialize(doc)
+            .with_context(|| format!("{}: parsing YAML", src))?;
+        let json =
+            serde_json::to_value(val).with_context(|| format!("{}: converting YAML", src))?;
+        push_object(json, &src, &mut out)?;
+    }
+    Ok(out)
+}
+
+pub(crate) fn push_object(json: Json, src: &str, out: &mut Vec<Manifest>) -> Result<()> {
+    if json.is_null() {
+        return Ok(());
+    }
+    let kind = json.get("kind").and_then(|v| v.as_str()).unwrap_or("");
+    if kind.ends_with("List") {
+        if let Some(items) = json.get("items").and_then(|v| v.as_array()) {
+            for (i, item) in items.iter().enumerate() {
+                push_object(item.clone(), &format!("{}.items[{}]", src, i), out)?;
+            }
+            return Ok(());
+        }
+    }
+    let field = |f: &str| json.get(f).and_then(|v| v.as_str()).map(|s| s.to_string());
+    let api_version =
+        field("apiVersion").ok_or_else(|| anyhow!("{}: YAML missing apiVersion", src))?;
+    let kind = field("kind").ok_or_else(|| anyhow!("{}: YAML missing kind", src))?;
+    let meta = json.get("metadata");
+    let name = meta
+        .and_then(|m| m.get("name"))
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| anyhow!("{}: YAML missing metadata.name", src))?
+        .to_string();
+    let namespace = meta
+        .and_then(|m| m.get("namespace"))
+        .and_then(|v| v.as_str())
+        .map(|s| s.to_string());
+    let yaml = serde_yaml::to_string(&json).context("serializing manifest")?;
+    out.push(Manifest {
+        source: src.to_string(),
+        api_version,
+        kind,
+        name,
+        namespace,
+        yaml,
+    });
+    Ok(())
+}
+
+/// Apply order by kind; unknown kinds (custom resources) go last.
+const KIND_ORDER: &[&str] = &[
+    "Namespace",
+    "CustomResourceDefinition",
+    "PriorityClass",
+    "ResourceQuota",
+    "LimitRange",
+    "ServiceAccount",
+    "Secret",
+    "ConfigMap",
+    "StorageClass",
+    "PersistentVolume",
+    "PersistentVolumeClaim",
+    "ClusterRole",
+    "ClusterRoleBinding",
+    "Role",
+    "RoleBinding",
+    "Service",
+    "DaemonSet",
+    "Pod",
+    "ReplicaSet",
+    "Deployment",
+    "StatefulSet",
+    "HorizontalPodAutoscaler",
+    "Job",
+    "CronJob",
+    "PodDisruptionBudget",
+    "NetworkPolicy",
+    "Ingress",
+    "APIService",
+    "MutatingWebhookConfiguration",
+    "ValidatingWebhookConfiguration",
+];
+
+fn kind_rank(kind: &str) -> usize {
+    KIND_ORDER
+        .iter()
+        .position(|k| *k == kind)
+        .unwrap_or(KIND_ORDER.len())
+}
+
+/// Stable sort into dependency order; objects of the same rank keep input order.
+pub fn order_for_apply(manifests: &mut [Manifest]) {
+    manifests.sort_by_key(|m| kind_rank(&m.kind));
+}
+
+/// Outcome for one object of a batch.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ObjectResult {
+    pub source: String,
+    pub api_version: String,
+    pub kind: String,
+    pub name: String,
+    pub namespace: Option<String>,
+    pub result: Option<ApplyResult>,
+    pub error: Option<String>,
+    /// Field ownership conflicts when the error was an SSA conflict.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub conflicts: Vec<FieldConflict>,
+}
+
+impl ObjectResult {
+    pub fn new(m: &Manifest, res: Result<ApplyResult>) -> Self {
+        let (result, error, conflicts) = match res {
+            Ok(r) => (Some(r), None, Vec::new()),
+            Err(e) => {
+                let conflicts = e
+                    .downcast_ref::<ApplyConflict>()
+                    .map(|c| c.conflicts.clone())
+                    .unwrap_or_default();
+                (None, Some(e.to_string()), conflicts)
+            }
+        };
+        Self {
+            source: m.source.clone(),
+            api_version: m.api_version.clone(),
+            kind: m.kind.clone(),
+            name: m.name.clone(),
+            namespace: m.namespace.clone(),
+            result,
+            error,
+            conflicts,
+        }
+    }
+}
+
+/// Totals across a batch; `diff` sums the per-object diff summaries.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BatchSummary {
+    pub total: usize,
+    pub applied: usize,
+    pub dry_run: usize,
+    pub failed: usize,
+    pub diff: DiffSummary,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BatchResult {
+    pub items: Vec<ObjectResult>,
+    pub summary: BatchSummary,
+}
+
+impl BatchResult {
+    pub fn from_items(items: Vec<ObjectResult>) -> Self {
+        let mut s = BatchSummary {
+            total: items.len(),
+            ..Default::default()
+        };
+        for it in &items {
+            match &it.result {
+                Some(r) => {
+                    if r.applied {
+                        s.applied += 1;
+                    }
+                    if r.dry_run {
+                        s.dry_run += 1;
+                    }
+                    s.diff.adds += r.summary.adds;
+                    s.diff.updates += r.summary.updates;
+                    s.diff.removes += r.summary.removes;
+                }
+                None => s.failed += 1,
+            }
+        }
+        Self { items, summary: s }
+    }
+}
+
+/// [`crate::edit_from_yaml`] for every manifest in apply order. Failures are
+/// recorded per object and do not stop the batch.
+pub async fn edit_from_manifests(
+    mut manifests: Vec<Manifest>,
+    ns_override: Option<&str>,
+    validate: bool,
+    do_apply: bool,
+    force: bool,
+) -> BatchResult {
+    order_for_apply(&mut manifests);
+    let mut items = Vec::with_capacity(manifests.len());
+    for m in &manifests {
+        let res = crate::edit_from_yaml_with(&m.yaml, ns_override, validate, do_apply, force).await;
+        items.push(ObjectResult::new(m, res));
+    }
+    BatchResult::from_items(items)
+}
+
+/// Per-object diff reports vs live and last-applied, in apply order.
+pub async fn diff_from_manifests(
+    mut manifests: Vec<Manifest>,
+    ns_override: Option<&str>,
+) -> Vec<(Manifest, Result<(DiffReport, Option<DiffReport>)>)> {
+    order_for_apply(&mut manifests);
+    let mut out = Vec::with_capacity(manifests.len());
+    for m in manifests {
+        let res = crate::diff_report_from_yaml(&m.yaml, ns_override).await;
+        out.push((m, res));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::{temp_dir, write};
+
+    const STREAM: &str = "\
+apiVersion: apps/v1
+kind: Deployment
+metadata: {name: web, namespace: shop}
+---
+# comment-only document
+---
+apiVersion: v1
+kind: List
+items:
+- apiVersion: v1
+  kind: ConfigMap
+  metadata: {name: cfg, namespace: shop}
+- apiVersion: v1
+  kind: Service
+  metadata: {name: web, namespace: shop}
+---
+apiVersion: example.com/v1
+kind: Widget
+metadata: {name: w1, namespace: shop}
+---
+apiVersion: apiextensions.k8s.io/v1
+kind: CustomResourceDefinition
+metadata: {name: widgets.example.com}
+---
+apiVersion: v1
+kind: Namespace
+metadata: {name: shop}
+";
+
+    #[test]
+    fn splits_documents_and_unwraps_lists() {
+        let ms = split_manifests(STREAM, "app.yaml").unwrap();
+        let names: Vec<String> = ms.iter().map(|m| m.display_name()).collect();
+        assert_eq!(
+            names,
+            vec![
+                "Deployment/shop/web",
+                "ConfigMap/shop/cfg",
+                "Service/shop/web",
+                "Widget/shop/w1",
+                "CustomResourceDefinition/widgets.example.com",
+                "Namespace/shop",
+            ]
+        );
+        assert_eq!(ms[1].source, "app.yaml#2.items[0]");
+        assert!(ms[0].yaml.contains("kind: Deployment"));
+    }
+
+    #[test]
+    fn orders_namespaces_and_crds_first_and_custom_kinds_last() {
+        let mut ms = split_manifests(STREAM, "app.yaml").unwrap();
+        order_for_apply(&mut ms);
+        let kinds: Vec<&str> = ms.iter().map(|m| m.kind.as_str()).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                "Namespace",
+                "CustomResourceDefinition",
+                "ConfigMap",
+                "Service",
+                "Deployment",
+                "Widget"
+            ]
+        );
+    }
+
+    #[test]
+    fn missing_fields_name_the_document() {
+        let err = split_manifests("apiVersion: v1\nkind: ConfigMap\n---\n", "x.yaml")
+            .unwrap_err()
+            .to_string();
+        assert!(err.starts_with("x.yaml#0"), "{}", err);
+    }
+
+    #[test]
+    fn loads_directories_recursively_in_path_order() {
+        let dir = temp_dir("manifests");
+        write(
+            &dir,
+            "b/cm.yaml",
+            "apiVersion: v1\nkind: ConfigMap\nmetadata: {name: two}\n",
+        );
+        write(
+            &dir,
+            "a.yml",
+            "apiVersion: v1\nkind: ConfigMap\nmetadata: {name: one}\n",
+        );
+        write(&dir, "README.md", "not yaml");
+        let ms = load_manifests(dir.to_str().unwrap()).unwrap();
+        let names: Vec<&str> = ms.iter().map(|m| m.name.as_str()).collect();
+        assert_eq!(names, vec!["one", "two"]);
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn batch_summary_counts_outcomes() {
+        let ms = split_manifests(STREAM, "s").unwrap();
+        let ok = ApplyResult {
+            applied: true,
+            summary: DiffSummary {
+                adds: 2,
+                updates: 1,
+                removes: 0,
+            },
+            ..Default::default()
+        };
+        let items = vec![
+            ObjectResult::new(&ms[0], Ok(ok.clone())),
+            ObjectResult::new(&ms[1], Ok(ok)),
+            ObjectResult::new(&ms[2], Err(anyhow!("boom"))),
+        ];
+        let b = BatchResult::from_items(items);
+        assert_eq!(
+            (b.summary.total, b.summary.applied, b.summary.failed),
+            (3, 2, 1)
+        );
+        assert_eq!(b.summary.diff.adds, 4);
+        assert_eq!(b.items[2].error.as_deref(), Some("boom"));
+    }
+}
@@ -0,0 +1,234 @@
+//! Field ownership decoded from `metadata.managedFields`.
+//!
+//! Each managedFields entry carries a FieldsV1 set: a nested map whose keys
+//! are prefixed path elements (`f:` field, `k:` list item by key, `v:` set
+//! value, `i:` index) and where `"."` marks the enclosing node itself as
+//! owned. Paths are rendered the way the apiserver prints them in apply
+//! conflicts, e.g. `.spec.template.spec.containers[name="app"].image`.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value as Json;
+
+/// One manager's claim on a field.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FieldOwner {
+    pub manager: String,
+    /// `Apply` or `Update`.
+    pub operation: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub api_version: Option<String>,
+    /// RFC 3339 timestamp of the manager's last write.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub time: Option<String>,
+    /// `status` or `scale` when the write went through a subresource.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub subresource: Option<String>,
+}
+
+/// All managers owning a single field path.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FieldOwnership {
+    pub path: String,
+    pub owners: Vec<FieldOwner>,
+}
+
+/// Decode an object's managedFields into per-path ownership, sorted by path.
+/// Objects without managedFields yield an empty list.
+pub fn field_ownership(obj: &Json) -> Vec<FieldOwnership> {
+    let entries = match obj.pointer("/metadata/managedFields") {
+        Some(Json::Array(a)) => a,
+        _ => return Vec::new(),
+    };
+    let mut by_path: BTreeMap<String, Vec<FieldOwner>> = BTreeMap::new();
+    for e in entries {
+        let Some(fields) = e.get("fieldsV1") else {
+            continue;
+        };
+        let s = |k: &str| e.get(k).and_then(|v| v.as_str()).map(|v| v.to_string());
+        let owner = FieldOwner {
+            manager: s("manager").unwrap_or_default(),
+            operation: s("operation").unwrap_or_default(),
+            api_version: s("apiVersion"),
+            time: s("time"),
+            subresource: s("subresource").filter(|v| !v.is_empty()),
+        };
+        for path in fieldsv1_paths(fields) {
+            by_path.entry(path).or_default().push(owner.clone());
+        }
+    }
+    by_path
+        .into_iter()
+        .map(|(path, owners)| FieldOwnership { path, owners })
+        .collect()
+}
+
+/// Keep rows at `field` or below it; an empty filter keeps everything.
+/// `field` may omit the leading dot (`spec.replicas`).
+pub fn filter_ownership(rows: Vec<FieldOwnership>, field: &str) -> Vec<FieldOwnership> {
+    let field = field.trim();
+    if field.is_empty() {
+        return rows;
+    }
+    let want = if field.starts_with('.') {
+        field.to_string()
+    } else {
+        format!(".{}", field)
+    };
+    rows.into_iter()
+        .filter(|r| match r.path.strip_prefix(want.as_str()) {
+            Some(rest) => rest.is_empty() || rest.starts_with('.') || rest.starts_with('['),
+            None => false,
+        })
+        .collect()
+}
+
+/// Owned paths in one FieldsV1 set: every leaf plus every node marked `"."`.
+pub fn fieldsv1_paths(fields: &Json) -> Vec<String> {
+    let mut out = Vec::new();
+    walk(fields, String::new(), &mut out);
+    out
+}
+
+fn walk(node: &Json, path: String, out: &mut Vec<String>) {
+    let Some(map) = node.as_object() else {
+        return;
+    };
+    if map.is_empty() {
+        if !path.is_empty() {
+            out.push(path);
+        }
+        return;
+    }
+    if map.contains_key(".") && !path.is_empty() {
+        out.push(path.clone());
+    }
+    for (k, child) in map {
+        if k == "." {
+            continue;
+        }
+        if let Some(elem) = render_element(k) {
+            walk(child, format!("{}{}", path, elem), out);
+        }
+    }
+}
+
+fn render_element(key: &str) -> Option<String> {
+    let (prefix, rest) = (key.get(..2)?, key.get(2..)?);
+    match prefix {
+        "f:" => Some(format!(".{}", rest)),
+        "i:" => Some(format!("[{}]", rest)),
+        "v:" => Some(format!("[={}]", rest)),
+        "k:" => {
+            let fields = match serde_json::from_str::<Json>(rest) {
+                Ok(Json::Object(m)) => m,
+                _ => return Some(format!("[{}]", rest)),
+            };
+            let parts: Vec<String> = fields.iter().map(|(k, v)| format!("{}={}", k, v)).collect();
+            Some(format!("[{}]", parts.join(",")))
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn deployment() -> Json {
+        json!({
+            "metadata": {
+                "name": "web",
+                "managedFields": [
+                    {
+                        "manager": "orka",
+                        "operation": "Apply",
+                        "apiVersion": "apps/v1",
+                        "time": "2024-05-01T10:00:00Z",
+                        "fieldsType": "FieldsV1",
+                        "fieldsV1": {
+                            "f:metadata": {"f:labels": {"f:app": {}}},
+                            "f:spec": {
+                                "f:replicas": {},
+                                "f:template": {"f:spec": {"f:containers": {
+                                    "k:{\"name\":\"app\"}": {".": {}, "f:image": {}, "f:name": {}},
+                                }}},
+                            },
+                        },
+                    },
+                    {
+                        "manager": "kube-controller-manager",
+                        "operation": "Update",
+                        "apiVersion": "apps/v1",
+                        "time": "2024-05-01T10:00:05Z",
+                        "fieldsType": "FieldsV1",
+                        "fieldsV1": {
+                            "f:metadata": {"f:finalizers": {".": {}, "v:\"example.com/x\"": {}}},
+                            "f:status": {"f:replicas": {}},
+                        },
+                        "subresource": "status",
+                    },
+                    {
+                        "manager": "hpa",
+                        "operation": "Update",
+                        "fieldsV1": {"f:spec": {"f:replicas": {}}},
+                    },
+                ],
+            },
+        })
+    }
+
+    #[test]
+    fn decodes_paths_and_owners() {
+        let rows = field_ownership(&deployment());
+        let paths: Vec<&str> = rows.iter().map(|r| r.path.as_str()).collect();
+        assert_eq!(
+            paths,
+            vec![
+                ".metadata.finalizers",
+                ".metadata.finalizers[=\"example.com/x\"]",
+                ".metadata.labels.app",
+                ".spec.replicas",
+                ".spec.template.spec.containers[name=\"app\"]",
+                ".spec.template.spec.containers[name=\"app\"].image",
+                ".spec.template.spec.containers[name=\"app\"].name",
+                ".status.replicas",
+            ]
+        );
+        let replicas = &rows[3];
+        let managers: Vec<&str> = replicas.owners.iter().map(|o| o.manager.as_str()).collect();
+        assert_eq!(managers, vec!["orka", "hpa"]);
+        assert_eq!(replicas.owners[0].operation, "Apply");
+        assert_eq!(
+            replicas.owners[0].time.as_deref(),
+            Some("2024-05-01T10:00:00Z")
+        );
+        assert_eq!(rows[7].owners[0].subresource.as_deref(), Some("status"));
+    }
+
+    #[test]
+    fn renders_multi_key_and_index_elements() {
+        let paths = fieldsv1_paths(&json!({
+            "f:ports": {"k:{\"containerPort\":80,\"protocol\":\"TCP\"}": {".": {}}},
+            "f:args": {"i:0": {}},
+        }));
+        assert_eq!(
+            paths,
+            vec![".args[0]", ".ports[containerPort=80,protocol=\"TCP\"]"]
+        );
+    }
+
+    #[test]
+    fn filters_by_field_prefix() {
+        let rows = field_ownership(&deployment());
+        let got = filter_ownership(rows.clone(), "spec.template.spec.containers");
+        assert_eq!(got.len(), 3);
+        assert_eq!(filter_ownership(rows.clone(), ".spec.replicas").len(), 1);
+        // `.spec.rep` is not a path boundary
+        assert!(filter_ownership(rows.clone(), ".spec.rep").is_empty());
+        assert_eq!(filter_ownership(rows.clone(), "").len(), rows.len());
+        assert!(field_ownership(&json!({"metadata": {}})).is_empty());
+    }
+}
@@ -0,0 +1,851 @@
+//! Pre-apply policy checks.
+//!
+//! Built-in rules look at pod templates (Pods, workloads, Jobs, CronJobs)
+//! and Services; user rules are JSON-path assertions from a YAML policy file
+//! (`ORKA_POLICY_PATH`, default `~/.orka/policy.yaml` when present):
+//!
+//! ```yaml
+//! disable: [missing-probes]
+//! severity: {latest-tag: error}
+//! rules:
+//!   - id: team-label
+//!     kinds: [Deployment, StatefulSet]
+//!     path: .metadata.labels.team
+//!     exists: true
+//!     severity: error
+//!     message: workloads need a team label
+//!   - id: internal-registry
+//!     path: .spec.template.spec.containers[*].image
+//!     matches: '^registry\.example\.com/'
+//! ```
+//!
+//! Findings are reported as apply warnings. Error-level ones block a real
+//! apply unless the caller lets the rule through: `ORKA_POLICY_OVERRIDE`
+//! (`1` for every rule, or comma-separated rule ids) or [`set_override`]
+//! (`orkactl edit --policy-override`). An object cannot exempt itself; rules
+//! it lists in its `orka.dev/policy-override` annotation are only demoted
+//! from error to warning, and the demotion shows in the finding.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::PathBuf;
+use std::sync::{Arc, RwLock};
+
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value as Json;
+
+/// Annotation listing rule ids (comma separated) whose error-level findings
+/// on this object are demoted to warnings; see [`demote_annotated`].
+pub const OVERRIDE_ANNOTATION: &str = "orka.dev/policy-override";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Info,
+    Warning,
+    Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Severity::Info => "info",
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Finding {
+    pub rule: String,
+    pub severity: Severity,
+    /// Field the finding is about, e.g. `.spec.template.spec.containers[0].image`.
+    pub path: String,
+    pub message: String,
+}
+
+impl fmt::Display for Finding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "policy {} [{}] {}: {}",
+            self.severity, self.rule, self.path, self.message
+        )
+    }
+}
+
+/// A real apply refused because of error-level findings; returned (inside
+/// `anyhow::Error`) by [`crate::edit_from_yaml_with`].
+#[derive(Debug, Clone, thiserror::Error)]
+#[error("blocked by policy: {}", .findings.iter().map(|f| f.to_string()).collect::<Vec<_>>().join("; "))]
+pub struct PolicyViolation {
+    pub findings: Vec<Finding>,
+}
+
+/// Built-in rule ids with their default severities.
+pub const BUILTIN_RULES: &[(&str, Severity)] = &[
+    ("missing-resources", Severity::Warning),
+    ("latest-tag", Severity::Warning),
+    ("privileged", Severity::Error),
+    ("host-path", Severity::Warning),
+    ("missing-probes", Severity::Info),
+    ("service-selector", Severity::Warning),
+];
+
+/// Assertion on every value a JSON path selects.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct UserRule {
+    pub id: String,
+    /// Kinds the rule applies to; all when empty.
+    pub kinds: Vec<String>,
+    pub path: String,
+    pub severity: Option<Severity>,
+    pub message: Option<String>,
+    /// `true`: the path must be present; `false`: it must be absent.
+    pub exists: Option<bool>,
+    pub equals: Option<Json>,
+    pub not_equals: Option<Json>,
+    pub one_of: Option<Vec<Json>>,
+    /// Regex the value (as text) must match.
+    pub matches: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct Policy {
+    /// Rule ids (built-in or user) to skip.
+    pub disable: Vec<String>,
+    /// Severity overrides by rule id.
+    pub severity: BTreeMap<String, Severity>,
+    pub rules: Vec<UserRule>,
+}
+
+/// Cluster state some rules need; rules without their input are skipped.
+#[derive(Debug, Clone, Default)]
+pub struct PolicyContext {
+    /// Labels of the pods in the object's namespace (or at least of those a
+    /// Service selector could match).
+    pub pods: Option<Vec<BTreeMap<String, String>>>,
+}
+
+fn policy_path() -> Option<PathBuf> {
+    if let Ok(p) = std::env::var("ORKA_POLICY_PATH") {
+        return Some(PathBuf::from(p));
+    }
+    let home = std::env::var("HOME").ok()?;
+    let p = PathBuf::from(home).join(".orka/policy.yaml");
+    p.exists().then_some(p)
+}
+
+fn env_on(name: &str) -> bool {
+    std::env::var(name)
+        .map(|v| v == "1" || v.eq_ignore_ascii_case("true") || v.eq_ignore_ascii_case("yes"))
+        .unwrap_or(false)
+}
+
+/// Whether policy checks run at all (`ORKA_POLICY=0` turns them off).
+pub fn enabled() -> bool {
+    std::env::var("ORKA_POLICY")
+        .map(|v| !(v == "0" || v.eq_ignore_ascii_case("false") || v.eq_ignore_ascii_case("no")))
+        .unwrap_or(true)
+}
+
+impl Policy {
+    pub fn from_yaml(yaml: &str) -> Result<Self> {
+        let p: Policy = serde_yaml::from_str(yaml)?;
+        for r in &p.rules {
+            if r.id.is_empty() {
+                return Err(anyhow!("policy rule without id"));
+            }
+            parse_path(&r.path).with_context(|| format!("rule {}", r.id))?;
+            if let Some(re) = &r.matches {
+                regex::Regex::new(re).with_context(|| format!("rule {}", r.id))?;
+            }
+        }
+        Ok(p)
+    }
+
+    /// The configured policy file, or built-in rules only.
+    pub fn load() -> Result<Self> {
+        match policy_path() {
+            Some(p) => {
+                let text = std::fs::read_to_string(&p)
+                    .with_context(|| format!("reading policy {}", p.display()))?;
+                Self::from_yaml(&text).with_context(|| format!("parsing policy {}", p.display()))
+            }
+            None => Ok(Self::default()),
+        }
+    }
+
+    fn active(&self, rule: &str) -> bool {
+        !self.disable.iter().any(|d| d == rule)
+    }
+
+    fn severity_of(&self, rule: &str, default: Severity) -> Severity {
+        self.severity.get(rule).copied().unwrap_or(default)
+    }
+
+    /// Run every active rule against one object.
+    pub fn lint(&self, obj: &Json, ctx: &PolicyContext) -> Vec<Finding> {
+        let mut out = Vec::new();
+        let mut push = |rule: &str, path: String, message: String| {
+            if !self.active(rule) {
+                return;
+            }
+            let default = BUILTIN_RULES
+                .iter()
+                .find(|(id, _)| *id == rule)
+                .map(|(_, s)| *s)
+                .unwrap_or(Severity::Warning);
+            out.push(Finding {
+                rule: rule.to_string(),
+                severity: self.severity_of(rule, default),
+                path,
+                message,
+            });
+        };
+        builtin_pod_rules(obj, &mut push);
+        builtin_service_rules(obj, ctx, &mut push);
+        let kind = obj.get("kind").and_then(|k| k.as_str()).unwrap_or("");
+        for rule in &self.rules {
+            if !self.active(&rule.id)
+                || !(rule.kinds.is_empty() || rule.kinds.iter().any(|k| k == kind))
+            {
+                continue;
+            }
+            for (path, message) in check_user_rule(rule, obj) {
+                out.push(Finding {
+                    rule: rule.id.clone(),
+                    severity: self
+                        .severity_of(&rule.id, rule.severity.unwrap_or(Severity::Warning)),
+                    path,
+                    message,
+                });
+            }
+        }
+        out
+    }
+}
+
+static OVERRIDE: RwLock<Vec<String>> = RwLock::new(Vec::new());
+
+/// Let error-level findings of `rules` (`*` for all) through for every
+/// apply in this process, on top of `ORKA_POLICY_OVERRIDE`.
+pub fn set_override(rules: Vec<String>) {
+    *OVERRIDE.write().unwrap_or_else(|e| e.into_inner()) = rules;
+}
+
+/// Rule ids the caller lets through: [`set_override`] plus
+/// `ORKA_POLICY_OVERRIDE` (`1`/`true`/`yes` meaning all).
+fn caller_override() -> Vec<String> {
+    let mut rules = OVERRIDE.read().unwrap_or_else(|e| e.into_inner()).clone();
+    if env_on("ORKA_POLICY_OVERRIDE") {
+        rules.push("*".to_string());
+    } else if let Ok(v) = std::env::var("ORKA_POLICY_OVERRIDE") {
+        if !(v == "0" || v.eq_ignore_ascii_case("false") || v.eq_ignore_ascii_case("no")) {
+            rules.extend(split_rules(&v));
+        }
+    }
+    rules
+}
+
+fn split_rules(s: &str) -> impl Iterator<Item = String> + '_ {
+    s.split(',')
+        .map(|r| r.trim())
+        .filter(|r| !r.is_empty())
+        .map(|r| r.to_string())
+}
+
+/// Demote error-level findings of the rules `obj` lists in its
+/// [`OVERRIDE_ANNOTATION`] to warnings, noting the demotion in the message.
+/// `*` is not honoured here: an object has to name what it opts out of.
+pub fn demote_annotated(mut findings: Vec<Finding>, obj: &Json) -> Vec<Finding> {
+    let annotated: Vec<String> = obj
+        .pointer(&format!(
+            "/metadata/annotations/{}",
+            OVERRIDE_ANNOTATION.replace('/', "~1")
+        ))
+        .and_then(|v| v.as_str())
+        .map(|s| split_rules(s).collect())
+        .unwrap_or_default();
+    for f in &mut findings {
+        if f.severity == Severity::Error && annotated.contains(&f.rule) {
+            f.severity = Severity::Warning;
+            f.message = format!(
+                "{} (error demoted by the {} annotation)",
+                f.message, OVERRIDE_ANNOTATION
+            );
+        }
+    }
+    findings
+}
+
+/// Error-level findings that should stop a real apply: all of them except
+/// rules the caller overrides.
+pub fn blocking(findings: &[Finding]) -> Vec<Finding> {
+    let overridden = caller_override();
+    findings
+        .iter()
+        .filter(|f| f.severity == Severity::Error)
+        .filter(|f| !overridden.iter().any(|r| r == "*" || *r == f.rule))
+        .cloned()
+        .collect()
+}
+
+/// Pod spec of a Pod, workload, Job or CronJob, with its JSON path.
+fn pod_spec(obj: &Json) -> Option<(&'static str, &Json)> {
+    let kind = obj.get("kind")?.as_str()?;
+    let (path, ptr) = match kind {
+        "Pod" => (".spec", "/spec"),
+        "Deployment"
+        | "StatefulSet"
+        | "DaemonSet"
+        | "ReplicaSet"
+        | "ReplicationController"
+        | "Job" => (".spec.template.spec", "/spec/template/spec"),
+        "CronJob" => (
+            ".spec.jobTemplate.spec.template.spec",
+            "/spec/jobTemplate/spec/template/spec",
+        ),
+        _ => return None,
+    };
+    obj.pointer(ptr).map(|s| (path, s))
+}
+
+fn builtin_pod_rules(obj: &Json, push: &mut impl FnMut(&str, String, String)) {
+    let Some((base, spec)) = pod_spec(obj) else {
+        return;
+    };
+    let kind = obj.get("kind").and_then(|k| k.as_str()).unwrap_or("");
+    let long_running = !matches!(kind, "Job" | "CronJob");
+    for list in ["initContainers", "containers"] {
+        let Some(containers) = spec.get(list).and_then(|c| c.as_array()) else {
+            continue;
+        };
+        for (i, c) in containers.iter().enumerate() {
+            let at = format!("{}.{}[{}]", base, list, i);
+            let name = c.get("name").and_then(|n| n.as_str()).unwrap_or("?");
+            if let Some(image) = c.get("image").and_then(|v| v.as_str()) {
+                if uses_latest(image) {
+                    push(
+                        "latest-tag",
+                        format!("{}.image", at),
+                        format!("container {} uses a floating image tag ({})", name, image),
+                    );
+                }
+            }
+            let missing: Vec<&str> = ["requests", "limits"]
+                .into_iter()
+                .filter(|k| {
+                    c.pointer(&format!("/resources/{}", k))
+                        .and_then(|v| v.as_object())
+                        .map(|m| m.is_empty())
+                        .unwrap_or(true)
+                })
+                .collect();
+            if !missing.is_empty() {
+                push(
+                    "missing-resources",
+                    format!("{}.resources", at),
+                    format!(
+                        "container {} sets no resource {}",
+                        name,
+                        missing.join(" or ")
+                    ),
+                );
+            }
+            if c.pointer("/securityContext/privileged")
+                .and_then(|v| v.as_bool())
+                == Some(true)
+            {
+                push(
+                    "privileged",
+                    format!("{}.securityContext.privileged", at),
+                    format!("container {} runs privileged", name),
+                );
+            }
+            if long_running && list == "containers" {
+                let probes: Vec<&str> = ["readinessProbe", "livenessProbe"]
+                    .into_iter()
+                    .filter(|p| c.get(*p).is_none())
+                    .collect();
+                if !probes.is_empty() {
+                    push(
+                        "missing-probes",
+                        at.clone(),
+                        format!("container {} has no {}", name, probes.join(" or ")),
+                    );
+                }
+            }
+        }
+    }
+    if let Some(volumes) = spec.get("volumes").and_then(|v| v.as_array()) {
+        for (i, v) in volumes.iter().enumerate() {
+            if let Some(p) = v.pointer("/hostPath/path").and_then(|p| p.as_str()) {
+                let name = v.get("name").and_then(|n| n.as_str()).unwrap_or("?");
+                push(
+                    "host-path",
+                    format!("{}.volumes[{}].hostPath", base, i),
+                    format!("volume {} mounts host path {}", name, p),
+                );
+            }
+        }
+    }
+}
+
+/// Untagged, `:latest`, or otherwise floating; digests are pinned.
+fn uses_latest(image: &str) -> bool {
+    if image.contains('@') {
+        return false;
+    }
+    // The tag follows the last ':' after the last '/' (registry ports come before it).
+    let last = image.rsplit('/').next().unwrap_or(image);
+    match last.rsplit_once(':') {
+        Some((_, tag)) => tag == "latest",
+        None => true,
+    }
+}
+
+/// `service-selector`: a Service whose selector matches none of
+/// [`PolicyContext::pods`]. [`pods_for_service`] fills those from the
+/// in-memory pod snapshot when one is published, and only otherwise from a
+/// live pod list.
+fn builtin_service_rules(
+    obj: &Json,
+    ctx: &PolicyContext,
+    push: &mut impl FnMut(&str, String, String),
+) {
+    if obj.get("kind").and_then(|k| k.as_str()) != Some("Service") {
+        return;
+    }
+    let (Some(selector), Some(pods)) = (service_selector(obj), ctx.pods.as_ref()) else {
+        return;
+    };
+    let matched = pods.iter().any(|labels| selects(&selector, labels));
+    if !matched {
+        let sel: Vec<String> = selector
+            .iter()
+            .map(|(k, v)| format!("{}={}", k, v))
+            .collect();
+        push(
+            "service-selector",
+            ".spec.selector".into(),
+            format!("selector {} matches no pods", sel.join(",")),
+        );
+    }
+}
+
+fn selects(selector: &BTreeMap<String, String>, labels: &BTreeMap<String, String>) -> bool {
+    selector.iter().all(|(k, v)| labels.get(k) == Some(v))
+}
+
+/// A Service's non-empty `spec.selector`.
+pub fn service_selector(obj: &Json) -> Option<BTreeMap<String, String>> {
+    let sel: BTreeMap<String, String> = obj
+        .pointer("/spec/selector")?
+        .as_object()?
+        .iter()
+        .filter_map(|(k, v)| Some((k.clone(), v.as_str()?.to_string())))
+        .collect();
+    (!sel.is_empty()).then_some(sel)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Seg {
+    Key(String),
+    Index(usize),
+    Any,
+}
+
+/// `.a.b[0].c[*]`, with `['x.y/z']` for keys containing dots or slashes.
+fn parse_path(path: &str) -> Result<Vec<Seg>> {
+    let mut out = Vec::new();
+    let mut rest = path.trim();
+    if rest.is_empty() || rest == "." {
+        return Ok(out);
+    }
+    while !rest.is_empty() {
+        if let Some(r) = rest.strip_prefix('.') {
+            let end = r.find(['.', '[']).unwrap_or(r.len());
+            if end == 0 {
+                return Err(anyhow!("empty segment in path {}", path));
+            }
+            out.push(Seg::Key(r[..end].to_string()));
+            rest = &r[end..];
+        } else if let Some(r) = rest.strip_prefix('[') {
+            let end = r
+                .find(']')
+                .ok_or_else(|| anyhow!("unclosed [ in path {}", path))?;
+            let inner = r[..end].trim();
+            let seg = if inner == "*" {
+                Seg::Any
+            } else if let Ok(i) = inner.parse::<usize>() {
+                Seg::Index(i)
+            } else if inner.len() >= 2
+                && ((inner.starts_with('\'') && inner.ends_with('\''))
+                    || (inner.starts_with('"') && inner.ends_with('"')))
+            {
+                Seg::Key(inner[1..inner.len() - 1].to_string())
+            } else {
+                return Err(anyhow!("bad index [{}] in path {}", inner, path));
+            };
+            out.push(seg);
+            rest = &r[end + 1..];
+        } else {
+            return Err(anyhow!("path must start with '.': {}", path));
+        }
+    }
+    Ok(out)
+}
+
+fn push_key(path: &str, key: &str) -> String {
+    if key.contains(['.', '/', '[', ']']) {
+        format!("{}['{}']", path, key)
+    } else {
+        format!("{}.{}", path, key)
+    }
+}
+
+/// Concrete paths a path expression selects, with their values (`None` when
+/// the last step is missing). Wildcards over missing or empty collections
+/// select nothing.
+fn select<'a>(obj: &'a Json, segs: &[Seg]) -> Vec<(String, Option<&'a Json>)> {
+    let mut cur: Vec<(String, Option<&Json>)> = vec![(String::new(), Some(obj))];
+    for seg in segs {
+        let mut next = Vec::new();
+        for (p, v) in cur {
+            match seg {
+                Seg::Key(k) => next.push((push_key(&p, k), v.and_then(|v| v.get(k)))),
+                Seg::Index(i) => next.push((format!("{}[{}]", p, i), v.and_then(|v| v.get(*i)))),
+                Seg::Any => match v {
+                    Some(Json::Array(a)) => next.extend(
+                        a.iter()
+                            .enumerate()
+                            .map(|(i, x)| (format!("{}[{}]", p, i), Some(x))),
+                    ),
+                    Some(Json::Object(m)) => {
+                        next.extend(m.iter().map(|(k, x)| (push_key(&p, k), Some(x))))
+                    }
+                    _ => {}
+                },
+            }
+        }
+        cur = next;
+    }
+    cur
+}
+
+fn as_text(v: &Json) -> String {
+    match v {
+        Json::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+fn check_user_rule(rule: &UserRule, obj: &Json) -> Vec<(String, String)> {
+    let Ok(segs) = parse_path(&rule.path) else {
+        return Vec::new();
+    };
+    let re = rule
+        .matches
+        .as_deref()
+        .and_then(|r| regex::Regex::new(r).ok());
+    let mut out = Vec::new();
+    for (path, value) in select(obj, &segs) {
+        let path = if path.is_empty() { ".".into() } else { path };
+        let failure = match value {
+            None => (rule.exists == Some(true)).then(|| "is missing".to_string()),
+            Some(v) => {
+                if rule.exists == Some(false) {
+                    Some("must not be set".into())
+                } else if rule.equals.as_ref().is_some_and(|e| e != v) {
+                    Some(format!(
+                        "is {}, expected {}",
+                        v,
+                        rule.equals.as_ref().unwrap()
+                    ))
+                } else if rule.not_equals.as_ref() == Some(v) {
+                    Some(format!("must not be {}", v))
+                } else if rule.one_of.as_ref().is_some_and(|o| !o.contains(v)) {
+                    Some(format!("is {}, not one of the allowed values", v))
+                } else if re.as_ref().is_some_and(|re| !re.is_match(&as_text(v))) {
+                    Some(format!(
+                        "{} does not match {}",
+                        v,
+                        rule.matches.as_deref().unwrap_or_default()
+                    ))
+                } else {
+                    None
+                }
+            }
+        };
+        if let Some(f) = failure {
+            out.push((path, rule.message.clone().unwrap_or(f)));
+        }
+    }
+    out
+}
+
+/// Labels of the pods in a namespace from an in-memory snapshot; `None`
+/// when no snapshot covers the namespace.
+pub type PodSnapshotSource =
+    Arc<dyn Fn(&str) -> Option<Vec<BTreeMap<String, String>>> + Send + Sync>;
+
+static POD_SNAPSHOT: RwLock<Option<PodSnapshotSource>> = RwLock::new(None);
+
+/// Publish (or clear) the pod snapshot a frontend keeps for its own views,
+/// so Service selectors are checked without asking the apiserver.
+pub fn set_pod_snapshot_source(source: Option<PodSnapshotSource>) {
+    *POD_SNAPSHOT.write().unwrap_or_else(|e| e.into_inner()) = source;
+}
+
+fn snapshot_pods(ns: &str) -> Option<Vec<BTreeMap<String, String>>> {
+    let source = POD_SNAPSHOT
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone()?;
+    source(ns)
+}
+
+/// Pods a Service's selector matches in its namespace (at most one), as
+/// [`PolicyContext::pods`]. Uses the published pod snapshot
+/// ([`set_pod_snapshot_source`]) when it covers the namespace and falls
+/// back to a live `list` (needs `list pods`) only when it does not. `None` when the rule
+/// does not apply or the lookup fails.
+pub async fn pods_for_service(
+    client: &kube::Client,
+    obj: &Json,
+    ns: Option<&str>,
+) -> Option<Vec<BTreeMap<String, String>>> {
+    use k8s_openapi::api::core::v1::Pod;
+    use kube::api::{Api, ListParams};
+
+    if obj.get("kind").and_then(|k| k.as_str()) != Some("Service") {
+        return None;
+    }
+    let selector = service_selector(obj)?;
+    let ns = ns.unwrap_or("default");
+    if let Some(pods) = snapshot_pods(ns) {
+        return Some(
+            pods.into_iter()
+                .filter(|labels| selects(&selector, labels))
+                .take(1)
+                .collect(),
+        );
+    }
+    let sel: Vec<String> = selector
+        .iter()
+        .map(|(k, v)| format!("{}={}", k, v))
+        .collect();
+    let api: Api<Pod> = Api::namespaced(client.clone(), ns);
+    let lp = ListParams::default().labels(&sel.join(",")).limit(1);
+    match api.list_metadata(&lp).await {
+        Ok(list) => Some(
+            list.items
+                .into_iter()
+                .map(|p| p.metadata.labels.unwrap_or_default())
+                .collect(),
+        ),
+        Err(e) => {
+            tracing::debug!(error = %e, "policy: pod lookup for service selector failed");
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn deploy() -> Json {
+        json!({
+            "apiVersion": "apps/v1",
+            "kind": "Deployment",
+            "metadata": {"name": "web", "labels": {"app.kubernetes.io/name": "web"}},
+            "spec": {"template": {"spec": {
+                "containers": [
+                    {"name": "app", "image": "nginx",
+                     "securityContext": {"privileged": true},
+                     "resources": {"requests": {"cpu": "100m"}}},
+                    {"name": "side", "image": "registry:5000/tools/side:1.2",
+                     "resources": {"requests": {"cpu": "1"}, "limits": {"cpu": "1"}},
+                     "readinessProbe": {}, "livenessProbe": {}},
+                ],
+                "volumes": [{"name": "sock", "hostPath": {"path": "/var/run/docker.sock"}}],
+            }}},
+        })
+    }
+
+    #[test]
+    fn builtin_rules_flag_pod_template_risks() {
+        let findings = Policy::default().lint(&deploy(), &PolicyContext::default());
+        let got: Vec<(&str, Severity, &str)> = findings
+            .iter()
+            .map(|f| (f.rule.as_str(), f.severity, f.path.as_str()))
+            .collect();
+        let c0 = ".spec.template.spec.containers[0]";
+        assert_eq!(
+            got,
+            vec![
+                ("latest-tag", Severity::Warning, &*format!("{}.image", c0)),
+                (
+                    "missing-resources",
+                    Severity::Warning,
+                    &*format!("{}.resources", c0)
+                ),
+                (
+                    "privileged",
+                    Severity::Error,
+                    &*format!("{}.securityContext.privileged", c0)
+                ),
+                ("missing-probes", Severity::Info, c0),
+                (
+                    "host-path",
+                    Severity::Warning,
+                    ".spec.template.spec.volumes[0].hostPath"
+                ),
+            ]
+        );
+        assert!(findings[1].message.contains("no resource limits"));
+        assert!(!uses_latest("nginx:1.25") && !uses_latest("nginx@sha256:abc"));
+        assert!(uses_latest("localhost:5000/nginx") && uses_latest("nginx:latest"));
+
+        assert_eq!(blocking(&findings).len(), 1);
+        // The object's annotation only demotes, and says so.
+        let mut obj = deploy();
+        obj["metadata"]["annotations"] = json!({OVERRIDE_ANNOTATION: "latest-tag, privileged"});
+        let demoted = demote_annotated(findings.clone(), &obj);
+        assert!(blocking(&demoted).is_empty());
+        assert_eq!(demoted[2].severity, Severity::Warning);
+        assert!(demoted[2]
+            .to_string()
+            .starts_with("policy warning [privileged]"));
+        assert!(demoted[2]
+            .message
+            .contains("demoted by the orka.dev/policy-override"));
+        assert_eq!(demoted[1], findings[1]);
+        obj["metadata"]["annotations"] = json!({OVERRIDE_ANNOTATION: "*"});
+        assert_eq!(blocking(&demote_annotated(findings.clone(), &obj)).len(), 1);
+        // Only the caller can let an error through.
+        set_override(vec!["privileged".to_string()]);
+        assert!(blocking(&findings).is_empty());
+        set_override(Vec::new());
+        assert_eq!(blocking(&findings).len(), 1);
+    }
+
+    #[test]
+    fn config_tunes_builtins_and_adds_path_rules() {
+        let policy = Policy::from_yaml(
+            r#"
+disable: [missing-probes, host-path, missing-resources]
+severity: {latest-tag: error, privileged: warning}
+rules:
+  - id: team-label
+    kinds: [Deployment]
+    path: .metadata.labels.team
+    exists: true
+    severity: error
+    message: workloads need a team label
+  - id: registry
+    path: .spec.template.spec.containers[*].image
+    matches: '^registry:5000/'
+  - id: name-label
+    path: .metadata.labels['app.kubernetes.io/name']
+    oneOf: [web, api]
+  - id: ignored-kind
+    kinds: [Service]
+    path: .spec.type
+    exists: true
+"#,
+        )
+        .unwrap();
+        let findings = policy.lint(&deploy(), &PolicyContext::default());
+        let got: Vec<(&str, Severity, &str)> = findings
+            .iter()
+            .map(|f| (f.rule.as_str(), f.severity, f.path.as_str()))
+            .collect();
+        assert_eq!(
+            got,
+            vec![
+                (
+                    "latest-tag",
+                    Severity::Error,
+                    ".spec.template.spec.containers[0].image"
+                ),
+                (
+                    "privileged",
+                    Severity::Warning,
+                    ".spec.template.spec.containers[0].securityContext.privileged"
+                ),
+                ("team-label", Severity::Error, ".metadata.labels.team"),
+                (
+                    "registry",
+                    Severity::Warning,
+                    ".spec.template.spec.containers[0].image"
+                ),
+            ]
+        );
+        assert_eq!(findings[2].message, "workloads need a team label");
+        assert_eq!(
+            findings[2].to_string(),
+            "policy error [team-label] .metadata.labels.team: workloads need a team label"
+        );
+        assert!(Policy::from_yaml("rules: [{id: x, path: 'spec'}]").is_err());
+    }
+
+    #[test]
+    fn service_selector_needs_matching_pods() {
+        let svc = json!({"kind": "Service", "spec": {"selector": {"app": "web"}}});
+        let policy = Policy::default();
+        assert!(policy.lint(&svc, &PolicyContext::default()).is_empty());
+        let ctx = |labels: &[(&str, &str)]| PolicyContext {
+            pods: Some(vec![labels
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect()]),
+        };
+        let found = policy.lint(&svc, &ctx(&[("app", "api")]));
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].message, "selector app=web matches no pods");
+        assert!(policy
+            .lint(&svc, &ctx(&[("app", "web"), ("tier", "fe")]))
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn service_pods_come_from_snapshot_before_live_list() {
+        use orka_kubehub::fake::FakeApiServer;
+
+        let srv = FakeApiServer::new();
+        srv.create(json!({
+            "apiVersion": "v1", "kind": "Pod",
+            "metadata": {"name": "live", "namespace": "live", "labels": {"app": "web"}}
+        }));
+        let client = srv.client();
+        set_pod_snapshot_source(Some(Arc::new(|ns: &str| {
+            (ns == "cached").then(|| {
+                vec![
+                    BTreeMap::from([("app".to_string(), "api".to_string())]),
+                    BTreeMap::from([("app".to_string(), "web".to_string())]),
+                ]
+            })
+        })));
+        let svc = |sel: &str| json!({"kind": "Service", "spec": {"selector": {"app": sel}}});
+
+        let pods = pods_for_service(&client, &svc("web"), Some("cached")).await;
+        assert_eq!(pods.map(|p| p.len()), Some(1));
+        let pods = pods_for_service(&client, &svc("db"), Some("cached")).await;
+        assert_eq!(pods, Some(vec![]));
+        assert_eq!(srv.count_requests("GET", "/pods"), 0);
+
+        // No snapshot for the namespace: fall back to the apiserver.
+        let pods = pods_for_service(&client, &svc("web"), Some("live")).await;
+        assert_eq!(pods.map(|p| p.len()), Some(1));
+        assert_eq!(srv.count_requests("GET", "/pods"), 1);
+        set_pod_snapshot_source(None);
+    }
+}
@@ -0,0 +1,15 @@
+//! Fixture helpers shared by this crate's unit tests.
+
+use std::path::{Path, PathBuf};
+
+/// A fresh path under the system temp dir, named `orka-<prefix>-<uuid>`.
+pub(crate) fn temp_dir(prefix: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("orka-{}-{}", prefix, uuid::Uuid::new_v4()))
+}
+
+/// Write `text` to `dir/name`, creating parent directories.
+pub(crate) fn write(dir: &Path, name: &str, text: &str) {
+    let p = dir.join(name);
+    std::fs::create_dir_all(p.parent().unwrap()).unwrap();
+    std::fs::write(p, text).unwrap();
+}
@@ -0,0 +1,346 @@
+//! Three-way comparison of a manifest against live state, using the newest
+//! last-applied snapshot as the common base.
+//!
+//! Each changed path is classified as changed by the manifest (`yours`),
+//! changed in the cluster since the last apply (`drifted`), or changed on
+//! both sides to different values (`conflict`), which is what gets
+//! overwritten by applying over a hot-fixed object. Live fields that neither
+//! the base nor the manifest mention (server defaults, other managers'
+//! fields) are left out before comparing. When the object is gone from the
+//! cluster there is nothing to drift or conflict: the manifest's fields are
+//! all `yours`, and applying creates the object.
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value as Json};
+
+use crate::diff::{structured_diff, MERGE_KEYS};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ThreeWayState {
+    /// Changed by the manifest since the last apply.
+    Yours,
+    /// Changed in the cluster since the last apply.
+    Drifted,
+    /// Changed on both sides, to different values.
+    Conflict,
+}
+
+/// One classified path with its value on each side (`None` when absent).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ThreeWayEntry {
+    pub path: String,
+    pub state: ThreeWayState,
+    pub base: Option<Json>,
+    pub live: Option<Json>,
+    pub new: Option<Json>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ThreeWayReport {
+    /// Whether a last-applied base was found; without one every difference
+    /// from live is reported as `yours`.
+    pub has_base: bool,
+    /// The object does not exist in the cluster (never created, or deleted);
+    /// every entry is a field the apply creates.
+    #[serde(default)]
+    pub live_missing: bool,
+    pub yours: usize,
+    pub drifted: usize,
+    pub conflicts: usize,
+    pub entries: Vec<ThreeWayEntry>,
+}
+
+/// `a` equals `b` or lies under it (`.x.y` under `.x`, `.x[0]` under `.x`).
+fn within(a: &str, b: &str) -> bool {
+    a == b
+        || b == "."
+        || (a.starts_with(b) && matches!(a.as_bytes().get(b.len()), Some(b'.') | Some(b'[')))
+}
+
+fn overlaps(a: &str, b: &str) -> bool {
+    within(a, b) || within(b, a)
+}
+
+impl ThreeWayReport {
+    /// Classify `new` against `live` with `base` (the last-applied document)
+    /// as common ancestor. Inputs should already be stripped of status and
+    /// server-managed metadata.
+    /// A `Json::Null` `live` means the object is missing.
+    pub fn new(base: Option<&Json>, live: &Json, new: &Json) -> Self {
+        if live.is_null() {
+            let entries = structured_diff(new, &Json::Object(Map::new()))
+                .into_iter()
+                .map(|c| ThreeWayEntry {
+                    base: base.and_then(|b| b.pointer(&c.pointer)).cloned(),
+                    path: c.path,
+                    state: ThreeWayState::Yours,
+                    live: None,
+                    new: c.new,
+                })
+                .collect();
+            return Self {
+                live_missing: true,
+                ..Self::from_entries(base.is_some(), entries)
+            };
+        }
+        let Some(base) = base else {
+            let live = project(live, &[new]);
+            let entries = structured_diff(new, &live)
+                .into_iter()
+                .map(|c| ThreeWayEntry {
+                    path: c.path,
+                    state: ThreeWayState::Yours,
+                    base: None,
+                    live: c.old,
+                    new: c.new,
+                })
+                .collect();
+            return Self::from_entries(false, entries);
+        };
+        let live = project(live, &[base, new]);
+        let ours = structured_diff(new, base);
+        let theirs = structured_diff(&live, base);
+        let mut entries = Vec::new();
+        for c in &ours {
+            match theirs.iter().find(|t| t.path == c.path) {
+                // Both sides made the same change: nothing to resolve.
+                Some(t) if t.new == c.new => {}
+                Some(t) => entries.push(ThreeWayEntry {
+                    path: c.path.clone(),
+                    state: ThreeWayState::Conflict,
+                    base: c.old.clone(),
+                    live: t.new.clone(),
+                    new: c.new.clone(),
+                }),
+                None => {
+                    let state = if theirs.iter().any(|t| overlaps(&t.path, &c.path)) {
+                        ThreeWayState::Conflict
+                    } else {
+                        ThreeWayState::Yours
+                    };
+                    let live = (state == ThreeWayState::Yours)
+                        .then(|| c.old.clone())
+                        .flatten();
+                    entries.push(ThreeWayEntry {
+                        path: c.path.clone(),
+                        state,
+                        base: c.old.clone(),
+                        live,
+                        new: c.new.clone(),
+                    });
+                }
+            }
+        }
+        for t in &theirs {
+            if ours.iter().any(|c| c.path == t.path) {
+                continue;
+            }
+            let state = if ours.iter().any(|c| overlaps(&c.path, &t.path)) {
+                ThreeWayState::Conflict
+            } else {
+                ThreeWayState::Drifted
+            };
+            let new = (state == ThreeWayState::Drifted)
+                .then(|| t.old.clone())
+                .flatten();
+            entries.push(ThreeWayEntry {
+                path: t.path.clone(),
+                state,
+                base: t.old.clone(),
+                live: t.new.clone(),
+                new,
+            });
+        }
+        entries.sort_by(|a, b| a.path.cmp(&b.path));
+        Self::from_entries(true, entries)
+    }
+
+    fn from_entries(has_base: bool, entries: Vec<ThreeWayEntry>) -> Self {
+        let count = |s| entries.iter().filter(|e| e.state == s).count();
+        Self {
+            has_base,
+            live_missing: false,
+            yours: count(ThreeWayState::Yours),
+            drifted: count(ThreeWayState::Drifted),
+            conflicts: count(ThreeWayState::Conflict),
+            entries,
+        }
+    }
+}
+
+fn item_key(item: &Json) -> Option<(&'static str, String)> {
+    MERGE_KEYS.iter().find_map(|k| {
+        let v = match item.get(*k)? {
+            Json::String(s) => s.clone(),
+            Json::Number(n) => n.to_string(),
+            _ => return None,
+        };
+        Some((*k, v))
+    })
+}
+
+/// Keep only the parts of `live` that some shape mentions: object keys
+/// present in a shape, recursively; list items are matched by merge key (or
+/// index) and kept whole when no shape has them.
+fn project(live: &Json, shapes: &[&Json]) -> Json {
+    match live {
+        Json::Object(m) => {
+            let objs: Vec<&Map<String, Json>> =
+                shapes.iter().filter_map(|s| s.as_object()).collect();
+            if objs.is_empty() {
+                return live.clone();
+            }
+            Json::Object(
+                m.iter()
+                    .filter_map(|(k, v)| {
+                        let sub: Vec<&Json> = objs.iter().filter_map(|o| o.get(k)).collect();
+                        (!sub.is_empty()).then(|| (k.clone(), project(v, &sub)))
+                    })
+                    .collect(),
+            )
+        }
+        Json::Array(items) => {
+            let arrays: Vec<&Vec<Json>> = shapes.iter().filter_map(|s| s.as_array()).collect();
+            if arrays.is_empty() {
+                return live.clone();
+            }
+            Json::Array(
+                items
+                    .iter()
+                    .enumerate()
+                    .map(|(i, item)| {
+                        let key = item_key(item);
+                        let sub: Vec<&Json> = arrays
+                            .iter()
+                            .filter_map(|a| match &key {
+                                Some(k) => a.iter().find(|x| item_key(x).as_ref() == Some(k)),
+                                None => a.get(i),
+                            })
+                            .collect();
+                        if sub.is_empty() {
+                            item.clone()
+                        } else {
+                            project(item, &sub)
+                        }
+                    })
+                    .collect(),
+            )
+        }
+        _ => live.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn deploy(replicas: i64, image: &str, env: Json) -> Json {
+        json!({
+            "apiVersion": "apps/v1",
+            "kind": "Deployment",
+            "metadata": {"name": "web", "namespace": "shop"},
+            "spec": {
+                "replicas": replicas,
+                "template": {"spec": {"containers": [
+                    {"name": "app", "image": image, "env": env},
+                ]}},
+            },
+        })
+    }
+
+    #[test]
+    fn classifies_yours_drifted_and_conflicts() {
+        let base = deploy(2, "web:1", json!([{"name": "MODE", "value": "a"}]));
+        // Hotfix in the cluster: replicas bumped and env changed; the server
+        // also defaults fields nobody manages.
+        let mut live = deploy(5, "web:1", json!([{"name": "MODE", "value": "hotfix"}]));
+        live["spec"]["strategy"] = json!({"type": "RollingUpdate"});
+        live["spec"]["template"]["spec"]["containers"][0]["imagePullPolicy"] =
+            json!("IfNotPresent");
+        live["spec"]["template"]["spec"]["dnsPolicy"] = json!("ClusterFirst");
+        // New manifest: image bump and env change of its own.
+        let new = deploy(2, "web:2", json!([{"name": "MODE", "value": "b"}]));
+
+        let r = ThreeWayReport::new(Some(&base), &live, &new);
+        let got: Vec<(&str, ThreeWayState)> = r
+            .entries
+            .iter()
+            .map(|e| (e.path.as_str(), e.state))
+            .collect();
+        assert_eq!(
+            got,
+            vec![
+                (".spec.replicas", ThreeWayState::Drifted),
+                (
+                    ".spec.template.spec.containers[name=app].env[name=MODE].value",
+                    ThreeWayState::Conflict
+                ),
+                (
+                    ".spec.template.spec.containers[name=app].image",
+                    ThreeWayState::Yours
+                ),
+            ]
+        );
+        assert_eq!((r.yours, r.drifted, r.conflicts), (1, 1, 1));
+        let c = &r.entries[1];
+        assert_eq!(
+            (c.base.as_ref(), c.live.as_ref(), c.new.as_ref()),
+            (Some(&json!("a")), Some(&json!("hotfix")), Some(&json!("b")))
+        );
+        let d = &r.entries[0];
+        assert_eq!(
+            (d.base.as_ref(), d.live.as_ref(), d.new.as_ref()),
+            (Some(&json!(2)), Some(&json!(5)), Some(&json!(2)))
+        );
+    }
+
+    #[test]
+    fn same_change_on_both_sides_and_missing_base() {
+        let base = deploy(2, "web:1", json!([]));
+        let live = deploy(3, "web:1", json!([]));
+        let new = deploy(3, "web:1", json!([]));
+        let r = ThreeWayReport::new(Some(&base), &live, &new);
+        assert!(r.entries.is_empty(), "{:?}", r.entries);
+
+        let r = ThreeWayReport::new(None, &live, &deploy(4, "web:1", json!([])));
+        assert!(!r.has_base);
+        assert_eq!(r.yours, 1);
+        assert_eq!(r.entries[0].live, Some(json!(3)));
+        assert_eq!(r.entries[0].new, Some(json!(4)));
+    }
+
+    #[test]
+    fn missing_live_object_is_all_yours() {
+        let base = deploy(2, "web:1", json!([]));
+        let new = deploy(3, "web:2", json!([]));
+        let r = ThreeWayReport::new(Some(&base), &Json::Null, &new);
+        assert!(r.live_missing && r.has_base);
+        assert_eq!((r.drifted, r.conflicts), (0, 0));
+        let paths: Vec<&str> = r.entries.iter().map(|e| e.path.as_str()).collect();
+        assert_eq!(paths, vec![".apiVersion", ".kind", ".metadata", ".spec"]);
+        assert_eq!(r.yours, 4);
+        let spec = &r.entries[3];
+        assert_eq!(spec.live, None);
+        assert_eq!(spec.base.as_ref(), base.get("spec"));
+        assert_eq!(spec.new.as_ref(), new.get("spec"));
+
+        let r = ThreeWayReport::new(None, &Json::Null, &new);
+        assert!(r.live_missing && !r.has_base);
+        assert_eq!(r.yours, 4);
+        let r = ThreeWayReport::new(None, &new, &new);
+        assert!(!r.live_missing && r.entries.is_empty());
+    }
+
+    #[test]
+    fn nested_overlap_is_a_conflict() {
+        let base = deploy(2, "web:1", json!([{"name": "A", "value": "1"}]));
+        let mut live = base.clone();
+        live["spec"]["template"]["spec"]["containers"][0]["env"] = json!([]);
+        let new = deploy(2, "web:1", json!([{"name": "A", "value": "2"}]));
+        let r = ThreeWayReport::new(Some(&base), &live, &new);
+        assert_eq!(r.conflicts, 2, "{:?}", r.entries);
+        assert!(within(".a.b[0]", ".a.b") && !within(".a.bc", ".a.b"));
+    }
+}
@@ -3,7 +3,7 @@ use std::str::FromStr;
 use anyhow::Result;
 use clap::{ArgAction, Parser, Subcommand, ValueEnum};
 use orka_api::{InProcApi, OrkaApi};
-use orka_ops::{KubeOps, LogOptions, OrkaOps};
+use orka_ops::{KubeOps, LogOptions, LogTarget, OrkaOps};
 use orka_persist::Store;
 use orka_store::spawn_ingest_with_projector;
 use regex::Regex;
@@ -35,6 +35,40 @@ enum Output {
     Json,
 }
 
+/// Where `edit` and `diff` read objects from: `-f`, `-k` or `--chart`.
+#[derive(clap::Args, Debug)]
+#[group(skip)]
+struct SourceArgs {
+    /// YAML file (multi-document allowed), directory, or '-' for stdin
+    #[arg(
+        short = 'f',
+        long = "file",
+        required_unless_present_any = ["kustomize", "chart"],
+        conflicts_with_all = ["kustomize", "chart"]
+    )]
+    file: Option<String>,
+    /// Render the kustomization in DIR
+    #[arg(
+        short = 'k',
+        long = "kustomize",
+        value_name = "DIR",
+        conflicts_with = "chart"
+    )]
+    kustomize: Option<String>,
+    /// Render the local Helm chart in DIR
+    #[arg(long = "chart", value_name = "DIR")]
+    chart: Option<String>,
+    /// Chart values file, applied in order over values.yaml (repeatable)
+    #[arg(long = "values", value_name = "FILE", conflicts_with_all = ["file", "kustomize"])]
+    values: Vec<String>,
+    /// Chart value override key.path=value (repeatable)
+    #[arg(long = "set", value_name = "KEY=VALUE", conflicts_with_all = ["file", "kustomize"])]
+    set: Vec<String>,
+    /// Release name for the chart (.Release.Name)
+    #[arg(long = "release", default_value = "release-name", conflicts_with_all = ["file", "kustomize"])]
+    release: String,
+}
+
 #[derive(Subcommand, Debug)]
 enum Commands {
     /// Discover served resources (incl. CRDs)
@@ -45,29 +79,44 @@ enum Commands {
     },
     /// List objects for a given group/version/kind key
     Ls {
-        /// GVK key, e.g. "v1/ConfigMap" or "cert-manager.io/v1/Certificate"
+        /// GVK key or resource name, e.g. "v1/ConfigMap", "cm" or "certificates.cert-manager.io"
         gvk: String,
     },
     /// Watch objects for a GVK and print +/- events
     Watch {
-        /// GVK key, e.g. "v1/ConfigMap" or "cert-manager.io/v1/Certificate"
+        /// GVK key or resource name, e.g. "v1/ConfigMap", "cm" or "certificates.cert-manager.io"
         gvk: String,
     },
     /// Inspect schema details for a GVK (CRDs only)
     Schema {
-        /// GVK key, e.g. "cert-manager.io/v1/Certificate"
-        gvk: String,
+        /// GVK key or resource name, e.g. "cert-manager.io/v1/Certificate" or "certificates"
+        #[arg(required_unless_present = "dump")]
+        gvk: Option<String>,
+        /// Write the cluster's OpenAPI v3 documents to DIR for offline
+        /// validation (ORKA_SCHEMA_PATH)
+        #[arg(long = "dump", value_name = "DIR", conflicts_with = "gvk")]
+        dump: Option<std::path::PathBuf>,
     },
     /// Get a single live object and print raw payload
     Get {
-        /// GVK key, e.g. "v1/ConfigMap" or "group/v1/Foo"
+        /// GVK key or resource name, e.g. "v1/ConfigMap", "configmaps" or "foos.group"
         gvk: String,
         /// Resource name
         name: String,
     },
+    /// Show which field managers own which fields of a live object
+    Owners {
+        /// GVK key or resource name, e.g. "apps/v1/Deployment" or "deploy"
+        gvk: String,
+        /// Resource name
+        name: String,
+        /// Only show this field and the fields below it, e.g. ".spec.replicas"
+        #[arg(long = "field")]
+        field: Option<String>,
+    },
     /// Search current snapshot (simple RAM index)
     Search {
-        /// GVK key to watch while indexing
+        /// GVK key or resource name to watch while indexing
         gvk: String,
         /// Query string (supports free text + typed filters)
         query: String,
@@ -86,24 +135,56 @@ enum Commands {
     },
     /// Edit a resource from a YAML file (dry-run or apply)
     Edit {
-        /// YAML path or '-' for stdin
-        #[arg(short = 'f', long = "file")]
-        file: String,
+        #[command(flatten)]
+        source: SourceArgs,
         /// Validate against CRD JSONSchema (feature-gated)
         #[arg(long = "validate", action = ArgAction::SetTrue)]
         validate: bool,
+        /// Only validate, without dry-run or apply; exits 1 when any issue
+        /// is found (offline with ORKA_SCHEMA_OFFLINE_ONLY=1)
+        #[arg(long = "validate-only", action = ArgAction::SetTrue, conflicts_with_all = ["dry_run", "apply", "applyset"])]
+        validate_only: bool,
         /// Perform a server-side dry-run
         #[arg(long = "dry-run", action = ArgAction::SetTrue)]
         dry_run: bool,
         /// Apply with SSA (fieldManager=orka)
         #[arg(long = "apply", action = ArgAction::SetTrue)]
         apply: bool,
+        /// Take ownership of fields owned by other field managers (SSA force)
+        #[arg(long = "force", action = ArgAction::SetTrue)]
+        force: bool,
+        /// Let error-level policy findings of RULE through (repeatable or
+        /// comma separated; `*` for every rule)
+        #[arg(long = "policy-override", value_name = "RULE", value_delimiter = ',')]
+        policy_override: Vec<String>,
+        /// Track the objects as an ApplySet whose parent is the Secret NAME
+        /// in the namespace (default: "default")
+        #[arg(long = "applyset", value_name = "NAME", requires = "prune")]
+        applyset: Option<String>,
+        /// Delete apply set members that are no longer in the manifests
+        #[arg(long = "prune", action = ArgAction::SetTrue, requires = "applyset")]
+        prune: bool,
     },
     /// Show minimal diffs vs live and last-applied
     Diff {
-        /// YAML path or '-' for stdin
-        #[arg(short = 'f', long = "file")]
-        file: String,
+        #[command(flatten)]
+        source: SourceArgs,
+        /// Print only add/update/remove counts
+        #[arg(long = "summary", action = ArgAction::SetTrue)]
+        summary: bool,
+        /// Print an RFC 6902 JSON Patch that turns live into the target
+        #[arg(long = "json-patch", action = ArgAction::SetTrue, conflicts_with = "summary")]
+        json_patch: bool,
+        /// Compare against live with the last-applied snapshot as base:
+        /// changed by you, drifted in the cluster, or both (conflict)
+        #[arg(long = "three-way", action = ArgAction::SetTrue, conflicts_with_all = ["summary", "json_patch"])]
+        three_way: bool,
+    },
+    /// Compare every object with last-applied history against live state
+    Drift {
+        /// Keep watching and print objects as their drift state changes
+        #[arg(long = "watch", action = ArgAction::SetTrue)]
+        watch: bool,
     },
     /// Inspect last-applied snapshots for a resource
     #[command(name = "last-applied")]
@@ -127,8 +208,11 @@ enum Commands {
 enum OpsCmd {
     /// Stream logs from a pod/container
     Logs {
-        /// Pod name
-        pod: String,
+        /// Pod name, or KIND/NAME (deploy, sts, ds, rs, job, svc) to follow all of its pods
+        pod: Option<String>,
+        /// Follow all pods matching a label selector instead of a single pod
+        #[arg(short = 'l', long = "selector", conflicts_with = "pod")]
+        selector: Option<String>,
         /// Container name (optional)
         #[arg(short = 'c', long = "container")]
         container: Vec<String>,
@@ -168,7 +252,7 @@ enum OpsCmd {
     },
     /// Scale a resource to N replicas (supports subresource when available)
     Scale {
-        /// GVK key, e.g. "apps/v1/Deployment"
+        /// GVK key or resource name, e.g. "apps/v1/Deployment" or "deploy"
         gvk: String,
         /// Resource name
         name: String,
@@ -181,7 +265,7 @@ enum OpsCmd {
     /// Rollout restart (patch template annotation)
     #[command(name = "rr")]
     RolloutRestart {
-        /// GVK key, e.g. "apps/v1/Deployment"
+        /// GVK key or resource name, e.g. "apps/v1/Deployment" or "deploy"
         gvk: String,
         /// Resource name
         name: String,
@@ -216,7 +300,7 @@ enum OpsCmd {
 enum LastAppliedCmd {
     /// Get last-applied snapshots for a resource
     Get {
-        /// GVK key, e.g. "v1/ConfigMap" or "group/v1/Foo"
+        /// GVK key or resource name, e.g. "v1/ConfigMap", "configmaps" or "foos.group"
         gvk: String,
         /// Resource name
         name: String,
@@ -227,6 +311,83 @@ enum LastAppliedCmd {
         #[arg(short = 'o', long = "output", value_enum)]
         output: Option<Output>,
     },
+    /// History of an object by kind, namespace and name, across every UID it
+    /// had (works after the object was deleted or recreated)
+    History {
+        /// GVK key or resource name, e.g. "apps/v1/Deployment" or "deploy"
+        gvk: String,
+        /// Resource name
+        name: String,
+        /// Limit number of entries
+        #[arg(long = "limit", default_value_t = 10)]
+        limit: usize,
+    },
+    /// List every object with history, deleted ones included
+    Ls {},
+    /// Drop history beyond the retention limits and rewrite sealed segments
+    Compact {},
+    /// Write history to a tar archive of YAML files plus manifest.json
+    /// (--ns limits it to a namespace)
+    Export {
+        /// Archive path, or '-' for stdout
+        #[arg(short = 'f', long = "file")]
+        file: String,
+        /// Only this kind (GVK key or resource name)
+        #[arg(long = "gvk")]
+        gvk: Option<String>,
+        /// Only snapshots applied at or after this time (unix seconds)
+        #[arg(long = "since")]
+        since: Option<i64>,
+        /// Only snapshots applied before this time (unix seconds)
+        #[arg(long = "until")]
+        until: Option<i64>,
+        /// Include Secrets (the archive is not encrypted)
+        #[arg(long = "include-secrets", action = ArgAction::SetTrue)]
+        include_secrets: bool,
+    },
+    /// Add the snapshots of an exported archive, skipping those already present
+    Import {
+        /// Archive path, or '-' for stdin
+        #[arg(short = 'f', long = "file")]
+        file: String,
+        /// Import Secrets even though the history store is not encrypted
+        #[arg(long = "include-secrets", action = ArgAction::SetTrue)]
+        include_secrets: bool,
+    },
+    /// Re-encrypt all history with a new key, read with the current one
+    /// (ORKA_DB_KEY / ORKA_DB_KEY_FILE)
+    RotateKey {
+        /// Keyfile holding the new key (64 hex digits or 32 raw bytes)
+        #[arg(long = "new-key-file", conflicts_with_all = ["generate", "decrypt"])]
+        new_key_file: Option<std::path::PathBuf>,
+        /// Write a new random key to this file (must not exist) and use it
+        #[arg(long = "generate", conflicts_with = "decrypt")]
+        generate: Option<std::path::PathBuf>,
+        /// Store the history in plain text again
+        #[arg(long = "decrypt", action = ArgAction::SetTrue)]
+        decrypt: bool,
+    },
+    /// Show a stored revision's diff against live and, with --apply, re-apply it
+    Rollback {
+        /// GVK key or resource name of the live object
+        #[arg(required_unless_present = "uid")]
+        gvk: Option<String>,
+        /// Resource name
+        #[arg(required_unless_present = "uid")]
+        name: Option<String>,
+        /// Address the history by UID instead (works after the object was deleted)
+        #[arg(long = "uid", conflicts_with_all = ["gvk", "name"])]
+        uid: Option<String>,
+        /// Revision index as listed by `last-applied get` (0 = newest)
+        #[arg(long = "revision", default_value_t = 1)]
+        revision: usize,
+        /// Re-apply the revision via SSA (default: show the diff only)
+        #[arg(long = "apply", action = ArgAction::SetTrue)]
+        apply: bool,
+        /// Take ownership of fields owned by other managers
+        #[arg(long = "force", action = ArgAction::SetTrue, requires = "apply")]
+        force: bool,
+    },
 }
 
 fn init_tracing() {
@@ -350,6 +511,7 @@ async fn main() -> Result<()> {
             }
         }
         Commands::Ls { gvk } => {
+            let gvk = orka_kubehub::resolve_gvk_key(&gvk).await?;
             let ns = cli.namespace.as_deref();
             info!(gvk = %gvk, ns = ?ns, "ls invoked");
             if let Some(api) = &api {
@@ -485,6 +647,7 @@ async fn main() -> Result<()> {
             }
         }
         Commands::Watch { gvk } => {
+            let gvk = orka_kubehub::resolve_gvk_key(&gvk).await?;
             let ns = cli.namespace.as_deref();
             info!(gvk = %gvk, ns = ?ns, "watch invoked");
             if let Some(api) = &api {
@@ -578,7 +741,21 @@ async fn main() -> Result<()> {
                 warn!("watch loop ended (graceful shutdown)");
             }
         }
-        Commands::Schema { gvk } => {
+        Commands::Schema { gvk, dump } => {
+            if let Some(dir) = dump {
+                #[cfg(feature = "validate")]
+                {
+                    let n = orka_schema::openapi::dump(&dir).await?;
+                    println!("wrote {} OpenAPI documents to {}", n, dir.display());
+                }
+                #[cfg(not(feature = "validate"))]
+                {
+                    let _ = dir;
+                    warn!("--dump requires the CLI 'validate' feature");
+                }
+                return Ok(());
+            }
+            let gvk = orka_kubehub::resolve_gvk_key(gvk.as_deref().unwrap_or_default()).await?;
             info!(gvk = %gvk, "schema invoked");
             if let Some(api) = &api {
                 match api.schema(&gvk).await {
@@ -657,22 +834,10 @@ async fn main() -> Result<()> {
             }
         }
         Commands::Get { gvk, name } => {
+            let gvk = orka_kubehub::resolve_gvk_key(&gvk).await?;
             let ns = cli.namespace.as_deref();
             if let Some(api) = &api {
-                let (group, version, kind) =
-                    parse_gvk(&gvk).ok_or_else(|| anyhow::anyhow!("invalid gvk: {}", gvk))?;
-                let rk = orka_api::ResourceKind {
-                    group,
-                    version,
-                    kind,
-                    namespaced: ns.is_some(),
-                };
-                let rr = orka_api::ResourceRef {
-                    cluster: None,
-                    gvk: rk,
-                    namespace: ns.map(|s| s.to_string()),
-                    name: name.clone(),
-                };
+                let rr = resource_ref(&gvk, ns, &name)?;
                 match api.get_raw(rr).await {
                     Ok(bytes) => {
                         let v: serde_json::Value = serde_json::from_slice(&bytes)?;
@@ -700,6 +865,29 @@ async fn main() -> Result<()> {
                 }
             }
         }
+        Commands::Owners { gvk, name, field } => {
+            let gvk = orka_kubehub::resolve_gvk_key(&gvk).await?;
+            let ns = cli.namespace.as_deref();
+            let rows = if let Some(api) = &api {
+                api.managed_fields(resource_ref(&gvk, ns, &name)?)
+                    .await
+                    .map_err(|e| anyhow::anyhow!(e.to_string()))
+            } else {
+                fetch_object_json(&gvk, &name, ns)
+                    .await
+                    .map(|v| orka_apply::field_ownership(&v))
+            };
+            match rows {
+                Ok(rows) => {
+                    let rows = orka_apply::filter_ownership(rows, field.as_deref().unwrap_or(""));
+                    match cli.output {
+                        Output::Human => print_owners(&rows),
+                        Output::Json => println!("{}", serde_json::to_string_pretty(&rows)?),
+                    }
+                }
+                Err(e) => eprintln!("owners error: {}", e),
+            }
+        }
         Commands::Search {
             gvk,
             query,
@@ -708,6 +896,7 @@ async fn main() -> Result<()> {
             min_score,
             explain,
         } => {
+            let gvk = orka_kubehub::resolve_gvk_key(&gvk).await?;
             // Choose watcher namespace: CLI --ns overrides, else extract from query ns:token
             let ns_from_query = query
                 .split_whitespace()
@@ -948,150 +1137,258 @@ async fn main() -> Result<()> {
             watcher_handle.abort();
         }
         Commands::Edit {
-            file,
+            source,
             validate,
+            validate_only,
             dry_run,
             apply,
+            force,
+            policy_override,
+            applyset,
+            prune: _,
         } => {
             let ns = cli.namespace.as_deref();
-            let yaml = read_input(&file)?;
-            if validate {
-                #[cfg(feature = "validate")]
-                {
-                    // Detect GVK from YAML for schema lookup
-                    let j: serde_yaml::Value = serde_yaml::from_str(&yaml)?;
-                    let api_ver = j.get("apiVersion").and_then(|v| v.as_str()).unwrap_or("");
-                    let kind = j.get("kind").and_then(|v| v.as_str()).unwrap_or("");
-                    let gvk_key = if api_ver.contains('/') {
-                        format!("{}/{}", api_ver, kind)
-                    } else {
-                        format!("{}/{}", api_ver, kind)
-                    };
-                    let issues =
-                        orka_schema::validate::validate_yaml_for_gvk(&gvk_key, &yaml).await?;
-                    if !issues.is_empty() {
-                        eprintln!("validation issues ({}):", issues.len());
-                        for it in issues {
-                            eprintln!(
-                                "- {}: {}{}",
-                                it.path,
-                                it.error,
-                                it.hint
-                                    .as_deref()
-                                    .map(|h| format!(" ({})", h))
-                                    .unwrap_or_default()
-                            );
-                        }
-                    }
+            orka_apply::set_policy_override(policy_override);
+            if validate_only {
+                let docs = match read_source(&source, ns)? {
+                    ManifestInput::Single(yaml) => vec![(yaml, None)],
+                    ManifestInput::Batch(manifests) => manifests
+                        .into_iter()
+                        .map(|m| {
+                            let label = m.display_name();
+                            (m.yaml, Some(label))
+                        })
+                        .collect(),
+                };
+                let mut issues = 0;
+                for (yaml, label) in &docs {
+                    issues += validate_yaml(yaml, label.as_deref()).await?;
                 }
-                #[cfg(not(feature = "validate"))]
-                {
-                    warn!("validate flag set but CLI built without 'validate' feature");
+                if issues > 0 {
+                    std::process::exit(1);
                 }
+                println!("valid: {} object(s)", docs.len());
+                return Ok(());
             }
             let do_apply = if apply { true } else { !dry_run };
-            if let Some(api) = &api {
-                let res = if do_apply {
-                    api.apply(&yaml).await
-                } else {
-                    api.dry_run(&yaml).await.map(|d| orka_apply::ApplyResult {
+            if let Some(set_name) = applyset {
+                let manifests = match read_source(&source, ns)? {
+                    ManifestInput::Batch(manifests) => manifests,
+                    ManifestInput::Single(yaml) => orka_apply::manifest::split_manifests(
+                        &yaml,
+                        source.file.as_deref().unwrap_or("-"),
+                    )?,
+                };
+                if validate {
+                    for m in &manifests {
+                        validate_yaml(&m.yaml, Some(&m.display_name())).await?;
+                    }
+                }
+                let set = orka_apply::ApplySet::new(&set_name, ns.unwrap_or("default"));
+                match orka_apply::apply_set(&set, manifests, ns, validate, do_apply, force).await {
+                    Ok(res) => print_apply_set(&res, cli.output)?,
+                    Err(e) => eprintln!("edit error: {}", e),
+                }
+                return Ok(());
+            }
+            let yaml = match read_source(&source, ns)? {
+                ManifestInput::Single(yaml) => yaml,
+                ManifestInput::Batch(manifests) => {
+                    if validate {
+                        for m in &manifests {
+                            validate_yaml(&m.yaml, Some(&m.display_name())).await?;
+                        }
+                    }
+                    let res =
+                        edit_batch(api.as_ref(), manifests, ns, validate, do_apply, force).await;
+                    print_batch(&res, cli.output)?;
+                    return Ok(());
+                }
+            };
+            if validate {
+                validate_yaml(&yaml, None).await?;
+            }
+            let res = match &api {
+                Some(api) if do_apply => api.apply(&yaml, force).await.map_err(api_err),
+                Some(api) => api
+                    .dry_run(&yaml)
+                    .await
+                    .map(|d| orka_apply::ApplyResult {
                         dry_run: true,
                         applied: false,
                         new_rv: None,
                         warnings: vec![],
                         summary: d,
                     })
-                };
-                match res {
-                    Ok(res) => match cli.output {
-                        Output::Human => {
-                            if res.dry_run {
-                                println!(
-                                    "dry-run: +{} ~{} -{}",
-                                    res.summary.adds, res.summary.updates, res.summary.removes
-                                );
-                            } else if res.applied {
-                                println!("applied rv={}", res.new_rv.unwrap_or_default());
-                            } else {
-                                println!("no-op");
-                            }
+                    .map_err(api_err),
+                None => orka_apply::edit_from_yaml_with(&yaml, ns, validate, do_apply, force).await,
+            };
+            match res {
+                Ok(res) => match cli.output {
+                    Output::Human => {
+                        if res.dry_run {
+                            println!(
+                                "dry-run: +{} ~{} -{}",
+                                res.summary.adds, res.summary.updates, res.summary.removes
+                            );
+                        } else if res.applied {
+                            println!("applied rv={}", res.new_rv.as_deref().unwrap_or_default());
+                        } else {
+                            println!("no-op");
                         }
-                        Output::Json => println!("{}", serde_json::to_string_pretty(&res)?),
-                    },
-                    Err(e) => eprintln!("edit error: {}", e),
-                }
-            } else {
-                match orka_apply::edit_from_yaml(&yaml, ns, validate, do_apply).await {
-                    Ok(res) => match cli.output {
-                        Output::Human => {
-                            if res.dry_run {
-                                println!(
-                                    "dry-run: +{} ~{} -{}",
-                                    res.summary.adds, res.summary.updates, res.summary.removes
-                                );
-                            } else if res.applied {
-                                println!("applied rv={}", res.new_rv.unwrap_or_default());
-                            } else {
-                                println!("no-op");
-                            }
+                        for w in &res.warnings {
+                            eprintln!("warning: {}", w);
                         }
-                        Output::Json => println!("{}", serde_json::to_string_pretty(&res)?),
-                    },
-                    Err(e) => {
-                        eprintln!("edit error: {}", e);
                     }
-                }
+                    Output::Json => println!("{}", serde_json::to_string_pretty(&res)?),
+                },
+                Err(e) => match (e.downcast_ref::<orka_apply::ApplyConflict>(), cli.output) {
+                    (Some(c), Output::Json) => {
+                        println!(
+                            "{}",
+                            serde_json::to_string_pretty(&serde_json::json!({
+                                "error": c.to_string(),
+                                "conflicts": c.conflicts,
+                            }))?
+                        );
+                    }
+                    (Some(c), Output::Human) => print_conflicts(c),
+                    (None, _) => eprintln!("edit error: {}", e),
+                },
             }
         }
-        Commands::Diff { file } => {
+        Commands::Diff {
+            source,
+            summary,
+            json_patch,
+            three_way,
+        } => {
             let ns = cli.namespace.as_deref();
-            let yaml = read_input(&file)?;
-            if let Some(api) = &api {
-                match api.diff(&yaml, ns).await {
-                    Ok((live, last)) => match cli.output {
-                        Output::Human => {
-                            println!(
-                                "vs live: +{} ~{} -{}",
-                                live.adds, live.updates, live.removes
-                            );
-                            if let Some(ls) = last {
-                                println!("vs last: +{} ~{} -{}", ls.adds, ls.updates, ls.removes);
-                            }
-                        }
-                        Output::Json => {
-                            #[derive(serde::Serialize)]
-                            struct D {
-                                live: orka_apply::DiffSummary,
-                                last: Option<orka_apply::DiffSummary>,
+            if three_way {
+                let manifests = match read_source(&source, ns)? {
+                    ManifestInput::Single(yaml) => vec![(None, yaml)],
+                    ManifestInput::Batch(mut manifests) => {
+                        orka_apply::manifest::order_for_apply(&mut manifests);
+                        manifests
+                            .into_iter()
+                            .map(|m| (Some(m.display_name()), m.yaml))
+                            .collect()
+                    }
+                };
+                let mut rows = Vec::new();
+                for (target, yaml) in &manifests {
+                    let res = match &api {
+                        Some(api) => api
+                            .diff_three_way(yaml, ns)
+                            .await
+                            .map_err(|e| anyhow::anyhow!(e.to_string())),
+                        None => orka_apply::three_way_from_yaml(yaml, ns).await,
+                    };
+                    match (res, cli.output) {
+                        (Ok(r), Output::Human) => print_three_way(target.as_deref(), &r),
+                        (Ok(r), Output::Json) => {
+                            let mut v = serde_json::to_value(&r)?;
+                            if let Some(t) = target {
+                                v["target"] = serde_json::json!(t);
                             }
-                            println!("{}", serde_json::to_string_pretty(&D { live, last })?);
+                            rows.push(v);
                         }
-                    },
-                    Err(e) => eprintln!("diff(api) error: {}", e),
+                        (Err(e), Output::Human) => eprintln!(
+                            "{}three-way diff error: {}",
+                            target
+                                .as_deref()
+                                .map(|t| format!("{}: ", t))
+                                .unwrap_or_default(),
+                            e
+                        ),
+                        (Err(e), Output::Json) => rows.push(serde_json::json!({
+                            "target": target,
+                            "error": e.to_string(),
+                        })),
+                    }
                 }
-            } else {
-                match orka_apply::diff_from_yaml(&yaml, ns).await {
-                    Ok((live, last)) => match cli.output {
-                        Output::Human => {
-                            println!(
-                                "vs live: +{} ~{} -{}",
-                                live.adds, live.updates, live.removes
-                            );
-                            if let Some(ls) = last {
-                                println!("vs last: +{} ~{} -{}", ls.adds, ls.updates, ls.removes);
-                            }
+                if cli.output == Output::Json {
+                    match rows.len() {
+                        1 if manifests[0].0.is_none() => {
+                            println!("{}", serde_json::to_string_pretty(&rows[0])?)
                         }
-                        Output::Json => {
-                            #[derive(serde::Serialize)]
-                            struct D {
-                                live: orka_apply::DiffSummary,
-                                last: Option<orka_apply::DiffSummary>,
-                            }
-                            println!("{}", serde_json::to_string_pretty(&D { live, last })?);
-                        }
-                    },
-                    Err(e) => eprintln!("diff error: {}", e),
+                        _ => println!("{}", serde_json::to_string_pretty(&rows)?),
+                    }
+                }
+                return Ok(());
+            }
+            let mode = DiffMode {
+                output: cli.output,
+                summary,
+                json_patch,
+            };
+            let yaml = match read_source(&source, ns)? {
+                ManifestInput::Single(yaml) => yaml,
+                ManifestInput::Batch(manifests) => {
+                    diff_batch(api.as_ref(), manifests, ns, mode).await?;
+                    return Ok(());
+                }
+            };
+            let res = match &api {
+                Some(api) => api
+                    .diff_report(&yaml, ns)
+                    .await
+                    .map_err(|e| anyhow::anyhow!("diff(api) error: {}", e)),
+                None => orka_apply::diff_report_from_yaml(&yaml, ns)
+                    .await
+                    .map_err(|e| anyhow::anyhow!("diff error: {}", e)),
+            };
+            match res {
+                Ok((live, last)) => print_diff(&live, last.as_ref(), mode)?,
+                Err(e) => eprintln!("{}", e),
+            }
+        }
+        Commands::Drift { watch } => {
+            if watch {
+                let (tx, mut rx) = tokio::sync::mpsc::channel(256);
+                let task = tokio::spawn(orka_apply::watch_drift(tx));
+                while let Some(item) = rx.recv().await {
+                    match cli.output {
+                        Output::Human => print_drift_item(&item),
+                        Output::Json => println!("{}", serde_json::to_string(&item)?),
+                    }
+                }
+                task.await??;
+                return Ok(());
+            }
+            let store = orka_persist::LogStore::open_default()?;
+            let report = orka_apply::scan_drift(&store).await?;
+            match cli.output {
+                Output::Human => {
+                    for item in &report.items {
+                        print_drift_item(item);
+                    }
+                    for e in &report.errors {
+                        eprintln!("drift: skipped {}", e);
+                    }
+                    if !report.legacy.is_empty() {
+                        eprintln!(
+                            "drift: skipped {} snapshot(s) recorded without a cluster: {}",
+                            report.legacy.len(),
+                            report.legacy.join(", ")
+                        );
+                    }
+                    if report.other_context > 0 {
+                        eprintln!(
+                            "drift: {} object(s) belong to other contexts",
+                            report.other_context
+                        );
+                    }
+                    println!(
+                        "scanned {}: {} in sync, {} drifted, {} missing, {} replaced",
+                        report.scanned,
+                        report.in_sync,
+                        report.drifted,
+                        report.missing,
+                        report.replaced
+                    );
                 }
+                Output::Json => println!("{}", serde_json::to_string_pretty(&report)?),
             }
         }
         Commands::LastApplied { sub } => {
@@ -1102,6 +1399,7 @@ async fn main() -> Result<()> {
                     limit,
                     output,
                 } => {
+                    let gvk = orka_kubehub::resolve_gvk_key(&gvk).await?;
                     let ns = cli.namespace.as_deref();
                     if let Some(api) = &api {
                         match api.last_applied(&gvk, &name, ns, Some(limit)).await {
@@ -1169,6 +1467,299 @@ async fn main() -> Result<()> {
                         }
                     }
                 }
+                LastAppliedCmd::History { gvk, name, limit } => {
+                    let ns = cli.namespace.as_deref();
+                    let rows = match &api {
+                        Some(api) => api
+                            .history(&gvk, &name, ns, Some(limit))
+                            .await
+                            .map_err(api_err)?,
+                        None => {
+                            // Kinds no longer served are looked up by exact key.
+                            let gvk = match orka_kubehub::resolve_gvk_key(&gvk).await {
+                                Ok(key) => key,
+                                Err(_) if gvk.contains('/') => gvk,
+                                Err(e) => return Err(e),
+                            };
+                            let namespaced = orka_kubehub::get_api_resource(&gvk)
+                                .await
+                                .map_or(ns.is_some(), |(_, namespaced)| namespaced);
+                            let key = orka_persist::ObjectKey {
+                                cluster: orka_kubehub::active_context(),
+                                gvk,
+                                namespace: ns.filter(|_| namespaced).map(|s| s.to_string()),
+                                name,
+                            };
+                            orka_persist::LogStore::open_default()?.history(&key, Some(limit))?
+                        }
+                    };
+                    match cli.output {
+                        Output::Human => {
+                            for r in &rows {
+                                println!(
+                                    "ts={} rv={} uid={}",
+                                    r.ts,
+                                    r.rv,
+                                    uuid::Uuid::from_bytes(r.uid)
+                                );
+                            }
+                        }
+                        Output::Json => {
+                            let out: Vec<serde_json::Value> = rows
+                                .into_iter()
+                                .map(|r| {
+                                    serde_json::json!({
+                                        "uid": uuid::Uuid::from_bytes(r.uid).to_string(),
+                                        "ts": r.ts,
+                                        "rv": r.rv,
+                                        "yaml": orka_persist::maybe_decompress(&r.yaml_zstd),
+                                    })
+                                })
+                                .collect();
+                            println!("{}", serde_json::to_string_pretty(&out)?);
+                        }
+                    }
+                }
+                LastAppliedCmd::Ls {} => {
+                    let objects = match &api {
+                        Some(api) => api.history_objects().await.map_err(api_err)?,
+                        None => orka_persist::LogStore::open_default()?.objects(),
+                    };
+                    match cli.output {
+                        Output::Human => {
+                            for o in &objects {
+                                let k = &o.key;
+                                let name = match &k.namespace {
+                                    Some(ns) => format!("{}/{}", ns, k.name),
+                                    None => k.name.clone(),
+                                };
+                                println!(
+                                    "{}  {}  {}  uids={} records={} last_ts={}",
+                                    k.cluster,
+                                    k.gvk,
+                                    name,
+                                    o.uids.len(),
+                                    o.records,
+                                    o.last_ts
+                                );
+                            }
+                        }
+                        Output::Json => {
+                            let out: Vec<serde_json::Value> = objects
+                                .iter()
+                                .map(|o| {
+                                    let uids: Vec<String> = o
+                                        .uids
+                                        .iter()
+                                        .map(|u| uuid::Uuid::from_bytes(*u).to_string())
+                                        .collect();
+                                    serde_json::json!({
+                                        "key": o.key,
+                                        "uids": uids,
+                                        "records": o.records,
+                                        "last_ts": o.last_ts,
+                                    })
+                                })
+                                .collect();
+                            println!("{}", serde_json::to_string_pretty(&out)?);
+                        }
+                    }
+                }
+                LastAppliedCmd::Compact {} => {
+                    let store = orka_persist::LogStore::open_default()?;
+                    let stats = store.compact()?;
+                    let after = store.stats();
+                    match (cli.output, stats) {
+                        (Output::Json, stats) => println!(
+                            "{}",
+                            serde_json::to_string_pretty(&serde_json::json!({
+                                "compaction": stats,
+                                "store": after,
+                            }))?
+                        ),
+                        (Output::Human, None) => {
+                            println!("another compaction is running; try again later")
+                        }
+                        (Output::Human, Some(c)) => println!(
+                            "dropped {} record(s), rewrote {} and removed {} segment(s): {} -> {} bytes; {} record(s) for {} object(s) in {} segment(s)",
+                            c.records_dropped,
+                            c.segments_rewritten,
+                            c.segments_removed,
+                            c.bytes_before,
+                            c.bytes_after,
+                            after.records,
+                            after.uids,
+                            after.segments
+                        ),
+                    }
+                }
+                LastAppliedCmd::Export {
+                    file,
+                    gvk,
+                    since,
+                    until,
+                    include_secrets,
+                } => {
+                    let gvk = match gvk {
+                        Some(g) => Some(match orka_kubehub::resolve_gvk_key(&g).await {
+                            Ok(key) => key,
+                            Err(_) if g.contains('/') => g,
+                            Err(e) => return Err(e),
+                        }),
+                        None => None,
+                    };
+                    let filter = orka_persist::ExportFilter {
+                        namespace: cli.namespace.clone(),
+                        gvk,
+                        since,
+                        until,
+                        secrets: include_secrets,
+                    };
+                    let store = orka_persist::LogStore::open_default()?;
+                    let stats = if file == "-" {
+                        store.export(&filter, std::io::stdout().lock())?
+                    } else {
+                        let out = std::fs::File::create(&file)
+                            .map_err(|e| anyhow::anyhow!("creating {}: {}", file, e))?;
+                        store.export(&filter, std::io::BufWriter::new(out))?
+                    };
+                    match cli.output {
+                        Output::Json => eprintln!("{}", serde_json::to_string(&stats)?),
+                        Output::Human => eprintln!(
+                            "exported {} snapshot(s) of {} object(s) ({} bytes of YAML)",
+                            stats.records, stats.objects, stats.bytes
+                        ),
+                    }
+                }
+                LastAppliedCmd::Import {
+                    file,
+                    include_secrets,
+                } => {
+                    let store = orka_persist::LogStore::open_default()?;
+                    let stats = if file == "-" {
+                        store.import(std::io::stdin().lock(), include_secrets)?
+                    } else {
+                        let input = std::fs::File::open(&file)
+                            .map_err(|e| anyhow::anyhow!("opening {}: {}", file, e))?;
+                        store.import(std::io::BufReader::new(input), include_secrets)?
+                    };
+                    match cli.output {
+                        Output::Json => println!("{}", serde_json::to_string_pretty(&stats)?),
+                        Output::Human => {
+                            println!(
+                                "imported {} snapshot(s), {} already present",
+                                stats.imported, stats.duplicates
+                            );
+                            if stats.skipped > 0 {
+                                eprintln!(
+                                    "skipped {} Secret snapshot(s): the store is not encrypted (pass --include-secrets to import them)",
+                                    stats.skipped
+                                );
+                            }
+                        }
+                    }
+                }
+                LastAppliedCmd::RotateKey {
+                    new_key_file,
+                    generate,
+                    decrypt,
+                } => {
+                    let (key, key_file) = match (new_key_file, generate, decrypt) {
+                        (Some(path), _, _) => (Some(orka_persist::Key::load(&path)?), Some(path)),
+                        (None, Some(path), _) => {
+                            let hex = orka_persist::Key::generate();
+                            write_key_file(&path, &hex)?;
+                            (Some(orka_persist::Key::parse(&hex)?), Some(path))
+                        }
+                        (None, None, true) => (None, None),
+                        _ => {
+                            return Err(anyhow::anyhow!(
+                                "one of --new-key-file, --generate or --decrypt required"
+                            ))
+                        }
+                    };
+                    let stats = orka_persist::LogStore::open_default()?.rotate_key(key)?;
+                    match cli.output {
+                        Output::Json => println!("{}", serde_json::to_string_pretty(&stats)?),
+                        Output::Human => {
+                            match &stats.key_id {
+                                Some(id) => println!(
+                                    "re-encrypted {} record(s) in {} segment(s) with key {}",
+                                    stats.records, stats.segments, id
+                                ),
+                                None => println!(
+                                    "decrypted {} record(s) in {} segment(s)",
+                                    stats.records, stats.segments
+                                ),
+                            }
+                            match key_file {
+                                Some(path) => println!(
+                                    "set ORKA_DB_KEY_FILE={} (and unset ORKA_DB_KEY) for every orka process",
+                                    path.display()
+                                ),
+                                None => println!("unset ORKA_DB_KEY and ORKA_DB_KEY_FILE"),
+                            }
+                        }
+                    }
+                }
+                LastAppliedCmd::Rollback {
+                    gvk,
+                    name,
+                    uid,
+                    revision,
+                    apply: do_apply,
+                    force,
+                } => {
+                    let ns = cli.namespace.as_deref();
+                    let uid = match (uid, gvk, name) {
+                        (Some(uid), _, _) => parse_uid(&uid)?,
+                        (None, Some(gvk), Some(name)) => {
+                            let gvk = orka_kubehub::resolve_gvk_key(&gvk).await?;
+                            parse_uid(&fetch_uid_for(&gvk, &name, ns).await?)?
+                        }
+                        _ => return Err(anyhow::anyhow!("either --uid or <gvk> <name> required")),
+                    };
+                    let res = match &api {
+                        Some(api) => api
+                            .rollback(uid, revision, ns, do_apply, force)
+                            .await
+                            .map_err(api_err),
+                        None => orka_apply::rollback(uid, revision, ns, do_apply, force).await,
+                    };
+                    match res {
+                        Ok(r) => match cli.output {
+                            Output::Human => {
+                                println!(
+                                    "revision {} (ts={} rv={}) of {}",
+                                    r.revision, r.revision_ts, r.revision_rv, r.uid
+                                );
+                                let mode = DiffMode {
+                                    output: Output::Human,
+                                    summary: false,
+                                    json_patch: false,
+                                };
+                                print_diff_human(None, &r.diff, None, mode);
+                                match &r.apply {
+                                    Some(a) => {
+                                        println!(
+                                            "rolled back rv={}",
+                                            a.new_rv.as_deref().unwrap_or_default()
+                                        );
+                                        for w in &a.warnings {
+                                            eprintln!("warning: {}", w);
+                                        }
+                                    }
+                                    None => println!("not applied; re-run with --apply"),
+                                }
+                            }
+                            Output::Json => println!("{}", serde_json::to_string_pretty(&r)?),
+                        },
+                        Err(e) => match e.downcast_ref::<orka_apply::ApplyConflict>() {
+                            Some(c) => print_conflicts(c),
+                            None => eprintln!("rollback error: {}", e),
+                        },
+                    }
+                }
             }
         }
         Commands::Stats {} => {
@@ -1184,6 +1775,8 @@ async fn main() -> Result<()> {
                 max_rss_mb: Option<usize>,
                 max_index_bytes: Option<usize>,
                 metrics_addr: Option<String>,
+                kube_qps: Option<u32>,
+                kube_burst: Option<u32>,
             }
             let out = if let Some(api) = &api {
                 let s = api.stats().await?;
@@ -1197,6 +1790,8 @@ async fn main() -> Result<()> {
                     max_rss_mb: s.max_rss_mb,
                     max_index_bytes: s.max_index_bytes,
                     metrics_addr: s.metrics_addr,
+                    kube_qps: s.throttle_qps,
+                    kube_burst: s.throttle_burst,
                 }
             } else {
                 // shards removed; single pipeline
@@ -1225,6 +1820,7 @@ async fn main() -> Result<()> {
                     .ok()
                     .and_then(|s| s.parse().ok());
                 let metrics_addr = std::env::var("ORKA_METRICS_ADDR").ok();
+                let throttle = orka_kubehub::throttle_state();
                 StatsOut {
                     shards,
                     relist_secs,
@@ -1235,6 +1831,8 @@ async fn main() -> Result<()> {
                     max_rss_mb,
                     max_index_bytes,
                     metrics_addr,
+                    kube_qps: throttle.map(|t| t.qps),
+                    kube_burst: throttle.map(|t| t.burst),
                 }
             };
 
@@ -1278,6 +1876,10 @@ async fn main() -> Result<()> {
                     } else {
                         println!("metrics_addr: (not set)");
                     }
+                    match (out.kube_qps, out.kube_burst) {
+                        (Some(q), Some(b)) => println!("kube_rate_limit: {} qps (burst {})", q, b),
+                        _ => println!("kube_rate_limit: (disabled)"),
+                    }
                 }
                 Output::Json => println!("{}", serde_json::to_string_pretty(&out)?),
             }
@@ -1286,6 +1888,7 @@ async fn main() -> Result<()> {
             match sub {
                 OpsCmd::Logs {
                     pod,
+                    selector,
                     container,
                     follow,
                     tail_lines,
@@ -1317,7 +1920,70 @@ async fn main() -> Result<()> {
                         },
                         None => None,
                     };
-                    if container.len() <= 1 {
+                    // A selector or KIND/NAME follows every matching pod
+                    let target = match (selector, pod.as_deref()) {
+                        (Some(sel), _) => Some(LogTarget::Selector(sel)),
+                        (None, Some(p)) if p.contains('/') => match LogTarget::workload(p) {
+                            Ok(t) => Some(t),
+                            Err(e) => {
+                                eprintln!("{}", e);
+                                return Ok(());
+                            }
+                        },
+                        (None, Some(_)) => None,
+                        (None, None) => {
+                            eprintln!("pass a pod, KIND/NAME or --selector");
+                            return Ok(());
+                        }
+                    };
+                    let pod = pod.unwrap_or_default();
+                    if let Some(target) = target {
+                        if container.len() > 1 {
+                            eprintln!("at most one -c with --selector or KIND/NAME");
+                            return Ok(());
+                        }
+                        let single = container.first().map(|s| s.as_str());
+                        match ops
+                            .logs_aggregate(ns.unwrap_or_default(), &target, single, opts)
+                            .await
+                        {
+                            Ok(mut handle) => loop {
+                                tokio::select! {
+                                    _ = signal::ctrl_c() => { handle.cancel.cancel(); break; }
+                                    maybe = handle.rx.recv() => {
+                                        match maybe {
+                                            Some(chunk) => {
+                                                if let Some(ref r) = re { if !r.is_match(&chunk.line) { continue; } }
+                                                match cli.output {
+                                                    Output::Human => println!(
+                                                        "[{}/{}] {}",
+                                                        chunk.pod.as_deref().unwrap_or("?"),
+                                                        chunk.container.as_deref().unwrap_or("?"),
+                                                        chunk.line
+                                                    ),
+                                                    Output::Json => println!("{}", serde_json::to_string(&chunk).unwrap_or_else(|_| "{}".into())),
+                                                }
+                                            }
+                                            None => break,
+                                        }
+                                    }
+                                }
+                            },
+                            Err(e) => {
+                                if let Some(kube::Error::Api(api_err)) =
+                                    e.downcast_ref::<kube::Error>()
+                                {
+                                    if api_err.code == 403 {
+                                        eprintln!("forbidden: missing list/watch on pods or get on pods/log in ns");
+                                    } else {
+                                        eprintln!("logs error: {}", e);
+                                    }
+                                } else {
+                                    eprintln!("logs error: {}", e);
+                                }
+                            }
+                        }
+                    } else if container.len() <= 1 {
                         let single = container.first().map(|s| s.as_str());
                         match ops.logs(ns, &pod, single, opts).await {
                             Ok(mut handle) => loop {
@@ -1830,13 +2496,456 @@ fn read_input(path: &str) -> Result<String> {
     }
 }
 
+/// `-f` input: one object (kept verbatim) or several from a multi-document
+/// file, a `List`, or a directory.
+enum ManifestInput {
+    Single(String),
+    Batch(Vec<orka_apply::Manifest>),
+}
+
+/// `-f FILE`, `-k DIR` or `--chart DIR`; rendered sources are always a
+/// batch. `ns` becomes the chart's `.Release.Namespace`.
+fn read_source(source: &SourceArgs, ns: Option<&str>) -> Result<ManifestInput> {
+    if let Some(dir) = &source.kustomize {
+        return Ok(ManifestInput::Batch(orka_apply::render_kustomization(dir)?));
+    }
+    if let Some(dir) = &source.chart {
+        let opts = orka_apply::ChartOptions {
+            release: source.release.clone(),
+            namespace: ns.unwrap_or("default").to_string(),
+            values_files: source.values.iter().map(std::path::PathBuf::from).collect(),
+            set: source.set.clone(),
+            ..Default::default()
+        };
+        return Ok(ManifestInput::Batch(orka_apply::render_chart(dir, &opts)?));
+    }
+    match &source.file {
+        Some(path) => read_manifests(path),
+        None => Err(anyhow::anyhow!(
+            "one of -f/--file, -k/--kustomize or --chart is required"
+        )),
+    }
+}
+
+fn read_manifests(path: &str) -> Result<ManifestInput> {
+    if path != "-" && std::path::Path::new(path).is_dir() {
+        return Ok(ManifestInput::Batch(orka_apply::load_manifests(path)?));
+    }
+    let text = read_input(path)?;
+    let mut manifests = orka_apply::manifest::split_manifests(&text, path)?;
+    if manifests.len() == 1 {
+        // Keep the user's formatting for last-applied unless the object had
+        // to be unwrapped from a List or a multi-document stream.
+        if serde_yaml::from_str::<serde_yaml::Value>(&text).is_ok()
+            && !manifests[0].source.contains(".items[")
+        {
+            return Ok(ManifestInput::Single(text));
+        }
+        return Ok(ManifestInput::Single(manifests.remove(0).yaml));
+    }
+    if manifests.is_empty() {
+        return Err(anyhow::anyhow!("{}: no objects found", path));
+    }
+    Ok(ManifestInput::Batch(manifests))
+}
+
+/// Print schema issues for one document; returns how many were found.
+#[allow(unused_variables)]
+async fn validate_yaml(yaml: &str, label: Option<&str>) -> Result<usize> {
+    #[cfg(feature = "validate")]
+    {
+        // Detect GVK from YAML for schema lookup
+        let j: serde_yaml::Value = serde_yaml::from_str(yaml)?;
+        let api_ver = j.get("apiVersion").and_then(|v| v.as_str()).unwrap_or("");
+        let kind = j.get("kind").and_then(|v| v.as_str()).unwrap_or("");
+        let gvk_key = format!("{}/{}", api_ver, kind);
+        let issues = orka_schema::validate::validate_yaml_for_gvk(&gvk_key, yaml).await?;
+        let count = issues.len();
+        if !issues.is_empty() {
+            match label {
+                Some(l) => eprintln!("{}: validation issues ({}):", l, issues.len()),
+                None => eprintln!("validation issues ({}):", issues.len()),
+            }
+            for it in issues {
+                let at = match (it.line, it.column) {
+                    (Some(l), Some(c)) => format!("{}:{} ", l, c),
+                    _ => String::new(),
+                };
+                eprintln!(
+                    "- {}{}: {}{}",
+                    at,
+                    it.path,
+                    it.error,
+                    it.hint
+                        .as_deref()
+                        .map(|h| format!(" ({})", h))
+                        .unwrap_or_default()
+                );
+            }
+        }
+        Ok(count)
+    }
+    #[cfg(not(feature = "validate"))]
+    {
+        warn!("validate flag set but CLI built without 'validate' feature");
+        Ok(0)
+    }
+}
+
+/// Apply or dry-run each manifest in dependency order, through the API
+/// façade when enabled.
+async fn edit_batch(
+    api: Option<&InProcApi>,
+    mut manifests: Vec<orka_apply::Manifest>,
+    ns: Option<&str>,
+    validate: bool,
+    do_apply: bool,
+    force: bool,
+) -> orka_apply::BatchResult {
+    let Some(api) = api else {
+        return orka_apply::edit_from_manifests(manifests, ns, validate, do_apply, force).await;
+    };
+    orka_apply::manifest::order_for_apply(&mut manifests);
+    let mut items = Vec::with_capacity(manifests.len());
+    for m in &manifests {
+        let res = if do_apply {
+            api.apply(&m.yaml, force).await
+        } else {
+            api.dry_run(&m.yaml).await.map(|d| orka_apply::ApplyResult {
+                dry_run: true,
+                applied: false,
+                new_rv: None,
+                warnings: vec![],
+                summary: d,
+            })
+        };
+        items.push(orka_apply::ObjectResult::new(m, res.map_err(api_err)));
+    }
+    orka_apply::BatchResult::from_items(items)
+}
+
+/// Back to `anyhow`, keeping SSA conflict details downcastable.
+fn api_err(e: orka_api::OrkaError) -> anyhow::Error {
+    match e {
+        orka_api::OrkaError::Conflict { message, conflicts } => orka_apply::ApplyConflict {
+            context: "conflict".into(),
+            message,
+            conflicts,
+        }
+        .into(),
+        other => anyhow::anyhow!(other.to_string()),
+    }
+}
+
+fn print_conflicts(c: &orka_apply::ApplyConflict) {
+    eprintln!(
+        "apply conflict: {} field(s) owned by other managers",
+        c.conflicts.len()
+    );
+    let width = c
+        .conflicts
+        .iter()
+        .map(|f| f.field.len())
+        .max()
+        .unwrap_or(5)
+        .max(5);
+    eprintln!("{:<width$}  MANAGER", "FIELD", width = width);
+    for f in &c.conflicts {
+        eprintln!("{:<width$}  {}", f.field, f.manager, width = width);
+    }
+    eprintln!(
+        "re-run with --force to take ownership from {}",
+        c.managers().join(", ")
+    );
+}
+
+fn print_batch(res: &orka_apply::BatchResult, output: Output) -> Result<()> {
+    match output {
+        Output::Human => {
+            for it in &res.items {
+                let target = match &it.namespace {
+                    Some(ns) => format!("{}/{}/{}", it.kind, ns, it.name),
+                    None => format!("{}/{}", it.kind, it.name),
+                };
+                match (&it.result, &it.error) {
+                    (Some(r), _) if r.dry_run => println!(
+                        "{}: dry-run: +{} ~{} -{}",
+                        target, r.summary.adds, r.summary.updates, r.summary.removes
+                    ),
+                    (Some(r), _) if r.applied => println!(
+                        "{}: applied rv={}",
+                        target,
+                        r.new_rv.as_deref().unwrap_or_default()
+                    ),
+                    (Some(_), _) => println!("{}: no-op", target),
+                    (None, err) => {
+                        eprintln!(
+                            "{}: error ({}): {}",
+                            target,
+                            it.source,
+                            err.as_deref().unwrap_or("unknown")
+                        );
+                        for c in &it.conflicts {
+                            eprintln!("  {}  owned by {}", c.field, c.manager);
+                        }
+                    }
+                }
+                if let Some(r) = &it.result {
+                    for w in &r.warnings {
+                        eprintln!("{}: warning: {}", target, w);
+                    }
+                }
+            }
+            let s = &res.summary;
+            println!(
+                "{} objects: {} applied, {} dry-run, {} failed (+{} ~{} -{})",
+                s.total,
+                s.applied,
+                s.dry_run,
+                s.failed,
+                s.diff.adds,
+                s.diff.updates,
+                s.diff.removes
+            );
+        }
+        Output::Json => println!("{}", serde_json::to_string_pretty(res)?),
+    }
+    Ok(())
+}
+
+fn print_apply_set(res: &orka_apply::ApplySetResult, output: Output) -> Result<()> {
+    if let Output::Json = output {
+        println!("{}", serde_json::to_string_pretty(res)?);
+        return Ok(());
+    }
+    print_batch(&res.batch, output)?;
+    for p in &res.prune {
+        let target = match &p.namespace {
+            Some(ns) => format!("{}/{}/{}", p.kind, ns, p.name),
+            None => format!("{}/{}", p.kind, p.name),
+        };
+        match (&p.error, p.deleted) {
+            (Some(err), _) => eprintln!("{}: prune error: {}", target, err),
+            (None, true) => println!("{}: pruned", target),
+            (None, false) if res.prune_skipped.is_some() => {
+                println!("{}: not pruned", target)
+            }
+            (None, false) => println!("{}: would prune", target),
+        }
+    }
+    if let Some(why) = &res.prune_skipped {
+        eprintln!("prune skipped: {}", why);
+    }
+    println!("apply set {}: {} to prune", res.id, res.prune.len());
+    Ok(())
+}
+
+#[derive(Clone, Copy)]
+struct DiffMode {
+    output: Output,
+    summary: bool,
+    json_patch: bool,
+}
+
+/// Machine form of a diff: the full reports (changes, unified text) plus a
+/// JSON Patch for each, or only the live JSON Patch with `--json-patch`.
+fn diff_json(
+    target: Option<&orka_apply::Manifest>,
+    live: &orka_apply::DiffReport,
+    last: Option<&orka_apply::DiffReport>,
+    mode: DiffMode,
+) -> Result<serde_json::Value> {
+    if mode.json_patch {
+        let patch = live.json_patch();
+        return Ok(match target {
+            Some(m) => serde_json::json!({"target": m.display_name(), "patch": patch}),
+            None => patch,
+        });
+    }
+    let report = |r: &orka_apply::DiffReport| -> Result<serde_json::Value> {
+        let mut v = serde_json::to_value(r)?;
+        v["json_patch"] = r.json_patch();
+        Ok(v)
+    };
+    let mut v = serde_json::json!({
+        "live": report(live)?,
+        "last": last.map(report).transpose()?,
+    });
+    if let Some(m) = target {
+        v["target"] = serde_json::json!(m.display_name());
+        v["source"] = serde_json::json!(m.source);
+    }
+    Ok(v)
+}
+
+/// Human form of a diff: counts, then the unified YAML diff vs live.
+fn print_diff_human(
+    target: Option<&str>,
+    live: &orka_apply::DiffReport,
+    last: Option<&orka_apply::DiffReport>,
+    mode: DiffMode,
+) {
+    let prefix = target.map(|t| format!("{}: ", t)).unwrap_or_default();
+    let l = &live.summary;
+    print!(
+        "{}vs live: +{} ~{} -{}",
+        prefix, l.adds, l.updates, l.removes
+    );
+    if let Some(ls) = last.map(|r| &r.summary) {
+        print!("; vs last: +{} ~{} -{}", ls.adds, ls.updates, ls.removes);
+    }
+    println!();
+    if !mode.summary {
+        print!("{}", live.unified);
+    }
+}
+
+/// One line per classified path: state, path, and the values that matter
+/// for it (base and live for drift, all three for conflicts).
+fn print_three_way(target: Option<&str>, r: &orka_apply::ThreeWayReport) {
+    use orka_apply::ThreeWayState;
+    let prefix = target.map(|t| format!("{}: ", t)).unwrap_or_default();
+    let val = |v: &Option<serde_json::Value>| {
+        v.as_ref()
+            .map(|v| v.to_string())
+            .unwrap_or_else(|| "(absent)".into())
+    };
+    if r.live_missing {
+        println!(
+            "{}not in the cluster; applying creates it ({} fields)",
+            prefix, r.yours
+        );
+    } else if r.has_base {
+        println!(
+            "{}three-way vs last-applied: {} yours, {} drifted, {} conflicts",
+            prefix, r.yours, r.drifted, r.conflicts
+        );
+    } else {
+        println!(
+            "{}no last-applied history; {} changes vs live",
+            prefix, r.yours
+        );
+    }
+    for e in &r.entries {
+        match e.state {
+            ThreeWayState::Yours => println!(
+                "  yours     {}  {} -> {}",
+                e.path,
+                val(&e.live),
+                val(&e.new)
+            ),
+            ThreeWayState::Drifted => println!(
+                "  drifted   {}  last-applied={} live={}",
+                e.path,
+                val(&e.base),
+                val(&e.live)
+            ),
+            ThreeWayState::Conflict => println!(
+                "  conflict  {}  last-applied={} live={} new={}",
+                e.path,
+                val(&e.base),
+                val(&e.live),
+                val(&e.new)
+            ),
+        }
+    }
+}
+
+fn print_drift_item(item: &orka_apply::DriftItem) {
+    let status = serde_json::to_value(item.status)
+        .ok()
+        .and_then(|v| v.as_str().map(str::to_string))
+        .unwrap_or_default();
+    println!(
+        "{:<9} {} {}/{}  ts={} rv={}",
+        status,
+        item.gvk,
+        item.namespace.as_deref().unwrap_or("-"),
+        item.name,
+        item.applied_ts,
+        item.applied_rv
+    );
+    let val = |v: &Option<serde_json::Value>| {
+        v.as_ref()
+            .map(|v| v.to_string())
+            .unwrap_or_else(|| "(absent)".into())
+    };
+    for c in &item.changes {
+        println!(
+            "  {}  last-applied={} live={}",
+            c.path,
+            val(&c.base),
+            val(&c.live)
+        );
+    }
+}
+
+fn print_diff(
+    live: &orka_apply::DiffReport,
+    last: Option<&orka_apply::DiffReport>,
+    mode: DiffMode,
+) -> Result<()> {
+    if mode.json_patch || mode.output == Output::Json {
+        let v = diff_json(None, live, last, mode)?;
+        println!("{}", serde_json::to_string_pretty(&v)?);
+    } else {
+        print_diff_human(None, live, last, mode);
+    }
+    Ok(())
+}
+
+async fn diff_batch(
+    api: Option<&InProcApi>,
+    mut manifests: Vec<orka_apply::Manifest>,
+    ns: Option<&str>,
+    mode: DiffMode,
+) -> Result<()> {
+    let results = match api {
+        Some(api) => {
+            orka_apply::manifest::order_for_apply(&mut manifests);
+            let mut out = Vec::with_capacity(manifests.len());
+            for m in manifests {
+                let res = api
+                    .diff_report(&m.yaml, ns)
+                    .await
+                    .map_err(|e| anyhow::anyhow!(e.to_string()));
+                out.push((m, res));
+            }
+            out
+        }
+        None => orka_apply::diff_from_manifests(manifests, ns).await,
+    };
+    let machine = mode.json_patch || mode.output == Output::Json;
+    let mut rows = Vec::new();
+    for (m, res) in results {
+        match res {
+            Ok((live, last)) if machine => {
+                rows.push(diff_json(Some(&m), &live, last.as_ref(), mode)?)
+            }
+            Ok((live, last)) => {
+                print_diff_human(Some(&m.display_name()), &live, last.as_ref(), mode)
+            }
+            Err(e) if machine => rows.push(serde_json::json!({
+                "target": m.display_name(),
+                "source": m.source,
+                "error": e.to_string(),
+            })),
+            Err(e) => eprintln!("{}: diff error ({}): {}", m.display_name(), m.source, e),
+        }
+    }
+    if machine {
+        println!("{}", serde_json::to_string_pretty(&rows)?);
+    }
+    Ok(())
+}
+
 async fn fetch_uid_for(gvk_key: &str, name: &str, namespace: Option<&str>) -> Result<String> {
     use kube::{
         api::Api,
         core::{DynamicObject, GroupVersionKind},
         discovery::{Discovery, Scope},
     };
-    let client = kube::Client::try_default().await?;
+    let client = orka_kubehub::get_kube_client().await?;
     // Parse key
     let (group, version, kind) =
         parse_gvk(gvk_key).ok_or_else(|| anyhow::anyhow!("invalid gvk: {}", gvk_key))?;
@@ -1875,11 +2984,85 @@ async fn fetch_uid_for(gvk_key: &str, name: &str, namespace: Option<&str>) -> Re
     Ok(uid)
 }
 
+/// Create a keyfile readable only by the owner; refuses to overwrite.
+fn write_key_file(path: &std::path::Path, hex: &str) -> Result<()> {
+    use std::io::Write;
+    let mut opts = std::fs::OpenOptions::new();
+    opts.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        opts.mode(0o600);
+    }
+    let mut f = opts
+        .open(path)
+        .map_err(|e| anyhow::anyhow!("creating {}: {}", path.display(), e))?;
+    writeln!(f, "{}", hex)?;
+    Ok(())
+}
+
 fn parse_uid(uid_str: &str) -> Result<orka_core::Uid> {
     let u = uuid::Uuid::parse_str(uid_str).map_err(|e| anyhow::anyhow!("invalid uid: {}", e))?;
     Ok(*u.as_bytes())
 }
 
+fn resource_ref(gvk: &str, ns: Option<&str>, name: &str) -> Result<orka_api::ResourceRef> {
+    let (group, version, kind) =
+        parse_gvk(gvk).ok_or_else(|| anyhow::anyhow!("invalid gvk: {}", gvk))?;
+    Ok(orka_api::ResourceRef {
+        cluster: None,
+        gvk: orka_api::ResourceKind {
+            group,
+            version,
+            kind,
+            namespaced: ns.is_some(),
+        },
+        namespace: ns.map(|s| s.to_string()),
+        name: name.to_string(),
+    })
+}
+
+/// One line per (field, manager); the field column is left blank for
+/// additional owners of the same path.
+fn print_owners(rows: &[orka_apply::FieldOwnership]) {
+    if rows.is_empty() {
+        println!("no managed fields");
+        return;
+    }
+    let width = rows.iter().map(|r| r.path.len()).max().unwrap_or(0).max(5);
+    let mgr_width = rows
+        .iter()
+        .flat_map(|r| r.owners.iter().map(|o| o.manager.len()))
+        .max()
+        .unwrap_or(0)
+        .max(7);
+    println!(
+        "{:<width$}  {:<mgr_width$}  {:<17}  TIME",
+        "FIELD",
+        "MANAGER",
+        "OPERATION",
+        width = width,
+        mgr_width = mgr_width
+    );
+    for r in rows {
+        for (i, o) in r.owners.iter().enumerate() {
+            let op = match &o.subresource {
+                Some(sub) => format!("{}({})", o.operation, sub),
+                None => o.operation.clone(),
+            };
+            println!(
+                "{:<width$}  {:<mgr_width$}  {:<17}  {}",
+                if i == 0 { r.path.as_str() } else { "" },
+                o.manager,
+                op,
+                o.time.as_deref().unwrap_or("-"),
+                width = width,
+                mgr_width = mgr_width
+            );
+        }
+    }
+}
+
 async fn fetch_object_json(
     gvk_key: &str,
     name: &str,
@@ -1890,7 +3073,7 @@ async fn fetch_object_json(
         core::{DynamicObject, GroupVersionKind},
         discovery::{Discovery, Scope},
     };
-    let client = kube::Client::try_default().await?;
+    let client = orka_kubehub::get_kube_client().await?;
     // Parse key
     let (group, version, kind) =
         parse_gvk(gvk_key).ok_or_else(|| anyhow::anyhow!("invalid gvk: {}", gvk_key))?;
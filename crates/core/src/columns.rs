@@ -195,6 +195,56 @@ pub fn builtin_columns_for(
     cols
 }
 
+/// Map a server-side printer column (`as=Table`) of a built-in kind to the
+/// stable column ID used by the built-in projector, so both paths fill the
+/// same `LiteObj.projected` slots.
+pub fn builtin_table_column_id(
+    group: &str,
+    version: &str,
+    kind: &str,
+    column: &str,
+) -> Option<u32> {
+    let id = match ((group, version, kind), column) {
+        (("", "v1", "Pod"), "Ready") => POD_READY,
+        (("", "v1", "Pod"), "Status") => POD_STATUS,
+        (("", "v1", "Pod"), "Restarts") => POD_RESTARTS,
+        (("", "v1", "Pod"), "Node") => POD_NODE,
+        (("apps", "v1", "Deployment"), "Ready") => DEP_READY,
+        (("apps", "v1", "Deployment"), "Up-to-date") => DEP_UPDATED,
+        (("apps", "v1", "Deployment"), "Available") => DEP_AVAILABLE,
+        (("apps", "v1", "StatefulSet"), "Ready") => STS_READY,
+        (("apps", "v1", "DaemonSet"), "Desired") => DS_DESIRED,
+        (("apps", "v1", "DaemonSet"), "Current") => DS_CURRENT,
+        (("apps", "v1", "DaemonSet"), "Ready") => DS_READY,
+        (("apps", "v1", "DaemonSet"), "Up-to-date") => DS_UPDATED,
+        (("apps", "v1", "DaemonSet"), "Available") => DS_AVAILABLE,
+        (("", "v1", "Service"), "Type") => SVC_TYPE,
+        (("", "v1", "Service"), "Cluster-IP") => SVC_CLUSTER_IP,
+        (("", "v1", "Service"), "External-IP") => SVC_EXTERNAL_IP,
+        (("", "v1", "Service"), "Port(s)") => SVC_PORTS,
+        (("networking.k8s.io", "v1", "Ingress"), "Class") => ING_CLASS,
+        (("networking.k8s.io", "v1", "Ingress"), "Hosts") => ING_HOSTS,
+        (("networking.k8s.io", "v1", "Ingress"), "Address") => ING_ADDRESS,
+        (("batch", "v1", "Job"), "Completions") => JOB_COMPLETIONS,
+        (("batch", "v1", "Job"), "Status") => JOB_STATUS,
+        (("batch", "v1", "CronJob"), "Schedule") => CJ_SCHEDULE,
+        (("batch", "v1", "CronJob"), "Suspend") => CJ_SUSPEND,
+        (("batch", "v1", "CronJob"), "Active") => CJ_ACTIVE,
+        (("batch", "v1", "CronJob"), "Last Schedule") => CJ_LAST_SCHEDULE,
+        (("", "v1", "PersistentVolumeClaim"), "Status") => PVC_STATUS,
+        (("", "v1", "PersistentVolumeClaim"), "Volume") => PVC_VOLUME,
+        (("", "v1", "PersistentVolumeClaim"), "Capacity") => PVC_CAPACITY,
+        (("", "v1", "PersistentVolumeClaim"), "Access Modes") => PVC_ACCESS_MODES,
+        (("", "v1", "PersistentVolumeClaim"), "StorageClass") => PVC_STORAGECLASS,
+        (("", "v1", "Node"), "Status") => NODE_STATUS,
+        (("", "v1", "Node"), "Roles") => NODE_ROLES,
+        (("", "v1", "Node"), "Version") => NODE_VERSION,
+        (("", "v1", "Namespace"), "Status") => NS_STATUS,
+        _ => return None,
+    };
+    Some(id)
+}
+
 fn gvk_key(group: &str, version: &str, kind: &str) -> String {
     if group.is_empty() {
         format!("{}/{}", version, kind)
@@ -72,6 +72,25 @@ impl OrkaGuiApp {
             {
                 self.start_edit_apply_task();
             }
+            if ui
+                .button("Kustomize…")
+                .on_hover_text("Render a kustomization folder offline and load the matching object")
+                .clicked()
+            {
+                if let Some(dir) = rfd::FileDialog::new()
+                    .set_title("Choose kustomization folder")
+                    .pick_folder()
+                {
+                    self.start_edit_kustomize_task(dir);
+                }
+            }
+            if ui
+                .button("History")
+                .on_hover_text("List last-applied revisions of this object")
+                .clicked()
+            {
+                self.start_edit_history_task();
+            }
             if self.edit.running {
                 ui.add(egui::Spinner::new());
             }
@@ -80,6 +99,40 @@ impl OrkaGuiApp {
                 ui.label(&self.edit.status);
             }
         });
+        if self.edit.history_uid.is_some()
+            && self.edit.history_uid == self.details.selected
+            && !self.edit.history.is_empty()
+        {
+            ui.horizontal(|ui| {
+                let label = |i: usize, (ts, rv): &(i64, String)| {
+                    format!("#{}  {} ago  rv={}", i, crate::util::render_age(*ts), rv)
+                };
+                let mut pick = self.edit.history_pick.min(self.edit.history.len() - 1);
+                egui::ComboBox::from_id_salt("edit_history_combo")
+                    .width(220.0)
+                    .selected_text(label(pick, &self.edit.history[pick]))
+                    .show_ui(ui, |ui| {
+                        for (i, rev) in self.edit.history.iter().enumerate() {
+                            ui.selectable_value(&mut pick, i, label(i, rev));
+                        }
+                    });
+                self.edit.history_pick = pick;
+                if ui
+                    .button("Preview")
+                    .on_hover_text("Load this revision and diff it against live")
+                    .clicked()
+                {
+                    self.start_edit_rollback_task(false);
+                }
+                if ui
+                    .button("Roll back")
+                    .on_hover_text("Re-apply this revision via SSA; recorded as a new revision")
+                    .clicked()
+                {
+                    self.start_edit_rollback_task(true);
+                }
+            });
+        }
         ui.add_space(4.0);
         // Secret panel (if current selection is a Secret)
         let is_secret = self
@@ -499,7 +552,7 @@ impl OrkaGuiApp {
                 self.toast("details: select a row first", crate::model::ToastKind::Info);
             }
         });
-        // Tab bar inside the Details pane (Edit | Logs | Svc Logs | Exec | Describe | Graph)
+        // Tab bar inside the Details pane (Edit | Logs | Svc Logs | Exec | Describe | Owners | Graph)
         ui.horizontal(|ui| {
             let tab = self.details.active_tab;
             let is_svc = self.selected_is_service();
@@ -535,6 +588,13 @@ impl OrkaGuiApp {
             {
                 self.details.active_tab = DetailsPaneTab::Describe;
             }
+            if ui
+                .selectable_label(matches!(tab, DetailsPaneTab::Owners), "Owners")
+                .on_hover_text("Field managers from managedFields")
+                .clicked()
+            {
+                self.details.active_tab = DetailsPaneTab::Owners;
+            }
             if ui
                 .selectable_label(matches!(tab, DetailsPaneTab::Graph), "Graph")
                 .on_hover_text("List-based relationships")
@@ -605,6 +665,9 @@ impl OrkaGuiApp {
                             .interactive(false);
                         ui.add(te);
                     }
+                    DetailsPaneTab::Owners => {
+                        self.ui_owners(ui);
+                    }
                     DetailsPaneTab::Graph => {
                         self.ui_graph(ui);
                     }
@@ -612,6 +675,77 @@ impl OrkaGuiApp {
             });
     }
 
+    fn ui_owners(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            if ui.small_button("Refresh").clicked() {
+                if let Some(uid) = self.details.selected {
+                    self.start_owners_task(uid);
+                }
+            }
+            ui.label("Field:");
+            ui.add(
+                egui::TextEdit::singleline(&mut self.owners.filter)
+                    .hint_text(".spec.replicas")
+                    .desired_width(240.0),
+            );
+            if self.owners.running {
+                ui.add(egui::Spinner::new());
+            }
+            if let Some(err) = &self.owners.error {
+                ui.colored_label(ui.visuals().error_fg_color, err);
+            }
+        });
+        ui.add_space(4.0);
+        if let Some(uid) = self.details.selected {
+            if self.owners.uid != Some(uid) && !self.owners.running {
+                self.start_owners_task(uid);
+            }
+        }
+        let rows = orka_api::filter_ownership(self.owners.rows.clone(), &self.owners.filter);
+        if rows.is_empty() {
+            ui.label(if self.owners.running {
+                "(loading)"
+            } else {
+                "(no managed fields)"
+            });
+            return;
+        }
+        egui::Grid::new("owners_grid")
+            .striped(true)
+            .num_columns(4)
+            .show(ui, |ui| {
+                ui.strong("Field");
+                ui.strong("Manager");
+                ui.strong("Operation");
+                ui.strong("Time");
+                ui.end_row();
+                for r in &rows {
+                    // Several owners of one field are worth a second look.
+                    let shared = r.owners.len() > 1;
+                    for (i, o) in r.owners.iter().enumerate() {
+                        if i == 0 {
+                            let text = egui::RichText::new(&r.path).monospace();
+                            if shared {
+                                ui.label(text.color(ui.visuals().warn_fg_color))
+                                    .on_hover_text("owned by more than one manager");
+                            } else {
+                                ui.label(text);
+                            }
+                        } else {
+                            ui.label("");
+                        }
+                        ui.label(&o.manager);
+                        match &o.subresource {
+                            Some(sub) => ui.label(format!("{} ({})", o.operation, sub)),
+                            None => ui.label(&o.operation),
+                        };
+                        ui.label(o.time.as_deref().unwrap_or("-"));
+                        ui.end_row();
+                    }
+                }
+            });
+    }
+
     fn ui_graph(&mut self, ui: &mut egui::Ui) {
         // Trigger fetch if first open or selection changed
         if let Some(sel) = self.details.selected {
@@ -1486,6 +1620,13 @@ impl OrkaGuiApp {
         self.describe.text.clear();
         self.describe.error = None;
         self.describe.uid = None;
+        if let Some(task) = self.owners.task.take() {
+            task.abort();
+        }
+        self.owners.running = false;
+        self.owners.rows.clear();
+        self.owners.error = None;
+        self.owners.uid = None;
         // If details are cached and fresh, render immediately and skip fetch
         let now = Instant::now();
         if let Some((arc_text, maybe_cont, ts)) = self.details_cache.get(&uid).cloned() {
@@ -22,7 +22,7 @@ mod ui;
 mod util;
 mod watch;
 use model::GraphState;
-use model::{DescribeState, DetailsPaneTab};
+use model::{DescribeState, DetailsPaneTab, OwnersState};
 use model::{DetachedDetailsWindow, DockedDetailsTab, FloatingDetailsWindow};
 use model::{
     DetailsState, DiscoveryState, EditState, ExecState, LogsState, OpsState, PrefixTheme,
@@ -57,6 +57,7 @@ pub struct OrkaGuiApp {
     edit: EditState,
     exec: ExecState,
     describe: DescribeState,
+    owners: OwnersState,
     graph: GraphState,
     // status
     last_error: Option<String>,
@@ -216,6 +217,7 @@ impl OrkaGuiApp {
                 task: None,
                 stop: None,
             },
+            owners: OwnersState::default(),
             graph: GraphState {
                 running: false,
                 text: String::new(),
@@ -418,6 +420,9 @@ impl OrkaGuiApp {
                 status: String::new(),
                 task: None,
                 stop: None,
+                history: Vec::new(),
+                history_uid: None,
+                history_pick: 0,
             },
             last_error: None,
             search: SearchState {
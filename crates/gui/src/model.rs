@@ -78,6 +78,14 @@ pub enum UiUpdate {
     EditApplyDone {
         message: String,
     },
+    EditLoaded {
+        yaml: String,
+        status: String,
+    },
+    EditHistoryLoaded {
+        uid: Uid,
+        revisions: Vec<(i64, String)>,
+    },
     // Ops updates
     OpsCaps(OpsCaps),
     OpsStatus(String),
@@ -111,6 +119,15 @@ pub enum UiUpdate {
         uid: Uid,
         error: String,
     },
+    // Managed-fields ownership for Details pane
+    OwnersReady {
+        uid: Uid,
+        rows: Vec<orka_api::FieldOwnership>,
+    },
+    OwnersError {
+        uid: Uid,
+        error: String,
+    },
     // Graph output for Details pane
     GraphReady {
         uid: Uid,
@@ -229,6 +246,7 @@ pub enum DetailsPaneTab {
     Exec,
     #[default]
     Describe,
+    Owners,
     Graph,
 }
 
@@ -272,6 +290,10 @@ pub struct EditState {
     pub task: Option<JoinHandle<()>>,
     #[allow(dead_code)]
     pub stop: Option<tokio::sync::oneshot::Sender<()>>,
+    /// Last-applied revisions (ts, rv) of `history_uid`, newest first.
+    pub history: Vec<(i64, String)>,
+    pub history_uid: Option<Uid>,
+    pub history_pick: usize,
 }
 
 #[derive(Default)]
@@ -465,6 +487,17 @@ pub struct DescribeState {
     pub stop: Option<tokio::sync::oneshot::Sender<()>>,
 }
 
+#[derive(Default)]
+pub struct OwnersState {
+    pub running: bool,
+    pub rows: Vec<orka_api::FieldOwnership>,
+    pub error: Option<String>,
+    pub uid: Option<Uid>,
+    /// Field path prefix filter, e.g. `.spec.replicas`.
+    pub filter: String,
+    pub task: Option<JoinHandle<()>>,
+}
+
 #[derive(Default)]
 pub struct GraphState {
     pub running: bool,
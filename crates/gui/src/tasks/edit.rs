@@ -1,5 +1,6 @@
 #![forbid(unsafe_code)]
 
+use crate::util::gvk_label;
 use crate::{OrkaGuiApp, UiUpdate};
 use tracing::info;
 
@@ -96,7 +97,7 @@ impl OrkaGuiApp {
         let api = self.api.clone();
         let yaml = self.edit.buffer.clone();
         self.edit.task = Some(tokio::spawn(async move {
-            match api.apply(&yaml).await {
+            match api.apply(&yaml, false).await {
                 Ok(res) => {
                     let msg = if res.applied {
                         let rv = res.new_rv.unwrap_or_default();
@@ -119,6 +120,11 @@ impl OrkaGuiApp {
                     } else {
                         "apply: no-op".into()
                     };
+                    let msg = match res.warnings.as_slice() {
+                        [] => msg,
+                        [w] => format!("{}  •  {}", msg, w),
+                        [w, rest @ ..] => format!("{}  •  {} (+{} more)", msg, w, rest.len()),
+                    };
                     let _ = tx.send(UiUpdate::EditApplyDone { message: msg });
                 }
                 Err(e) => {
@@ -128,4 +134,168 @@ impl OrkaGuiApp {
             info!("edit: apply task ended");
         }));
     }
+
+    /// Load the last-applied revisions of the selected object.
+    pub(crate) fn start_edit_history_task(&mut self) {
+        let Some(uid) = self.details.selected else {
+            self.edit.status = "history: no object selected".into();
+            return;
+        };
+        let Some(row) = self
+            .results
+            .index
+            .get(&uid)
+            .and_then(|i| self.results.rows.get(*i))
+        else {
+            return;
+        };
+        let (name, ns) = (row.name.clone(), row.namespace.clone());
+        let Some(gvk) = self.current_selected_kind().map(gvk_label) else {
+            return;
+        };
+        if let Some(task) = self.edit.task.take() {
+            task.abort();
+        }
+        self.edit.running = true;
+        self.edit.status = "history…".into();
+        let tx = self.ensure_updates_channel();
+        let api = self.api.clone();
+        self.edit.task = Some(tokio::spawn(async move {
+            match api.last_applied(&gvk, &name, ns.as_deref(), Some(20)).await {
+                Ok(rows) => {
+                    let revisions = rows.into_iter().map(|r| (r.ts, r.rv)).collect();
+                    let _ = tx.send(UiUpdate::EditHistoryLoaded { uid, revisions });
+                }
+                Err(e) => {
+                    let _ = tx.send(UiUpdate::EditStatus(format!("history error: {}", e)));
+                }
+            }
+            info!("edit: history task ended");
+        }));
+    }
+
+    /// Diff the picked revision against live and load it into the editor;
+    /// with `apply`, re-apply it via SSA.
+    pub(crate) fn start_edit_rollback_task(&mut self, apply: bool) {
+        let Some(uid) = self.edit.history_uid else {
+            return;
+        };
+        if let Some(task) = self.edit.task.take() {
+            task.abort();
+        }
+        self.edit.running = true;
+        self.edit.status = if apply {
+            "rollback…".into()
+        } else {
+            "revision diff…".into()
+        };
+        let tx = self.ensure_updates_channel();
+        let api = self.api.clone();
+        let revision = self.edit.history_pick;
+        let ns_override = self
+            .current_selected_kind()
+            .filter(|k| k.namespaced)
+            .map(|_| self.selection.namespace.clone());
+        self.edit.task = Some(tokio::spawn(async move {
+            match api
+                .rollback(uid, revision, ns_override.as_deref(), apply, false)
+                .await
+            {
+                Ok(res) => {
+                    let s = &res.diff.summary;
+                    let diff = format!(
+                        "adds={} updates={} removes={}",
+                        s.adds, s.updates, s.removes
+                    );
+                    match res.apply {
+                        Some(a) => {
+                            let message = format!(
+                                "rolled back to revision {} ({})  •  rv={}",
+                                revision,
+                                diff,
+                                a.new_rv.unwrap_or_default()
+                            );
+                            let _ = tx.send(UiUpdate::EditApplyDone { message });
+                        }
+                        None => {
+                            let _ = tx.send(UiUpdate::EditLoaded {
+                                yaml: res.yaml,
+                                status: format!("revision {} vs live: {}", revision, diff),
+                            });
+                        }
+                    }
+                }
+                Err(e) => {
+                    let _ = tx.send(UiUpdate::EditStatus(format!("rollback error: {}", e)));
+                }
+            }
+            info!("edit: rollback task ended");
+        }));
+    }
+
+    /// Render the kustomization in `dir` and load the object matching the
+    /// current selection (same kind, name ending with the selected base name)
+    /// into the editor.
+    pub(crate) fn start_edit_kustomize_task(&mut self, dir: std::path::PathBuf) {
+        if let Some(task) = self.edit.task.take() {
+            task.abort();
+        }
+        self.edit.status = "rendering kustomization…".into();
+        let tx = self.ensure_updates_channel();
+        let kind = self.current_selected_kind().map(|k| k.kind.clone());
+        let name = self
+            .details
+            .selected
+            .and_then(|uid| self.results.index.get(&uid))
+            .and_then(|i| self.results.rows.get(*i))
+            .map(|row| row.name.clone());
+        self.edit.task = Some(tokio::spawn(async move {
+            let rendered =
+                tokio::task::spawn_blocking(move || orka_api::render_kustomization(&dir)).await;
+            match rendered {
+                Ok(Ok(manifests)) => {
+                    let same_kind: Vec<&orka_api::Manifest> = manifests
+                        .iter()
+                        .filter(|m| kind.as_deref().map(|k| m.kind == k).unwrap_or(true))
+                        .collect();
+                    // Prefer the exact name, then a prefixed/suffixed one.
+                    let pick = name
+                        .as_deref()
+                        .and_then(|n| {
+                            same_kind
+                                .iter()
+                                .find(|m| m.name == n)
+                                .or_else(|| same_kind.iter().find(|m| m.name.contains(n)))
+                        })
+                        .or_else(|| (same_kind.len() == 1).then(|| &same_kind[0]));
+                    match pick {
+                        Some(m) => {
+                            let _ = tx.send(UiUpdate::EditLoaded {
+                                yaml: m.yaml.clone(),
+                                status: format!(
+                                    "kustomize: loaded {} ({} objects rendered)",
+                                    m.display_name(),
+                                    manifests.len()
+                                ),
+                            });
+                        }
+                        None => {
+                            let _ = tx.send(UiUpdate::EditStatus(format!(
+                                "kustomize: no {} matching the selection among {} objects",
+                                kind.as_deref().unwrap_or("object"),
+                                manifests.len()
+                            )));
+                        }
+                    }
+                }
+                Ok(Err(e)) => {
+                    let _ = tx.send(UiUpdate::EditStatus(format!("kustomize error: {}", e)));
+                }
+                Err(e) => {
+                    let _ = tx.send(UiUpdate::EditStatus(format!("kustomize error: {}", e)));
+                }
+            }
+            info!("edit: kustomize task ended");
+        }));
+    }
 }
@@ -6,6 +6,7 @@ pub mod exec;
 pub mod graph;
 pub mod logs;
 pub mod ops;
+pub mod owners;
 pub mod search;
 pub mod stats;
 pub mod svc_logs;
@@ -0,0 +1,68 @@
+#![forbid(unsafe_code)]
+
+use crate::{OrkaGuiApp, UiUpdate};
+use orka_api::ResourceRef;
+use orka_core::Uid;
+use tracing::info;
+
+impl OrkaGuiApp {
+    fn ensure_updates_channel_for_owners(&mut self) -> std::sync::mpsc::Sender<UiUpdate> {
+        if let Some(tx) = &self.watch.updates_tx {
+            return tx.clone();
+        }
+        let (tx, rx) = std::sync::mpsc::channel::<UiUpdate>();
+        self.watch.updates_tx = Some(tx.clone());
+        self.watch.updates_rx = Some(rx);
+        tx
+    }
+
+    pub(crate) fn start_owners_task(&mut self, uid: Uid) {
+        if let Some(task) = self.owners.task.take() {
+            task.abort();
+        }
+        self.owners.running = true;
+        self.owners.rows.clear();
+        self.owners.error = None;
+        self.owners.uid = Some(uid);
+
+        let (gvk_opt, ns_opt, name_opt) = match self
+            .results
+            .index
+            .get(&uid)
+            .and_then(|i| self.results.rows.get(*i))
+        {
+            Some(row) => (
+                self.current_selected_kind().cloned(),
+                row.namespace.clone(),
+                Some(row.name.clone()),
+            ),
+            None => (self.current_selected_kind().cloned(), None, None),
+        };
+        let (Some(gvk), Some(name)) = (gvk_opt, name_opt) else {
+            self.owners.running = false;
+            return;
+        };
+        let tx = self.ensure_updates_channel_for_owners();
+        let api = self.api.clone();
+        let reference = ResourceRef {
+            cluster: None,
+            gvk,
+            namespace: ns_opt,
+            name,
+        };
+        self.owners.task = Some(tokio::spawn(async move {
+            match api.managed_fields(reference).await {
+                Ok(rows) => {
+                    let _ = tx.send(UiUpdate::OwnersReady { uid, rows });
+                }
+                Err(e) => {
+                    let _ = tx.send(UiUpdate::OwnersError {
+                        uid,
+                        error: e.to_string(),
+                    });
+                }
+            }
+            info!("owners: task ended");
+        }));
+    }
+}
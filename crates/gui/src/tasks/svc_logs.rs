@@ -1,7 +1,7 @@
 #![forbid(unsafe_code)]
 
 use crate::{OrkaGuiApp, UiUpdate};
-use orka_api::{api_ops, ResourceRef};
+use orka_api::{api_ops, OpsLogOptions, OpsLogTarget};
 use tracing::info;
 
 impl OrkaGuiApp {
@@ -50,90 +50,31 @@ impl OrkaGuiApp {
         let api = self.api.clone();
         let tx_opt = self.watch.updates_tx.clone();
         let theme = self.svc_logs.prefix_theme;
-        // Build reference to fetch service JSON and extract selector
-        let gvk = orka_api::ResourceKind {
-            group: String::new(),
-            version: "v1".into(),
-            kind: "Service".into(),
-            namespaced: true,
-        };
-        let reference = ResourceRef {
-            cluster: None,
-            gvk,
-            namespace: Some(ns.clone()),
-            name: svc.clone(),
-        };
         self.svc_logs.running = true;
         // Route updates to the rendering window (if any)
         self.svc_logs_owner = self.rendering_window_id;
         let task = tokio::spawn(async move {
-            // Fetch service JSON to get selector
-            let selector = match api.get_raw(reference).await {
-                Ok(bytes) => match serde_json::from_slice::<serde_json::Value>(&bytes) {
-                    Ok(v) => v.get("spec").and_then(|s| s.get("selector")).cloned(),
-                    Err(_) => None,
-                },
-                Err(_) => None,
+            // One aggregated stream over the Service's pods; pods that appear
+            // later are picked up by the ops layer.
+            let ops = api_ops(api.as_ref());
+            let target = OpsLogTarget::Workload {
+                kind: "Service".into(),
+                name: svc.clone(),
             };
-            let selector = match selector {
-                Some(serde_json::Value::Object(map)) if !map.is_empty() => {
-                    let mut parts: Vec<String> = Vec::new();
-                    for (k, vv) in map.into_iter() {
-                        if let Some(val) = vv.as_str() {
-                            parts.push(format!("{}={}", k, val));
-                        }
-                    }
-                    parts.join(",")
-                }
-                _ => String::new(),
+            let opts = OpsLogOptions {
+                follow,
+                tail_lines: tail,
+                since_seconds: since,
             };
-            // List pods by selector using kube
-            let client = match orka_kubehub::get_kube_client().await {
-                Ok(c) => c,
+            let mut h = match ops.logs_aggregate(&ns, &target, None, opts).await {
+                Ok(h) => h,
                 Err(e) => {
                     if let Some(tx) = &tx_opt {
-                        let _ = tx.send(UiUpdate::SvcLogError(format!(
-                            "svc_logs: kube client: {}",
-                            e
-                        )));
+                        let _ = tx.send(UiUpdate::SvcLogError(format!("svc_logs: {}", e)));
                     }
                     return;
                 }
             };
-            use k8s_openapi::api::core::v1::Pod;
-            use kube::api::{Api, ListParams};
-            let pods_api: Api<Pod> = Api::namespaced(client, &ns);
-            let lp = if selector.is_empty() {
-                ListParams::default()
-            } else {
-                ListParams::default().labels(&selector)
-            };
-            let mut pod_names: Vec<String> = Vec::new();
-            match pods_api.list(&lp).await {
-                Ok(list) => {
-                    for p in list.items {
-                        if let Some(name) = p.metadata.name {
-                            pod_names.push(name);
-                        }
-                    }
-                }
-                Err(e) => {
-                    if let Some(tx) = &tx_opt {
-                        let _ =
-                            tx.send(UiUpdate::SvcLogError(format!("svc_logs: list pods: {}", e)));
-                    }
-                }
-            }
-            if pod_names.is_empty() {
-                if let Some(tx) = &tx_opt {
-                    let _ = tx.send(UiUpdate::SvcLogError("svc_logs: no matching pods".into()));
-                }
-                return;
-            }
-            // Start one log stream per pod and forward lines with a [pod] prefix
-            let ops = api_ops(api.as_ref());
-            let mut handles: Vec<(tokio::task::JoinHandle<()>, orka_api::CancelHandle)> =
-                Vec::new();
             if let Some(tx) = &tx_opt {
                 let _ = tx.send(UiUpdate::SvcLogStarted);
             }
@@ -159,44 +100,21 @@ impl OrkaGuiApp {
                     PrefixTheme::None => None,
                 }
             }
-            let theme = theme;
-            for pod in pod_names.into_iter() {
-                let tx2 = tx_opt.clone();
-                let pod2 = pod.clone();
-                let opts = orka_api::OpsLogOptions {
-                    follow,
-                    tail_lines: tail,
-                    since_seconds: since,
+            // Forward lines with a [pod/container] prefix colored by pod
+            while let Some(chunk) = h.rx.recv().await {
+                let pod = chunk.pod.as_deref().unwrap_or("?");
+                let label = match chunk.container.as_deref() {
+                    Some(c) => format!("{}/{}", pod, c),
+                    None => pod.to_string(),
                 };
-                match ops.logs(Some(&ns), &pod, None, opts).await {
-                    Ok(mut h) => {
-                        let theme2 = theme;
-                        let t = tokio::spawn(async move {
-                            let prefix = match color_code_for(&pod2, theme2) {
-                                Some(code) => format!("\x1b[{}m[{}]\x1b[0m ", code, pod2),
-                                None => format!("[{}] ", pod2),
-                            };
-                            while let Some(line) = h.rx.recv().await {
-                                if let Some(tx) = &tx2 {
-                                    let _ = tx
-                                        .send(UiUpdate::SvcLogLine(format!("{}{}", prefix, line)));
-                                }
-                            }
-                        });
-                        handles.push((t, h.cancel));
-                    }
-                    Err(e) => {
-                        if let Some(tx) = &tx_opt {
-                            let _ = tx
-                                .send(UiUpdate::SvcLogError(format!("svc_logs: {}: {}", pod2, e)));
-                        }
-                    }
+                let prefix = match color_code_for(pod, theme) {
+                    Some(code) => format!("\x1b[{}m[{}]\x1b[0m ", code, label),
+                    None => format!("[{}] ", label),
+                };
+                if let Some(tx) = &tx_opt {
+                    let _ = tx.send(UiUpdate::SvcLogLine(format!("{}{}", prefix, chunk.line)));
                 }
             }
-            // Wait for all to end gracefully; on task abort Drop will cancel
-            for (t, _c) in handles.into_iter() {
-                let _ = t.await;
-            }
             if let Some(tx) = &tx_opt {
                 let _ = tx.send(UiUpdate::SvcLogEnded);
             }
@@ -54,6 +54,7 @@ pub(crate) fn process_discovery(app: &mut OrkaGuiApp) {
                 // Prewarm watchers for common kinds to reduce first-click latency
                 if !app.watch.prewarm_started {
                     app.watch.prewarm_started = true;
+                    crate::watch::watch_hub_publish_pods();
                     let api = app.api.clone();
                     let keys = std::env::var("ORKA_PREWARM_KINDS").unwrap_or_else(|_| "v1/Pod,apps/v1/Deployment,v1/Service,v1/Namespace,v1/Node,v1/ConfigMap,v1/Secret".into());
                     for key in keys
@@ -4,6 +4,9 @@ use eframe::egui;
 
 use crate::OrkaGuiApp;
 
+/// Rows of the per-kind traffic breakdown shown in the modal.
+const TRAFFIC_TOP_ROWS: usize = 8;
+
 pub(crate) fn ui_stats_modal(app: &mut OrkaGuiApp, ctx: &egui::Context) {
     if !app.stats.open {
         return;
@@ -91,6 +94,76 @@ pub(crate) fn ui_stats_modal(app: &mut OrkaGuiApp, ctx: &egui::Context) {
                         grid_kv(ui, "Watch bytes", "(n/a)");
                         grid_kv(ui, "Details bytes", "(n/a)");
                     }
+                    if !s.traffic.is_empty() {
+                        ui.add_space(4.0);
+                        egui::Grid::new("stats_traffic_breakdown")
+                            .num_columns(5)
+                            .spacing([10.0, 4.0])
+                            .striped(true)
+                            .show(ui, |ui| {
+                                for h in ["Kind", "Verb", "Requests", "Received", "Sent"] {
+                                    ui.label(egui::RichText::new(h).strong());
+                                }
+                                ui.end_row();
+                                for t in s.traffic.iter().take(TRAFFIC_TOP_ROWS) {
+                                    ui.label(&t.gvk).on_hover_text(&t.context);
+                                    ui.label(&t.verb);
+                                    ui.label(t.requests.to_string());
+                                    ui.label(fmt_bytes(t.rx_bytes));
+                                    ui.label(fmt_bytes(t.tx_bytes));
+                                    ui.end_row();
+                                }
+                            });
+                        if s.traffic.len() > TRAFFIC_TOP_ROWS {
+                            ui.label(
+                                egui::RichText::new(format!(
+                                    "+{} more (see kube_rx_bytes_total on /metrics)",
+                                    s.traffic.len() - TRAFFIC_TOP_ROWS
+                                ))
+                                .weak(),
+                            );
+                        }
+                    }
+                    ui.separator();
+                }
+
+                ui.heading("Kube rate limit");
+                ui.separator();
+                if let Some(s) = &app.stats.data {
+                    match (s.throttle_qps, s.throttle_burst) {
+                        (Some(qps), Some(burst)) => {
+                            grid_kv(ui, "Limit", &format!("{} qps (burst {})", qps, burst));
+                            let pct = s.throttle_effective_pct.unwrap_or(100);
+                            let color = if pct < 100 {
+                                ui.visuals().warn_fg_color
+                            } else {
+                                ui.visuals().text_color()
+                            };
+                            grid_kv_colored(ui, "Effective rate", &format!("{}%", pct), color);
+                            let throttled = s.throttle_429_total.unwrap_or(0);
+                            let color = if throttled > 0 {
+                                ui.visuals().warn_fg_color
+                            } else {
+                                ui.visuals().text_color()
+                            };
+                            grid_kv_colored(ui, "429 responses", &throttled.to_string(), color);
+                            let backoff = s.throttle_backoff_ms.unwrap_or(0);
+                            if backoff > 0 {
+                                grid_kv_colored(
+                                    ui,
+                                    "Backing off",
+                                    &format!("{} ms", backoff),
+                                    ui.visuals().error_fg_color,
+                                );
+                            }
+                            grid_kv(
+                                ui,
+                                "Time waiting",
+                                &format!("{} ms", s.throttle_wait_ms_total.unwrap_or(0)),
+                            );
+                        }
+                        _ => grid_kv(ui, "Limit", "(disabled)"),
+                    }
                     ui.separator();
                 }
 
@@ -729,6 +729,25 @@ pub(crate) fn process_updates(app: &mut OrkaGuiApp, ctx: &egui::Context) {
                         ctx.request_repaint();
                     }
                 }
+                Ok(UiUpdate::OwnersReady { uid, rows }) => {
+                    if app.details.selected == Some(uid) {
+                        app.owners.rows = rows;
+                        app.owners.error = None;
+                        app.owners.running = false;
+                        app.owners.uid = Some(uid);
+                        processed += 1;
+                        ctx.request_repaint();
+                    }
+                }
+                Ok(UiUpdate::OwnersError { uid, error }) => {
+                    if app.details.selected == Some(uid) {
+                        app.owners.error = Some(error);
+                        app.owners.running = false;
+                        app.owners.uid = Some(uid);
+                        processed += 1;
+                        ctx.request_repaint();
+                    }
+                }
                 Ok(UiUpdate::GraphReady { uid, text }) => {
                     if app.details.selected == Some(uid) {
                         app.graph.text = text;
@@ -783,6 +802,24 @@ pub(crate) fn process_updates(app: &mut OrkaGuiApp, ctx: &egui::Context) {
                     pending_toasts.push((message, ToastKind::Success));
                     processed += 1;
                 }
+                Ok(UiUpdate::EditLoaded { yaml, status }) => {
+                    app.edit.buffer = yaml;
+                    app.edit.dirty = true;
+                    app.edit.status = status;
+                    processed += 1;
+                }
+                Ok(UiUpdate::EditHistoryLoaded { uid, revisions }) => {
+                    app.edit.running = false;
+                    app.edit.status = match revisions.len() {
+                        0 => "no last-applied history".into(),
+                        n => format!("{} last-applied revision(s)", n),
+                    };
+                    // Default to the revision before the last apply.
+                    app.edit.history_pick = revisions.len().saturating_sub(1).min(1);
+                    app.edit.history = revisions;
+                    app.edit.history_uid = Some(uid);
+                    processed += 1;
+                }
                 Ok(UiUpdate::OpsCaps(c)) => {
                     app.ops.caps = Some(c);
                     app.ops.caps_task = None;
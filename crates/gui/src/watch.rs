@@ -120,3 +120,25 @@ pub(crate) fn watch_hub_reset() {
     watch_hub().map.lock().unwrap().clear();
     watch_hub().cache.lock().unwrap().clear();
 }
+
+/// Pod labels in `ns` from the hub cache (namespace watch first, then the
+/// all-namespaces one); `None` when no Pod watcher has been started.
+fn watch_hub_pod_labels(ns: &str) -> Option<Vec<std::collections::BTreeMap<String, String>>> {
+    let cache = watch_hub().cache.lock().unwrap();
+    let labels = |lo: &LiteObj| lo.labels.iter().cloned().collect();
+    if let Some(map) = cache.get(&format!("v1/Pod|{}", ns)) {
+        return Some(map.values().map(labels).collect());
+    }
+    let map = cache.get("v1/Pod|")?;
+    Some(
+        map.values()
+            .filter(|lo| lo.namespace.as_deref() == Some(ns))
+            .map(labels)
+            .collect(),
+    )
+}
+
+/// Let apply policy checks match Service selectors against the cached pods.
+pub(crate) fn watch_hub_publish_pods() {
+    orka_api::set_pod_snapshot_source(Some(std::sync::Arc::new(watch_hub_pod_labels)));
+}
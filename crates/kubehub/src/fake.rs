@@ -0,0 +1,2648 @@
+//! In-process fake Kubernetes API server for end-to-end tests.
+//!
+//! [`FakeApiServer`] is a tower service holding objects in memory. It speaks
+//! enough of the API for kube's `Client`, `Api<K>`, `Discovery` and
+//! `watcher` to run unchanged:
+//!
+//! - discovery (`/api`, `/apis`, per group-version resource lists, `/version`)
+//! - list with label/field selectors and `limit`/`continue` paging
+//! - watch from a resourceVersion, replaying history; versions older than the
+//!   last [`FakeApiServer::expire_watches`] get a 410 `Expired` error event
+//! - get, create, replace (with resourceVersion preconditions), delete
+//! - merge, strategic-merge (list maps keyed by `name`) and JSON patches
+//! - server-side apply with per-manager `managedFields` (FieldsV1), 409
+//!   conflicts naming the owning manager, `force` and `dryRun`
+//! - `status`, `scale`, `log` and `eviction` subresources; evictions honour
+//!   `PodDisruptionBudget.status.disruptionsAllowed` and answer 429 otherwise
+//!
+//! There are no controllers: nothing reconciles, schedules or garbage
+//! collects. Tests drive state with [`FakeApiServer::create`],
+//! [`FakeApiServer::update`] and friends, and can inject failures with
+//! [`FakeApiServer::fail_next`]. [`FakeApiServer::install`] points
+//! [`super::get_kube_client`] at the server for the life of the guard, and
+//! the history store and discovery cache (`ORKA_DB_PATH`,
+//! `ORKA_DISCOVERY_PATH`) at a fresh temp dir so tests never touch `~/.orka`.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::convert::Infallible;
+use std::ffi::OsString;
+use std::future::{ready, Ready};
+use std::path::PathBuf;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::task::{Context, Poll};
+
+use bytes::Bytes;
+use http::{Method, Request, Response, StatusCode};
+use http_body::{Body, Frame};
+use serde_json::{json, Map, Value as Json};
+use tokio::sync::mpsc;
+
+const APPLY_PATCH: &str = "application/apply-patch+yaml";
+const MERGE_PATCH: &str = "application/merge-patch+json";
+const STRATEGIC_PATCH: &str = "application/strategic-merge-patch+json";
+const JSON_PATCH: &str = "application/json-patch+json";
+
+/// A served resource type.
+#[derive(Debug, Clone)]
+pub struct FakeResource {
+    pub group: String,
+    pub version: String,
+    pub kind: String,
+    pub plural: String,
+    pub singular: String,
+    pub short_names: Vec<String>,
+    pub namespaced: bool,
+    /// Served subresources, e.g. `status`, `scale`, `log`, `eviction`.
+    pub subresources: Vec<String>,
+}
+
+impl FakeResource {
+    pub fn new(group: &str, version: &str, kind: &str, plural: &str, namespaced: bool) -> Self {
+        Self {
+            group: group.to_string(),
+            version: version.to_string(),
+            kind: kind.to_string(),
+            plural: plural.to_string(),
+            singular: kind.to_ascii_lowercase(),
+            short_names: Vec::new(),
+            namespaced,
+            subresources: Vec::new(),
+        }
+    }
+
+    pub fn short_names(mut self, names: &[&str]) -> Self {
+        self.short_names = names.iter().map(|s| s.to_string()).collect();
+        self
+    }
+
+    pub fn subresources(mut self, subs: &[&str]) -> Self {
+        self.subresources = subs.iter().map(|s| s.to_string()).collect();
+        self
+    }
+
+    fn api_version(&self) -> String {
+        if self.group.is_empty() {
+            self.version.clone()
+        } else {
+            format!("{}/{}", self.group, self.version)
+        }
+    }
+
+    fn gvk_key(&self) -> String {
+        format!("{}/{}", self.api_version(), self.kind)
+    }
+}
+
+fn builtin_resources() -> Vec<FakeResource> {
+    vec![
+        FakeResource::new("", "v1", "Namespace", "namespaces", false)
+            .short_names(&["ns"])
+            .subresources(&["status"]),
+        FakeResource::new("", "v1", "Node", "nodes", false)
+            .short_names(&["no"])
+            .subresources(&["status"]),
+        FakeResource::new("", "v1", "Pod", "pods", true)
+            .short_names(&["po"])
+            .subresources(&["status", "log", "eviction"]),
+        FakeResource::new("", "v1", "ConfigMap", "configmaps", true).short_names(&["cm"]),
+        FakeResource::new("", "v1", "Secret", "secrets", true),
+        FakeResource::new("", "v1", "Service", "services", true)
+            .short_names(&["svc"])
+            .subresources(&["status"]),
+        FakeResource::new("", "v1", "ServiceAccount", "serviceaccounts", true).short_names(&["sa"]),
+        FakeResource::new("", "v1", "Event", "events", true).short_names(&["ev"]),
+        FakeResource::new("apps", "v1", "Deployment", "deployments", true)
+            .short_names(&["deploy"])
+            .subresources(&["status", "scale"]),
+        FakeResource::new("apps", "v1", "StatefulSet", "statefulsets", true)
+            .short_names(&["sts"])
+            .subresources(&["status", "scale"]),
+        FakeResource::new("apps", "v1", "ReplicaSet", "replicasets", true)
+            .short_names(&["rs"])
+            .subresources(&["status", "scale"]),
+        FakeResource::new("apps", "v1", "DaemonSet", "daemonsets", true)
+            .short_names(&["ds"])
+            .subresources(&["status"]),
+        FakeResource::new("batch", "v1", "Job", "jobs", true).subresources(&["status"]),
+        FakeResource::new(
+            "policy",
+            "v1",
+            "PodDisruptionBudget",
+            "poddisruptionbudgets",
+            true,
+        )
+        .short_names(&["pdb"])
+        .subresources(&["status"]),
+        FakeResource::new(
+            "apiextensions.k8s.io",
+            "v1",
+            "CustomResourceDefinition",
+            "customresourcedefinitions",
+            false,
+        )
+        .short_names(&["crd", "crds"])
+        .subresources(&["status"]),
+        FakeResource::new(
+            "authorization.k8s.io",
+            "v1",
+            "SelfSubjectAccessReview",
+            "selfsubjectaccessreviews",
+            false,
+        ),
+    ]
+}
+
+/// A failure returned instead of the next matching request(s).
+#[derive(Debug, Clone)]
+struct Fault {
+    method: Method,
+    path_contains: String,
+    status: u16,
+    reason: String,
+    remaining: usize,
+}
+
+struct Watcher {
+    resource: usize,
+    namespace: Option<String>,
+    labels: Vec<LabelReq>,
+    fields: Vec<FieldReq>,
+    tx: mpsc::UnboundedSender<Bytes>,
+}
+
+struct HistoryEntry {
+    rv: u64,
+    resource: usize,
+    event: &'static str,
+    object: Json,
+}
+
+type ObjKey = (usize, String, String);
+
+#[derive(Default)]
+struct State {
+    resources: Vec<FakeResource>,
+    objects: BTreeMap<ObjKey, Json>,
+    rv: u64,
+    /// Watches from versions at or below this get 410 Expired.
+    compacted: u64,
+    history: Vec<HistoryEntry>,
+    watchers: Vec<Watcher>,
+    faults: Vec<Fault>,
+    logs: HashMap<(String, String), String>,
+    requests: Vec<String>,
+}
+
+/// In-memory Kubernetes API server; cheap to clone (shared state).
+#[derive(Clone)]
+pub struct FakeApiServer {
+    state: Arc<Mutex<State>>,
+}
+
+impl Default for FakeApiServer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Only one fake server may back `get_kube_client` at a time.
+static INSTALL_LOCK: Mutex<()> = Mutex::new(());
+static INSTALLS: AtomicU64 = AtomicU64::new(0);
+
+/// State paths redirected while installed, with their subdirectory.
+const ISOLATED_ENV: &[(&str, &str)] = &[
+    ("ORKA_DB_PATH", "history"),
+    ("ORKA_DISCOVERY_PATH", "discovery"),
+];
+
+/// Keeps a [`FakeApiServer`] installed as the kubehub client; restores the
+/// kubeconfig client and the redirected environment on drop.
+pub struct FakeGuard {
+    home: PathBuf,
+    saved: Vec<(&'static str, Option<OsString>)>,
+    _lock: MutexGuard<'static, ()>,
+}
+
+impl FakeGuard {
+    /// Temp dir holding the history store and discovery cache.
+    pub fn home(&self) -> &std::path::Path {
+        &self.home
+    }
+}
+
+impl Drop for FakeGuard {
+    fn drop(&mut self) {
+        super::use_client(None);
+        for (name, old) in self.saved.drain(..) {
+            match old {
+                Some(v) => std::env::set_var(name, v),
+                None => std::env::remove_var(name),
+            }
+        }
+        let _ = std::fs::remove_dir_all(&self.home);
+    }
+}
+
+impl FakeApiServer {
+    /// Server with the common built-in kinds registered and no objects.
+    pub fn new() -> Self {
+        let state = State {
+            resources: builtin_resources(),
+            rv: 1,
+            ..Default::default()
+        };
+        Self {
+            state: Arc::new(Mutex::new(state)),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Serve an additional resource type (e.g. a CRD kind).
+    pub fn register(&self, res: FakeResource) {
+        self.lock().resources.push(res);
+    }
+
+    /// kube client talking to this server.
+    pub fn client(&self) -> kube::Client {
+        kube::Client::new(self.clone(), "default")
+    }
+
+    /// Route [`super::get_kube_client`] to this server, and `ORKA_DB_PATH` /
+    /// `ORKA_DISCOVERY_PATH` to a fresh temp dir, until the guard drops.
+    /// Installs are serialized across threads so parallel tests do not race.
+    pub fn install(&self) -> FakeGuard {
+        let lock = INSTALL_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let home = std::env::temp_dir().join(format!(
+            "orka-fake-{}-{}",
+            std::process::id(),
+            INSTALLS.fetch_add(1, Ordering::Relaxed)
+        ));
+        let _ = std::fs::remove_dir_all(&home);
+        let saved = ISOLATED_ENV
+            .iter()
+            .map(|(name, sub)| {
+                let old = std::env::var_os(name);
+                std::env::set_var(name, home.join(sub));
+                (*name, old)
+            })
+            .collect();
+        super::use_client(Some(self.client()));
+        FakeGuard {
+            home,
+            saved,
+            _lock: lock,
+        }
+    }
+
+    /// Store an object (with `apiVersion`/`kind`), filling server-owned
+    /// metadata, and notify watchers. Replaces any existing object.
+    pub fn create(&self, obj: Json) -> Json {
+        let mut st = self.lock();
+        let idx = st
+            .resource_for_object(&obj)
+            .unwrap_or_else(|| panic!("fake: unknown kind in {}", obj));
+        let ns = obj_ns(&obj);
+        let name = obj_name(&obj);
+        let exists = st.objects.contains_key(&(idx, ns.clone(), name.clone()));
+        let mut obj = obj;
+        if !exists {
+            st.init_metadata(idx, &mut obj, &ns);
+        }
+        st.commit(
+            idx,
+            &ns,
+            &name,
+            obj,
+            if exists { "MODIFIED" } else { "ADDED" },
+        )
+    }
+
+    /// Replace an existing object as an out-of-band writer (bumps resourceVersion).
+    pub fn update(&self, obj: Json) -> Json {
+        let mut st = self.lock();
+        let idx = st
+            .resource_for_object(&obj)
+            .unwrap_or_else(|| panic!("fake: unknown kind in {}", obj));
+        let ns = obj_ns(&obj);
+        let name = obj_name(&obj);
+        let old = st
+            .objects
+            .get(&(idx, ns.clone(), name.clone()))
+            .cloned()
+            .unwrap_or_else(|| panic!("fake: update of missing object {}/{}", ns, name));
+        let mut obj = obj;
+        carry_server_metadata(&old, &mut obj);
+        st.commit(idx, &ns, &name, obj, "MODIFIED")
+    }
+
+    /// Fetch an object by GVK key (`v1/Pod`, `apps/v1/Deployment`).
+    pub fn get(&self, gvk_key: &str, namespace: Option<&str>, name: &str) -> Option<Json> {
+        let st = self.lock();
+        let idx = st.resource_for_key(gvk_key)?;
+        st.objects
+            .get(&(idx, namespace.unwrap_or("").to_string(), name.to_string()))
+            .cloned()
+    }
+
+    /// Delete an object out-of-band; returns whether it existed.
+    pub fn delete(&self, gvk_key: &str, namespace: Option<&str>, name: &str) -> bool {
+        let mut st = self.lock();
+        let Some(idx) = st.resource_for_key(gvk_key) else {
+            return false;
+        };
+        st.remove(idx, namespace.unwrap_or(""), name).is_some()
+    }
+
+    /// Log text served by `pods/{name}/log`.
+    pub fn set_logs(&self, namespace: &str, pod: &str, text: &str) {
+        self.lock()
+            .logs
+            .insert((namespace.to_string(), pod.to_string()), text.to_string());
+    }
+
+    /// Fail the next `times` requests whose method matches and whose path
+    /// contains `path_contains` with an API `Status` of the given code.
+    pub fn fail_next(&self, method: &str, path_contains: &str, status: u16, times: usize) {
+        let reason = StatusCode::from_u16(status)
+            .ok()
+            .and_then(|s| s.canonical_reason())
+            .unwrap_or("Error")
+            .replace(' ', "");
+        self.lock().faults.push(Fault {
+            method: Method::from_bytes(method.as_bytes()).unwrap_or(Method::GET),
+            path_contains: path_contains.to_string(),
+            status,
+            reason,
+            remaining: times,
+        });
+    }
+
+    /// Simulate etcd compaction: forget history and end every open watch with
+    /// a 410 `Expired` error event, as the apiserver does.
+    pub fn expire_watches(&self) {
+        let mut st = self.lock();
+        st.compacted = st.rv;
+        st.history.clear();
+        let rv = st.rv;
+        for w in st.watchers.drain(..) {
+            let _ = w.tx.send(expired_event(rv, rv));
+        }
+    }
+
+    /// Requests seen so far, as `METHOD path?query`.
+    pub fn requests(&self) -> Vec<String> {
+        self.lock().requests.clone()
+    }
+
+    /// Number of requests seen whose method matches and path contains `fragment`.
+    pub fn count_requests(&self, method: &str, fragment: &str) -> usize {
+        self.lock()
+            .requests
+            .iter()
+            .filter(|r| r.starts_with(method) && r.contains(fragment))
+            .count()
+    }
+
+    /// Number of open watch streams.
+    pub fn watch_count(&self) -> usize {
+        let mut st = self.lock();
+        st.watchers.retain(|w| !w.tx.is_closed());
+        st.watchers.len()
+    }
+
+    fn handle(&self, method: Method, path: &str, query: &str, ctype: &str, body: &[u8]) -> Reply {
+        let mut st = self.lock();
+        st.requests.push(if query.is_empty() {
+            format!("{} {}", method, path)
+        } else {
+            format!("{} {}?{}", method, path, query)
+        });
+        if let Some(f) = st
+            .faults
+            .iter_mut()
+            .find(|f| f.remaining > 0 && f.method == method && path.contains(&f.path_contains))
+        {
+            f.remaining -= 1;
+            let (code, reason) = (f.status, f.reason.clone());
+            return Reply::status(code, &reason, &format!("injected failure for {}", path));
+        }
+        let params = parse_query(query);
+        let body = if body.is_empty() {
+            Json::Null
+        } else {
+            match serde_json::from_slice(body) {
+                Ok(v) => v,
+                Err(e) => return Reply::status(400, "BadRequest", &format!("invalid body: {}", e)),
+            }
+        };
+        st.route(&method, path, &params, ctype, body)
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Request routing
+// ---------------------------------------------------------------------------
+
+struct Reply {
+    status: u16,
+    content_type: &'static str,
+    body: FakeBody,
+}
+
+impl Reply {
+    fn json(status: u16, v: &Json) -> Self {
+        Self {
+            status,
+            content_type: "application/json",
+            body: FakeBody::once(Bytes::from(v.to_string())),
+        }
+    }
+
+    fn status(code: u16, reason: &str, message: &str) -> Self {
+        Self::json(code, &status_json(code, reason, message, None))
+    }
+
+    fn not_found(res: &FakeResource, name: &str) -> Self {
+        Self::json(
+            404,
+            &status_json(
+                404,
+                "NotFound",
+                &format!("{} \"{}\" not found", res.plural, name),
+                Some(json!({"name": name, "group": res.group, "kind": res.plural})),
+            ),
+        )
+    }
+}
+
+fn status_json(code: u16, reason: &str, message: &str, details: Option<Json>) -> Json {
+    let mut v = json!({
+        "kind": "Status",
+        "apiVersion": "v1",
+        "metadata": {},
+        "status": if code < 400 { "Success" } else { "Failure" },
+        "message": message,
+        "reason": reason,
+        "code": code,
+    });
+    if let Some(d) = details {
+        v["details"] = d;
+    }
+    v
+}
+
+fn expired_event(requested: u64, current: u64) -> Bytes {
+    let ev = json!({
+        "type": "ERROR",
+        "object": status_json(
+            410,
+            "Expired",
+            &format!("too old resource version: {} ({})", requested, current),
+            None,
+        ),
+    });
+    Bytes::from(format!("{}\n", ev))
+}
+
+struct Params(Vec<(String, String)>);
+
+impl Params {
+    fn get(&self, key: &str) -> Option<&str> {
+        self.0
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+    }
+
+    fn flag(&self, key: &str) -> bool {
+        matches!(self.get(key), Some("true") | Some("1"))
+    }
+}
+
+fn parse_query(q: &str) -> Params {
+    Params(
+        q.split('&')
+            .filter(|kv| !kv.is_empty())
+            .map(|kv| {
+                let (k, v) = kv.split_once('=').unwrap_or((kv, ""));
+                (percent_decode(k), percent_decode(v))
+            })
+            .collect(),
+    )
+}
+
+fn percent_decode(s: &str) -> String {
+    let b = s.as_bytes();
+    let mut out = Vec::with_capacity(b.len());
+    let mut i = 0;
+    while i < b.len() {
+        match b[i] {
+            b'+' => out.push(b' '),
+            b'%' if i + 2 < b.len() => match u8::from_str_radix(&s[i + 1..i + 3], 16) {
+                Ok(v) => {
+                    out.push(v);
+                    i += 2;
+                }
+                Err(_) => out.push(b'%'),
+            },
+            c => out.push(c),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+/// Resource request after path parsing.
+struct Target {
+    resource: usize,
+    namespace: Option<String>,
+    name: Option<String>,
+    sub: Option<String>,
+}
+
+impl State {
+    fn resource_for_key(&self, gvk_key: &str) -> Option<usize> {
+        self.resources.iter().position(|r| r.gvk_key() == gvk_key)
+    }
+
+    fn resource_for_object(&self, obj: &Json) -> Option<usize> {
+        let av = obj.get("apiVersion")?.as_str()?;
+        let kind = obj.get("kind")?.as_str()?;
+        self.resource_for_key(&format!("{}/{}", av, kind))
+    }
+
+    fn resource_for_plural(&self, group: &str, version: &str, plural: &str) -> Option<usize> {
+        self.resources
+            .iter()
+            .position(|r| r.group == group && r.version == version && r.plural == plural)
+    }
+
+    fn route(
+        &mut self,
+        method: &Method,
+        path: &str,
+        params: &Params,
+        ctype: &str,
+        body: Json,
+    ) -> Reply {
+        let segs: Vec<&str> = path.trim_matches('/').split('/').collect();
+        let (group, version, rest): (&str, &str, &[&str]) = match segs.as_slice() {
+            ["version"] => {
+                return Reply::json(
+                    200,
+                    &json!({"major": "1", "minor": "30", "gitVersion": "v1.30.0-orka-fake",
+                            "platform": "linux/amd64"}),
+                )
+            }
+            ["api"] => {
+                return Reply::json(
+                    200,
+                    &json!({"kind": "APIVersions", "versions": ["v1"],
+                            "serverAddressByClientCIDRs": []}),
+                )
+            }
+            ["apis"] => return Reply::json(200, &self.group_list()),
+            ["api", v, rest @ ..] => ("", *v, rest),
+            ["apis", g, v, rest @ ..] => (*g, *v, rest),
+            _ => return Reply::status(404, "NotFound", &format!("unknown path {}", path)),
+        };
+        if rest.is_empty() {
+            return match self.resource_list(group, version) {
+                Some(v) => Reply::json(200, &v),
+                None => Reply::status(404, "NotFound", &format!("unknown group version {}", path)),
+            };
+        }
+        let Some(t) = self.parse_target(group, version, rest) else {
+            return Reply::status(404, "NotFound", &format!("unknown resource {}", path));
+        };
+        let res = self.resources[t.resource].clone();
+        if let Some(sub) = t.sub.as_deref() {
+            if !res.subresources.iter().any(|s| s == sub) {
+                return Reply::status(404, "NotFound", &format!("unknown subresource {}", sub));
+            }
+        }
+        let manager = params.get("fieldManager").unwrap_or("unknown").to_string();
+        let dry_run = params.get("dryRun").is_some();
+        match (method, t.name.as_deref(), t.sub.as_deref()) {
+            (&Method::GET, None, None) if params.flag("watch") => self.watch(&t, params),
+            (&Method::GET, None, None) => self.list(&t, params),
+            (&Method::POST, None, None) if res.kind == "SelfSubjectAccessReview" => {
+                let mut v = body;
+                v["status"] = json!({"allowed": true});
+                Reply::json(201, &v)
+            }
+            (&Method::POST, None, None) => self.create_req(&t, body, &manager, dry_run),
+            (&Method::GET, Some(name), None | Some("status")) => {
+                match self.objects.get(&self.key(&t, name)) {
+                    Some(o) => Reply::json(200, o),
+                    None => Reply::not_found(&res, name),
+                }
+            }
+            (&Method::PUT, Some(name), sub @ (None | Some("status"))) => {
+                self.replace_req(&t, name, sub.is_some(), body, &manager, dry_run)
+            }
+            (&Method::PATCH, Some(name), sub @ (None | Some("status"))) => self.patch_req(
+                &t,
+                name,
+                sub.is_some(),
+                ctype,
+                body,
+                &manager,
+                params.flag("force"),
+                dry_run,
+            ),
+            (&Method::DELETE, Some(name), None) => match self.remove(t.resource, &t.ns(), name) {
+                Some(o) => Reply::json(200, &o),
+                None => Reply::not_found(&res, name),
+            },
+            (&Method::GET, Some(name), Some("scale")) => {
+                match self.objects.get(&self.key(&t, name)) {
+                    Some(o) => Reply::json(200, &scale_of(o)),
+                    None => Reply::not_found(&res, name),
+                }
+            }
+            (&Method::PUT | &Method::PATCH, Some(name), Some("scale")) => {
+                self.scale_req(&t, name, method, ctype, body, &manager)
+            }
+            (&Method::GET, Some(name), Some("log")) => self.log_req(&t, name, params),
+            (&Method::POST, Some(name), Some("eviction")) => self.evict(&t, name),
+            _ => Reply::status(405, "MethodNotAllowed", &format!("{} {}", method, path)),
+        }
+    }
+
+    fn parse_target(&self, group: &str, version: &str, rest: &[&str]) -> Option<Target> {
+        let (namespace, rest) = match rest {
+            ["namespaces", ns, plural, tail @ ..]
+                if self
+                    .resource_for_plural(group, version, plural)
+                    .map(|i| self.resources[i].namespaced)
+                    .unwrap_or(false) =>
+            {
+                (Some(ns.to_string()), {
+                    let mut v = vec![*plural];
+                    v.extend_from_slice(tail);
+                    v
+                })
+            }
+            other => (None, other.to_vec()),
+        };
+        let resource = self.resource_for_plural(group, version, rest.first()?)?;
+        Some(Target {
+            resource,
+            namespace,
+            name: rest.get(1).map(|s| s.to_string()),
+            sub: rest.get(2).map(|s| s.to_string()),
+        })
+    }
+
+    fn key(&self, t: &Target, name: &str) -> ObjKey {
+        (t.resource, t.ns(), name.to_string())
+    }
+
+    fn group_list(&self) -> Json {
+        let mut groups: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+        for r in self.resources.iter().filter(|r| !r.group.is_empty()) {
+            let versions = groups.entry(r.group.as_str()).or_default();
+            if !versions.contains(&r.version.as_str()) {
+                versions.push(r.version.as_str());
+            }
+        }
+        let groups: Vec<Json> = groups
+            .into_iter()
+            .map(|(g, vs)| {
+                let versions: Vec<Json> = vs
+                    .iter()
+                    .map(|v| json!({"groupVersion": format!("{}/{}", g, v), "version": v}))
+                    .collect();
+                json!({"name": g, "versions": versions, "preferredVersion": versions[0]})
+            })
+            .collect();
+        json!({"kind": "APIGroupList", "apiVersion": "v1", "groups": groups})
+    }
+
+    fn resource_list(&self, group: &str, version: &str) -> Option<Json> {
+        let served: Vec<&FakeResource> = self
+            .resources
+            .iter()
+            .filter(|r| r.group == group && r.version == version)
+            .collect();
+        if served.is_empty() {
+            return None;
+        }
+        let mut resources = Vec::new();
+        for r in served {
+            resources.push(json!({
+                "name": r.plural,
+                "singularName": r.singular,
+                "namespaced": r.namespaced,
+                "kind": r.kind,
+                "verbs": ["create", "delete", "get", "list", "patch", "update", "watch"],
+                "shortNames": r.short_names,
+            }));
+            for sub in &r.subresources {
+                let (g, v, kind, verbs) = match sub.as_str() {
+                    "scale" => (
+                        "autoscaling",
+                        "v1",
+                        "Scale",
+                        json!(["get", "patch", "update"]),
+                    ),
+                    "eviction" => ("policy", "v1", "Eviction", json!(["create"])),
+                    "log" => ("", "v1", "Pod", json!(["get"])),
+                    _ => (
+                        r.group.as_str(),
+                        r.version.as_str(),
+                        r.kind.as_str(),
+                        json!(["get", "patch", "update"]),
+                    ),
+                };
+                resources.push(json!({
+                    "name": format!("{}/{}", r.plural, sub),
+                    "singularName": "",
+                    "namespaced": r.namespaced,
+                    "group": g,
+                    "version": v,
+                    "kind": kind,
+                    "verbs": verbs,
+                }));
+            }
+        }
+        let gv = if group.is_empty() {
+            version.to_string()
+        } else {
+            format!("{}/{}", group, version)
+        };
+        Some(json!({
+            "kind": "APIResourceList",
+            "apiVersion": "v1",
+            "groupVersion": gv,
+            "resources": resources,
+        }))
+    }
+
+    // ---- reads ----
+
+    fn matching<'a>(
+        &'a self,
+        t: &'a Target,
+        labels: &'a [LabelReq],
+        fields: &'a [FieldReq],
+    ) -> impl Iterator<Item = &'a Json> + 'a {
+        self.objects
+            .iter()
+            .filter(move |((r, ns, _), _)| {
+                *r == t.resource && t.namespace.as_deref().map(|n| n == ns).unwrap_or(true)
+            })
+            .map(|(_, o)| o)
+            .filter(move |o| labels_match(labels, o) && fields_match(fields, o))
+    }
+
+    fn list(&self, t: &Target, params: &Params) -> Reply {
+        let labels = match parse_label_selector(params.get("labelSelector").unwrap_or("")) {
+            Ok(l) => l,
+            Err(e) => return Reply::status(400, "BadRequest", &e),
+        };
+        let fields = parse_field_selector(params.get("fieldSelector").unwrap_or(""));
+        let all: Vec<&Json> = self.matching(t, &labels, &fields).collect();
+        let offset: usize = params
+            .get("continue")
+            .and_then(|c| c.parse().ok())
+            .unwrap_or(0);
+        let limit: usize = params
+            .get("limit")
+            .and_then(|l| l.parse().ok())
+            .filter(|l| *l > 0)
+            .unwrap_or(usize::MAX);
+        let page: Vec<Json> = all
+            .iter()
+            .skip(offset)
+            .take(limit)
+            .map(|o| (*o).clone())
+            .collect();
+        let next = offset.saturating_add(page.len());
+        let mut meta = json!({"resourceVersion": self.rv.to_string()});
+        if next < all.len() {
+            meta["continue"] = json!(next.to_string());
+            meta["remainingItemCount"] = json!(all.len() - next);
+        }
+        let res = &self.resources[t.resource];
+        Reply::json(
+            200,
+            &json!({
+                "apiVersion": res.api_version(),
+                "kind": format!("{}List", res.kind),
+                "metadata": meta,
+                "items": page,
+            }),
+        )
+    }
+
+    fn watch(&mut self, t: &Target, params: &Params) -> Reply {
+        let labels = match parse_label_selector(params.get("labelSelector").unwrap_or("")) {
+            Ok(l) => l,
+            Err(e) => return Reply::status(400, "BadRequest", &e),
+        };
+        let fields = parse_field_selector(params.get("fieldSelector").unwrap_or(""));
+        let (tx, rx) = mpsc::unbounded_channel();
+        let from: Option<u64> = params
+            .get("resourceVersion")
+            .filter(|v| !v.is_empty() && *v != "0")
+            .and_then(|v| v.parse().ok());
+        match from {
+            Some(rv) if rv < self.compacted => {
+                let _ = tx.send(expired_event(rv, self.rv));
+                return Reply::stream(rx);
+            }
+            Some(rv) => {
+                for h in self
+                    .history
+                    .iter()
+                    .filter(|h| h.rv > rv && h.resource == t.resource)
+                {
+                    if t.namespace
+                        .as_deref()
+                        .map(|n| n == obj_ns(&h.object))
+                        .unwrap_or(true)
+                        && labels_match(&labels, &h.object)
+                        && fields_match(&fields, &h.object)
+                    {
+                        let _ = tx.send(watch_event(h.event, &h.object));
+                    }
+                }
+            }
+            None => {
+                for o in self.matching(t, &labels, &fields) {
+                    let _ = tx.send(watch_event("ADDED", o));
+                }
+            }
+        }
+        self.watchers.push(Watcher {
+            resource: t.resource,
+            namespace: t.namespace.clone(),
+            labels,
+            fields,
+            tx,
+        });
+        Reply::stream(rx)
+    }
+
+    fn log_req(&self, t: &Target, name: &str, params: &Params) -> Reply {
+        if !self.objects.contains_key(&self.key(t, name)) {
+            return Reply::not_found(&self.resources[t.resource], name);
+        }
+        let text = self
+            .logs
+            .get(&(t.ns(), name.to_string()))
+            .cloned()
+            .unwrap_or_default();
+        let text = match params
+            .get("tailLines")
+            .and_then(|n| n.parse::<usize>().ok())
+        {
+            Some(n) => {
+                let lines: Vec<&str> = text.lines().collect();
+                let start = lines.len().saturating_sub(n);
+                let mut s = lines[start..].join("\n");
+                if !s.is_empty() {
+                    s.push('\n');
+                }
+                s
+            }
+            None => text,
+        };
+        Reply {
+            status: 200,
+            content_type: "text/plain",
+            body: FakeBody::once(Bytes::from(text)),
+        }
+    }
+
+    // ---- writes ----
+
+    fn init_metadata(&mut self, idx: usize, obj: &mut Json, ns: &str) {
+        let res = &self.resources[idx];
+        let api_version = res.api_version();
+        let kind = res.kind.clone();
+        let namespaced = res.namespaced;
+        let o = ensure_object(obj);
+        o.insert("apiVersion".into(), json!(api_version));
+        o.insert("kind".into(), json!(kind));
+        let meta = ensure_object(o.entry("metadata").or_insert_with(|| json!({})));
+        if namespaced {
+            meta.insert("namespace".into(), json!(ns));
+        }
+        meta.insert("uid".into(), json!(uuid::Uuid::new_v4().to_string()));
+        meta.insert("creationTimestamp".into(), json!(now_rfc3339()));
+        meta.insert("generation".into(), json!(1));
+    }
+
+    /// Store `obj`, bump the global resourceVersion, record history and fan
+    /// out the event to watchers.
+    fn commit(
+        &mut self,
+        idx: usize,
+        ns: &str,
+        name: &str,
+        mut obj: Json,
+        event: &'static str,
+    ) -> Json {
+        self.rv += 1;
+        let rv = self.rv;
+        ensure_object(
+            ensure_object(&mut obj)
+                .entry("metadata")
+                .or_insert_with(|| json!({})),
+        )
+        .insert("resourceVersion".into(), json!(rv.to_string()));
+        self.objects
+            .insert((idx, ns.to_string(), name.to_string()), obj.clone());
+        self.publish(idx, event, &obj);
+        obj
+    }
+
+    fn remove(&mut self, idx: usize, ns: &str, name: &str) -> Option<Json> {
+        let mut obj = self
+            .objects
+            .remove(&(idx, ns.to_string(), name.to_string()))?;
+        self.rv += 1;
+        obj["metadata"]["resourceVersion"] = json!(self.rv.to_string());
+        self.publish(idx, "DELETED", &obj);
+        Some(obj)
+    }
+
+    fn publish(&mut self, idx: usize, event: &'static str, obj: &Json) {
+        self.history.push(HistoryEntry {
+            rv: self.rv,
+            resource: idx,
+            event,
+            object: obj.clone(),
+        });
+        let ns = obj_ns(obj);
+        let line = watch_event(event, obj);
+        self.watchers.retain(|w| {
+            if w.resource != idx
+                || !w.namespace.as_deref().map(|n| n == ns).unwrap_or(true)
+                || !labels_match(&w.labels, obj)
+                || !fields_match(&w.fields, obj)
+            {
+                return !w.tx.is_closed();
+            }
+            w.tx.send(line.clone()).is_ok()
+        });
+    }
+
+    fn create_req(&mut self, t: &Target, mut body: Json, manager: &str, dry_run: bool) -> Reply {
+        let res = self.resources[t.resource].clone();
+        let ns = t.ns();
+        if res.namespaced {
+            body["metadata"]["namespace"] = json!(ns);
+        }
+        let name = match body["metadata"]["name"].as_str() {
+            Some(n) if !n.is_empty() => n.to_string(),
+            _ => match body["metadata"]["generateName"].as_str() {
+                Some(prefix) => format!("{}{:05x}", prefix, self.rv & 0xfffff),
+                None => return Reply::status(422, "Invalid", "metadata.name: Required value"),
+            },
+        };
+        body["metadata"]["name"] = json!(name);
+        if self.objects.contains_key(&self.key(t, &name)) {
+            return Reply::json(
+                409,
+                &status_json(
+                    409,
+                    "AlreadyExists",
+                    &format!("{} \"{}\" already exists", res.plural, name),
+                    Some(json!({"name": name, "group": res.group, "kind": res.plural})),
+                ),
+            );
+        }
+        self.init_metadata(t.resource, &mut body, &ns);
+        let fields = leaf_paths(&body);
+        set_owner(
+            &mut body,
+            manager,
+            "Update",
+            &res.api_version(),
+            &fields,
+            false,
+        );
+        if dry_run {
+            return Reply::json(201, &body);
+        }
+        let out = self.commit(t.resource, &ns, &name, body, "ADDED");
+        Reply::json(201, &out)
+    }
+
+    fn replace_req(
+        &mut self,
+        t: &Target,
+        name: &str,
+        status_only: bool,
+        body: Json,
+        manager: &str,
+        dry_run: bool,
+    ) -> Reply {
+        let res = self.resources[t.resource].clone();
+        let Some(old) = self.objects.get(&self.key(t, name)).cloned() else {
+            return Reply::not_found(&res, name);
+        };
+        if let Some(want) = body["metadata"]["resourceVersion"].as_str() {
+            if !want.is_empty() && Some(want) != old["metadata"]["resourceVersion"].as_str() {
+                return modified_conflict(&res, name);
+            }
+        }
+        let new = if status_only {
+            let mut n = old.clone();
+            n["status"] = body.get("status").cloned().unwrap_or(Json::Null);
+            n
+        } else {
+            let mut n = body;
+            if let Some(s) = old.get("status") {
+                n["status"] = s.clone();
+            }
+            n
+        };
+        self.write_update(t, name, old, new, manager, dry_run)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn patch_req(
+        &mut self,
+        t: &Target,
+        name: &str,
+        status_only: bool,
+        ctype: &str,
+        patch: Json,
+        manager: &str,
+        force: bool,
+        dry_run: bool,
+    ) -> Reply {
+        let res = self.resources[t.resource].clone();
+        let old = self.objects.get(&self.key(t, name)).cloned();
+        if ctype.starts_with(APPLY_PATCH) {
+            return self.apply(t, name, old, patch, manager, force, dry_run);
+        }
+        let Some(old) = old else {
+            return Reply::not_found(&res, name);
+        };
+        if let Some(want) = patch
+            .get("metadata")
+            .and_then(|m| m.get("resourceVersion"))
+            .and_then(|v| v.as_str())
+        {
+            if Some(want) != old["metadata"]["resourceVersion"].as_str() {
+                return modified_conflict(&res, name);
+            }
+        }
+        let mut new = old.clone();
+        if ctype.starts_with(JSON_PATCH) {
+            if let Err(e) = json_patch(&mut new, &patch) {
+                return Reply::status(422, "Invalid", &e);
+            }
+        } else if ctype.starts_with(STRATEGIC_PATCH) {
+            merge_patch(&mut new, &patch, true);
+        } else if ctype.starts_with(MERGE_PATCH) {
+            merge_patch(&mut new, &patch, false);
+        } else {
+            return Reply::status(
+                415,
+                "UnsupportedMediaType",
+                &format!("content type {}", ctype),
+            );
+        }
+        if status_only {
+            let mut n = old.clone();
+            n["status"] = new.get("status").cloned().unwrap_or(Json::Null);
+            new = n;
+        } else if let Some(s) = old.get("status") {
+            new["status"] = s.clone();
+        }
+        self.write_update(t, name, old, new, manager, dry_run)
+    }
+
+    /// Non-apply write: the manager takes ownership of every changed field.
+    fn write_update(
+        &mut self,
+        t: &Target,
+        name: &str,
+        old: Json,
+        mut new: Json,
+        manager: &str,
+        dry_run: bool,
+    ) -> Reply {
+        let res = self.resources[t.resource].clone();
+        carry_server_metadata(&old, &mut new);
+        new["metadata"]["managedFields"] = old["metadata"]["managedFields"].clone();
+        let changed: BTreeSet<FieldPath> = leaf_paths(&new)
+            .into_iter()
+            .filter(|p| value_at(&old, p) != value_at(&new, p))
+            .collect();
+        if !changed.is_empty() {
+            for o in owners_mut(&mut new).iter_mut() {
+                o.fields.retain(|p| !changed.contains(p));
+            }
+            set_owner(
+                &mut new,
+                manager,
+                "Update",
+                &res.api_version(),
+                &changed,
+                true,
+            );
+        }
+        bump_generation(&old, &mut new);
+        if dry_run {
+            return Reply::json(200, &new);
+        }
+        let out = self.commit(t.resource, &t.ns(), name, new, "MODIFIED");
+        Reply::json(200, &out)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn apply(
+        &mut self,
+        t: &Target,
+        name: &str,
+        old: Option<Json>,
+        patch: Json,
+        manager: &str,
+        force: bool,
+        dry_run: bool,
+    ) -> Reply {
+        let res = self.resources[t.resource].clone();
+        if manager == "unknown" {
+            return Reply::status(
+                422,
+                "Invalid",
+                "PatchOptions.meta.k8s.io \"\" is invalid: fieldManager: Required value: is required for apply patch",
+            );
+        }
+        let applied = leaf_paths(&patch);
+        let Some(old) = old else {
+            let mut obj = patch;
+            obj["metadata"]["name"] = json!(name);
+            let ns = t.ns();
+            self.init_metadata(t.resource, &mut obj, &ns);
+            set_owner(
+                &mut obj,
+                manager,
+                "Apply",
+                &res.api_version(),
+                &applied,
+                false,
+            );
+            if dry_run {
+                return Reply::json(201, &obj);
+            }
+            let out = self.commit(t.resource, &ns, name, obj, "ADDED");
+            return Reply::json(201, &out);
+        };
+
+        let mut new = old.clone();
+        let mut conflicts: Vec<(String, FieldPath)> = Vec::new();
+        for o in owners(&old).iter().filter(|o| o.manager != manager) {
+            for p in &o.fields {
+                let overlaps = applied.iter().any(|a| a.starts_with(p) || p.starts_with(a));
+                if overlaps
+                    && value_at(&old, p) != value_at(&patch, p)
+                    && value_at(&patch, p).is_some()
+                {
+                    conflicts.push((o.manager.clone(), p.clone()));
+                }
+            }
+        }
+        if !conflicts.is_empty() && !force {
+            return apply_conflict(&res, &conflicts);
+        }
+        // Fields this manager applied before but dropped now are removed
+        // unless someone else also owns them.
+        let previous: BTreeSet<FieldPath> = owners(&old)
+            .iter()
+            .filter(|o| o.manager == manager && o.operation == "Apply")
+            .flat_map(|o| o.fields.iter().cloned())
+            .collect();
+        let others: BTreeSet<FieldPath> = owners(&old)
+            .iter()
+            .filter(|o| o.manager != manager)
+            .flat_map(|o| o.fields.iter().cloned())
+            .collect();
+        for p in previous.difference(&applied) {
+            if !others.contains(p) {
+                remove_at(&mut new, p);
+            }
+        }
+        merge_patch(&mut new, &strip_nulls(patch), true);
+        if let Some(s) = old.get("status") {
+            new["status"] = s.clone();
+        }
+        carry_server_metadata(&old, &mut new);
+        new["metadata"]["managedFields"] = old["metadata"]["managedFields"].clone();
+        if force {
+            let taken: BTreeSet<FieldPath> = conflicts.into_iter().map(|(_, p)| p).collect();
+            for o in owners_mut(&mut new)
+                .iter_mut()
+                .filter(|o| o.manager != manager)
+            {
+                o.fields
+                    .retain(|p| !taken.iter().any(|t| p.starts_with(t) || t.starts_with(p)));
+            }
+        }
+        set_owner(
+            &mut new,
+            manager,
+            "Apply",
+            &res.api_version(),
+            &applied,
+            false,
+        );
+        bump_generation(&old, &mut new);
+        if dry_run {
+            return Reply::json(200, &new);
+        }
+        let out = self.commit(t.resource, &t.ns(), name, new, "MODIFIED");
+        Reply::json(200, &out)
+    }
+
+    fn scale_req(
+        &mut self,
+        t: &Target,
+        name: &str,
+        method: &Method,
+        ctype: &str,
+        body: Json,
+        manager: &str,
+    ) -> Reply {
+        let res = self.resources[t.resource].clone();
+        let Some(old) = self.objects.get(&self.key(t, name)).cloned() else {
+            return Reply::not_found(&res, name);
+        };
+        let mut scale = scale_of(&old);
+        if method == Method::PUT || ctype.starts_with(APPLY_PATCH) {
+            merge_patch(&mut scale, &body, false);
+        } else if ctype.starts_with(JSON_PATCH) {
+            if let Err(e) = json_patch(&mut scale, &body) {
+                return Reply::status(422, "Invalid", &e);
+            }
+        } else {
+            merge_patch(&mut scale, &body, false);
+        }
+        let Some(replicas) = scale["spec"]["replicas"].as_i64() else {
+            return Reply::status(422, "Invalid", "spec.replicas: Required value");
+        };
+        let mut new = old.clone();
+        new["spec"]["replicas"] = json!(replicas);
+        match self.write_update(t, name, old, new, manager, false) {
+            r if r.status == 200 => {
+                let obj = self.objects[&self.key(t, name)].clone();
+                Reply::json(200, &scale_of(&obj))
+            }
+            r => r,
+        }
+    }
+
+    fn evict(&mut self, t: &Target, name: &str) -> Reply {
+        let res = self.resources[t.resource].clone();
+        let ns = t.ns();
+        let Some(pod) = self.objects.get(&self.key(t, name)).cloned() else {
+            return Reply::not_found(&res, name);
+        };
+        let Some(pdb_idx) = self.resource_for_key("policy/v1/PodDisruptionBudget") else {
+            return self.evict_delete(t, name);
+        };
+        let pdbs: Vec<(String, Json)> = self
+            .objects
+            .iter()
+            .filter(|((r, n, _), _)| *r == pdb_idx && *n == ns)
+            .filter(|(_, pdb)| selector_matches(&pdb["spec"]["selector"], &pod))
+            .map(|((_, _, n), pdb)| (n.clone(), pdb.clone()))
+            .collect();
+        for (pdb_name, pdb) in &pdbs {
+            if pdb["status"]["disruptionsAllowed"].as_i64().unwrap_or(0) < 1 {
+                return Reply::json(
+                    429,
+                    &status_json(
+                        429,
+                        "TooManyRequests",
+                        "Cannot evict pod as it would violate the pod's disruption budget.",
+                        Some(json!({"causes": [{
+                            "reason": "DisruptionBudget",
+                            "message": format!(
+                                "The disruption budget {} needs more healthy pods to allow disruption.",
+                                pdb_name
+                            ),
+                        }]})),
+                    ),
+                );
+            }
+        }
+        for (pdb_name, mut pdb) in pdbs {
+            let allowed = pdb["status"]["disruptionsAllowed"].as_i64().unwrap_or(0);
+            pdb["status"]["disruptionsAllowed"] = json!(allowed - 1);
+            self.commit(pdb_idx, &ns, &pdb_name, pdb, "MODIFIED");
+        }
+        self.evict_delete(t, name)
+    }
+
+    fn evict_delete(&mut self, t: &Target, name: &str) -> Reply {
+        self.remove(t.resource, &t.ns(), name);
+        Reply::json(201, &status_json(201, "", "", None))
+    }
+}
+
+impl Target {
+    fn ns(&self) -> String {
+        self.namespace.clone().unwrap_or_default()
+    }
+}
+
+fn modified_conflict(res: &FakeResource, name: &str) -> Reply {
+    let kind = if res.group.is_empty() {
+        res.plural.clone()
+    } else {
+        format!("{}.{}", res.plural, res.group)
+    };
+    Reply::json(
+        409,
+        &status_json(
+            409,
+            "Conflict",
+            &format!(
+                "Operation cannot be fulfilled on {} \"{}\": the object has been modified; please apply your changes to the latest version and try again",
+                kind, name
+            ),
+            Some(json!({"name": name, "group": res.group, "kind": res.plural})),
+        ),
+    )
+}
+
+fn apply_conflict(res: &FakeResource, conflicts: &[(String, FieldPath)]) -> Reply {
+    let api_version = res.api_version();
+    let causes: Vec<Json> = conflicts
+        .iter()
+        .map(|(m, p)| {
+            json!({
+                "reason": "FieldManagerConflict",
+                "message": format!("conflict with \"{}\" using {}", m, api_version),
+                "field": path_display(p),
+            })
+        })
+        .collect();
+    // Same shapes as the apiserver: inline for one conflict, grouped by
+    // manager otherwise.
+    let body = match conflicts {
+        [(m, p)] => format!(
+            "conflict with \"{}\" using {}: {}",
+            m,
+            api_version,
+            path_display(p)
+        ),
+        _ => {
+            let mut managers: Vec<&str> = Vec::new();
+            for (m, _) in conflicts {
+                if !managers.contains(&m.as_str()) {
+                    managers.push(m);
+                }
+            }
+            let groups: Vec<String> = managers
+                .iter()
+                .map(|m| {
+                    let mut g = format!("conflicts with \"{}\" using {}:", m, api_version);
+                    for (_, p) in conflicts.iter().filter(|(o, _)| o == m) {
+                        g.push_str(&format!("\n- {}", path_display(p)));
+                    }
+                    g
+                })
+                .collect();
+            groups.join("\n")
+        }
+    };
+    let noun = if conflicts.len() == 1 {
+        "conflict"
+    } else {
+        "conflicts"
+    };
+    let message = format!("Apply failed with {} {}: {}", conflicts.len(), noun, body);
+    Reply::json(
+        409,
+        &status_json(
+            409,
+            "Conflict",
+            &message,
+            Some(json!({"group": res.group, "kind": res.plural, "causes": causes})),
+        ),
+    )
+}
+
+fn watch_event(kind: &str, obj: &Json) -> Bytes {
+    Bytes::from(format!("{}\n", json!({"type": kind, "object": obj})))
+}
+
+fn scale_of(obj: &Json) -> Json {
+    let selector = obj["spec"]["selector"]["matchLabels"]
+        .as_object()
+        .map(|m| {
+            m.iter()
+                .map(|(k, v)| format!("{}={}", k, v.as_str().unwrap_or("")))
+                .collect::<Vec<_>>()
+                .join(",")
+        })
+        .unwrap_or_default();
+    json!({
+        "apiVersion": "autoscaling/v1",
+        "kind": "Scale",
+        "metadata": {
+            "name": obj["metadata"]["name"],
+            "namespace": obj["metadata"]["namespace"],
+            "uid": obj["metadata"]["uid"],
+            "resourceVersion": obj["metadata"]["resourceVersion"],
+        },
+        "spec": {"replicas": obj["spec"]["replicas"].as_i64().unwrap_or(1)},
+        "status": {
+            "replicas": obj["status"]["replicas"].as_i64().unwrap_or(0),
+            "selector": selector,
+        },
+    })
+}
+
+fn obj_ns(obj: &Json) -> String {
+    obj["metadata"]["namespace"]
+        .as_str()
+        .unwrap_or("")
+        .to_string()
+}
+
+fn obj_name(obj: &Json) -> String {
+    obj["metadata"]["name"].as_str().unwrap_or("").to_string()
+}
+
+fn ensure_object(v: &mut Json) -> &mut Map<String, Json> {
+    if !v.is_object() {
+        *v = json!({});
+    }
+    v.as_object_mut().expect("object")
+}
+
+fn now_rfc3339() -> String {
+    k8s_openapi::chrono::Utc::now().to_rfc3339_opts(k8s_openapi::chrono::SecondsFormat::Secs, true)
+}
+
+/// Server-owned metadata survives client writes.
+fn carry_server_metadata(old: &Json, new: &mut Json) {
+    let meta = ensure_object(
+        ensure_object(new)
+            .entry("metadata")
+            .or_insert_with(|| json!({})),
+    );
+    for k in [
+        "uid",
+        "creationTimestamp",
+        "generation",
+        "namespace",
+        "name",
+    ] {
+        if let Some(v) = old["metadata"].get(k) {
+            meta.insert(k.to_string(), v.clone());
+        }
+    }
+    if let (Some(av), Some(kind)) = (old.get("apiVersion"), old.get("kind")) {
+        let o = ensure_object(new);
+        o.insert("apiVersion".into(), av.clone());
+        o.insert("kind".into(), kind.clone());
+    }
+}
+
+fn bump_generation(old: &Json, new: &mut Json) {
+    let spec_changed = old.get("spec") != new.get("spec");
+    if spec_changed {
+        let gen = old["metadata"]["generation"].as_i64().unwrap_or(1);
+        new["metadata"]["generation"] = json!(gen + 1);
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Managed fields (FieldsV1)
+// ---------------------------------------------------------------------------
+
+/// Element of a field path: an object field or a list-map entry keyed by `name`.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+enum PathElem {
+    Field(String),
+    Key(String),
+}
+
+type FieldPath = Vec<PathElem>;
+
+fn path_display(p: &FieldPath) -> String {
+    let mut s = String::new();
+    for e in p {
+        match e {
+            PathElem::Field(f) => {
+                s.push('.');
+                s.push_str(f);
+            }
+            PathElem::Key(k) => s.push_str(&format!("[name=\"{}\"]", k)),
+        }
+    }
+    s
+}
+
+/// Lists of objects that all carry a `name` merge by it (containers, ports, env, volumes).
+fn is_list_map(items: &[Json]) -> bool {
+    !items.is_empty()
+        && items
+            .iter()
+            .all(|i| i.get("name").and_then(|n| n.as_str()).is_some())
+}
+
+/// Leaf field paths set by an object, excluding identity and server-owned fields.
+fn leaf_paths(obj: &Json) -> BTreeSet<FieldPath> {
+    fn walk(v: &Json, prefix: &mut FieldPath, out: &mut BTreeSet<FieldPath>) {
+        match v {
+            Json::Object(m) if !m.is_empty() => {
+                for (k, child) in m {
+                    prefix.push(PathElem::Field(k.clone()));
+                    walk(child, prefix, out);
+                    prefix.pop();
+                }
+            }
+            Json::Array(items) if is_list_map(items) => {
+                for item in items {
+                    let key = item["name"].as_str().unwrap_or_default().to_string();
+                    prefix.push(PathElem::Key(key));
+                    walk(item, prefix, out);
+                    prefix.pop();
+                }
+            }
+            _ => {
+                out.insert(prefix.clone());
+            }
+        }
+    }
+    let mut out = BTreeSet::new();
+    let Some(m) = obj.as_object() else {
+        return out;
+    };
+    for (k, v) in m {
+        match k.as_str() {
+            "apiVersion" | "kind" | "status" => {}
+            "metadata" => {
+                for (mk, mv) in v.as_object().into_iter().flatten() {
+                    if matches!(
+                        mk.as_str(),
+                        "labels" | "annotations" | "finalizers" | "ownerReferences"
+                    ) {
+                        let mut prefix = vec![
+                            PathElem::Field("metadata".into()),
+                            PathElem::Field(mk.clone()),
+                        ];
+                        walk(mv, &mut prefix, &mut out);
+                    }
+                }
+            }
+            _ => {
+                let mut prefix = vec![PathElem::Field(k.clone())];
+                walk(v, &mut prefix, &mut out);
+            }
+        }
+    }
+    out
+}
+
+fn value_at<'a>(v: &'a Json, path: &FieldPath) -> Option<&'a Json> {
+    let mut cur = v;
+    for e in path {
+        cur = match e {
+            PathElem::Field(f) => cur.get(f)?,
+            PathElem::Key(k) => cur
+                .as_array()?
+                .iter()
+                .find(|i| i["name"].as_str() == Some(k.as_str()))?,
+        };
+    }
+    Some(cur)
+}
+
+fn remove_at(v: &mut Json, path: &FieldPath) {
+    let Some((last, parent)) = path.split_last() else {
+        return;
+    };
+    let mut cur = v;
+    for e in parent {
+        let next = match e {
+            PathElem::Field(f) => cur.get_mut(f),
+            PathElem::Key(k) => cur.as_array_mut().and_then(|a| {
+                a.iter_mut()
+                    .find(|i| i["name"].as_str() == Some(k.as_str()))
+            }),
+        };
+        match next {
+            Some(n) => cur = n,
+            None => return,
+        }
+    }
+    match last {
+        PathElem::Field(f) => {
+            if let Some(m) = cur.as_object_mut() {
+                m.remove(f);
+            }
+        }
+        PathElem::Key(k) => {
+            if let Some(a) = cur.as_array_mut() {
+                a.retain(|i| i["name"].as_str() != Some(k.as_str()));
+            }
+        }
+    }
+}
+
+struct Owner {
+    manager: String,
+    operation: String,
+    fields: BTreeSet<FieldPath>,
+}
+
+fn fields_from_v1(v: &Json) -> BTreeSet<FieldPath> {
+    fn walk(v: &Json, prefix: &mut FieldPath, out: &mut BTreeSet<FieldPath>) {
+        let Some(m) = v.as_object() else {
+            return;
+        };
+        let children: Vec<(&String, &Json)> = m.iter().filter(|(k, _)| k.as_str() != ".").collect();
+        if children.is_empty() {
+            out.insert(prefix.clone());
+            return;
+        }
+        for (k, child) in children {
+            let elem = if let Some(f) = k.strip_prefix("f:") {
+                PathElem::Field(f.to_string())
+            } else if let Some(key) = k.strip_prefix("k:") {
+                let name = serde_json::from_str::<Json>(key)
+                    .ok()
+                    .and_then(|j| j["name"].as_str().map(|s| s.to_string()))
+                    .unwrap_or_default();
+                PathElem::Key(name)
+            } else {
+                continue;
+            };
+            prefix.push(elem);
+            walk(child, prefix, out);
+            prefix.pop();
+        }
+    }
+    let mut out = BTreeSet::new();
+    walk(v, &mut Vec::new(), &mut out);
+    out
+}
+
+fn fields_to_v1(paths: &BTreeSet<FieldPath>) -> Json {
+    let mut root = json!({});
+    for p in paths {
+        let mut cur = &mut root;
+        for e in p {
+            let key = match e {
+                PathElem::Field(f) => format!("f:{}", f),
+                PathElem::Key(k) => format!("k:{}", json!({ "name": k })),
+            };
+            let m = ensure_object(cur);
+            if matches!(e, PathElem::Key(_)) {
+                m.entry(key.clone()).or_insert_with(|| json!({".": {}}));
+            }
+            cur = m.entry(key).or_insert_with(|| json!({}));
+        }
+    }
+    root
+}
+
+fn owners(obj: &Json) -> Vec<Owner> {
+    obj["metadata"]["managedFields"]
+        .as_array()
+        .map(|entries| {
+            entries
+                .iter()
+                .map(|e| Owner {
+                    manager: e["manager"].as_str().unwrap_or("").to_string(),
+                    operation: e["operation"].as_str().unwrap_or("").to_string(),
+                    fields: fields_from_v1(&e["fieldsV1"]),
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Edit the ownership table in place via a decoded view.
+struct OwnersMut<'a> {
+    obj: &'a mut Json,
+    owners: Vec<Owner>,
+}
+
+impl<'a> std::ops::Deref for OwnersMut<'a> {
+    type Target = Vec<Owner>;
+    fn deref(&self) -> &Vec<Owner> {
+        &self.owners
+    }
+}
+
+impl<'a> std::ops::DerefMut for OwnersMut<'a> {
+    fn deref_mut(&mut self) -> &mut Vec<Owner> {
+        &mut self.owners
+    }
+}
+
+impl<'a> Drop for OwnersMut<'a> {
+    fn drop(&mut self) {
+        let old: Vec<Json> = self.obj["metadata"]["managedFields"]
+            .as_array()
+            .cloned()
+            .unwrap_or_default();
+        let entries: Vec<Json> = self
+            .owners
+            .iter()
+            .zip(old)
+            .filter(|(o, _)| !o.fields.is_empty())
+            .map(|(o, mut e)| {
+                e["fieldsV1"] = fields_to_v1(&o.fields);
+                e
+            })
+            .collect();
+        self.obj["metadata"]["managedFields"] = json!(entries);
+    }
+}
+
+fn owners_mut(obj: &mut Json) -> OwnersMut<'_> {
+    let owners = owners(obj);
+    OwnersMut { obj, owners }
+}
+
+/// Record `fields` for `manager`/`operation`, replacing (or with `merge`,
+/// extending) that manager's existing entry.
+fn set_owner(
+    obj: &mut Json,
+    manager: &str,
+    operation: &str,
+    api_version: &str,
+    fields: &BTreeSet<FieldPath>,
+    merge: bool,
+) {
+    let mut entries: Vec<Json> = obj["metadata"]["managedFields"]
+        .as_array()
+        .cloned()
+        .unwrap_or_default();
+    let existing = entries
+        .iter()
+        .position(|e| e["manager"] == json!(manager) && e["operation"] == json!(operation));
+    let mut all = fields.clone();
+    if let (true, Some(i)) = (merge, existing) {
+        all.extend(fields_from_v1(&entries[i]["fieldsV1"]));
+    }
+    let entry = json!({
+        "manager": manager,
+        "operation": operation,
+        "apiVersion": api_version,
+        "time": now_rfc3339(),
+        "fieldsType": "FieldsV1",
+        "fieldsV1": fields_to_v1(&all),
+    });
+    match existing {
+        Some(i) => entries[i] = entry,
+        None => entries.push(entry),
+    }
+    entries.retain(|e| {
+        e["fieldsV1"]
+            .as_object()
+            .map(|m| !m.is_empty())
+            .unwrap_or(false)
+    });
+    obj["metadata"]["managedFields"] = json!(entries);
+}
+
+// ---------------------------------------------------------------------------
+// Patches
+// ---------------------------------------------------------------------------
+
+/// RFC 7386 merge; with `strategic`, lists of named objects merge by `name`.
+fn merge_patch(target: &mut Json, patch: &Json, strategic: bool) {
+    match patch {
+        Json::Object(pm) => {
+            let tm = ensure_object(target);
+            for (k, pv) in pm {
+                if pv.is_null() {
+                    tm.remove(k);
+                    continue;
+                }
+                let tv = tm.entry(k.clone()).or_insert(Json::Null);
+                merge_patch(tv, pv, strategic);
+            }
+        }
+        Json::Array(items) if strategic && is_list_map(items) => {
+            let Some(existing) = target.as_array_mut().filter(|a| is_list_map(a)) else {
+                *target = patch.clone();
+                return;
+            };
+            for item in items {
+                let name = item["name"].as_str();
+                match existing.iter_mut().find(|e| e["name"].as_str() == name) {
+                    Some(e) => merge_patch(e, item, strategic),
+                    None => existing.push(item.clone()),
+                }
+            }
+        }
+        other => *target = other.clone(),
+    }
+}
+
+fn strip_nulls(v: Json) -> Json {
+    match v {
+        Json::Object(m) => Json::Object(
+            m.into_iter()
+                .filter(|(_, v)| !v.is_null())
+                .map(|(k, v)| (k, strip_nulls(v)))
+                .collect(),
+        ),
+        Json::Array(a) => Json::Array(a.into_iter().map(strip_nulls).collect()),
+        other => other,
+    }
+}
+
+fn pointer_parts(ptr: &str) -> Vec<String> {
+    ptr.split('/')
+        .skip(1)
+        .map(|s| s.replace("~1", "/").replace("~0", "~"))
+        .collect()
+}
+
+/// RFC 6902 subset: add, remove, replace, test.
+fn json_patch(doc: &mut Json, ops: &Json) -> Result<(), String> {
+    let ops = ops.as_array().ok_or("json patch must be an array")?;
+    for op in ops {
+        let kind = op["op"].as_str().ok_or("json patch op missing")?;
+        let path = op["path"].as_str().ok_or("json patch path missing")?;
+        let parts = pointer_parts(path);
+        let Some((last, parent)) = parts.split_last() else {
+            return Err("json patch on document root is not supported".into());
+        };
+        let parent_ptr: String = parent
+            .iter()
+            .map(|p| format!("/{}", p.replace('~', "~0").replace('/', "~1")))
+            .collect();
+        if kind == "test" {
+            if doc.pointer(path) != Some(&op["value"]) {
+                return Err(format!("test failed at {}", path));
+            }
+            continue;
+        }
+        let container = doc
+            .pointer_mut(&parent_ptr)
+            .ok_or_else(|| format!("path not found: {}", parent_ptr))?;
+        match (kind, container) {
+            ("add" | "replace", Json::Object(m)) => {
+                if kind == "replace" && !m.contains_key(last) {
+                    return Err(format!("path not found: {}", path));
+                }
+                m.insert(last.clone(), op["value"].clone());
+            }
+            ("add", Json::Array(a)) => {
+                let idx = if last == "-" {
+                    a.len()
+                } else {
+                    last.parse().map_err(|_| "bad index")?
+                };
+                if idx > a.len() {
+                    return Err(format!("index out of range: {}", path));
+                }
+                a.insert(idx, op["value"].clone());
+            }
+            ("replace", Json::Array(a)) => {
+                let idx: usize = last.parse().map_err(|_| "bad index")?;
+                *a.get_mut(idx)
+                    .ok_or_else(|| format!("path not found: {}", path))? = op["value"].clone();
+            }
+            ("remove", Json::Object(m)) => {
+                m.remove(last)
+                    .ok_or_else(|| format!("path not found: {}", path))?;
+            }
+            ("remove", Json::Array(a)) => {
+                let idx: usize = last.parse().map_err(|_| "bad index")?;
+                if idx >= a.len() {
+                    return Err(format!("path not found: {}", path));
+                }
+                a.remove(idx);
+            }
+            (other, _) => return Err(format!("unsupported json patch op {} at {}", other, path)),
+        }
+    }
+    Ok(())
+}
+
+// ---------------------------------------------------------------------------
+// Selectors
+// ---------------------------------------------------------------------------
+
+#[derive(Debug, Clone, PartialEq)]
+enum LabelReq {
+    Eq(String, String),
+    NotEq(String, String),
+    In(String, Vec<String>),
+    NotIn(String, Vec<String>),
+    Exists(String),
+    NotExists(String),
+}
+
+fn split_top_level(s: &str) -> Vec<String> {
+    let mut out = Vec::new();
+    let mut depth = 0;
+    let mut cur = String::new();
+    for c in s.chars() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            ',' if depth == 0 => {
+                out.push(std::mem::take(&mut cur));
+                continue;
+            }
+            _ => {}
+        }
+        cur.push(c);
+    }
+    out.push(cur);
+    out.into_iter()
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+        .collect()
+}
+
+fn parse_label_selector(s: &str) -> Result<Vec<LabelReq>, String> {
+    let mut out = Vec::new();
+    for term in split_top_level(s) {
+        let set = |rest: &str| -> Vec<String> {
+            rest.trim()
+                .trim_start_matches('(')
+                .trim_end_matches(')')
+                .split(',')
+                .map(|v| v.trim().to_string())
+                .filter(|v| !v.is_empty())
+                .collect()
+        };
+        let req = if let Some((k, v)) = term.split_once("!=") {
+            LabelReq::NotEq(k.trim().into(), v.trim().into())
+        } else if let Some((k, v)) = term.split_once("==") {
+            LabelReq::Eq(k.trim().into(), v.trim().into())
+        } else if let Some((k, v)) = term.split_once('=') {
+            LabelReq::Eq(k.trim().into(), v.trim().into())
+        } else if let Some((k, rest)) = term.split_once(" notin ") {
+            LabelReq::NotIn(k.trim().into(), set(rest))
+        } else if let Some((k, rest)) = term.split_once(" in ") {
+            LabelReq::In(k.trim().into(), set(rest))
+        } else if let Some(k) = term.strip_prefix('!') {
+            LabelReq::NotExists(k.trim().into())
+        } else if term
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_./".contains(c))
+        {
+            LabelReq::Exists(term)
+        } else {
+            return Err(format!("unable to parse requirement: {}", term));
+        };
+        out.push(req);
+    }
+    Ok(out)
+}
+
+fn labels_match(reqs: &[LabelReq], obj: &Json) -> bool {
+    let labels = &obj["metadata"]["labels"];
+    let get = |k: &str| labels.get(k).and_then(|v| v.as_str());
+    reqs.iter().all(|r| match r {
+        LabelReq::Eq(k, v) => get(k) == Some(v.as_str()),
+        LabelReq::NotEq(k, v) => get(k) != Some(v.as_str()),
+        LabelReq::In(k, vs) => get(k).map(|x| vs.iter().any(|v| v == x)).unwrap_or(false),
+        LabelReq::NotIn(k, vs) => get(k).map(|x| !vs.iter().any(|v| v == x)).unwrap_or(true),
+        LabelReq::Exists(k) => get(k).is_some(),
+        LabelReq::NotExists(k) => get(k).is_none(),
+    })
+}
+
+/// `metav1.LabelSelector` (matchLabels + matchExpressions) against an object.
+fn selector_matches(sel: &Json, obj: &Json) -> bool {
+    if !sel.is_object() {
+        return false;
+    }
+    let mut reqs = Vec::new();
+    for (k, v) in sel["matchLabels"].as_object().into_iter().flatten() {
+        reqs.push(LabelReq::Eq(
+            k.clone(),
+            v.as_str().unwrap_or("").to_string(),
+        ));
+    }
+    for e in sel["matchExpressions"].as_array().into_iter().flatten() {
+        let key = e["key"].as_str().unwrap_or("").to_string();
+        let values: Vec<String> = e["values"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|v| v.as_str().map(|s| s.to_string()))
+            .collect();
+        reqs.push(match e["operator"].as_str().unwrap_or("") {
+            "In" => LabelReq::In(key, values),
+            "NotIn" => LabelReq::NotIn(key, values),
+            "Exists" => LabelReq::Exists(key),
+            _ => LabelReq::NotExists(key),
+        });
+    }
+    labels_match(&reqs, obj)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct FieldReq {
+    path: String,
+    value: String,
+    negate: bool,
+}
+
+fn parse_field_selector(s: &str) -> Vec<FieldReq> {
+    split_top_level(s)
+        .into_iter()
+        .filter_map(|term| {
+            let (path, value, negate) = if let Some((k, v)) = term.split_once("!=") {
+                (k, v, true)
+            } else if let Some((k, v)) = term.split_once("==") {
+                (k, v, false)
+            } else {
+                let (k, v) = term.split_once('=')?;
+                (k, v, false)
+            };
+            Some(FieldReq {
+                path: path.trim().to_string(),
+                value: value.trim().to_string(),
+                negate,
+            })
+        })
+        .collect()
+}
+
+fn fields_match(reqs: &[FieldReq], obj: &Json) -> bool {
+    reqs.iter().all(|r| {
+        let mut cur = obj;
+        for seg in r.path.split('.') {
+            cur = &cur[seg];
+        }
+        let actual = match cur {
+            Json::String(s) => s.clone(),
+            Json::Null => String::new(),
+            other => other.to_string(),
+        };
+        (actual == r.value) != r.negate
+    })
+}
+
+// ---------------------------------------------------------------------------
+// tower glue
+// ---------------------------------------------------------------------------
+
+/// Response body: a single buffer, or a watch stream fed by the server.
+pub struct FakeBody {
+    once: Option<Bytes>,
+    stream: Option<mpsc::UnboundedReceiver<Bytes>>,
+}
+
+impl FakeBody {
+    fn once(b: Bytes) -> Self {
+        Self {
+            once: Some(b),
+            stream: None,
+        }
+    }
+}
+
+impl Reply {
+    fn stream(rx: mpsc::UnboundedReceiver<Bytes>) -> Self {
+        Self {
+            status: 200,
+            content_type: "application/json",
+            body: FakeBody {
+                once: None,
+                stream: Some(rx),
+            },
+        }
+    }
+}
+
+impl Body for FakeBody {
+    type Data = Bytes;
+    type Error = Infallible;
+
+    fn poll_frame(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+        if let Some(b) = self.once.take() {
+            return Poll::Ready(Some(Ok(Frame::data(b))));
+        }
+        match self.stream.as_mut() {
+            Some(rx) => rx.poll_recv(cx).map(|o| o.map(|b| Ok(Frame::data(b)))),
+            None => Poll::Ready(None),
+        }
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.once.is_none() && self.stream.is_none()
+    }
+}
+
+impl<B> tower::Service<Request<B>> for FakeApiServer
+where
+    B: Body<Data = Bytes> + From<Bytes> + Unpin,
+{
+    type Response = Response<FakeBody>;
+    type Error = Infallible;
+    type Future = Ready<Result<Self::Response, Infallible>>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, mut req: Request<B>) -> Self::Future {
+        let ctype = req
+            .headers()
+            .get(http::header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .unwrap_or("")
+            .to_string();
+        let reply = match super::fixture::take_buffered(req.body_mut()) {
+            Ok(body) => self.handle(
+                req.method().clone(),
+                req.uri().path(),
+                req.uri().query().unwrap_or(""),
+                &ctype,
+                &body,
+            ),
+            Err(e) => Reply::status(400, "BadRequest", &e.to_string()),
+        };
+        let mut resp = Response::new(reply.body);
+        *resp.status_mut() = StatusCode::from_u16(reply.status).unwrap_or(StatusCode::OK);
+        resp.headers_mut().insert(
+            http::header::CONTENT_TYPE,
+            http::HeaderValue::from_static(reply.content_type),
+        );
+        ready(Ok(resp))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn send(srv: &FakeApiServer, method: &str, uri: &str, ctype: &str, body: Json) -> (u16, Json) {
+        let (status, text) = send_raw(srv, method, uri, ctype, body);
+        (status, serde_json::from_str(&text).unwrap_or(Json::Null))
+    }
+
+    fn send_raw(
+        srv: &FakeApiServer,
+        method: &str,
+        uri: &str,
+        ctype: &str,
+        body: Json,
+    ) -> (u16, String) {
+        let bytes = if body.is_null() {
+            Bytes::new()
+        } else {
+            Bytes::from(body.to_string())
+        };
+        let req = Request::builder()
+            .method(method)
+            .uri(uri)
+            .header(http::header::CONTENT_TYPE, ctype)
+            .body(http_body_util::Full::new(bytes))
+            .unwrap();
+        let resp =
+            futures::executor::block_on(tower::Service::call(&mut srv.clone(), req)).unwrap();
+        let status = resp.status().as_u16();
+        let mut body = resp.into_body();
+        let mut out = Vec::new();
+        // Drain whatever is buffered without waiting on open watch streams.
+        let waker = futures::task::noop_waker_ref();
+        let mut cx = Context::from_waker(waker);
+        while let Poll::Ready(Some(Ok(f))) = Pin::new(&mut body).poll_frame(&mut cx) {
+            out.extend_from_slice(f.data_ref().unwrap());
+        }
+        (status, String::from_utf8(out).unwrap())
+    }
+
+    fn deployment(name: &str, replicas: i64) -> Json {
+        json!({
+            "apiVersion": "apps/v1",
+            "kind": "Deployment",
+            "metadata": {"name": name, "namespace": "default", "labels": {"app": name}},
+            "spec": {
+                "replicas": replicas,
+                "selector": {"matchLabels": {"app": name}},
+                "template": {
+                    "metadata": {"labels": {"app": name}},
+                    "spec": {"containers": [{"name": "app", "image": "nginx:1.25"}]}
+                }
+            }
+        })
+    }
+
+    fn pod(name: &str, node: &str, app: &str) -> Json {
+        json!({
+            "apiVersion": "v1",
+            "kind": "Pod",
+            "metadata": {"name": name, "namespace": "default", "labels": {"app": app}},
+            "spec": {"nodeName": node, "containers": [{"name": "c", "image": "busybox"}]}
+        })
+    }
+
+    const DEPLOY: &str = "/apis/apps/v1/namespaces/default/deployments/web";
+
+    #[tokio::test]
+    async fn install_redirects_state_paths_until_dropped() {
+        let srv = FakeApiServer::new();
+        let guard = srv.install();
+        let db = PathBuf::from(std::env::var_os("ORKA_DB_PATH").unwrap());
+        let cache = PathBuf::from(std::env::var_os("ORKA_DISCOVERY_PATH").unwrap());
+        assert!(db.starts_with(guard.home()) && cache.starts_with(guard.home()));
+        std::fs::create_dir_all(&db).unwrap();
+        let home = guard.home().to_path_buf();
+        drop(guard);
+        assert!(
+            std::env::var_os("ORKA_DB_PATH").is_none_or(|p| !PathBuf::from(p).starts_with(&home))
+        );
+        assert!(!home.exists());
+    }
+
+    #[test]
+    fn discovery_lists_groups_and_subresources() {
+        let srv = FakeApiServer::new();
+        let (s, groups) = send(&srv, "GET", "/apis", "", Json::Null);
+        assert_eq!(s, 200);
+        assert!(groups["groups"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .any(|g| g["name"] == "apps"));
+        let (_, core) = send(&srv, "GET", "/api/v1", "", Json::Null);
+        let names: Vec<&str> = core["resources"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|r| r["name"].as_str().unwrap())
+            .collect();
+        assert!(names.contains(&"pods") && names.contains(&"pods/eviction"));
+    }
+
+    #[test]
+    fn list_filters_by_selector_and_pages() {
+        let srv = FakeApiServer::new();
+        srv.create(pod("a", "n1", "web"));
+        srv.create(pod("b", "n2", "web"));
+        srv.create(pod("c", "n1", "db"));
+        let (_, l) = send(
+            &srv,
+            "GET",
+            "/api/v1/pods?labelSelector=app%3Dweb",
+            "",
+            Json::Null,
+        );
+        assert_eq!(l["items"].as_array().unwrap().len(), 2);
+        let (_, l) = send(
+            &srv,
+            "GET",
+            "/api/v1/pods?fieldSelector=spec.nodeName%3Dn1",
+            "",
+            Json::Null,
+        );
+        assert_eq!(l["items"].as_array().unwrap().len(), 2);
+        let (_, l) = send(
+            &srv,
+            "GET",
+            "/api/v1/namespaces/default/pods?limit=2",
+            "",
+            Json::Null,
+        );
+        assert_eq!(l["items"].as_array().unwrap().len(), 2);
+        let cont = l["metadata"]["continue"].as_str().unwrap().to_string();
+        let (_, l) = send(
+            &srv,
+            "GET",
+            &format!("/api/v1/namespaces/default/pods?limit=2&continue={}", cont),
+            "",
+            Json::Null,
+        );
+        assert_eq!(l["items"][0]["metadata"]["name"], "c");
+        assert!(l["metadata"].get("continue").is_none());
+    }
+
+    #[test]
+    fn watch_replays_from_version_and_expires_after_compaction() {
+        let srv = FakeApiServer::new();
+        let a = srv.create(pod("a", "n1", "web"));
+        let rv_a: u64 = a["metadata"]["resourceVersion"]
+            .as_str()
+            .unwrap()
+            .parse()
+            .unwrap();
+        srv.create(pod("b", "n1", "web"));
+        let (_, events) = send_raw(
+            &srv,
+            "GET",
+            &format!("/api/v1/pods?watch=true&resourceVersion={}", rv_a),
+            "",
+            Json::Null,
+        );
+        let lines: Vec<Json> = events
+            .lines()
+            .map(|l| serde_json::from_str(l).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 1);
+        assert_eq!(lines[0]["type"], "ADDED");
+        assert_eq!(lines[0]["object"]["metadata"]["name"], "b");
+
+        // An open watch is told its version expired and is closed.
+        let req = Request::get("/api/v1/pods?watch=true")
+            .body(http_body_util::Full::new(Bytes::new()))
+            .unwrap();
+        let resp =
+            futures::executor::block_on(tower::Service::call(&mut srv.clone(), req)).unwrap();
+        let mut body = resp.into_body();
+        assert_eq!(srv.watch_count(), 1);
+        srv.expire_watches();
+        assert_eq!(srv.watch_count(), 0);
+        let mut cx = Context::from_waker(futures::task::noop_waker_ref());
+        let mut last = Bytes::new();
+        while let Poll::Ready(Some(Ok(f))) = Pin::new(&mut body).poll_frame(&mut cx) {
+            last = f.into_data().unwrap();
+        }
+        let ev: Json = serde_json::from_slice(&last).unwrap();
+        assert_eq!(ev["object"]["reason"], "Expired");
+
+        // Resuming from a compacted version fails straight away.
+        let (_, events) = send_raw(
+            &srv,
+            "GET",
+            &format!("/api/v1/pods?watch=true&resourceVersion={}", rv_a),
+            "",
+            Json::Null,
+        );
+        let ev: Json = serde_json::from_str(events.lines().next().unwrap()).unwrap();
+        assert_eq!(ev["type"], "ERROR");
+        assert_eq!(ev["object"]["code"], 410);
+    }
+
+    #[test]
+    fn server_side_apply_tracks_owners_and_conflicts() {
+        let srv = FakeApiServer::new();
+        let (s, created) = send(
+            &srv,
+            "PATCH",
+            &format!("{}?fieldManager=orka", DEPLOY),
+            APPLY_PATCH,
+            deployment("web", 2),
+        );
+        assert_eq!(s, 201);
+        assert_eq!(created["metadata"]["managedFields"][0]["manager"], "orka");
+        assert!(
+            created["metadata"]["managedFields"][0]["fieldsV1"]["f:spec"]["f:template"]["f:spec"]
+                ["f:containers"]
+                .get("k:{\"name\":\"app\"}")
+                .is_some()
+        );
+
+        // Another writer takes over replicas via a plain merge patch.
+        let (s, _) = send(
+            &srv,
+            "PATCH",
+            &format!("{}?fieldManager=kubectl-edit", DEPLOY),
+            MERGE_PATCH,
+            json!({"spec": {"replicas": 5}}),
+        );
+        assert_eq!(s, 200);
+
+        // Re-applying a different replica count now conflicts.
+        let (s, status) = send(
+            &srv,
+            "PATCH",
+            &format!("{}?fieldManager=orka", DEPLOY),
+            APPLY_PATCH,
+            deployment("web", 3),
+        );
+        assert_eq!(s, 409);
+        assert_eq!(status["reason"], "Conflict");
+        assert_eq!(status["details"]["causes"][0]["field"], ".spec.replicas");
+        assert!(status["message"].as_str().unwrap().contains("kubectl-edit"));
+
+        // Same value is shared ownership, not a conflict.
+        let (s, _) = send(
+            &srv,
+            "PATCH",
+            &format!("{}?fieldManager=orka", DEPLOY),
+            APPLY_PATCH,
+            deployment("web", 5),
+        );
+        assert_eq!(s, 200);
+
+        // Force takes the field back.
+        let (s, forced) = send(
+            &srv,
+            "PATCH",
+            &format!("{}?fieldManager=orka&force=true", DEPLOY),
+            APPLY_PATCH,
+            deployment("web", 1),
+        );
+        assert_eq!(s, 200);
+        assert_eq!(forced["spec"]["replicas"], 1);
+        let edit = forced["metadata"]["managedFields"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|e| e["manager"] == "kubectl-edit");
+        assert!(edit.is_none(), "kubectl-edit should lose its only field");
+    }
+
+    #[test]
+    fn apply_drops_fields_no_longer_applied() {
+        let srv = FakeApiServer::new();
+        let mut d = deployment("web", 1);
+        d["metadata"]["labels"]["tier"] = json!("front");
+        send(
+            &srv,
+            "PATCH",
+            &format!("{}?fieldManager=orka", DEPLOY),
+            APPLY_PATCH,
+            d,
+        );
+        let (_, out) = send(
+            &srv,
+            "PATCH",
+            &format!("{}?fieldManager=orka", DEPLOY),
+            APPLY_PATCH,
+            deployment("web", 1),
+        );
+        assert!(out["metadata"]["labels"].get("tier").is_none());
+        assert_eq!(out["metadata"]["generation"], 1);
+    }
+
+    #[test]
+    fn replace_checks_resource_version() {
+        let srv = FakeApiServer::new();
+        let cm = srv.create(json!({
+            "apiVersion": "v1", "kind": "ConfigMap",
+            "metadata": {"name": "cfg", "namespace": "default"},
+            "data": {"k": "v"}
+        }));
+        let mut stale = cm.clone();
+        stale["metadata"]["resourceVersion"] = json!("1");
+        let (s, st) = send(
+            &srv,
+            "PUT",
+            "/api/v1/namespaces/default/configmaps/cfg",
+            "application/json",
+            stale,
+        );
+        assert_eq!(s, 409);
+        assert!(st["message"]
+            .as_str()
+            .unwrap()
+            .contains("has been modified"));
+        let mut fresh = cm;
+        fresh["data"]["k"] = json!("w");
+        let (s, out) = send(
+            &srv,
+            "PUT",
+            "/api/v1/namespaces/default/configmaps/cfg",
+            "application/json",
+            fresh,
+        );
+        assert_eq!(s, 200);
+        assert_eq!(out["data"]["k"], "w");
+    }
+
+    #[test]
+    fn scale_subresource_updates_replicas() {
+        let srv = FakeApiServer::new();
+        srv.create(deployment("web", 2));
+        let (_, scale) = send(&srv, "GET", &format!("{}/scale", DEPLOY), "", Json::Null);
+        assert_eq!(scale["spec"]["replicas"], 2);
+        assert_eq!(scale["status"]["selector"], "app=web");
+        let (s, _) = send(
+            &srv,
+            "PATCH",
+            &format!("{}/scale", DEPLOY),
+            MERGE_PATCH,
+            json!({"spec": {"replicas": 4}}),
+        );
+        assert_eq!(s, 200);
+        assert_eq!(
+            srv.get("apps/v1/Deployment", Some("default"), "web")
+                .unwrap()["spec"]["replicas"],
+            4
+        );
+    }
+
+    #[test]
+    fn eviction_honours_disruption_budget() {
+        let srv = FakeApiServer::new();
+        srv.create(pod("web-0", "n1", "web"));
+        srv.create(json!({
+            "apiVersion": "policy/v1", "kind": "PodDisruptionBudget",
+            "metadata": {"name": "web", "namespace": "default"},
+            "spec": {"selector": {"matchLabels": {"app": "web"}}},
+            "status": {"disruptionsAllowed": 0}
+        }));
+        let evict = "/api/v1/namespaces/default/pods/web-0/eviction";
+        let body =
+            json!({"apiVersion": "policy/v1", "kind": "Eviction", "metadata": {"name": "web-0"}});
+        let (s, st) = send(&srv, "POST", evict, "application/json", body.clone());
+        assert_eq!(s, 429);
+        assert_eq!(st["details"]["causes"][0]["reason"], "DisruptionBudget");
+
+        let mut pdb = srv
+            .get("policy/v1/PodDisruptionBudget", Some("default"), "web")
+            .unwrap();
+        pdb["status"]["disruptionsAllowed"] = json!(1);
+        srv.update(pdb);
+        let (s, _) = send(&srv, "POST", evict, "application/json", body);
+        assert_eq!(s, 201);
+        assert!(srv.get("v1/Pod", Some("default"), "web-0").is_none());
+        let pdb = srv
+            .get("policy/v1/PodDisruptionBudget", Some("default"), "web")
+            .unwrap();
+        assert_eq!(pdb["status"]["disruptionsAllowed"], 0);
+    }
+
+    #[test]
+    fn logs_and_injected_faults() {
+        let srv = FakeApiServer::new();
+        srv.create(pod("web-0", "n1", "web"));
+        srv.set_logs("default", "web-0", "one\ntwo\nthree\n");
+        let (s, text) = send_raw(
+            &srv,
+            "GET",
+            "/api/v1/namespaces/default/pods/web-0/log?tailLines=2",
+            "",
+            Json::Null,
+        );
+        assert_eq!((s, text.as_str()), (200, "two\nthree\n"));
+        srv.fail_next("GET", "/pods/web-0", 500, 1);
+        let (s, st) = send(
+            &srv,
+            "GET",
+            "/api/v1/namespaces/default/pods/web-0",
+            "",
+            Json::Null,
+        );
+        assert_eq!(
+            (s, st["reason"].as_str()),
+            (500, Some("InternalServerError"))
+        );
+        let (s, _) = send(
+            &srv,
+            "GET",
+            "/api/v1/namespaces/default/pods/web-0",
+            "",
+            Json::Null,
+        );
+        assert_eq!(s, 200);
+        assert_eq!(srv.count_requests("GET", "/pods/web-0"), 3);
+    }
+
+    #[test]
+    fn json_and_strategic_patches() {
+        let srv = FakeApiServer::new();
+        srv.create(deployment("web", 1));
+        let (s, out) = send(
+            &srv,
+            "PATCH",
+            DEPLOY,
+            STRATEGIC_PATCH,
+            json!({"spec": {"template": {"spec": {"containers": [{"name": "side", "image": "envoy"}]}}}}),
+        );
+        assert_eq!(s, 200);
+        assert_eq!(
+            out["spec"]["template"]["spec"]["containers"]
+                .as_array()
+                .unwrap()
+                .len(),
+            2
+        );
+        let (s, out) = send(
+            &srv,
+            "PATCH",
+            DEPLOY,
+            JSON_PATCH,
+            json!([{"op": "replace", "path": "/spec/replicas", "value": 7},
+                   {"op": "remove", "path": "/spec/template/spec/containers/1"}]),
+        );
+        assert_eq!(s, 200);
+        assert_eq!(out["spec"]["replicas"], 7);
+        assert_eq!(
+            out["spec"]["template"]["spec"]["containers"]
+                .as_array()
+                .unwrap()
+                .len(),
+            1
+        );
+    }
+}
@@ -0,0 +1,658 @@
+//! Kube API request recording and offline replay.
+//!
+//! With `ORKA_KUBE_RECORD_DIR` set, every client built by this crate writes
+//! each HTTP exchange (method, path, query, request body, status and the
+//! response body frames as they arrived, so watch streams keep their event
+//! boundaries) to `<dir>/<seq>.json`. With `ORKA_KUBE_REPLAY_DIR` set,
+//! [`super::get_kube_client`] skips kubeconfig entirely and serves those
+//! fixtures instead, so discovery, snapshots, watches, apply and ops run
+//! without a cluster.
+//!
+//! Replay matches on method, path and query with `resourceVersion`,
+//! `resourceVersionMatch` and `timeoutSeconds` ignored. Exchanges with the
+//! same key are served in recorded order; once exhausted, the last one is
+//! repeated, except for watches, which stay open without further events.
+//! Unknown requests get a 404 `Status` naming the missing fixture.
+//! Upgraded connections (exec, attach, port-forward) are not replayable.
+
+use std::collections::{HashMap, VecDeque};
+use std::convert::Infallible;
+use std::future::{ready, Future, Ready};
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+
+use anyhow::{anyhow, Result};
+use bytes::{Buf, Bytes};
+use http::{Request, Response, StatusCode};
+use http_body::{Body, Frame, SizeHint};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use tower::{Layer, Service};
+use tracing::warn;
+
+/// Query parameters that change between runs and are ignored when matching.
+const VOLATILE_PARAMS: &[&str] = &["resourceVersion", "resourceVersionMatch", "timeoutSeconds"];
+
+/// One recorded request/response pair.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Exchange {
+    pub seq: u64,
+    pub method: String,
+    pub path: String,
+    #[serde(default)]
+    pub query: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request_body: Option<String>,
+    pub status: u16,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content_type: Option<String>,
+    /// Response body frames in arrival order (one per watch chunk).
+    #[serde(default)]
+    pub chunks: Vec<String>,
+}
+
+/// Fixture directory for recording, from `ORKA_KUBE_RECORD_DIR`.
+pub fn record_dir() -> Option<PathBuf> {
+    dir_from_env("ORKA_KUBE_RECORD_DIR")
+}
+
+/// Fixture directory to replay instead of talking to a cluster, from `ORKA_KUBE_REPLAY_DIR`.
+pub fn replay_dir() -> Option<PathBuf> {
+    dir_from_env("ORKA_KUBE_REPLAY_DIR")
+}
+
+fn dir_from_env(var: &str) -> Option<PathBuf> {
+    std::env::var(var)
+        .ok()
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+        .map(PathBuf::from)
+}
+
+/// Load all exchanges from a fixture directory, ordered by sequence number.
+pub fn load_fixtures(dir: &Path) -> Result<Vec<Exchange>> {
+    let mut out = Vec::new();
+    let entries = std::fs::read_dir(dir)
+        .map_err(|e| anyhow!("reading fixture dir {}: {}", dir.display(), e))?;
+    for entry in entries {
+        let path = entry?.path();
+        if path.extension().and_then(|e| e.to_str()) != Some("json") {
+            continue;
+        }
+        let raw = std::fs::read(&path)?;
+        let ex: Exchange = serde_json::from_slice(&raw)
+            .map_err(|e| anyhow!("parsing fixture {}: {}", path.display(), e))?;
+        out.push(ex);
+    }
+    out.sort_by_key(|e| e.seq);
+    Ok(out)
+}
+
+fn normalize_query(query: &str) -> String {
+    let mut parts: Vec<&str> = query
+        .split('&')
+        .filter(|kv| !kv.is_empty())
+        .filter(|kv| {
+            let key = kv.split('=').next().unwrap_or("");
+            !VOLATILE_PARAMS.contains(&key)
+        })
+        .collect();
+    parts.sort_unstable();
+    parts.join("&")
+}
+
+fn is_watch(query: &str) -> bool {
+    query
+        .split('&')
+        .any(|kv| kv == "watch=true" || kv == "watch=1")
+}
+
+fn match_key(method: &str, path: &str, query: &str) -> String {
+    format!("{} {}?{}", method, path, normalize_query(query))
+}
+
+// ---------------------------------------------------------------------------
+// Recording
+// ---------------------------------------------------------------------------
+
+struct Recorder {
+    dir: PathBuf,
+    next_seq: AtomicU64,
+}
+
+impl Recorder {
+    fn open(dir: PathBuf) -> Result<Self> {
+        std::fs::create_dir_all(&dir)?;
+        // Continue after existing fixtures so repeated runs append.
+        let next = load_fixtures(&dir)?.last().map(|e| e.seq + 1).unwrap_or(0);
+        Ok(Self {
+            dir,
+            next_seq: AtomicU64::new(next),
+        })
+    }
+
+    fn write(&self, ex: &Exchange) {
+        let path = self.dir.join(format!("{:06}.json", ex.seq));
+        let tmp = path.with_extension("json.tmp");
+        let res = serde_json::to_vec_pretty(ex)
+            .map_err(anyhow::Error::from)
+            .and_then(|buf| Ok(std::fs::write(&tmp, buf)?))
+            .and_then(|_| Ok(std::fs::rename(&tmp, &path)?));
+        if let Err(e) = res {
+            warn!(path = %path.display(), error = %e, "kube fixture write failed");
+        }
+    }
+}
+
+static RECORDER: Lazy<Option<Arc<Recorder>>> = Lazy::new(|| {
+    let dir = record_dir()?;
+    match Recorder::open(dir.clone()) {
+        Ok(r) => Some(Arc::new(r)),
+        Err(e) => {
+            warn!(dir = %dir.display(), error = %e, "kube recording disabled");
+            None
+        }
+    }
+});
+
+/// Tower layer recording exchanges when `ORKA_KUBE_RECORD_DIR` is set; a
+/// pass-through otherwise.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RecordLayer;
+
+impl<S> Layer<S> for RecordLayer {
+    type Service = RecordService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RecordService {
+            inner,
+            recorder: RECORDER.clone(),
+        }
+    }
+}
+
+pub struct RecordService<S> {
+    inner: S,
+    recorder: Option<Arc<Recorder>>,
+}
+
+/// Drain a request body that is already buffered. kube sends fully buffered
+/// request bodies with an exact size hint, so this completes without
+/// waiting. Bodies without one are left untouched and rejected; a body that
+/// stops short of its hint is rebuilt from what was read and reported as an
+/// error rather than passed off as complete.
+pub(crate) fn take_buffered<B>(body: &mut B) -> Result<Bytes>
+where
+    B: Body<Data = Bytes> + From<Bytes> + Unpin,
+{
+    let len = body
+        .size_hint()
+        .exact()
+        .ok_or_else(|| anyhow!("request body is streamed, not buffered"))?;
+    let mut cx = Context::from_waker(futures::task::noop_waker_ref());
+    let mut buf = Vec::with_capacity(len as usize);
+    let stopped = loop {
+        match Pin::new(&mut *body).poll_frame(&mut cx) {
+            Poll::Ready(Some(Ok(frame))) => {
+                if let Some(data) = frame.data_ref() {
+                    buf.extend_from_slice(data.chunk());
+                }
+            }
+            Poll::Ready(None) => break None,
+            Poll::Ready(Some(Err(_))) => break Some("failed"),
+            Poll::Pending => break Some("was not ready"),
+        }
+    };
+    let bytes = Bytes::from(buf);
+    *body = B::from(bytes.clone());
+    match stopped {
+        Some(why) => Err(anyhow!(
+            "request body {} after {} of {} bytes",
+            why,
+            bytes.len(),
+            len
+        )),
+        None if bytes.len() as u64 != len => Err(anyhow!(
+            "request body ended after {} of {} bytes",
+            bytes.len(),
+            len
+        )),
+        None => Ok(bytes),
+    }
+}
+
+impl<S, B, RB> Service<Request<B>> for RecordService<S>
+where
+    S: Service<Request<B>, Response = Response<RB>>,
+    S::Future: Send + 'static,
+    S::Error: Send + 'static,
+    B: Body<Data = Bytes> + From<Bytes> + Unpin,
+    RB: Body<Data = Bytes> + Unpin + Send + 'static,
+{
+    type Response = Response<RecordingBody<RB>>;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, S::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut req: Request<B>) -> Self::Future {
+        let pending = self.recorder.as_ref().and_then(|rec| {
+            let body = match take_buffered(req.body_mut()) {
+                Ok(body) => body,
+                Err(e) => {
+                    warn!(path = %req.uri().path(), error = %e, "kube request not recorded");
+                    return None;
+                }
+            };
+            let exchange = Exchange {
+                seq: rec.next_seq.fetch_add(1, Ordering::Relaxed),
+                method: req.method().to_string(),
+                path: req.uri().path().to_string(),
+                query: req.uri().query().unwrap_or("").to_string(),
+                request_body: (!body.is_empty())
+                    .then(|| String::from_utf8_lossy(&body).into_owned()),
+                status: 0,
+                content_type: None,
+                chunks: Vec::new(),
+            };
+            Some((rec.clone(), exchange))
+        });
+        let fut = self.inner.call(req);
+        Box::pin(async move {
+            let resp = fut.await?;
+            let pending = pending.map(|(recorder, mut exchange)| {
+                exchange.status = resp.status().as_u16();
+                exchange.content_type = resp
+                    .headers()
+                    .get(http::header::CONTENT_TYPE)
+                    .and_then(|v| v.to_str().ok())
+                    .map(|s| s.to_string());
+                PendingExchange { recorder, exchange }
+            });
+            Ok(resp.map(|inner| RecordingBody { inner, pending }))
+        })
+    }
+}
+
+struct PendingExchange {
+    recorder: Arc<Recorder>,
+    exchange: Exchange,
+}
+
+/// Response body wrapper that collects frames and writes the exchange once
+/// the body is finished or dropped (cancelled watches are kept too).
+pub struct RecordingBody<B> {
+    inner: B,
+    pending: Option<PendingExchange>,
+}
+
+impl<B> Body for RecordingBody<B>
+where
+    B: Body<Data = Bytes> + Unpin,
+{
+    type Data = Bytes;
+    type Error = B::Error;
+
+    fn poll_frame(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+        let polled = Pin::new(&mut self.inner).poll_frame(cx);
+        if let Poll::Ready(Some(Ok(frame))) = &polled {
+            if let (Some(data), Some(p)) = (frame.data_ref(), self.pending.as_mut()) {
+                p.exchange
+                    .chunks
+                    .push(String::from_utf8_lossy(data).into_owned());
+            }
+        }
+        if let Poll::Ready(None) = &polled {
+            if let Some(p) = self.pending.take() {
+                p.recorder.write(&p.exchange);
+            }
+        }
+        polled
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.inner.is_end_stream()
+    }
+
+    fn size_hint(&self) -> SizeHint {
+        self.inner.size_hint()
+    }
+}
+
+impl<B> Drop for RecordingBody<B> {
+    fn drop(&mut self) {
+        if let Some(p) = self.pending.take() {
+            p.recorder.write(&p.exchange);
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Replay
+// ---------------------------------------------------------------------------
+
+#[derive(Default)]
+struct Track {
+    exchanges: Vec<Exchange>,
+    next: usize,
+}
+
+/// Tower service answering requests from recorded exchanges.
+#[derive(Clone)]
+pub struct ReplayService {
+    tracks: Arc<Mutex<HashMap<String, Track>>>,
+}
+
+impl ReplayService {
+    pub fn new(exchanges: Vec<Exchange>) -> Self {
+        let mut tracks: HashMap<String, Track> = HashMap::new();
+        for ex in exchanges {
+            tracks
+                .entry(match_key(&ex.method, &ex.path, &ex.query))
+                .or_default()
+                .exchanges
+                .push(ex);
+        }
+        Self {
+            tracks: Arc::new(Mutex::new(tracks)),
+        }
+    }
+
+    pub fn load(dir: &Path) -> Result<Self> {
+        Ok(Self::new(load_fixtures(dir)?))
+    }
+
+    fn respond(&self, method: &str, path: &str, query: &str) -> Response<ReplayBody> {
+        let key = match_key(method, path, query);
+        let mut tracks = self.tracks.lock().unwrap();
+        let Some(track) = tracks.get_mut(&key).filter(|t| !t.exchanges.is_empty()) else {
+            return not_found(&key);
+        };
+        let idx = if track.next < track.exchanges.len() {
+            track.next += 1;
+            track.next - 1
+        } else if is_watch(query) {
+            // Keep exhausted watches open instead of spinning through reconnects.
+            return Response::new(ReplayBody {
+                chunks: VecDeque::new(),
+                hang: true,
+            });
+        } else {
+            track.exchanges.len() - 1
+        };
+        let ex = &track.exchanges[idx];
+        let mut resp = Response::new(ReplayBody {
+            chunks: ex.chunks.iter().map(|c| Bytes::from(c.clone())).collect(),
+            hang: false,
+        });
+        *resp.status_mut() = StatusCode::from_u16(ex.status).unwrap_or(StatusCode::OK);
+        if let Some(ct) = ex
+            .content_type
+            .as_deref()
+            .and_then(|ct| http::HeaderValue::from_str(ct).ok())
+        {
+            resp.headers_mut().insert(http::header::CONTENT_TYPE, ct);
+        }
+        resp
+    }
+}
+
+fn not_found(key: &str) -> Response<ReplayBody> {
+    let status = serde_json::json!({
+        "kind": "Status",
+        "apiVersion": "v1",
+        "metadata": {},
+        "status": "Failure",
+        "message": format!("no recorded fixture for {}", key),
+        "reason": "NotFound",
+        "code": 404
+    });
+    let mut resp = Response::new(ReplayBody {
+        chunks: VecDeque::from([Bytes::from(status.to_string())]),
+        hang: false,
+    });
+    *resp.status_mut() = StatusCode::NOT_FOUND;
+    resp.headers_mut().insert(
+        http::header::CONTENT_TYPE,
+        http::HeaderValue::from_static("application/json"),
+    );
+    resp
+}
+
+impl<B> Service<Request<B>> for ReplayService {
+    type Response = Response<ReplayBody>;
+    type Error = Infallible;
+    type Future = Ready<Result<Self::Response, Infallible>>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, req: Request<B>) -> Self::Future {
+        let uri = req.uri();
+        ready(Ok(self.respond(
+            req.method().as_str(),
+            uri.path(),
+            uri.query().unwrap_or(""),
+        )))
+    }
+}
+
+/// Recorded response frames; `hang` keeps the stream open once they run out.
+pub struct ReplayBody {
+    chunks: VecDeque<Bytes>,
+    hang: bool,
+}
+
+impl Body for ReplayBody {
+    type Data = Bytes;
+    type Error = Infallible;
+
+    fn poll_frame(
+        mut self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+        match self.chunks.pop_front() {
+            Some(c) => Poll::Ready(Some(Ok(Frame::data(c)))),
+            None if self.hang => Poll::Pending,
+            None => Poll::Ready(None),
+        }
+    }
+
+    fn is_end_stream(&self) -> bool {
+        !self.hang && self.chunks.is_empty()
+    }
+}
+
+/// Build a client that serves recorded fixtures from `dir`.
+pub fn replay_client(dir: &Path) -> Result<kube::Client> {
+    let svc = ReplayService::load(dir)?;
+    let svc = super::TrafficLayer::new("replay").layer(svc);
+    Ok(kube::Client::new(svc, "default"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::executor::block_on;
+    use http_body_util::BodyExt;
+
+    fn exchange(seq: u64, path: &str, query: &str, chunks: &[&str]) -> Exchange {
+        Exchange {
+            seq,
+            method: "GET".into(),
+            path: path.into(),
+            query: query.into(),
+            request_body: None,
+            status: 200,
+            content_type: Some("application/json".into()),
+            chunks: chunks.iter().map(|c| c.to_string()).collect(),
+        }
+    }
+
+    fn body_text(resp: Response<ReplayBody>) -> String {
+        let bytes = block_on(resp.into_body().collect()).unwrap().to_bytes();
+        String::from_utf8(bytes.to_vec()).unwrap()
+    }
+
+    fn get(svc: &mut ReplayService, uri: &str) -> Response<ReplayBody> {
+        let req = Request::get(uri).body(()).unwrap();
+        block_on(svc.call(req)).unwrap()
+    }
+
+    #[test]
+    fn volatile_params_are_ignored_when_matching() {
+        assert_eq!(
+            normalize_query("watch=true&resourceVersion=42&timeoutSeconds=290&labelSelector=a%3Db"),
+            "labelSelector=a%3Db&watch=true"
+        );
+        assert_eq!(normalize_query(""), "");
+    }
+
+    #[test]
+    fn replays_in_order_then_repeats_or_hangs() {
+        let mut svc = ReplayService::new(vec![
+            exchange(0, "/api/v1/pods", "limit=500", &["{\"items\":[1]}"]),
+            exchange(1, "/api/v1/pods", "limit=500", &["{\"items\":[2]}"]),
+            exchange(
+                2,
+                "/api/v1/pods",
+                "watch=true&resourceVersion=7",
+                &["{\"a\":1}\n", "{\"b\":2}\n"],
+            ),
+        ]);
+        assert_eq!(
+            body_text(get(&mut svc, "/api/v1/pods?limit=500")),
+            "{\"items\":[1]}"
+        );
+        assert_eq!(
+            body_text(get(&mut svc, "/api/v1/pods?limit=500")),
+            "{\"items\":[2]}"
+        );
+        // Exhausted non-watch requests repeat the last response.
+        assert_eq!(
+            body_text(get(&mut svc, "/api/v1/pods?limit=500")),
+            "{\"items\":[2]}"
+        );
+        let watch = get(&mut svc, "/api/v1/pods?resourceVersion=99&watch=true");
+        assert_eq!(body_text(watch), "{\"a\":1}\n{\"b\":2}\n");
+        let again = get(&mut svc, "/api/v1/pods?resourceVersion=100&watch=true");
+        assert!(!again.body().is_end_stream());
+        let missing = get(&mut svc, "/api/v1/secrets");
+        assert_eq!(missing.status(), StatusCode::NOT_FOUND);
+        assert!(body_text(missing).contains("GET /api/v1/secrets"));
+    }
+
+    #[test]
+    fn recorded_exchanges_replay() {
+        let dir = std::env::temp_dir().join(format!(
+            "orka-fixture-{}",
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap()
+                .as_nanos()
+        ));
+        let recorder = Arc::new(Recorder::open(dir.clone()).unwrap());
+        let upstream = ReplayService::new(vec![exchange(
+            0,
+            "/apis/apps/v1/namespaces/default/deployments",
+            "watch=true",
+            &["{\"type\":\"ADDED\"}\n", "{\"type\":\"MODIFIED\"}\n"],
+        )]);
+        let mut svc = RecordService {
+            inner: upstream,
+            recorder: Some(recorder),
+        };
+        let req = Request::get("/apis/apps/v1/namespaces/default/deployments?watch=true")
+            .body(http_body_util::Full::new(Bytes::new()))
+            .unwrap();
+        let resp = block_on(svc.call(req)).unwrap();
+        let body = block_on(resp.into_body().collect()).unwrap().to_bytes();
+        assert_eq!(
+            &body[..],
+            b"{\"type\":\"ADDED\"}\n{\"type\":\"MODIFIED\"}\n"
+        );
+
+        let recorded = load_fixtures(&dir).unwrap();
+        assert_eq!(recorded.len(), 1);
+        assert_eq!(recorded[0].status, 200);
+        assert_eq!(recorded[0].chunks.len(), 2);
+        let mut replay = ReplayService::new(recorded);
+        let again = get(
+            &mut replay,
+            "/apis/apps/v1/namespaces/default/deployments?watch=true&resourceVersion=5",
+        );
+        assert_eq!(
+            body_text(again),
+            "{\"type\":\"ADDED\"}\n{\"type\":\"MODIFIED\"}\n"
+        );
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    /// Body yielding its chunks, then `Pending` where `None` sits in the queue.
+    struct Stalling {
+        chunks: VecDeque<Option<Bytes>>,
+        hint: Option<u64>,
+    }
+
+    impl From<Bytes> for Stalling {
+        fn from(b: Bytes) -> Self {
+            Stalling {
+                hint: Some(b.len() as u64),
+                chunks: VecDeque::from([Some(b)]),
+            }
+        }
+    }
+
+    impl Body for Stalling {
+        type Data = Bytes;
+        type Error = Infallible;
+
+        fn poll_frame(
+            mut self: Pin<&mut Self>,
+            _cx: &mut Context<'_>,
+        ) -> Poll<Option<Result<Frame<Bytes>, Infallible>>> {
+            match self.chunks.pop_front() {
+                Some(Some(c)) => Poll::Ready(Some(Ok(Frame::data(c)))),
+                Some(None) => Poll::Pending,
+                None => Poll::Ready(None),
+            }
+        }
+
+        fn size_hint(&self) -> SizeHint {
+            self.hint.map(SizeHint::with_exact).unwrap_or_default()
+        }
+    }
+
+    #[test]
+    fn take_buffered_refuses_bodies_it_cannot_read_whole() {
+        let mut whole = http_body_util::Full::new(Bytes::from_static(b"{\"a\":1}"));
+        assert_eq!(&take_buffered(&mut whole).unwrap()[..], b"{\"a\":1}");
+        let rebuilt = block_on(whole.collect()).unwrap().to_bytes();
+        assert_eq!(&rebuilt[..], b"{\"a\":1}");
+
+        let mut streamed = Stalling {
+            chunks: VecDeque::from([Some(Bytes::from_static(b"{\"a\""))]),
+            hint: None,
+        };
+        let err = take_buffered(&mut streamed).unwrap_err();
+        assert!(err.to_string().contains("streamed"), "{err}");
+        // Left untouched for the inner service.
+        assert_eq!(streamed.chunks.len(), 1);
+
+        let mut stalled = Stalling {
+            chunks: VecDeque::from([Some(Bytes::from_static(b"{\"a\"")), None]),
+            hint: Some(7),
+        };
+        let err = take_buffered(&mut stalled).unwrap_err();
+        assert!(err.to_string().contains("4 of 7 bytes"), "{err}");
+    }
+}
@@ -22,19 +22,33 @@ use smallvec::SmallVec;
 use std::collections::HashMap;
 use std::fs;
 use std::path::PathBuf;
-use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::RwLock;
 use std::sync::RwLock as StdRwLock;
 use tokio::sync::mpsc;
 use tokio::sync::OnceCell;
 use uuid::Uuid;
 
+#[cfg(any(test, feature = "fake"))]
+pub mod fake;
+pub mod fixture;
+mod ratelimit;
+pub mod resolve;
+mod table;
+mod traffic;
+pub use fixture::{replay_client, RecordLayer};
+pub use ratelimit::{throttle_state, RateLimitLayer, ThrottleState};
+pub use resolve::{ResolveError, ResourceNames};
+pub use table::{decode_table_page, set_table_columns};
+pub use traffic::{traffic_breakdown, TrafficLayer, TrafficSample};
+
 // Reuse a single kube Client across this crate to avoid repeated setup costs.
 static KUBE_CLIENT: OnceCell<Client> = OnceCell::const_new();
 // Optional override client that can be swapped when the user selects a
 // different kubeconfig context. When set, this takes precedence over the
 // default `KUBE_CLIENT` above.
 static OVERRIDE_CLIENT: StdLazy<StdRwLock<Option<Client>>> = StdLazy::new(|| StdRwLock::new(None));
+// Name of the context selected via `set_context`.
+static OVERRIDE_CONTEXT: StdLazy<StdRwLock<Option<String>>> = StdLazy::new(|| StdRwLock::new(None));
 
 /// Get a kube client honoring the currently selected context if set.
 pub async fn get_kube_client() -> Result<Client> {
@@ -43,14 +57,33 @@ pub async fn get_kube_client() -> Result<Client> {
     }
     KUBE_CLIENT
         .get_or_try_init(|| async {
-            Client::try_default()
+            if let Some(dir) = fixture::replay_dir() {
+                info!(dir = %dir.display(), "kube: replaying recorded fixtures");
+                return replay_client(&dir);
+            }
+            let cfg = kube::Config::infer()
                 .await
-                .map_err(|e| anyhow!(e.to_string()))
+                .map_err(|e| anyhow!(e.to_string()))?;
+            let context = current_context()
+                .ok()
+                .flatten()
+                .unwrap_or_else(|| "default".to_string());
+            build_client(cfg, &context)
         })
         .await
         .cloned()
 }
 
+/// Build a kube client whose requests go through the shared rate limiter and
+/// are accounted under `context` (and recorded when `ORKA_KUBE_RECORD_DIR` is set).
+fn build_client(cfg: kube::Config, context: &str) -> Result<Client> {
+    Ok(kube::client::ClientBuilder::try_from(cfg)?
+        .with_layer(&RecordLayer)
+        .with_layer(&RateLimitLayer)
+        .with_layer(&TrafficLayer::new(context))
+        .build())
+}
+
 /// List kubeconfig contexts available to the current process.
 pub fn list_contexts() -> Result<Vec<String>> {
     use kube::config::Kubeconfig;
@@ -67,10 +100,27 @@ pub fn current_context() -> Result<Option<String>> {
     Ok(kc.current_context)
 }
 
+/// Name of the context requests go to: the one chosen with [`set_context`],
+/// else the kubeconfig's current context, else `"default"`.
+pub fn active_context() -> String {
+    if let Some(name) = OVERRIDE_CONTEXT.read().unwrap().as_ref() {
+        return name.clone();
+    }
+    current_context()
+        .ok()
+        .flatten()
+        .unwrap_or_else(|| "default".to_string())
+}
+
 /// Set the active kube client to use a specific kubeconfig context.
 /// Passing `None` clears the override and reverts to the default context.
 pub async fn set_context(context: Option<&str>) -> Result<()> {
     use kube::config::KubeConfigOptions;
+    if fixture::replay_dir().is_some() {
+        return Err(anyhow!(
+            "cannot switch context while replaying kube fixtures"
+        ));
+    }
     if let Some(name) = context {
         // Validate context name strictly and ensure it exists in kubeconfig
         let name = name.trim();
@@ -98,33 +148,62 @@ pub async fn set_context(context: Option<&str>) -> Result<()> {
             ..Default::default()
         };
         let cfg = kube::Config::from_kubeconfig(&opts).await?;
-        let client = Client::try_from(cfg)?;
+        let client = build_client(cfg, name)?;
         *OVERRIDE_CLIENT.write().unwrap() = Some(client);
+        *OVERRIDE_CONTEXT.write().unwrap() = Some(name.to_string());
     } else {
         *OVERRIDE_CLIENT.write().unwrap() = None;
+        *OVERRIDE_CONTEXT.write().unwrap() = None;
     }
     // Clear discovery cache so subsequent lookups are correct for the new cluster
     DISCOVERY_CACHE.write().unwrap().clear();
+    RESOURCE_NAMES.write().unwrap().clear();
+    table::clear_table_columns();
     Ok(())
 }
 
-// ---- Traffic Measurement (optional) ----
-static MEASURE_TRAFFIC: Lazy<bool> = Lazy::new(|| {
-    std::env::var("ORKA_MEASURE_TRAFFIC")
-        .ok()
-        .map(|v| v == "1" || v.eq_ignore_ascii_case("true"))
-        .unwrap_or(false)
-});
+/// Use `client` for all subsequent kubehub calls instead of the kubeconfig
+/// one (`None` restores it). Intended for tests and embedders that build
+/// their own client, e.g. against `fake::FakeApiServer`.
+pub fn use_client(client: Option<Client>) {
+    *OVERRIDE_CLIENT.write().unwrap() = client;
+    DISCOVERY_CACHE.write().unwrap().clear();
+    RESOURCE_NAMES.write().unwrap().clear();
+}
 
-static TRAFFIC_SNAPSHOT_BYTES: AtomicU64 = AtomicU64::new(0);
-static TRAFFIC_WATCH_BYTES: AtomicU64 = AtomicU64::new(0);
+// ---- Traffic Measurement ----
 
-/// Cumulative traffic bytes since process start (snapshot, watch).
+/// Cumulative response bytes since process start (list, watch), across all
+/// contexts and kinds. See [`traffic_breakdown`] for the labeled counters.
 pub fn traffic_bytes() -> (u64, u64) {
-    (
-        TRAFFIC_SNAPSHOT_BYTES.load(Ordering::Relaxed),
-        TRAFFIC_WATCH_BYTES.load(Ordering::Relaxed),
-    )
+    let mut list = 0u64;
+    let mut watch = 0u64;
+    for t in traffic_breakdown() {
+        match t.verb.as_str() {
+            "list" => list += t.rx_bytes,
+            "watch" => watch += t.rx_bytes,
+            _ => {}
+        }
+    }
+    (list, watch)
+}
+
+/// Traffic label for a request path's resource: the GVK key when the plural is
+/// known from discovery, else `group/version/plural`.
+pub(crate) fn gvk_label_for_plural(group: &str, version: &str, plural: &str) -> String {
+    let known = DISCOVERY_CACHE
+        .read()
+        .unwrap()
+        .values()
+        .find(|(ar, _)| ar.group == group && ar.version == version && ar.plural == plural)
+        .map(|(ar, _)| gvk_to_key(&GroupVersionKind::gvk(&ar.group, &ar.version, &ar.kind)));
+    known.unwrap_or_else(|| {
+        if group.is_empty() {
+            format!("{}/{}", version, plural)
+        } else {
+            format!("{}/{}/{}", group, version, plural)
+        }
+    })
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -150,6 +229,7 @@ pub async fn discover(_prefer_crd: bool) -> Result<Vec<DiscoveredResource>> {
     // Try to load discovery cache from disk first
     if let Some(entries) = load_discovery_cache().ok().flatten() {
         let mut out: Vec<DiscoveredResource> = Vec::with_capacity(entries.len());
+        let mut names: Vec<ResourceNames> = Vec::with_capacity(entries.len());
         for e in entries {
             // Rebuild ApiResource and seed cache for fast lookups
             let api_version = if e.group.is_empty() {
@@ -173,6 +253,15 @@ pub async fn discover(_prefer_crd: bool) -> Result<Vec<DiscoveredResource>> {
                 .write()
                 .unwrap()
                 .insert(key, (ar, e.namespaced));
+            names.push(ResourceNames {
+                group: e.group.clone(),
+                version: e.version.clone(),
+                kind: e.kind.clone(),
+                plural: e.plural.clone(),
+                singular: e.singular.clone(),
+                short_names: e.short_names.clone(),
+                namespaced: e.namespaced,
+            });
             out.push(DiscoveredResource {
                 group: e.group,
                 version: e.version,
@@ -180,6 +269,7 @@ pub async fn discover(_prefer_crd: bool) -> Result<Vec<DiscoveredResource>> {
                 namespaced: e.namespaced,
             });
         }
+        *RESOURCE_NAMES.write().unwrap() = names;
         // Stable-ish order
         out.sort_by(|a, b| {
             a.group
@@ -194,6 +284,16 @@ pub async fn discover(_prefer_crd: bool) -> Result<Vec<DiscoveredResource>> {
     let discovery = Discovery::new(client.clone()).run().await?;
     let mut out = Vec::new();
     let mut disk_entries: Vec<DiskEntry> = Vec::new();
+    let mut group_versions: Vec<(String, String)> = Vec::new();
+    for group in discovery.groups() {
+        for (ar, _caps) in group.recommended_resources() {
+            let gv = (ar.group.clone(), ar.version.clone());
+            if !group_versions.contains(&gv) {
+                group_versions.push(gv);
+            }
+        }
+    }
+    let extra_names = fetch_resource_names(&client, &group_versions).await;
     for group in discovery.groups() {
         for (ar, caps) in group.recommended_resources() {
             let namespaced = matches!(caps.scope, Scope::Namespaced);
@@ -213,15 +313,33 @@ pub async fn discover(_prefer_crd: bool) -> Result<Vec<DiscoveredResource>> {
                 kind: ar.kind.clone(),
                 namespaced,
             });
+            let (singular, short_names) = extra_names
+                .get(&(ar.group.clone(), ar.version.clone(), ar.kind.clone()))
+                .cloned()
+                .unwrap_or_default();
             disk_entries.push(DiskEntry {
                 group: ar.group.clone(),
                 version: ar.version.clone(),
                 kind: ar.kind.clone(),
                 plural: ar.plural.clone(),
+                singular,
+                short_names,
                 namespaced,
             });
         }
     }
+    *RESOURCE_NAMES.write().unwrap() = disk_entries
+        .iter()
+        .map(|e| ResourceNames {
+            group: e.group.clone(),
+            version: e.version.clone(),
+            kind: e.kind.clone(),
+            plural: e.plural.clone(),
+            singular: e.singular.clone(),
+            short_names: e.short_names.clone(),
+            namespaced: e.namespaced,
+        })
+        .collect();
     // Stable-ish order
     out.sort_by(|a, b| {
         a.group
@@ -253,6 +371,93 @@ fn parse_gvk_key(key: &str) -> Result<GroupVersionKind> {
     }
 }
 
+/// Fetch singular and short names per (group, version, kind); kube's `Discovery`
+/// does not surface them. Best-effort: failing group versions are skipped.
+async fn fetch_resource_names(
+    client: &Client,
+    group_versions: &[(String, String)],
+) -> HashMap<(String, String, String), (String, Vec<String>)> {
+    let lists = futures::future::join_all(group_versions.iter().map(|(g, v)| async move {
+        let res = if g.is_empty() {
+            client.list_core_api_resources(v).await
+        } else {
+            client
+                .list_api_group_resources(&format!("{}/{}", g, v))
+                .await
+        };
+        (g.clone(), v.clone(), res)
+    }))
+    .await;
+    let mut out = HashMap::new();
+    for (group, version, res) in lists {
+        let list = match res {
+            Ok(l) => l,
+            Err(e) => {
+                debug!(group = %group, version = %version, error = %e, "discovery: names fetch failed");
+                continue;
+            }
+        };
+        for r in list.resources {
+            // Skip subresources like pods/log
+            if r.name.contains('/') {
+                continue;
+            }
+            out.insert(
+                (group.clone(), version.clone(), r.kind.clone()),
+                (r.singular_name, r.short_names.unwrap_or_default()),
+            );
+        }
+    }
+    out
+}
+
+// Names of served resources for kubectl-style resolution (plural/singular/short names).
+static RESOURCE_NAMES: Lazy<RwLock<Vec<ResourceNames>>> = Lazy::new(|| RwLock::new(Vec::new()));
+
+/// Resolve user input to a served resource. Accepts strict keys (`v1/Pod`,
+/// `apps/v1/Deployment`) as well as kubectl-style names (`pods`, `po`,
+/// `deploy`, `deployments.apps`). Ambiguous or unknown names fail with a
+/// [`ResolveError`] listing candidates.
+pub async fn resolve_resource(input: &str) -> Result<ResourceNames> {
+    if input.contains('/') {
+        let gvk = parse_gvk_key(input)?;
+        let client = get_kube_client().await?;
+        let (ar, namespaced) = find_api_resource(client, &gvk).await?;
+        let known = RESOURCE_NAMES
+            .read()
+            .unwrap()
+            .iter()
+            .find(|n| n.group == ar.group && n.version == ar.version && n.kind == ar.kind)
+            .cloned();
+        return Ok(known.unwrap_or(ResourceNames {
+            group: ar.group,
+            version: ar.version,
+            kind: ar.kind,
+            plural: ar.plural,
+            singular: String::new(),
+            short_names: Vec::new(),
+            namespaced,
+        }));
+    }
+    if RESOURCE_NAMES.read().unwrap().is_empty() {
+        discover(false).await?;
+    }
+    let names = RESOURCE_NAMES.read().unwrap();
+    let found = resolve::resolve(&names, input)?;
+    debug!(input = %input, gvk = %found.gvk_key(), "resolve: name resolved");
+    Ok(found.clone())
+}
+
+/// Resolve user input (strict key or kubectl-style name) to a canonical GVK key.
+pub async fn resolve_gvk_key(input: &str) -> Result<String> {
+    if input.contains('/') {
+        // Strict keys pass through untouched; lookups validate them later.
+        let gvk = parse_gvk_key(input)?;
+        return Ok(gvk_to_key(&gvk));
+    }
+    Ok(resolve_resource(input).await?.gvk_key())
+}
+
 // Discovery cache: GVK key -> (ApiResource, namespaced)
 static DISCOVERY_CACHE: Lazy<RwLock<HashMap<String, (kube::core::ApiResource, bool)>>> =
     Lazy::new(|| RwLock::new(HashMap::new()));
@@ -275,33 +480,60 @@ async fn find_api_resource(
         debug!(gvk = %key, namespaced = ns, "discovery: cache hit");
         return Ok((ar, ns));
     }
-    // Miss: run discovery and populate cache
-    let discovery = Discovery::new(client).run().await?;
-    for group in discovery.groups() {
-        for (ar, caps) in group.recommended_resources() {
-            if ar.group == gvk.group && ar.version == gvk.version && ar.kind == gvk.kind {
-                let namespaced = matches!(caps.scope, Scope::Namespaced);
-                DISCOVERY_CACHE
-                    .write()
-                    .unwrap()
-                    .insert(key.clone(), (ar.clone(), namespaced));
-                debug!(gvk = %key, namespaced = namespaced, "discovery: cache miss (populated)");
-                return Ok((ar.clone(), namespaced));
-            }
-        }
+    // Miss: ask only this group/version and populate cache
+    let (ar, caps) = kube::discovery::pinned_kind(&client, gvk)
+        .await
+        .map_err(|e| anyhow!("GVK not found: {}: {}", key, e))?;
+    let namespaced = matches!(caps.scope, Scope::Namespaced);
+    DISCOVERY_CACHE
+        .write()
+        .unwrap()
+        .insert(key.clone(), (ar.clone(), namespaced));
+    debug!(gvk = %key, namespaced = namespaced, "discovery: cache miss (populated)");
+    Ok((ar, namespaced))
+}
+
+/// Resolve a versionless kind in `group` (`""` for core) at the group's
+/// preferred version. Served from the discovery cache when any version is
+/// cached, else by discovering just that group. `None` when the group or
+/// kind is not served.
+pub async fn find_group_kind(
+    group: &str,
+    kind: &str,
+) -> Result<Option<(kube::core::ApiResource, bool)>> {
+    let cached = DISCOVERY_CACHE
+        .read()
+        .unwrap()
+        .values()
+        .find(|(ar, _)| ar.group == group && ar.kind == kind)
+        .cloned();
+    if cached.is_some() {
+        return Ok(cached);
     }
-    Err(anyhow!(
-        "GVK not found: {}/{}/{}",
-        gvk.group,
-        gvk.version,
-        gvk.kind
-    ))
+    let client = get_kube_client().await?;
+    let api_group = match kube::discovery::group(&client, group).await {
+        Ok(g) => g,
+        Err(kube::Error::Discovery(kube::error::DiscoveryError::MissingApiGroup(_))) => {
+            return Ok(None)
+        }
+        Err(e) => return Err(e.into()),
+    };
+    let Some((ar, caps)) = api_group.recommended_kind(kind) else {
+        return Ok(None);
+    };
+    let namespaced = matches!(caps.scope, Scope::Namespaced);
+    let key = gvk_to_key(&GroupVersionKind::gvk(&ar.group, &ar.version, &ar.kind));
+    DISCOVERY_CACHE
+        .write()
+        .unwrap()
+        .insert(key, (ar.clone(), namespaced));
+    Ok(Some((ar, namespaced)))
 }
 
 /// Expose cached discovery for external callers (API crate).
 pub async fn get_api_resource(gvk_key: &str) -> Result<(kube::core::ApiResource, bool)> {
     let client = get_kube_client().await?;
-    let gvk = parse_gvk_key(gvk_key)?;
+    let gvk = parse_gvk_key(&resolve_gvk_key(gvk_key).await?)?;
     find_api_resource(client, &gvk).await
 }
 
@@ -339,7 +571,7 @@ pub async fn start_watcher(
     delta_tx: mpsc::Sender<Delta>,
 ) -> Result<()> {
     let client = get_kube_client().await?;
-    let gvk = parse_gvk_key(gvk_key)?;
+    let gvk = parse_gvk_key(&resolve_gvk_key(gvk_key).await?)?;
     let (ar, namespaced) = find_api_resource(client.clone(), &gvk).await?;
 
     // Periodic relist interval (seconds)
@@ -393,7 +625,6 @@ pub async fn start_watcher(
                 maybe_ev = stream.try_next() => {
                     match maybe_ev {
                         Ok(Some(Event::Applied(o))) => {
-                            if *MEASURE_TRAFFIC { if let Ok(b) = serde_json::to_vec(&o) { TRAFFIC_WATCH_BYTES.fetch_add(b.len() as u64, Ordering::Relaxed); } }
                             let d = delta_from(&o, DeltaKind::Applied)?;
                             if delta_tx.send(d).await.is_err() {
                                 info!("delta channel closed; stopping watcher");
@@ -401,7 +632,6 @@ pub async fn start_watcher(
                             }
                         }
                         Ok(Some(Event::Deleted(o))) => {
-                            if *MEASURE_TRAFFIC { if let Ok(b) = serde_json::to_vec(&o) { TRAFFIC_WATCH_BYTES.fetch_add(b.len() as u64, Ordering::Relaxed); } }
                             let d = delta_from(&o, DeltaKind::Deleted)?;
                             if delta_tx.send(d).await.is_err() {
                                 info!("delta channel closed; stopping watcher");
@@ -411,7 +641,6 @@ pub async fn start_watcher(
                         Ok(Some(Event::Restarted(list))) => {
                             debug!(count = list.len(), "watch restart");
                             for o in list.iter() {
-                                if *MEASURE_TRAFFIC { if let Ok(b) = serde_json::to_vec(o) { TRAFFIC_WATCH_BYTES.fetch_add(b.len() as u64, Ordering::Relaxed); } }
                                 let d = delta_from(o, DeltaKind::Applied)?;
                                 if delta_tx.send(d).await.is_err() {
                                     info!("delta channel closed; stopping watcher");
@@ -475,7 +704,7 @@ pub async fn prime_list(
     delta_tx: &mpsc::Sender<Delta>,
 ) -> Result<usize> {
     let client = get_kube_client().await?;
-    let gvk = parse_gvk_key(gvk_key)?;
+    let gvk = parse_gvk_key(&resolve_gvk_key(gvk_key).await?)?;
     let (ar, namespaced) = find_api_resource(client.clone(), &gvk).await?;
 
     let api: Api<DynamicObject> = if namespaced {
@@ -508,11 +737,6 @@ pub async fn prime_list(
         let page_items = list.items.len();
         let next_token = list.metadata.continue_.clone();
         for o in list.items {
-            if *MEASURE_TRAFFIC {
-                if let Ok(b) = serde_json::to_vec(&o) {
-                    TRAFFIC_SNAPSHOT_BYTES.fetch_add(b.len() as u64, Ordering::Relaxed);
-                }
-            }
             let d = delta_from(&o, DeltaKind::Applied)?;
             if delta_tx.send(d).await.is_ok() {
                 sent += 1;
@@ -545,7 +769,7 @@ pub enum LiteEvent {
 /// Used for fast snapshots on built-in kinds where we only need Lite fields.
 pub async fn list_lite(gvk_key: &str, namespace: Option<&str>) -> Result<Vec<orka_core::LiteObj>> {
     let client = get_kube_client().await?;
-    let gvk = parse_gvk_key(gvk_key)?;
+    let gvk = parse_gvk_key(&resolve_gvk_key(gvk_key).await?)?;
     let (ar, namespaced) = find_api_resource(client.clone(), &gvk).await?;
 
     let api: Api<DynamicObject> = if namespaced {
@@ -561,6 +785,12 @@ pub async fn list_lite(gvk_key: &str, namespace: Option<&str>) -> Result<Vec<ork
         .ok()
         .and_then(|s| s.parse::<u32>().ok())
         .unwrap_or(500);
+    if table_list_enabled() {
+        match list_lite_table(&client, &ar, namespaced, namespace, &gvk, page_limit).await {
+            Ok(items) => return Ok(items),
+            Err(e) => debug!(error = %e, "list_lite: table list failed; falling back to JSON"),
+        }
+    }
     let projector = orka_core::columns::builtin_projector_for(&gvk.group, &gvk.version, &gvk.kind);
     let mut out: Vec<orka_core::LiteObj> = Vec::new();
     let mut continue_token: Option<String> = None;
@@ -576,11 +806,6 @@ pub async fn list_lite(gvk_key: &str, namespace: Option<&str>) -> Result<Vec<ork
         let list = api.list(&params).await?;
         let page_ms = l0.elapsed().as_millis() as f64;
         for o in list.items.iter() {
-            if *MEASURE_TRAFFIC {
-                if let Ok(b) = serde_json::to_vec(o) {
-                    TRAFFIC_SNAPSHOT_BYTES.fetch_add(b.len() as u64, Ordering::Relaxed);
-                }
-            }
             let mut lo = lite_from_dynamic(o)?;
             if let Some(p) = projector.as_ref() {
                 let enabled = std::env::var("ORKA_LITE_PROJECT")
@@ -614,7 +839,7 @@ pub async fn list_lite_first_page(
     namespace: Option<&str>,
 ) -> Result<Vec<orka_core::LiteObj>> {
     let client = get_kube_client().await?;
-    let gvk = parse_gvk_key(gvk_key)?;
+    let gvk = parse_gvk_key(&resolve_gvk_key(gvk_key).await?)?;
     let (ar, namespaced) = find_api_resource(client.clone(), &gvk).await?;
 
     let api: Api<DynamicObject> = if namespaced {
@@ -630,6 +855,18 @@ pub async fn list_lite_first_page(
         .ok()
         .and_then(|s| s.parse::<u32>().ok())
         .unwrap_or(500);
+    if table_list_enabled() {
+        let l0 = std::time::Instant::now();
+        match table_page(&client, &ar, namespaced, namespace, &gvk, page_limit, None).await {
+            Ok((items, _)) => {
+                histogram!("list_lite_first_page_ms", l0.elapsed().as_millis() as f64);
+                return Ok(items);
+            }
+            Err(e) => {
+                debug!(error = %e, "list_lite_first_page: table list failed; falling back to JSON")
+            }
+        }
+    }
     let mut params = kube::api::ListParams::default();
     if page_limit > 0 {
         params = params.limit(page_limit);
@@ -640,11 +877,6 @@ pub async fn list_lite_first_page(
     let projector = orka_core::columns::builtin_projector_for(&gvk.group, &gvk.version, &gvk.kind);
     let mut out: Vec<orka_core::LiteObj> = Vec::with_capacity(list.items.len());
     for o in list.items.iter() {
-        if *MEASURE_TRAFFIC {
-            if let Ok(b) = serde_json::to_vec(o) {
-                TRAFFIC_SNAPSHOT_BYTES.fetch_add(b.len() as u64, Ordering::Relaxed);
-            }
-        }
         let mut lo = lite_from_dynamic(o)?;
         if let Some(p) = projector.as_ref() {
             let enabled = std::env::var("ORKA_LITE_PROJECT")
@@ -665,6 +897,79 @@ pub async fn list_lite_first_page(
     Ok(out)
 }
 
+fn table_list_enabled() -> bool {
+    std::env::var("ORKA_LIST_TABLE")
+        .ok()
+        .map(|v| v == "1" || v.eq_ignore_ascii_case("true"))
+        .unwrap_or(false)
+}
+
+/// Fetch one list page rendered server-side as a Table (printer columns only).
+async fn table_page(
+    client: &Client,
+    ar: &kube::core::ApiResource,
+    namespaced: bool,
+    namespace: Option<&str>,
+    gvk: &GroupVersionKind,
+    page_limit: u32,
+    continue_token: Option<&str>,
+) -> Result<(Vec<orka_core::LiteObj>, Option<String>)> {
+    use kube::Resource;
+    let ns = if namespaced { namespace } else { None };
+    let url = DynamicObject::url_path(ar, ns);
+    let mut params = kube::api::ListParams::default();
+    if page_limit > 0 {
+        params = params.limit(page_limit);
+    }
+    if let Some(token) = continue_token {
+        params = params.continue_token(token);
+    }
+    let mut req = kube::core::Request::new(url).list(&params)?;
+    req.headers_mut().insert(
+        http::header::ACCEPT,
+        http::HeaderValue::from_static(table::TABLE_ACCEPT),
+    );
+    let body = client.request_text(req).await?;
+    table::decode_table_page(body.as_bytes(), gvk)
+}
+
+/// Paginated Table list; see [`table_page`].
+async fn list_lite_table(
+    client: &Client,
+    ar: &kube::core::ApiResource,
+    namespaced: bool,
+    namespace: Option<&str>,
+    gvk: &GroupVersionKind,
+    page_limit: u32,
+) -> Result<Vec<orka_core::LiteObj>> {
+    let mut out: Vec<orka_core::LiteObj> = Vec::new();
+    let mut continue_token: Option<String> = None;
+    loop {
+        let l0 = std::time::Instant::now();
+        let (items, next) = table_page(
+            client,
+            ar,
+            namespaced,
+            namespace,
+            gvk,
+            page_limit,
+            continue_token.as_deref(),
+        )
+        .await?;
+        counter!("snapshot_pages_total", 1u64);
+        counter!("list_lite_table_pages_total", 1u64);
+        histogram!("snapshot_page_items", items.len() as f64);
+        histogram!("list_lite_page_ms", l0.elapsed().as_millis() as f64);
+        out.extend(items);
+        continue_token = next;
+        if continue_token.is_none() {
+            break;
+        }
+        tokio::task::yield_now().await;
+    }
+    Ok(out)
+}
+
 fn to_uid_fast(uid_str: &str) -> Result<orka_core::Uid> {
     let u = Uuid::parse_str(uid_str).context("parsing metadata.uid as uuid")?;
     Ok(*u.as_bytes())
@@ -727,18 +1032,52 @@ pub async fn start_watcher_lite(
     evt_tx: mpsc::Sender<LiteEvent>,
 ) -> Result<()> {
     let client = get_kube_client().await?;
-    let gvk = parse_gvk_key(gvk_key)?;
+    let gvk = parse_gvk_key(&resolve_gvk_key(gvk_key).await?)?;
     let (ar, namespaced) = find_api_resource(client.clone(), &gvk).await?;
     start_watcher_lite_with(client, ar, namespaced, namespace, evt_tx).await
 }
 
 /// Variant that skips discovery when ApiResource is already known.
+/// Picks metadata-only mode automatically (see [`lite_watch_metadata_only`]).
 pub async fn start_watcher_lite_with(
     client: Client,
     ar: kube::core::ApiResource,
     namespaced: bool,
     namespace: Option<&str>,
     evt_tx: mpsc::Sender<LiteEvent>,
+) -> Result<()> {
+    let metadata_only = lite_watch_metadata_only(&ar);
+    start_watcher_lite_with_mode(client, ar, namespaced, namespace, metadata_only, evt_tx).await
+}
+
+/// Whether a lite watch can request `PartialObjectMetadata` instead of full
+/// objects. Disable with `ORKA_WATCH_METADATA=0`.
+pub fn lite_watch_metadata_only(ar: &kube::core::ApiResource) -> bool {
+    let enabled = std::env::var("ORKA_WATCH_METADATA")
+        .ok()
+        .map(|v| v == "1" || v.eq_ignore_ascii_case("true"))
+        .unwrap_or(true);
+    metadata_only_mode(enabled, ar)
+}
+
+/// Only the built-in projector reads the object body of a lite watch. Search
+/// `field:` filters match `LiteObj.projected`, which that projector alone
+/// fills, so a kind without one has nothing a `field:` filter could match and
+/// metadata is all it needs.
+fn metadata_only_mode(enabled: bool, ar: &kube::core::ApiResource) -> bool {
+    enabled && orka_core::columns::builtin_projector_for(&ar.group, &ar.version, &ar.kind).is_none()
+}
+
+/// Lite watcher with an explicit payload mode. With `metadata_only`, only
+/// object metadata (names, labels, annotations, owners, timestamps) travels;
+/// `LiteObj.projected` stays empty.
+pub async fn start_watcher_lite_with_mode(
+    client: Client,
+    ar: kube::core::ApiResource,
+    namespaced: bool,
+    namespace: Option<&str>,
+    metadata_only: bool,
+    evt_tx: mpsc::Sender<LiteEvent>,
 ) -> Result<()> {
     // Interval and backoff from env for parity
     let relist_secs: u64 = std::env::var("ORKA_RELIST_SECS")
@@ -764,7 +1103,12 @@ pub async fn start_watcher_lite_with(
         };
 
         let cfg = watcher::Config::default();
-        let stream = watcher::watcher(api, cfg);
+        let stream: futures::stream::BoxStream<'static, watcher::Result<Event<DynamicObject>>> =
+            if metadata_only {
+                futures::StreamExt::boxed(watcher::metadata_watcher(api, cfg).map_ok(meta_event))
+            } else {
+                futures::StreamExt::boxed(watcher::watcher(api, cfg))
+            };
         futures::pin_mut!(stream);
         // Jittered relist
         let jitter = ((relist_secs as f64) * 0.1) as i64;
@@ -781,15 +1125,19 @@ pub async fn start_watcher_lite_with(
         let relist_actual = (relist_secs as i64 + jval).max(1) as u64;
         let relist_timer = tokio::time::sleep(std::time::Duration::from_secs(relist_actual));
         tokio::pin!(relist_timer);
-        info!(relist_actual, "lite watch stream opened");
+        info!(relist_actual, metadata_only, "lite watch stream opened");
 
         let projector = orka_core::columns::builtin_projector_for(&ar.group, &ar.version, &ar.kind);
+        // Pod labels are kept even without ORKA_LIST_ENRICH: apply policy
+        // checks match Service selectors against the watched pods.
+        let pod_labels = ar.group.is_empty() && ar.kind == "Pod";
         let ended = loop {
             tokio::select! {
                 maybe_ev = stream.try_next() => {
                     match maybe_ev {
                         Ok(Some(Event::Applied(o))) => {
                             let mut lo = lite_from_dynamic(&o)?;
+                            if pod_labels { keep_labels(&mut lo, &o); }
                             if let Some(p) = projector.as_ref() {
                                 let enabled = std::env::var("ORKA_LITE_PROJECT").ok().map(|v| v == "1" || v.eq_ignore_ascii_case("true")).unwrap_or(true);
                                 if enabled {
@@ -814,6 +1162,7 @@ pub async fn start_watcher_lite_with(
                             debug!(count = list.len(), "lite watch restart");
                             for o in list.iter() {
                                 let mut lo = lite_from_dynamic(o)?;
+                                if pod_labels { keep_labels(&mut lo, o); }
                                 if let Some(p) = projector.as_ref() {
                                     let enabled = std::env::var("ORKA_LITE_PROJECT").ok().map(|v| v == "1" || v.eq_ignore_ascii_case("true")).unwrap_or(true);
                                     if enabled {
@@ -849,6 +1198,34 @@ pub async fn start_watcher_lite_with(
     }
 }
 
+/// Fill `lo.labels` from the object when shaping skipped them.
+fn keep_labels(lo: &mut orka_core::LiteObj, o: &DynamicObject) {
+    if lo.labels.is_empty() {
+        if let Some(labels) = &o.metadata.labels {
+            lo.labels = labels
+                .iter()
+                .take(128)
+                .map(|(k, v)| (k.clone(), v.clone()))
+                .collect();
+        }
+    }
+}
+
+/// Carry a metadata-only event as a body-less DynamicObject so the lite
+/// shaping path stays shared.
+fn meta_event(ev: Event<kube::core::PartialObjectMeta<DynamicObject>>) -> Event<DynamicObject> {
+    let to_dyn = |p: kube::core::PartialObjectMeta<DynamicObject>| DynamicObject {
+        types: None,
+        metadata: p.metadata,
+        data: serde_json::Value::Null,
+    };
+    match ev {
+        Event::Applied(p) => Event::Applied(to_dyn(p)),
+        Event::Deleted(p) => Event::Deleted(to_dyn(p)),
+        Event::Restarted(list) => Event::Restarted(list.into_iter().map(to_dyn).collect()),
+    }
+}
+
 // -------- Discovery Disk Cache (best-effort) --------
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -857,11 +1234,22 @@ struct DiskEntry {
     version: String,
     kind: String,
     plural: String,
+    #[serde(default)]
+    singular: String,
+    #[serde(default)]
+    short_names: Vec<String>,
     namespaced: bool,
 }
 
+/// Bump when [`DiskEntry`] gains data older caches lack; such caches are
+/// then rediscovered instead of used.
+const DISK_CACHE_FORMAT: u32 = 2;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 struct DiskCache {
+    /// Missing (0) in caches written before names were recorded.
+    #[serde(default)]
+    format: u32,
     generated_at: u64,
     entries: Vec<DiskEntry>,
 }
@@ -904,6 +1292,9 @@ fn load_discovery_cache() -> Result<Option<Vec<DiskEntry>>> {
     if now.saturating_sub(dc.generated_at) > cache_ttl_secs() {
         return Ok(None);
     }
+    if dc.format != DISK_CACHE_FORMAT {
+        return Ok(None);
+    }
     Ok(Some(dc.entries))
 }
 
@@ -915,6 +1306,7 @@ fn save_discovery_cache(entries: &[DiskEntry]) -> Result<()> {
     let mut finalp = dir;
     finalp.push("default.json");
     let dc = DiskCache {
+        format: DISK_CACHE_FORMAT,
         generated_at: std::time::SystemTime::now()
             .duration_since(std::time::UNIX_EPOCH)
             .unwrap_or_default()
@@ -926,3 +1318,170 @@ fn save_discovery_cache(entries: &[DiskEntry]) -> Result<()> {
     fs::rename(&tmp, &finalp).context("rename discovery cache")?;
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fake::FakeApiServer;
+    use std::time::Duration;
+
+    async fn next_delta(rx: &mut mpsc::Receiver<Delta>) -> Delta {
+        tokio::time::timeout(Duration::from_secs(10), rx.recv())
+            .await
+            .expect("delta within timeout")
+            .expect("watcher still running")
+    }
+
+    #[tokio::test]
+    async fn start_watcher_relists_after_410() {
+        let srv = FakeApiServer::new();
+        let _guard = srv.install();
+        srv.create(serde_json::json!({
+            "apiVersion": "v1",
+            "kind": "ConfigMap",
+            "metadata": {"name": "a", "namespace": "default"},
+            "data": {"k": "1"}
+        }));
+        let (tx, mut rx) = mpsc::channel(16);
+        let task = tokio::spawn(async move { start_watcher("v1/ConfigMap", None, tx).await });
+        assert_eq!(next_delta(&mut rx).await.raw["metadata"]["name"], "a");
+        while srv.watch_count() == 0 {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+
+        let lists = |srv: &FakeApiServer| {
+            srv.requests()
+                .iter()
+                .filter(|r| r.starts_with("GET /api/v1/configmaps") && !r.contains("watch=true"))
+                .count()
+        };
+        let lists_before = lists(&srv);
+        // Compaction drops the history; a change made while the watch is
+        // expired must still arrive through the recovery relist.
+        srv.expire_watches();
+        srv.create(serde_json::json!({
+            "apiVersion": "v1",
+            "kind": "ConfigMap",
+            "metadata": {"name": "b", "namespace": "default"}
+        }));
+        let mut seen = Vec::new();
+        while !seen.contains(&"b".to_string()) {
+            let d = next_delta(&mut rx).await;
+            seen.push(d.raw["metadata"]["name"].as_str().unwrap_or("").to_string());
+        }
+        assert!(lists(&srv) > lists_before, "expected a relist after 410");
+        task.abort();
+    }
+
+    #[tokio::test]
+    async fn api_resource_lookups_query_a_single_group() {
+        let srv = FakeApiServer::new();
+        let _guard = srv.install();
+        let (ar, namespaced) = get_api_resource("apps/v1/Deployment").await.unwrap();
+        assert_eq!((ar.plural.as_str(), namespaced), ("deployments", true));
+        assert_eq!(srv.count_requests("GET", "/apis/apps/v1"), 1);
+        assert!(!srv.requests().iter().any(|r| r == "GET /apis"));
+
+        // Versionless lookups reuse the cache, then discover just the group.
+        let (ar, _) = find_group_kind("apps", "Deployment")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(ar.version, "v1");
+        assert_eq!(srv.count_requests("GET", "/apis/apps/v1"), 1);
+        let (ar, namespaced) = find_group_kind("", "Namespace").await.unwrap().unwrap();
+        assert_eq!((ar.plural.as_str(), namespaced), ("namespaces", false));
+        assert!(find_group_kind("example.com", "Widget")
+            .await
+            .unwrap()
+            .is_none());
+        assert!(find_group_kind("apps", "Widget").await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn discovery_cache_is_versioned_not_guessed_from_names() {
+        let _guard = FakeApiServer::new().install();
+        // Older clusters report no singular names; such a cache is still valid.
+        let entry = DiskEntry {
+            group: "apps".into(),
+            version: "v1".into(),
+            kind: "Deployment".into(),
+            plural: "deployments".into(),
+            singular: String::new(),
+            short_names: Vec::new(),
+            namespaced: true,
+        };
+        save_discovery_cache(std::slice::from_ref(&entry)).unwrap();
+        let loaded = load_discovery_cache()
+            .unwrap()
+            .expect("current format is used");
+        assert_eq!(loaded[0].plural, "deployments");
+
+        // A cache without a format field predates name resolution.
+        let mut old: serde_json::Value =
+            serde_json::from_slice(&fs::read(cache_file()).unwrap()).unwrap();
+        old.as_object_mut().unwrap().remove("format");
+        old["entries"][0]["singular"] = "deployment".into();
+        fs::write(cache_file(), serde_json::to_vec(&old).unwrap()).unwrap();
+        assert!(load_discovery_cache().unwrap().is_none());
+    }
+
+    #[test]
+    fn metadata_only_for_kinds_without_projector() {
+        let ar = |g: &str, v: &str, k: &str| {
+            kube::core::ApiResource::from_gvk(&kube::core::GroupVersionKind::gvk(g, v, k))
+        };
+        assert!(metadata_only_mode(true, &ar("", "v1", "Secret")));
+        assert!(metadata_only_mode(true, &ar("", "v1", "ConfigMap")));
+        assert!(metadata_only_mode(true, &ar("example.com", "v1", "Widget")));
+        // Projected kinds need the body for their columns (and `field:` filters).
+        assert!(!metadata_only_mode(true, &ar("", "v1", "Pod")));
+        assert!(!metadata_only_mode(true, &ar("apps", "v1", "Deployment")));
+        // ORKA_WATCH_METADATA=0 turns it off everywhere.
+        assert!(!metadata_only_mode(false, &ar("", "v1", "Secret")));
+    }
+
+    #[test]
+    fn meta_event_carries_metadata_without_body() {
+        let meta = |name: &str| -> kube::core::PartialObjectMeta<DynamicObject> {
+            serde_json::from_value(serde_json::json!({
+                "apiVersion": "meta.k8s.io/v1",
+                "kind": "PartialObjectMetadata",
+                "metadata": {
+                    "name": name,
+                    "namespace": "default",
+                    "uid": "0d2f5c3e-9b1a-4c1e-8f00-000000000001",
+                    "labels": {"app": "web"},
+                    "creationTimestamp": "2024-01-01T00:00:00Z"
+                }
+            }))
+            .unwrap()
+        };
+        let Event::Applied(o) = meta_event(Event::Applied(meta("a"))) else {
+            panic!("expected Applied");
+        };
+        assert!(o.data.is_null());
+        assert_eq!(
+            o.metadata.labels.as_ref().and_then(|l| l.get("app")),
+            Some(&"web".to_string())
+        );
+        let lo = lite_from_dynamic(&o).unwrap();
+        assert_eq!(lo.name, "a");
+        assert_eq!(lo.namespace.as_deref(), Some("default"));
+        assert_eq!(lo.creation_ts, 1_704_067_200);
+        assert!(lo.projected.is_empty());
+
+        assert!(
+            matches!(meta_event(Event::Deleted(meta("a"))), Event::Deleted(d) if d.metadata.name.as_deref() == Some("a"))
+        );
+        let Event::Restarted(list) = meta_event(Event::Restarted(vec![meta("a"), meta("b")]))
+        else {
+            panic!("expected Restarted");
+        };
+        let names: Vec<_> = list
+            .iter()
+            .filter_map(|o| o.metadata.name.as_deref())
+            .collect();
+        assert_eq!(names, vec!["a", "b"]);
+    }
+}
@@ -0,0 +1,307 @@
+//! Client-side rate limiting for kube API traffic.
+//!
+//! One token bucket (`ORKA_KUBE_QPS` / `ORKA_KUBE_BURST`) is shared by every
+//! client built through this crate, so lists, watches, details prefetch and
+//! prewarm all draw from the same budget. A 429 from API Priority and Fairness
+//! pauses the bucket for the server's `Retry-After` and halves the effective
+//! rate; successful responses restore it step by step.
+
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Mutex;
+use std::task::{ready, Context, Poll};
+use std::time::{Duration, Instant};
+
+use http::{header::RETRY_AFTER, HeaderMap, Request, Response, StatusCode};
+use metrics::{counter, histogram};
+use once_cell::sync::Lazy;
+use tower::{Layer, Service};
+use tracing::warn;
+
+const DEFAULT_QPS: u32 = 50;
+const DEFAULT_BURST: u32 = 100;
+// Effective rate never drops below this fraction of the configured QPS.
+const MIN_FACTOR: f64 = 0.1;
+// Additive recovery per successful response after a slowdown.
+const RECOVERY_STEP: f64 = 0.02;
+// Pause used when a 429 carries no usable Retry-After.
+const DEFAULT_PAUSE: Duration = Duration::from_secs(1);
+const MAX_PAUSE: Duration = Duration::from_secs(60);
+
+/// Current limiter state, for stats and UI.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ThrottleState {
+    pub qps: u32,
+    pub burst: u32,
+    /// Effective rate as a percentage of `qps` (100 unless slowed down by 429s).
+    pub effective_pct: u32,
+    pub throttled_total: u64,
+    /// Remaining pause requested by the server via Retry-After.
+    pub backoff_ms: u64,
+    /// Total time requests spent waiting for a token.
+    pub wait_ms_total: u64,
+}
+
+struct Bucket {
+    qps: u32,
+    burst: u32,
+    tokens: f64,
+    last: Instant,
+    factor: f64,
+    paused_until: Option<Instant>,
+    throttled_total: u64,
+    wait_ms_total: u64,
+}
+
+impl Bucket {
+    fn new(qps: u32, burst: u32, now: Instant) -> Self {
+        let burst = burst.max(1);
+        Self {
+            qps,
+            burst,
+            tokens: burst as f64,
+            last: now,
+            factor: 1.0,
+            paused_until: None,
+            throttled_total: 0,
+            wait_ms_total: 0,
+        }
+    }
+
+    fn rate(&self) -> f64 {
+        self.qps as f64 * self.factor
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.last).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.rate()).min(self.burst as f64);
+        self.last = now;
+    }
+
+    /// Take one token and return how long the caller must wait before sending.
+    /// Tokens may go negative; the debt is what spaces out queued requests.
+    fn reserve(&mut self, now: Instant) -> Duration {
+        self.refill(now);
+        let pause = match self.paused_until {
+            Some(until) if until > now => until - now,
+            _ => {
+                self.paused_until = None;
+                Duration::ZERO
+            }
+        };
+        self.tokens -= 1.0;
+        let debt = if self.tokens < 0.0 {
+            Duration::from_secs_f64(-self.tokens / self.rate())
+        } else {
+            Duration::ZERO
+        };
+        let wait = pause + debt;
+        self.wait_ms_total += wait.as_millis() as u64;
+        wait
+    }
+
+    fn on_throttled(&mut self, now: Instant, retry_after: Option<Duration>) {
+        self.refill(now);
+        self.throttled_total += 1;
+        self.factor = (self.factor * 0.5).max(MIN_FACTOR);
+        // Drop any saved-up burst so queued requests resume at the slower rate.
+        self.tokens = self.tokens.min(0.0);
+        let until = now + retry_after.unwrap_or(DEFAULT_PAUSE).min(MAX_PAUSE);
+        if self.paused_until.map(|p| p < until).unwrap_or(true) {
+            self.paused_until = Some(until);
+        }
+    }
+
+    fn on_success(&mut self, now: Instant) {
+        if self.factor < 1.0 {
+            self.refill(now);
+            self.factor = (self.factor + RECOVERY_STEP).min(1.0);
+        }
+    }
+
+    fn state(&self, now: Instant) -> ThrottleState {
+        ThrottleState {
+            qps: self.qps,
+            burst: self.burst,
+            effective_pct: (self.factor * 100.0).round() as u32,
+            throttled_total: self.throttled_total,
+            backoff_ms: self
+                .paused_until
+                .map(|u| u.saturating_duration_since(now).as_millis() as u64)
+                .unwrap_or(0),
+            wait_ms_total: self.wait_ms_total,
+        }
+    }
+}
+
+// `None` when disabled via ORKA_KUBE_QPS=0.
+static LIMITER: Lazy<Option<Mutex<Bucket>>> = Lazy::new(|| {
+    let qps: u32 = std::env::var("ORKA_KUBE_QPS")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(DEFAULT_QPS);
+    let burst: u32 = std::env::var("ORKA_KUBE_BURST")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(DEFAULT_BURST);
+    if qps == 0 {
+        return None;
+    }
+    Some(Mutex::new(Bucket::new(qps, burst, Instant::now())))
+});
+
+fn reserve() -> Duration {
+    match LIMITER.as_ref() {
+        Some(b) => b.lock().unwrap().reserve(Instant::now()),
+        None => Duration::ZERO,
+    }
+}
+
+fn observe(status: StatusCode, headers: &HeaderMap) {
+    let Some(b) = LIMITER.as_ref() else {
+        return;
+    };
+    if status == StatusCode::TOO_MANY_REQUESTS {
+        let retry_after = parse_retry_after(headers);
+        b.lock().unwrap().on_throttled(Instant::now(), retry_after);
+        counter!("kube_throttled_total", 1u64);
+        warn!(retry_after = ?retry_after, "kube: 429 Too Many Requests; slowing down");
+    } else {
+        b.lock().unwrap().on_success(Instant::now());
+    }
+}
+
+/// Retry-After in delta-seconds (the form API Priority and Fairness sends).
+fn parse_retry_after(headers: &HeaderMap) -> Option<Duration> {
+    headers
+        .get(RETRY_AFTER)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|s| s.trim().parse::<u64>().ok())
+        .map(Duration::from_secs)
+}
+
+/// Snapshot of the shared limiter; `None` when rate limiting is disabled.
+pub fn throttle_state() -> Option<ThrottleState> {
+    LIMITER
+        .as_ref()
+        .map(|b| b.lock().unwrap().state(Instant::now()))
+}
+
+/// Tower layer applying the shared limiter to a kube client's service stack.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RateLimitLayer;
+
+impl<S> Layer<S> for RateLimitLayer {
+    type Service = RateLimitService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RateLimitService {
+            inner,
+            sleep: None,
+            reserved: false,
+        }
+    }
+}
+
+pub struct RateLimitService<S> {
+    inner: S,
+    sleep: Option<Pin<Box<tokio::time::Sleep>>>,
+    reserved: bool,
+}
+
+impl<S, B, RB> Service<Request<B>> for RateLimitService<S>
+where
+    S: Service<Request<B>, Response = Response<RB>>,
+    S::Future: Send + 'static,
+    S::Error: Send + 'static,
+    RB: Send + 'static,
+{
+    type Response = Response<RB>;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Response<RB>, S::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        if !self.reserved {
+            let wait = reserve();
+            if !wait.is_zero() {
+                histogram!("kube_ratelimit_wait_ms", wait.as_millis() as f64);
+                self.sleep = Some(Box::pin(tokio::time::sleep(wait)));
+            }
+            self.reserved = true;
+        }
+        if let Some(sleep) = self.sleep.as_mut() {
+            ready!(sleep.as_mut().poll(cx));
+            self.sleep = None;
+        }
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Request<B>) -> Self::Future {
+        self.reserved = false;
+        let fut = self.inner.call(req);
+        Box::pin(async move {
+            let resp = fut.await?;
+            observe(resp.status(), resp.headers());
+            Ok(resp)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn burst_then_paced_by_qps() {
+        let t0 = Instant::now();
+        let mut b = Bucket::new(10, 2, t0);
+        assert_eq!(b.reserve(t0), Duration::ZERO);
+        assert_eq!(b.reserve(t0), Duration::ZERO);
+        // Third request owes one token at 10 qps.
+        assert_eq!(b.reserve(t0), Duration::from_millis(100));
+        assert_eq!(b.reserve(t0), Duration::from_millis(200));
+        // A second later the debt is repaid and the bucket is full again.
+        let t1 = t0 + Duration::from_secs(1);
+        assert_eq!(b.reserve(t1), Duration::ZERO);
+    }
+
+    #[test]
+    fn throttle_pauses_and_halves_rate() {
+        let t0 = Instant::now();
+        let mut b = Bucket::new(10, 5, t0);
+        b.on_throttled(t0, Some(Duration::from_secs(2)));
+        let st = b.state(t0);
+        assert_eq!(st.effective_pct, 50);
+        assert_eq!(st.throttled_total, 1);
+        assert_eq!(st.backoff_ms, 2000);
+        // Pause plus one token at the halved rate (5 qps).
+        assert_eq!(b.reserve(t0), Duration::from_millis(2200));
+    }
+
+    #[test]
+    fn slowdown_has_a_floor_and_recovers() {
+        let t0 = Instant::now();
+        let mut b = Bucket::new(10, 5, t0);
+        for _ in 0..10 {
+            b.on_throttled(t0, None);
+        }
+        assert_eq!(b.state(t0).effective_pct, 10);
+        for _ in 0..100 {
+            b.on_success(t0);
+        }
+        assert_eq!(b.state(t0).effective_pct, 100);
+    }
+
+    #[test]
+    fn retry_after_seconds_are_parsed() {
+        let mut h = HeaderMap::new();
+        assert_eq!(parse_retry_after(&h), None);
+        h.insert(RETRY_AFTER, "3".parse().unwrap());
+        assert_eq!(parse_retry_after(&h), Some(Duration::from_secs(3)));
+        h.insert(
+            RETRY_AFTER,
+            "Wed, 21 Oct 2015 07:28:00 GMT".parse().unwrap(),
+        );
+        assert_eq!(parse_retry_after(&h), None);
+    }
+}
@@ -0,0 +1,205 @@
+//! kubectl-style resource name resolution.
+//!
+//! Maps user input such as `pods`, `po`, `deploy`, `Deployment`,
+//! `deployments.apps` or `deployments.v1.apps` to a discovered GVK. Strict
+//! `group/version/Kind` keys are handled by the caller and never reach here.
+
+use serde::{Deserialize, Serialize};
+
+/// Names a served resource can be addressed by, as reported by discovery.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ResourceNames {
+    pub group: String,
+    pub version: String,
+    pub kind: String,
+    pub plural: String,
+    pub singular: String,
+    pub short_names: Vec<String>,
+    pub namespaced: bool,
+}
+
+impl ResourceNames {
+    pub fn gvk_key(&self) -> String {
+        if self.group.is_empty() {
+            format!("{}/{}", self.version, self.kind)
+        } else {
+            format!("{}/{}/{}", self.group, self.version, self.kind)
+        }
+    }
+
+    fn matches_name(&self, name: &str) -> bool {
+        self.plural.eq_ignore_ascii_case(name)
+            || self.singular.eq_ignore_ascii_case(name)
+            || self.kind.eq_ignore_ascii_case(name)
+            || self
+                .short_names
+                .iter()
+                .any(|s| s.eq_ignore_ascii_case(name))
+    }
+}
+
+#[derive(Debug, thiserror::Error, PartialEq, Eq)]
+pub enum ResolveError {
+    #[error("unknown resource type: {0}")]
+    NotFound(String),
+    #[error("ambiguous resource type '{input}'; candidates: {}", .candidates.join(", "))]
+    Ambiguous {
+        input: String,
+        candidates: Vec<String>,
+    },
+}
+
+/// Resolve a kubectl-style resource name against discovered resources.
+///
+/// Accepted forms: `name`, `name.group` and `name.version.group`, where `name`
+/// is the plural, singular, Kind or any short name (case-insensitive). When a
+/// bare name matches several groups, the core group wins (like kubectl, so
+/// `pods` never means `metrics.k8s.io` PodMetrics); otherwise the candidates
+/// are reported back.
+pub fn resolve<'a>(
+    entries: &'a [ResourceNames],
+    input: &str,
+) -> Result<&'a ResourceNames, ResolveError> {
+    let needle = input.trim().to_ascii_lowercase();
+    if needle.is_empty() {
+        return Err(ResolveError::NotFound(input.to_string()));
+    }
+    // Bare names first; fall back to `name.group` / `name.version.group`.
+    let mut candidates: Vec<&ResourceNames> =
+        entries.iter().filter(|e| e.matches_name(&needle)).collect();
+    if candidates.is_empty() {
+        if let Some((name, rest)) = needle.split_once('.') {
+            candidates = entries
+                .iter()
+                .filter(|e| e.matches_name(name))
+                .filter(|e| {
+                    let group = e.group.to_ascii_lowercase();
+                    let version_group = format!("{}.{}", e.version, group);
+                    rest == group || rest == version_group
+                })
+                .collect();
+        }
+    }
+    match candidates.len() {
+        0 => Err(ResolveError::NotFound(input.to_string())),
+        1 => Ok(candidates[0]),
+        _ => {
+            let core: Vec<&ResourceNames> = candidates
+                .iter()
+                .copied()
+                .filter(|e| e.group.is_empty())
+                .collect();
+            if core.len() == 1 {
+                return Ok(core[0]);
+            }
+            let mut keys: Vec<String> = candidates.iter().map(|e| e.gvk_key()).collect();
+            keys.sort();
+            keys.dedup();
+            Err(ResolveError::Ambiguous {
+                input: input.to_string(),
+                candidates: keys,
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(
+        group: &str,
+        version: &str,
+        kind: &str,
+        plural: &str,
+        short: &[&str],
+    ) -> ResourceNames {
+        ResourceNames {
+            group: group.into(),
+            version: version.into(),
+            kind: kind.into(),
+            plural: plural.into(),
+            singular: kind.to_ascii_lowercase(),
+            short_names: short.iter().map(|s| s.to_string()).collect(),
+            namespaced: true,
+        }
+    }
+
+    fn fixture() -> Vec<ResourceNames> {
+        vec![
+            entry("", "v1", "Pod", "pods", &["po"]),
+            entry("", "v1", "Event", "events", &["ev"]),
+            entry("apps", "v1", "Deployment", "deployments", &["deploy"]),
+            entry("events.k8s.io", "v1", "Event", "events", &["ev"]),
+            entry("metrics.k8s.io", "v1beta1", "PodMetrics", "pods", &[]),
+            entry("example.com", "v1", "Widget", "widgets", &["wd"]),
+            entry("other.io", "v1alpha1", "Widget", "widgets", &["wd"]),
+        ]
+    }
+
+    #[test]
+    fn resolves_plural_singular_short_and_kind() {
+        let e = fixture();
+        for input in ["pods", "pod", "po", "Pod", "PODS"] {
+            assert_eq!(resolve(&e, input).unwrap().gvk_key(), "v1/Pod", "{}", input);
+        }
+        for input in ["deployments", "deployment", "deploy", "Deployment"] {
+            assert_eq!(resolve(&e, input).unwrap().gvk_key(), "apps/v1/Deployment");
+        }
+    }
+
+    #[test]
+    fn core_group_wins_bare_name_collisions() {
+        let e = fixture();
+        assert_eq!(resolve(&e, "events").unwrap().gvk_key(), "v1/Event");
+        assert_eq!(resolve(&e, "ev").unwrap().gvk_key(), "v1/Event");
+    }
+
+    #[test]
+    fn group_qualified_names_select_group() {
+        let e = fixture();
+        assert_eq!(
+            resolve(&e, "events.events.k8s.io").unwrap().gvk_key(),
+            "events.k8s.io/v1/Event"
+        );
+        assert_eq!(
+            resolve(&e, "deployments.v1.apps").unwrap().gvk_key(),
+            "apps/v1/Deployment"
+        );
+        assert_eq!(
+            resolve(&e, "wd.other.io").unwrap().gvk_key(),
+            "other.io/v1alpha1/Widget"
+        );
+    }
+
+    #[test]
+    fn ambiguity_lists_candidates() {
+        let e = fixture();
+        let err = resolve(&e, "widgets").unwrap_err();
+        assert_eq!(
+            err,
+            ResolveError::Ambiguous {
+                input: "widgets".into(),
+                candidates: vec![
+                    "example.com/v1/Widget".into(),
+                    "other.io/v1alpha1/Widget".into()
+                ],
+            }
+        );
+        assert!(err.to_string().contains("example.com/v1/Widget"));
+    }
+
+    #[test]
+    fn unknown_names_are_not_found() {
+        let e = fixture();
+        assert!(matches!(
+            resolve(&e, "gizmos"),
+            Err(ResolveError::NotFound(_))
+        ));
+        assert!(matches!(
+            resolve(&e, "pods.apps"),
+            Err(ResolveError::NotFound(_))
+        ));
+        assert!(matches!(resolve(&e, ""), Err(ResolveError::NotFound(_))));
+    }
+}
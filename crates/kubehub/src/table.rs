@@ -0,0 +1,307 @@
+//! Server-side Table list decoding (`Accept: application/json;as=Table`).
+//!
+//! The API server renders printer columns itself and returns each row with a
+//! `PartialObjectMetadata`, so a page costs a fraction of the full JSON list
+//! and needs no client-side projection. Cells are mapped into
+//! `LiteObj.projected`: built-in kinds use the stable column IDs from
+//! `orka_core::columns`; CRD columns are matched by name against the printer
+//! columns `orka_schema` registered through [`set_table_columns`], so they
+//! land on the same IDs as the JSON projection. Columns nobody registered
+//! (including the Age column the server adds by default) are dropped.
+
+use anyhow::{anyhow, Result};
+use kube::core::{DynamicObject, GroupVersionKind, ObjectMeta};
+use once_cell::sync::Lazy;
+use serde::Deserialize;
+use smallvec::SmallVec;
+use std::collections::HashMap;
+use std::sync::RwLock;
+
+/// Ask for a Table, falling back to a plain list on servers that cannot render one.
+pub(crate) const TABLE_ACCEPT: &str =
+    "application/json;as=Table;v=v1;g=meta.k8s.io,application/json";
+
+/// Printer column name and the projected id it feeds.
+type ColumnIds = Vec<(String, u32)>;
+
+// GVK key -> registered columns for CRD kinds.
+static TABLE_COLUMNS: Lazy<RwLock<HashMap<String, ColumnIds>>> =
+    Lazy::new(|| RwLock::new(HashMap::new()));
+
+/// Record which projected id each printer column of `gvk_key` feeds, so Table
+/// lists of that kind project the same ids as the JSON path. Called by
+/// `orka_schema` whenever it loads a CRD schema; an empty list clears it.
+pub fn set_table_columns(gvk_key: &str, columns: ColumnIds) {
+    let mut map = TABLE_COLUMNS.write().unwrap();
+    if columns.is_empty() {
+        map.remove(gvk_key);
+    } else {
+        map.insert(gvk_key.to_string(), columns);
+    }
+}
+
+pub(crate) fn clear_table_columns() {
+    TABLE_COLUMNS.write().unwrap().clear();
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Table {
+    #[serde(default)]
+    kind: String,
+    #[serde(default)]
+    metadata: TableMeta,
+    #[serde(default)]
+    column_definitions: Vec<ColumnDefinition>,
+    #[serde(default)]
+    rows: Vec<TableRow>,
+}
+
+#[derive(Deserialize, Default)]
+struct TableMeta {
+    #[serde(rename = "continue", default)]
+    continue_: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct ColumnDefinition {
+    name: String,
+    #[serde(default)]
+    format: String,
+}
+
+#[derive(Deserialize)]
+struct TableRow {
+    #[serde(default)]
+    cells: Vec<serde_json::Value>,
+    #[serde(default)]
+    object: Option<PartialObjectMetadata>,
+}
+
+#[derive(Deserialize)]
+struct PartialObjectMetadata {
+    #[serde(default)]
+    metadata: ObjectMeta,
+}
+
+/// Projected column ID per table column; `None` for columns we do not keep
+/// (Name, Age and columns without a built-in or registered ID).
+fn column_ids(gvk: &GroupVersionKind, cols: &[ColumnDefinition]) -> Vec<Option<u32>> {
+    let builtin =
+        orka_core::columns::builtin_projector_for(&gvk.group, &gvk.version, &gvk.kind).is_some();
+    if builtin {
+        return cols
+            .iter()
+            .map(|c| {
+                orka_core::columns::builtin_table_column_id(
+                    &gvk.group,
+                    &gvk.version,
+                    &gvk.kind,
+                    &c.name,
+                )
+            })
+            .collect();
+    }
+    let registered = TABLE_COLUMNS.read().unwrap();
+    let known = registered.get(&super::gvk_to_key(gvk));
+    cols.iter()
+        .map(|c| {
+            if c.format == "name" {
+                return None;
+            }
+            known?
+                .iter()
+                .find(|(name, _)| *name == c.name)
+                .map(|(_, id)| *id)
+        })
+        .collect()
+}
+
+fn cell_to_string(v: &serde_json::Value) -> Option<String> {
+    match v {
+        serde_json::Value::Null => None,
+        serde_json::Value::String(s) if s.is_empty() => None,
+        serde_json::Value::String(s) => Some(s.clone()),
+        serde_json::Value::Number(n) => Some(n.to_string()),
+        serde_json::Value::Bool(b) => Some(b.to_string()),
+        other => Some(other.to_string()),
+    }
+}
+
+/// Decode one Table page into LiteObjs plus the continue token, if any.
+pub fn decode_table_page(
+    body: &[u8],
+    gvk: &GroupVersionKind,
+) -> Result<(Vec<orka_core::LiteObj>, Option<String>)> {
+    let table: Table = serde_json::from_slice(body)?;
+    if table.kind != "Table" {
+        return Err(anyhow!("server returned {} instead of Table", table.kind));
+    }
+    let ids = column_ids(gvk, &table.column_definitions);
+    let mut out = Vec::with_capacity(table.rows.len());
+    for row in table.rows {
+        let meta = row
+            .object
+            .ok_or_else(|| anyhow!("table row missing object metadata"))?
+            .metadata;
+        let obj = DynamicObject {
+            types: None,
+            metadata: meta,
+            data: serde_json::Value::Null,
+        };
+        let mut lo = super::lite_from_dynamic(&obj)?;
+        let mut projected: SmallVec<[(u32, String); 8]> = SmallVec::new();
+        for (cell, id) in row.cells.iter().zip(ids.iter()) {
+            if let (Some(id), Some(s)) = (id, cell_to_string(cell)) {
+                projected.push((*id, s));
+            }
+        }
+        lo.projected = projected;
+        out.push(lo);
+    }
+    let cont = table.metadata.continue_.filter(|c| !c.is_empty());
+    Ok((out, cont))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn gvk(group: &str, version: &str, kind: &str) -> GroupVersionKind {
+        GroupVersionKind {
+            group: group.into(),
+            version: version.into(),
+            kind: kind.into(),
+        }
+    }
+
+    fn row(name: &str, uid: &str, cells: serde_json::Value) -> serde_json::Value {
+        serde_json::json!({
+            "cells": cells,
+            "object": {
+                "kind": "PartialObjectMetadata",
+                "apiVersion": "meta.k8s.io/v1",
+                "metadata": {
+                    "name": name,
+                    "namespace": "default",
+                    "uid": uid,
+                    "creationTimestamp": "2024-01-01T00:00:00Z"
+                }
+            }
+        })
+    }
+
+    #[test]
+    fn builtin_columns_map_to_stable_ids() {
+        use orka_core::columns::{POD_NODE, POD_READY, POD_RESTARTS, POD_STATUS};
+        let body = serde_json::json!({
+            "kind": "Table",
+            "apiVersion": "meta.k8s.io/v1",
+            "metadata": {"resourceVersion": "42", "continue": "abc"},
+            "columnDefinitions": [
+                {"name": "Name", "type": "string", "format": "name"},
+                {"name": "Ready", "type": "string", "format": ""},
+                {"name": "Status", "type": "string", "format": ""},
+                {"name": "Restarts", "type": "string", "format": ""},
+                {"name": "Age", "type": "string", "format": ""},
+                {"name": "IP", "type": "string", "format": ""},
+                {"name": "Node", "type": "string", "format": ""}
+            ],
+            "rows": [row(
+                "web-0",
+                "7c9e6679-7425-40de-944b-e07fc1f90ae7",
+                serde_json::json!(["web-0", "1/1", "Running", "0", "5m", "10.0.0.1", "node-a"])
+            )]
+        });
+        let (items, cont) =
+            decode_table_page(body.to_string().as_bytes(), &gvk("", "v1", "Pod")).unwrap();
+        assert_eq!(cont.as_deref(), Some("abc"));
+        assert_eq!(items.len(), 1);
+        let lo = &items[0];
+        assert_eq!(lo.name, "web-0");
+        assert_eq!(lo.namespace.as_deref(), Some("default"));
+        assert_eq!(lo.creation_ts, 1_704_067_200);
+        let projected: Vec<(u32, &str)> = lo
+            .projected
+            .iter()
+            .map(|(id, v)| (*id, v.as_str()))
+            .collect();
+        assert_eq!(
+            projected,
+            vec![
+                (POD_READY, "1/1"),
+                (POD_STATUS, "Running"),
+                (POD_RESTARTS, "0"),
+                (POD_NODE, "node-a")
+            ]
+        );
+    }
+
+    fn projected(items: &[orka_core::LiteObj]) -> Vec<(u32, &str)> {
+        items[0]
+            .projected
+            .iter()
+            .map(|(id, v)| (*id, v.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn crd_columns_match_registered_printer_columns_by_name() {
+        set_table_columns(
+            "example.com/v1/Widget",
+            vec![("Replicas".into(), 0), ("Ready".into(), 1)],
+        );
+        let body = serde_json::json!({
+            "kind": "Table",
+            "metadata": {},
+            "columnDefinitions": [
+                {"name": "Name", "type": "string", "format": "name"},
+                {"name": "Ready", "type": "string"},
+                {"name": "Replicas", "type": "integer"},
+                {"name": "Age", "type": "date"}
+            ],
+            "rows": [row(
+                "w1",
+                "1b4e28ba-2fa1-11d2-883f-0016d3cca427",
+                serde_json::json!(["w1", true, 3, "5m"])
+            )]
+        });
+        let (items, cont) = decode_table_page(
+            body.to_string().as_bytes(),
+            &gvk("example.com", "v1", "Widget"),
+        )
+        .unwrap();
+        assert_eq!(cont, None);
+        assert_eq!(projected(&items), vec![(1, "true"), (0, "3")]);
+    }
+
+    #[test]
+    fn crd_without_printer_columns_projects_nothing() {
+        // The server's default columns are Name and Age; neither feeds an id.
+        let body = serde_json::json!({
+            "kind": "Table",
+            "metadata": {},
+            "columnDefinitions": [
+                {"name": "Name", "type": "string", "format": "name"},
+                {"name": "Age", "type": "date"}
+            ],
+            "rows": [row(
+                "g1",
+                "6fa459ea-ee8a-3ca4-894e-db77e160355e",
+                serde_json::json!(["g1", "5m"])
+            )]
+        });
+        let (items, _) = decode_table_page(
+            body.to_string().as_bytes(),
+            &gvk("example.com", "v1", "Gadget"),
+        )
+        .unwrap();
+        assert!(projected(&items).is_empty());
+    }
+
+    #[test]
+    fn non_table_responses_are_rejected() {
+        let body = br#"{"kind":"PodList","items":[]}"#;
+        assert!(decode_table_page(body, &gvk("", "v1", "Pod")).is_err());
+    }
+}
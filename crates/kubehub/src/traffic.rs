@@ -0,0 +1,316 @@
+//! HTTP-level traffic accounting for kube API requests.
+//!
+//! Every client built through this crate carries a [`TrafficLayer`] that
+//! counts requests and wire bytes (request bodies out, response bodies in, as
+//! they stream) per kube context, GVK and verb. Totals are exported as labeled
+//! metrics (`kube_requests_total`, `kube_rx_bytes_total`, `kube_tx_bytes_total`)
+//! and returned by [`traffic_breakdown`].
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+
+use bytes::Buf;
+use http::{Method, Request, Response};
+use http_body::{Body, Frame, SizeHint};
+use metrics::{register_counter, Counter};
+use once_cell::sync::Lazy;
+use tower::{Layer, Service};
+
+/// Label used for requests that do not address a resource (discovery, openapi, version).
+pub const NON_RESOURCE: &str = "-";
+
+/// Accumulated traffic for one (context, GVK, verb) triple.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TrafficSample {
+    pub context: String,
+    /// `group/version/Kind`, `version/Kind`, or the plural when the kind is not
+    /// yet discovered; `-` for non-resource paths.
+    pub gvk: String,
+    /// One of list, watch, get, create, update, patch, delete, discovery.
+    pub verb: String,
+    pub requests: u64,
+    pub rx_bytes: u64,
+    pub tx_bytes: u64,
+}
+
+struct Entry {
+    requests: AtomicU64,
+    rx_bytes: AtomicU64,
+    tx_bytes: AtomicU64,
+    requests_metric: Counter,
+    rx_metric: Counter,
+    tx_metric: Counter,
+}
+
+type EntryKey = (String, String, &'static str);
+
+static ENTRIES: Lazy<Mutex<HashMap<EntryKey, Arc<Entry>>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+fn entry(context: &str, gvk: String, verb: &'static str) -> Arc<Entry> {
+    let key = (context.to_string(), gvk, verb);
+    let mut map = ENTRIES.lock().unwrap();
+    if let Some(e) = map.get(&key) {
+        return e.clone();
+    }
+    let labels = [
+        ("context", key.0.clone()),
+        ("gvk", key.1.clone()),
+        ("verb", verb.to_string()),
+    ];
+    let e = Arc::new(Entry {
+        requests: AtomicU64::new(0),
+        rx_bytes: AtomicU64::new(0),
+        tx_bytes: AtomicU64::new(0),
+        requests_metric: register_counter!("kube_requests_total", &labels),
+        rx_metric: register_counter!("kube_rx_bytes_total", &labels),
+        tx_metric: register_counter!("kube_tx_bytes_total", &labels),
+    });
+    map.insert(key, e.clone());
+    e
+}
+
+/// Snapshot of all traffic counters since process start, largest receivers first.
+pub fn traffic_breakdown() -> Vec<TrafficSample> {
+    let map = ENTRIES.lock().unwrap();
+    let mut out: Vec<TrafficSample> = map
+        .iter()
+        .map(|((context, gvk, verb), e)| TrafficSample {
+            context: context.clone(),
+            gvk: gvk.clone(),
+            verb: verb.to_string(),
+            requests: e.requests.load(Ordering::Relaxed),
+            rx_bytes: e.rx_bytes.load(Ordering::Relaxed),
+            tx_bytes: e.tx_bytes.load(Ordering::Relaxed),
+        })
+        .collect();
+    out.sort_by(|a, b| {
+        b.rx_bytes
+            .cmp(&a.rx_bytes)
+            .then_with(|| a.gvk.cmp(&b.gvk))
+            .then_with(|| a.verb.cmp(&b.verb))
+    });
+    out
+}
+
+/// Resource addressed by a request path, before Kind lookup.
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) struct PathTarget {
+    pub group: String,
+    pub version: String,
+    pub plural: String,
+    pub named: bool,
+}
+
+/// Parse `/api/v1/...` and `/apis/{group}/{version}/...` resource paths.
+pub(crate) fn parse_path(path: &str) -> Option<PathTarget> {
+    let segs: Vec<&str> = path.trim_matches('/').split('/').collect();
+    let (group, version, rest) = match segs.as_slice() {
+        ["api", version, rest @ ..] => ("", *version, rest),
+        ["apis", group, version, rest @ ..] => (*group, *version, rest),
+        _ => return None,
+    };
+    let rest = match rest {
+        // namespaces/{ns}/{plural}/... addresses a namespaced resource;
+        // namespaces and namespaces/{ns} address Namespace objects themselves.
+        ["namespaces", _ns, tail @ ..] if !tail.is_empty() => tail,
+        other => other,
+    };
+    let (plural, tail) = rest.split_first()?;
+    Some(PathTarget {
+        group: group.to_string(),
+        version: version.to_string(),
+        plural: plural.to_string(),
+        named: !tail.is_empty(),
+    })
+}
+
+pub(crate) fn verb_for(method: &Method, named: bool, query: Option<&str>) -> &'static str {
+    let watch = query
+        .map(|q| q.split('&').any(|kv| kv == "watch=true" || kv == "watch=1"))
+        .unwrap_or(false);
+    match *method {
+        Method::GET if watch => "watch",
+        Method::GET if named => "get",
+        Method::GET => "list",
+        Method::POST => "create",
+        Method::PUT => "update",
+        Method::PATCH => "patch",
+        Method::DELETE => "delete",
+        _ => "other",
+    }
+}
+
+fn classify<B>(req: &Request<B>) -> (String, &'static str) {
+    let uri = req.uri();
+    match parse_path(uri.path()) {
+        Some(t) => {
+            let verb = verb_for(req.method(), t.named, uri.query());
+            let gvk = super::gvk_label_for_plural(&t.group, &t.version, &t.plural);
+            (gvk, verb)
+        }
+        None => (NON_RESOURCE.to_string(), "discovery"),
+    }
+}
+
+/// Tower layer that accounts traffic for one kube context.
+#[derive(Debug, Clone)]
+pub struct TrafficLayer {
+    context: Arc<str>,
+}
+
+impl TrafficLayer {
+    pub fn new(context: &str) -> Self {
+        Self {
+            context: Arc::from(context),
+        }
+    }
+}
+
+impl<S> Layer<S> for TrafficLayer {
+    type Service = TrafficService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        TrafficService {
+            inner,
+            context: self.context.clone(),
+        }
+    }
+}
+
+pub struct TrafficService<S> {
+    inner: S,
+    context: Arc<str>,
+}
+
+impl<S, B, RB> Service<Request<B>> for TrafficService<S>
+where
+    S: Service<Request<B>, Response = Response<RB>>,
+    S::Future: Send + 'static,
+    S::Error: Send + 'static,
+    B: Body,
+    RB: Body + Unpin + Send + 'static,
+{
+    type Response = Response<CountingBody<RB>>;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, S::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Request<B>) -> Self::Future {
+        let (gvk, verb) = classify(&req);
+        let e = entry(&self.context, gvk, verb);
+        e.requests.fetch_add(1, Ordering::Relaxed);
+        e.requests_metric.increment(1);
+        let tx = req.body().size_hint().exact().unwrap_or(0);
+        if tx > 0 {
+            e.tx_bytes.fetch_add(tx, Ordering::Relaxed);
+            e.tx_metric.increment(tx);
+        }
+        let fut = self.inner.call(req);
+        Box::pin(async move {
+            let resp = fut.await?;
+            Ok(resp.map(|inner| CountingBody { inner, entry: e }))
+        })
+    }
+}
+
+/// Response body wrapper counting data bytes as frames arrive.
+pub struct CountingBody<B> {
+    inner: B,
+    entry: Arc<Entry>,
+}
+
+impl<B> Body for CountingBody<B>
+where
+    B: Body + Unpin,
+{
+    type Data = B::Data;
+    type Error = B::Error;
+
+    fn poll_frame(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+        let polled = Pin::new(&mut self.inner).poll_frame(cx);
+        if let Poll::Ready(Some(Ok(frame))) = &polled {
+            if let Some(data) = frame.data_ref() {
+                let n = data.remaining() as u64;
+                self.entry.rx_bytes.fetch_add(n, Ordering::Relaxed);
+                self.entry.rx_metric.increment(n);
+            }
+        }
+        polled
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.inner.is_end_stream()
+    }
+
+    fn size_hint(&self) -> SizeHint {
+        self.inner.size_hint()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_core_and_group_paths() {
+        assert_eq!(
+            parse_path("/api/v1/namespaces/default/pods"),
+            Some(PathTarget {
+                group: String::new(),
+                version: "v1".into(),
+                plural: "pods".into(),
+                named: false,
+            })
+        );
+        assert_eq!(
+            parse_path("/apis/apps/v1/namespaces/prod/deployments/web/scale"),
+            Some(PathTarget {
+                group: "apps".into(),
+                version: "v1".into(),
+                plural: "deployments".into(),
+                named: true,
+            })
+        );
+        assert_eq!(
+            parse_path("/apis/apps/v1/deployments").map(|t| t.named),
+            Some(false)
+        );
+        assert_eq!(parse_path("/apis"), None);
+        assert_eq!(parse_path("/version"), None);
+        assert_eq!(parse_path("/openapi/v3"), None);
+    }
+
+    #[test]
+    fn namespace_objects_are_their_own_resource() {
+        let list = parse_path("/api/v1/namespaces").unwrap();
+        assert_eq!((list.plural.as_str(), list.named), ("namespaces", false));
+        let get = parse_path("/api/v1/namespaces/kube-system").unwrap();
+        assert_eq!((get.plural.as_str(), get.named), ("namespaces", true));
+    }
+
+    #[test]
+    fn verbs_follow_method_and_watch_flag() {
+        assert_eq!(verb_for(&Method::GET, false, Some("limit=500")), "list");
+        assert_eq!(
+            verb_for(&Method::GET, false, Some("resourceVersion=1&watch=true")),
+            "watch"
+        );
+        assert_eq!(verb_for(&Method::GET, true, None), "get");
+        assert_eq!(
+            verb_for(&Method::PATCH, true, Some("fieldManager=orka")),
+            "patch"
+        );
+        assert_eq!(verb_for(&Method::DELETE, true, None), "delete");
+    }
+}
@@ -10,13 +10,45 @@ use kube::{
     Client,
 };
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use tokio::sync::{mpsc, oneshot};
 use tracing::{info, warn};
 
-/// A single chunk of log output (line oriented for now).
+/// A single chunk of log output (line oriented for now), tagged with the
+/// pod and container it came from when known.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LogChunk {
     pub line: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pod: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub container: Option<String>,
+}
+
+/// Pods followed by `logs_aggregate`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LogTarget {
+    /// Label selector, e.g. `app=web,tier in (fe,be)`.
+    Selector(String),
+    /// Pods selected by a Deployment, StatefulSet, DaemonSet, ReplicaSet,
+    /// Job or Service; `kind` also accepts kubectl short names (`deploy`, `sts`, ...).
+    Workload { kind: String, name: String },
+}
+
+impl LogTarget {
+    /// Parse `KIND/NAME` as accepted by `kubectl logs`.
+    pub fn workload(spec: &str) -> Result<Self> {
+        match spec.split_once('/') {
+            Some((kind, name)) if !kind.is_empty() && !name.is_empty() => {
+                workload_gvk(kind)?;
+                Ok(Self::Workload {
+                    kind: kind.to_string(),
+                    name: name.to_string(),
+                })
+            }
+            _ => Err(anyhow!("invalid workload: {} (expect KIND/NAME)", spec)),
+        }
+    }
 }
 
 /// Options for `logs` operation.
@@ -78,6 +110,17 @@ pub trait OrkaOps: Send + Sync {
         opts: LogOptions,
     ) -> Result<StreamHandle<LogChunk>>;
 
+    /// Stream logs from every pod matching `target` (all containers unless
+    /// `container` is given). With `follow`, pods that appear later are picked
+    /// up from a watch and restarted containers are followed again.
+    async fn logs_aggregate(
+        &self,
+        namespace: &str,
+        target: &LogTarget,
+        container: Option<&str>,
+        opts: LogOptions,
+    ) -> Result<StreamHandle<LogChunk>>;
+
     // Stubs for upcoming ops in this milestone
     async fn exec(
         &self,
@@ -264,7 +307,8 @@ impl KubeOps {
         let mut scale_caps: Option<ScaleCaps> = None;
         if let Some(gvk_key) = scale_gvk {
             use kube::core::GroupVersionKind;
-            let (group, version, kind) = parse_gvk_key(gvk_key)?;
+            let (group, version, kind) =
+                parse_gvk_key(&orka_kubehub::resolve_gvk_key(gvk_key).await?)?;
             let gvk = GroupVersionKind {
                 group,
                 version,
@@ -358,23 +402,116 @@ impl OrkaOps for KubeOps {
         };
 
         // Spawn a task to stream logs and forward into bounded channel.
-        let pod_name = pod.to_string();
-        let container_label = container.map(|s| s.to_string());
+        spawn_pod_logs(api, pod.to_string(), lp, tx, cancel_rx);
+
+        Ok(StreamHandle { rx, cancel })
+    }
+
+    async fn logs_aggregate(
+        &self,
+        namespace: &str,
+        target: &LogTarget,
+        container: Option<&str>,
+        opts: LogOptions,
+    ) -> Result<StreamHandle<LogChunk>> {
+        use k8s_openapi::api::core::v1::Pod;
+        use kube::runtime::watcher::{self, Event};
+
+        let client = orka_kubehub::get_kube_client().await?;
+        let selector = match target {
+            LogTarget::Selector(s) => s.trim().to_string(),
+            LogTarget::Workload { kind, name } => {
+                let (group, version, kind) = workload_gvk(kind)?;
+                let ar = kube::core::ApiResource::from_gvk(&kube::core::GroupVersionKind::gvk(
+                    group, version, kind,
+                ));
+                let api: Api<kube::core::DynamicObject> =
+                    Api::namespaced_with(client.clone(), namespace, &ar);
+                let obj = api.get(name).await?;
+                let selector = selector_from_object(kind, &obj.data)?;
+                if selector.is_empty() {
+                    return Err(anyhow!("{}/{} has no pod selector", kind, name));
+                }
+                selector
+            }
+        };
+        if selector.is_empty() {
+            return Err(anyhow!("an empty selector would match every pod"));
+        }
+        let api: Api<Pod> = Api::namespaced(client, namespace);
+        // Without follow the pod set is fixed up front, so a failed list is
+        // the caller's error rather than an empty stream.
+        let listed = if opts.follow {
+            None
+        } else {
+            Some(
+                api.list(&ListParams::default().labels(&selector))
+                    .await?
+                    .items,
+            )
+        };
+
+        let cap = std::env::var("ORKA_OPS_QUEUE_CAP")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(1024);
+        let (tx, rx) = mpsc::channel::<LogChunk>(cap);
+        let (cancel_tx, mut cancel_rx) = oneshot::channel::<()>();
+        let cancel = CancelHandle {
+            tx: Some(cancel_tx),
+        };
+
+        let mut agg = PodLogs {
+            api: api.clone(),
+            tx,
+            opts,
+            only: container.map(|s| s.to_string()),
+            streams: HashMap::new(),
+        };
+        let ns = namespace.to_string();
         tokio::spawn(async move {
-            use tokio_util::{compat::FuturesAsyncReadCompatExt, io::ReaderStream};
-            info!(pod = %pod_name, container = ?container_label, follow = lp.follow, tail = ?lp.tail_lines, since = ?lp.since_seconds, "logs stream starting");
-            let stream_res = api.log_stream(&pod_name, &lp).await;
-            let reader = match stream_res {
-                Ok(r) => r,
-                Err(e) => {
-                    warn!(error = %e, "log_stream failed to open");
-                    return;
+            info!(ns = %ns, selector = %selector, follow = agg.opts.follow, "aggregated logs starting");
+            if let Some(pods) = listed {
+                pods.iter().for_each(|p| agg.reconcile(p, true));
+                let tasks = agg.streams.values_mut().map(|s| &mut s.task);
+                tokio::select! {
+                    _ = &mut cancel_rx => {}
+                    _ = futures::future::join_all(tasks) => {}
                 }
-            };
-            // Convert futures::io::AsyncRead into tokio::io::AsyncRead, then into a bytes Stream
-            let compat_reader = reader.compat();
-            let stream = ReaderStream::new(compat_reader);
-            pump_bytes_to_lines(stream, tx, cancel_rx, Some(&pod_name)).await;
+                info!(ns = %ns, "aggregated logs ended");
+                return;
+            }
+            let stream = watcher::watcher(api, watcher::Config::default().labels(&selector)).fuse();
+            futures::pin_mut!(stream);
+            let mut initial = true;
+            loop {
+                tokio::select! {
+                    _ = &mut cancel_rx => break,
+                    _ = agg.tx.closed() => break,
+                    ev = stream.next() => match ev {
+                        Some(Ok(Event::Applied(p))) => agg.reconcile(&p, false),
+                        Some(Ok(Event::Deleted(p))) => {
+                            if let Some(name) = p.metadata.name.as_deref() {
+                                agg.forget(|pod| pod == name);
+                            }
+                        }
+                        Some(Ok(Event::Restarted(list))) => {
+                            let names: std::collections::HashSet<&str> =
+                                list.iter().filter_map(|p| p.metadata.name.as_deref()).collect();
+                            agg.forget(|pod| !names.contains(pod));
+                            list.iter().for_each(|p| agg.reconcile(p, initial));
+                            initial = false;
+                        }
+                        Some(Err(e)) => {
+                            warn!(error = %e, "aggregated logs: pod watch error; retrying");
+                            tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+                        }
+                        None => break,
+                    },
+                }
+            }
+            // Dropping `agg` cancels every per-container stream.
+            info!(ns = %ns, "aggregated logs ended");
         });
 
         Ok(StreamHandle { rx, cancel })
@@ -701,7 +838,7 @@ impl OrkaOps for KubeOps {
     ) -> Result<()> {
         use kube::core::{DynamicObject, GroupVersionKind};
         let client = orka_kubehub::get_kube_client().await?;
-        let (group, version, kind) = parse_gvk_key(gvk_key)?;
+        let (group, version, kind) = parse_gvk_key(&orka_kubehub::resolve_gvk_key(gvk_key).await?)?;
         let gvk = GroupVersionKind {
             group,
             version,
@@ -741,7 +878,7 @@ impl OrkaOps for KubeOps {
     ) -> Result<()> {
         use kube::core::{DynamicObject, GroupVersionKind};
         let client = orka_kubehub::get_kube_client().await?;
-        let (group, version, kind) = parse_gvk_key(gvk_key)?;
+        let (group, version, kind) = parse_gvk_key(&orka_kubehub::resolve_gvk_key(gvk_key).await?)?;
         let gvk = GroupVersionKind {
             group,
             version,
@@ -793,12 +930,6 @@ impl OrkaOps for KubeOps {
     }
 
     async fn drain(&self, node: &str) -> Result<()> {
-        use k8s_openapi::api::core::v1::Pod;
-        use std::collections::HashSet;
-        let client = orka_kubehub::get_kube_client().await?;
-        let all_pods: Api<Pod> = Api::all(client.clone());
-        let lp = ListParams::default().fields(&format!("spec.nodeName={}", node));
-
         let timeout_secs: u64 = std::env::var("ORKA_DRAIN_TIMEOUT_SECS")
             .ok()
             .and_then(|s| s.parse().ok())
@@ -807,7 +938,35 @@ impl OrkaOps for KubeOps {
             .ok()
             .and_then(|s| s.parse().ok())
             .unwrap_or(2);
-        let deadline = std::time::Instant::now() + std::time::Duration::from_secs(timeout_secs);
+        KubeOps::drain_with(
+            node,
+            std::time::Duration::from_secs(timeout_secs),
+            std::time::Duration::from_secs(poll_secs),
+        )
+        .await
+    }
+
+    async fn caps(&self, namespace: Option<&str>, scale_gvk: Option<&str>) -> Result<OpsCaps> {
+        KubeOps::discover_caps(namespace, scale_gvk).await
+    }
+}
+
+impl KubeOps {
+    /// [`OrkaOps::drain`] with explicit timing: evict every pod on `node`
+    /// (except DaemonSet and mirror pods), then re-list and retry evictions
+    /// blocked by a PodDisruptionBudget every `poll` until the node is empty
+    /// or `timeout` passes.
+    pub async fn drain_with(
+        node: &str,
+        timeout: std::time::Duration,
+        poll: std::time::Duration,
+    ) -> Result<()> {
+        use k8s_openapi::api::core::v1::Pod;
+        use std::collections::HashSet;
+        let client = orka_kubehub::get_kube_client().await?;
+        let all_pods: Api<Pod> = Api::all(client.clone());
+        let lp = ListParams::default().fields(&format!("spec.nodeName={}", node));
+        let deadline = std::time::Instant::now() + timeout;
 
         // Helper to filter target pods for eviction
         let list_target = || async {
@@ -886,14 +1045,10 @@ impl OrkaOps for KubeOps {
                     Err(_e) => { /* best-effort; try again next round */ }
                 }
             }
-            tokio::time::sleep(std::time::Duration::from_secs(poll_secs)).await;
+            tokio::time::sleep(poll).await;
         }
         Ok(())
     }
-
-    async fn caps(&self, namespace: Option<&str>, scale_gvk: Option<&str>) -> Result<OpsCaps> {
-        KubeOps::discover_caps(namespace, scale_gvk).await
-    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -981,23 +1136,202 @@ impl KubeOps {
     }
 }
 
+/// Internal: open a pod's log stream and pump it into `tx` until it ends or
+/// `cancel_rx` fires (or its sender is dropped).
+fn spawn_pod_logs(
+    api: Api<k8s_openapi::api::core::v1::Pod>,
+    pod: String,
+    lp: LogParams,
+    tx: mpsc::Sender<LogChunk>,
+    cancel_rx: oneshot::Receiver<()>,
+) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        use tokio_util::{compat::FuturesAsyncReadCompatExt, io::ReaderStream};
+        info!(pod = %pod, container = ?lp.container, follow = lp.follow, tail = ?lp.tail_lines, since = ?lp.since_seconds, "logs stream starting");
+        let stream_res = api.log_stream(&pod, &lp).await;
+        let reader = match stream_res {
+            Ok(r) => r,
+            Err(e) => {
+                warn!(pod = %pod, error = %e, "log_stream failed to open");
+                return;
+            }
+        };
+        // Convert futures::io::AsyncRead into tokio::io::AsyncRead, then into a bytes Stream
+        let compat_reader = reader.compat();
+        let stream = ReaderStream::new(compat_reader);
+        pump_bytes_to_lines(stream, tx, cancel_rx, Some(&pod), lp.container.as_deref()).await;
+    })
+}
+
+/// One followed container of an aggregated log stream.
+struct ContainerLogs {
+    restarts: i32,
+    task: tokio::task::JoinHandle<()>,
+    _cancel: CancelHandle,
+}
+
+/// Per-(pod, container) log streams behind `logs_aggregate`, all feeding one
+/// channel. Dropping an entry cancels its stream.
+struct PodLogs {
+    api: Api<k8s_openapi::api::core::v1::Pod>,
+    tx: mpsc::Sender<LogChunk>,
+    opts: LogOptions,
+    only: Option<String>,
+    streams: HashMap<(String, String), ContainerLogs>,
+}
+
+impl PodLogs {
+    /// Start streams for containers of `pod` that are new or have restarted.
+    /// `tail_lines`/`since_seconds` only apply to containers that were already
+    /// running when the stream started (`initial`); later ones are read whole.
+    fn reconcile(&mut self, pod: &k8s_openapi::api::core::v1::Pod, initial: bool) {
+        let Some(name) = pod.metadata.name.as_deref() else {
+            return;
+        };
+        for (container, restarts) in started_containers(pod, self.only.as_deref()) {
+            let key = (name.to_string(), container);
+            let fresh = match self.streams.get(&key) {
+                Some(s) if s.restarts >= restarts => continue,
+                Some(_) => true,
+                None => !initial,
+            };
+            let lp = LogParams {
+                follow: self.opts.follow,
+                container: Some(key.1.clone()),
+                tail_lines: if fresh { None } else { self.opts.tail_lines },
+                since_seconds: if fresh { None } else { self.opts.since_seconds },
+                ..Default::default()
+            };
+            let (cancel_tx, cancel_rx) = oneshot::channel::<()>();
+            let task = spawn_pod_logs(
+                self.api.clone(),
+                key.0.clone(),
+                lp,
+                self.tx.clone(),
+                cancel_rx,
+            );
+            self.streams.insert(
+                key,
+                ContainerLogs {
+                    restarts,
+                    task,
+                    _cancel: CancelHandle {
+                        tx: Some(cancel_tx),
+                    },
+                },
+            );
+        }
+    }
+
+    /// Stop streams of pods matching `gone`.
+    fn forget(&mut self, gone: impl Fn(&str) -> bool) {
+        self.streams.retain(|(pod, _), _| !gone(pod));
+    }
+}
+
+/// Containers of `pod` (or just `only`) that have started and so have logs,
+/// with their restart counts.
+fn started_containers(
+    pod: &k8s_openapi::api::core::v1::Pod,
+    only: Option<&str>,
+) -> Vec<(String, i32)> {
+    let statuses = pod
+        .status
+        .as_ref()
+        .and_then(|s| s.container_statuses.as_ref());
+    statuses
+        .into_iter()
+        .flatten()
+        .filter(|cs| only.is_none() || only == Some(cs.name.as_str()))
+        .filter(|cs| {
+            cs.state
+                .as_ref()
+                .is_some_and(|st| st.running.is_some() || st.terminated.is_some())
+        })
+        .map(|cs| (cs.name.clone(), cs.restart_count))
+        .collect()
+}
+
+/// `(group, version, kind)` of a workload kind as `kubectl` spells it.
+fn workload_gvk(kind: &str) -> Result<(&'static str, &'static str, &'static str)> {
+    Ok(match kind.to_ascii_lowercase().as_str() {
+        "deploy" | "deployment" | "deployments" => ("apps", "v1", "Deployment"),
+        "sts" | "statefulset" | "statefulsets" => ("apps", "v1", "StatefulSet"),
+        "ds" | "daemonset" | "daemonsets" => ("apps", "v1", "DaemonSet"),
+        "rs" | "replicaset" | "replicasets" => ("apps", "v1", "ReplicaSet"),
+        "job" | "jobs" => ("batch", "v1", "Job"),
+        "svc" | "service" | "services" => ("", "v1", "Service"),
+        _ => {
+            return Err(anyhow!(
+                "unsupported workload kind: {} (expect deploy, sts, ds, rs, job or svc)",
+                kind
+            ))
+        }
+    })
+}
+
+/// Label selector string for `spec.selector` of a workload object. Services
+/// carry a plain label map; the other kinds a `LabelSelector`.
+fn selector_from_object(kind: &str, obj: &serde_json::Value) -> Result<String> {
+    let sel = obj.pointer("/spec/selector");
+    let (labels, exprs) = if kind == "Service" {
+        (sel, None)
+    } else {
+        (
+            sel.and_then(|s| s.get("matchLabels")),
+            sel.and_then(|s| s.get("matchExpressions")),
+        )
+    };
+    let mut parts: Vec<String> = Vec::new();
+    for (k, v) in labels.and_then(|v| v.as_object()).into_iter().flatten() {
+        parts.push(format!("{}={}", k, v.as_str().unwrap_or_default()));
+    }
+    for e in exprs.and_then(|v| v.as_array()).into_iter().flatten() {
+        let key = e
+            .get("key")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| anyhow!("matchExpressions entry without key"))?;
+        let values = e
+            .get("values")
+            .and_then(|v| v.as_array())
+            .map(|vs| vs.iter().filter_map(|v| v.as_str()).collect::<Vec<_>>())
+            .unwrap_or_default()
+            .join(",");
+        parts.push(match e.get("operator").and_then(|v| v.as_str()) {
+            Some("In") => format!("{} in ({})", key, values),
+            Some("NotIn") => format!("{} notin ({})", key, values),
+            Some("Exists") => key.to_string(),
+            Some("DoesNotExist") => format!("!{}", key),
+            op => return Err(anyhow!("unsupported selector operator: {:?}", op)),
+        });
+    }
+    Ok(parts.join(","))
+}
+
 /// Internal: consume a stream of bytes, split into lines, send via bounded channel.
-/// Drops lines when channel is full. Flushes last partial line on end.
+/// Drops lines when channel is full. Flushes last partial line on end. Lines
+/// are tagged with `pod` and `container`.
 async fn pump_bytes_to_lines<S, E>(
     stream: S,
     tx: mpsc::Sender<LogChunk>,
     mut cancel_rx: oneshot::Receiver<()>,
-    ctx: Option<&str>,
+    pod: Option<&str>,
+    container: Option<&str>,
 ) where
     S: futures::Stream<Item = Result<bytes::Bytes, E>>,
     E: std::fmt::Display,
 {
+    let chunk_of = |line: &str| LogChunk {
+        line: line.to_string(),
+        pod: pod.map(|s| s.to_string()),
+        container: container.map(|s| s.to_string()),
+    };
     let stream = stream.fuse();
     futures::pin_mut!(stream);
     let mut buf = bytes::BytesMut::new();
     loop {
         tokio::select! {
-            _ = &mut cancel_rx => { if let Some(c) = ctx { info!(ctx = %c, "log pump cancelled"); } break; }
+            _ = &mut cancel_rx => { if let Some(c) = pod { info!(pod = %c, "log pump cancelled"); } break; }
             next = stream.next() => {
                 match next {
                     Some(Ok(chunk)) => {
@@ -1006,11 +1340,11 @@ async fn pump_bytes_to_lines<S, E>(
                             let line = buf.split_to(pos);
                             let _ = buf.split_to(1); // drop '\n'
                             if let Ok(s) = std::str::from_utf8(&line) {
-                                let _ = tx.try_send(LogChunk { line: s.to_string() });
+                                let _ = tx.try_send(chunk_of(s));
                             }
                         }
                     }
-                    Some(Err(e)) => { if let Some(c) = ctx { warn!(ctx = %c, error = %e, "log stream error"); } else { warn!(error = %e, "log stream error"); } break; }
+                    Some(Err(e)) => { if let Some(c) = pod { warn!(pod = %c, error = %e, "log stream error"); } else { warn!(error = %e, "log stream error"); } break; }
                     None => break,
                 }
             }
@@ -1018,13 +1352,11 @@ async fn pump_bytes_to_lines<S, E>(
     }
     if !buf.is_empty() {
         if let Ok(s) = std::str::from_utf8(&buf) {
-            let _ = tx.try_send(LogChunk {
-                line: s.to_string(),
-            });
+            let _ = tx.try_send(chunk_of(s));
         }
     }
-    if let Some(c) = ctx {
-        info!(ctx = %c, "log pump ended");
+    if let Some(c) = pod {
+        info!(pod = %c, "log pump ended");
     } else {
         info!("log pump ended");
     }
@@ -1083,7 +1415,7 @@ mod tests {
             Ok::<bytes::Bytes, std::io::Error>(bytes::Bytes::from_static(b"tail")),
         ];
         let s = stream::iter(chunks);
-        pump_bytes_to_lines(s, tx, cancel_rx, Some("test")).await;
+        pump_bytes_to_lines(s, tx, cancel_rx, Some("test"), None).await;
         let mut out = Vec::new();
         while let Some(c) = rx.recv().await {
             out.push(c.line);
@@ -1101,7 +1433,7 @@ mod tests {
             Ok::<bytes::Bytes, std::io::Error>(bytes::Bytes::from_static(b"c\n")),
         ];
         let s = stream::iter(lines);
-        pump_bytes_to_lines(s, tx, cancel_rx, Some("drop-test")).await;
+        pump_bytes_to_lines(s, tx, cancel_rx, Some("drop-test"), None).await;
         // We expect at least 1 line (the first), subsequent may be dropped due to full channel
         let mut recv = Vec::new();
         while let Ok(Some(c)) =
@@ -1128,7 +1460,13 @@ mod tests {
                 yield Ok::<bytes::Bytes, std::io::Error>(bytes::Bytes::from_static(b"line\n"));
             }
         };
-        let handle = tokio::spawn(pump_bytes_to_lines(s, tx, cancel_rx, Some("cancel-test")));
+        let handle = tokio::spawn(pump_bytes_to_lines(
+            s,
+            tx,
+            cancel_rx,
+            Some("cancel-test"),
+            None,
+        ));
         // Cancel shortly after
         tokio::time::sleep(std::time::Duration::from_millis(120)).await;
         let _ = cancel_tx.send(());
@@ -1162,4 +1500,177 @@ mod tests {
         assert!(parse_gvk_key("").is_err());
         assert!(parse_gvk_key("a/b/c/d").is_err());
     }
+
+    #[test]
+    fn selector_from_workload_and_service_specs() {
+        let deploy = serde_json::json!({"spec": {"selector": {
+            "matchLabels": {"app": "web"},
+            "matchExpressions": [
+                {"key": "tier", "operator": "In", "values": ["fe", "be"]},
+                {"key": "canary", "operator": "DoesNotExist"}
+            ]
+        }}});
+        assert_eq!(
+            selector_from_object("Deployment", &deploy).unwrap(),
+            "app=web,tier in (fe,be),!canary"
+        );
+        let svc = serde_json::json!({"spec": {"selector": {"app": "web"}}});
+        assert_eq!(selector_from_object("Service", &svc).unwrap(), "app=web");
+        let headless = serde_json::json!({"spec": {}});
+        assert_eq!(selector_from_object("Service", &headless).unwrap(), "");
+
+        assert_eq!(
+            LogTarget::workload("sts/db").unwrap(),
+            LogTarget::Workload {
+                kind: "sts".into(),
+                name: "db".into()
+            }
+        );
+        assert!(LogTarget::workload("cronjob/x").is_err());
+        assert!(LogTarget::workload("web").is_err());
+    }
+
+    #[test]
+    fn started_containers_skip_waiting_and_report_restarts() {
+        let pod: k8s_openapi::api::core::v1::Pod = serde_json::from_value(serde_json::json!({
+            "metadata": {"name": "web-1"},
+            "status": {"containerStatuses": [
+                {"name": "app", "image": "x", "imageID": "", "ready": true, "restartCount": 2,
+                 "state": {"running": {}}},
+                {"name": "sidecar", "image": "x", "imageID": "", "ready": false, "restartCount": 0,
+                 "state": {"waiting": {"reason": "ContainerCreating"}}},
+                {"name": "init-done", "image": "x", "imageID": "", "ready": false, "restartCount": 0,
+                 "state": {"terminated": {"exitCode": 0}}}
+            ]}
+        }))
+        .unwrap();
+        assert_eq!(
+            started_containers(&pod, None),
+            vec![("app".to_string(), 2), ("init-done".to_string(), 0)]
+        );
+        assert_eq!(started_containers(&pod, Some("sidecar")), vec![]);
+    }
+
+    fn running_pod(name: &str) -> serde_json::Value {
+        serde_json::json!({
+            "apiVersion": "v1", "kind": "Pod",
+            "metadata": {"name": name, "namespace": "default", "labels": {"app": "web"}},
+            "spec": {"containers": [{"name": "app", "image": "nginx"}]},
+            "status": {"containerStatuses": [{
+                "name": "app", "image": "nginx", "imageID": "", "ready": true,
+                "restartCount": 0, "state": {"running": {}}
+            }]}
+        })
+    }
+
+    /// Next chunk as `(pod, container, line)`.
+    async fn next(rx: &mut mpsc::Receiver<LogChunk>) -> (String, String, String) {
+        let c = tokio::time::timeout(std::time::Duration::from_secs(10), rx.recv())
+            .await
+            .expect("log line in time")
+            .expect("stream open");
+        (
+            c.pod.unwrap_or_default(),
+            c.container.unwrap_or_default(),
+            c.line,
+        )
+    }
+
+    #[tokio::test]
+    async fn aggregated_logs_pick_up_pods_created_later() {
+        use orka_kubehub::fake::FakeApiServer;
+        let srv = FakeApiServer::new();
+        let _guard = srv.install();
+        srv.set_logs("default", "web-1", "one\n");
+        srv.create(running_pod("web-1"));
+
+        let opts = LogOptions {
+            follow: true,
+            ..Default::default()
+        };
+        let target = LogTarget::Selector("app=web".into());
+        let mut h = KubeOps::new()
+            .logs_aggregate("default", &target, None, opts)
+            .await
+            .unwrap();
+        let first = next(&mut h.rx).await;
+        assert_eq!(first, ("web-1".into(), "app".into(), "one".into()));
+
+        // Started after the stream: found through the pod watch.
+        srv.set_logs("default", "web-2", "two\n");
+        srv.create(running_pod("web-2"));
+        let second = next(&mut h.rx).await;
+        assert_eq!(second, ("web-2".into(), "app".into(), "two".into()));
+        h.cancel.cancel();
+    }
+
+    #[tokio::test]
+    async fn aggregated_logs_without_follow_report_list_errors() {
+        use orka_kubehub::fake::FakeApiServer;
+        let srv = FakeApiServer::new();
+        let _guard = srv.install();
+        srv.fail_next("GET", "/namespaces/default/pods", 403, 1);
+        let target = LogTarget::Selector("app=web".into());
+        let res = KubeOps::new()
+            .logs_aggregate("default", &target, None, LogOptions::default())
+            .await;
+        assert!(res.is_err());
+    }
+
+    #[tokio::test]
+    async fn drain_retries_evictions_blocked_by_pdb() {
+        use orka_kubehub::fake::FakeApiServer;
+        use serde_json::json;
+        let srv = FakeApiServer::new();
+        let _guard = srv.install();
+        srv.create(json!({
+            "apiVersion": "v1", "kind": "Pod",
+            "metadata": {"name": "web-0", "namespace": "default", "labels": {"app": "web"}},
+            "spec": {"nodeName": "n1", "containers": [{"name": "c", "image": "nginx"}]}
+        }));
+        srv.create(json!({
+            "apiVersion": "v1", "kind": "Pod",
+            "metadata": {
+                "name": "agent-x", "namespace": "default",
+                "ownerReferences": [{"apiVersion": "apps/v1", "kind": "DaemonSet", "name": "agent", "uid": "1"}]
+            },
+            "spec": {"nodeName": "n1", "containers": [{"name": "c", "image": "agent"}]}
+        }));
+        srv.create(json!({
+            "apiVersion": "policy/v1", "kind": "PodDisruptionBudget",
+            "metadata": {"name": "web", "namespace": "default"},
+            "spec": {"selector": {"matchLabels": {"app": "web"}}},
+            "status": {"disruptionsAllowed": 0}
+        }));
+
+        let drain = tokio::spawn(async {
+            KubeOps::drain_with(
+                "n1",
+                std::time::Duration::from_secs(300),
+                std::time::Duration::ZERO,
+            )
+            .await
+        });
+        while srv.count_requests("POST", "/pods/web-0/eviction") < 2 {
+            assert!(
+                !drain.is_finished(),
+                "drain finished while PDB blocks eviction"
+            );
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+        let mut pdb = srv
+            .get("policy/v1/PodDisruptionBudget", Some("default"), "web")
+            .unwrap();
+        pdb["status"]["disruptionsAllowed"] = json!(1);
+        srv.update(pdb);
+
+        tokio::time::timeout(std::time::Duration::from_secs(10), drain)
+            .await
+            .expect("drain completes once the PDB allows it")
+            .unwrap()
+            .expect("drain ok");
+        assert!(srv.get("v1/Pod", Some("default"), "web-0").is_none());
+        assert!(srv.get("v1/Pod", Some("default"), "agent-x").is_some());
+        assert_eq!(srv.count_requests("POST", "/pods/agent-x/eviction"), 0);
+    }
 }
@@ -0,0 +1,482 @@
+//! Export and import of history as a portable archive.
+//!
+//! An archive is an uncompressed tar (readable with `tar -xf`) holding one
+//! YAML file per snapshot, `records/<uid>/<ts>-<n>.yaml`, and a
+//! `manifest.json` listing each file with its UID, rv, timestamp, object
+//! key and SHA-256. Snapshots are written decrypted and decompressed, so
+//! Secrets are left out unless asked for, and only imported into an
+//! encrypted store or when asked for. Importing skips snapshots the store
+//! already holds (same UID, timestamp, rv and content).
+
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
+use std::io::{Read, Write};
+use tracing::info;
+
+use crate::{LastApplied, LogStore, ObjectKey, Store};
+
+const FORMAT: &str = "orka-history/1";
+const MANIFEST: &str = "manifest.json";
+const BLOCK: usize = 512;
+
+/// Which snapshots to export; empty fields match everything. Snapshots
+/// recorded without an object key only match when no namespace or GVK is
+/// given.
+#[derive(Debug, Clone, Default)]
+pub struct ExportFilter {
+    pub namespace: Option<String>,
+    pub gvk: Option<String>,
+    /// Unix seconds, inclusive.
+    pub since: Option<i64>,
+    /// Unix seconds, exclusive.
+    pub until: Option<i64>,
+    /// Include `v1/Secret` snapshots (written in plain text).
+    pub secrets: bool,
+}
+
+impl ExportFilter {
+    fn matches(&self, la: &LastApplied) -> bool {
+        let key = la.key.as_ref();
+        if key.is_some_and(|k| k.gvk == "v1/Secret") && !self.secrets {
+            return false;
+        }
+        if self.since.is_some_and(|t| la.ts < t) || self.until.is_some_and(|t| la.ts >= t) {
+            return false;
+        }
+        let gvk = key.map(|k| &k.gvk);
+        let ns = key.and_then(|k| k.namespace.as_ref());
+        !(self.gvk.as_ref().is_some_and(|g| gvk != Some(g))
+            || self.namespace.as_ref().is_some_and(|n| ns != Some(n)))
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Manifest {
+    pub format: String,
+    pub exported_at: i64,
+    pub entries: Vec<ManifestEntry>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ManifestEntry {
+    pub path: String,
+    pub uid: String,
+    pub rv: String,
+    pub ts: i64,
+    pub key: Option<ObjectKey>,
+    pub sha256: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ExportStats {
+    pub objects: usize,
+    pub records: usize,
+    pub bytes: u64,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ImportStats {
+    pub imported: usize,
+    /// Already in the store.
+    pub duplicates: usize,
+    /// Secrets left out: the store is not encrypted and they were not asked for.
+    pub skipped: usize,
+}
+
+impl LogStore {
+    /// Write the snapshots matching `filter` (all retained ones per UID) as
+    /// an archive to `out`.
+    pub fn export(&self, filter: &ExportFilter, out: impl Write) -> Result<ExportStats> {
+        let mut tar = TarWriter::new(out);
+        let mut stats = ExportStats::default();
+        let mut entries = Vec::new();
+        let mut uids = self.uids();
+        uids.sort();
+        for uid in uids {
+            let mut rows = self.get_last(uid, Some(usize::MAX))?;
+            rows.retain(|la| filter.matches(la));
+            if rows.is_empty() {
+                continue;
+            }
+            stats.objects += 1;
+            // Oldest first so indices are stable across exports.
+            for (n, la) in rows.into_iter().rev().enumerate() {
+                let yaml = crate::maybe_decompress(&la.yaml_zstd);
+                let path = format!("records/{}/{}-{}.yaml", fmt_uid(&uid), la.ts, n);
+                tar.append(&path, la.ts, yaml.as_bytes())?;
+                stats.records += 1;
+                stats.bytes += yaml.len() as u64;
+                entries.push(ManifestEntry {
+                    path,
+                    uid: fmt_uid(&uid),
+                    rv: la.rv,
+                    ts: la.ts,
+                    key: la.key,
+                    sha256: sha256_hex(yaml.as_bytes()),
+                });
+            }
+        }
+        let manifest = Manifest {
+            format: FORMAT.to_string(),
+            exported_at: crate::now_ts(),
+            entries,
+        };
+        let json = serde_json::to_vec_pretty(&manifest)?;
+        tar.append(MANIFEST, manifest.exported_at, &json)?;
+        tar.finish()?;
+        info!(
+            objects = stats.objects,
+            records = stats.records,
+            "persist: exported history"
+        );
+        Ok(stats)
+    }
+
+    /// Add the snapshots of an archive written by [`LogStore::export`],
+    /// skipping those already present. `v1/Secret` snapshots are only
+    /// written when the store is encrypted or `include_secrets` is set.
+    /// Every file is checked against the manifest before anything is
+    /// written.
+    pub fn import(&self, input: impl Read, include_secrets: bool) -> Result<ImportStats> {
+        let mut files = read_tar(input)?;
+        let manifest: Manifest = serde_json::from_slice(
+            &files
+                .remove(MANIFEST)
+                .ok_or_else(|| anyhow!("archive has no {}", MANIFEST))?,
+        )
+        .context("parsing manifest")?;
+        if manifest.format != FORMAT {
+            return Err(anyhow!("unsupported archive format {:?}", manifest.format));
+        }
+        let mut records = Vec::with_capacity(manifest.entries.len());
+        for e in manifest.entries {
+            let yaml = files
+                .remove(&e.path)
+                .ok_or_else(|| anyhow!("archive is missing {}", e.path))?;
+            if sha256_hex(&yaml) != e.sha256 {
+                return Err(anyhow!("checksum mismatch for {}", e.path));
+            }
+            let uid = parse_uid(&e.uid).ok_or_else(|| anyhow!("bad uid {:?}", e.uid))?;
+            records.push((uid, e, yaml));
+        }
+        records.sort_by_key(|(_, e, _)| e.ts);
+
+        let mut stats = ImportStats::default();
+        let keep_secrets = include_secrets || self.encrypted();
+        let mut present: HashMap<[u8; 16], HashSet<(i64, String, String)>> = HashMap::new();
+        for (uid, e, yaml) in records {
+            if !keep_secrets && is_secret(e.key.as_ref(), &yaml) {
+                stats.skipped += 1;
+                continue;
+            }
+            let have = match present.entry(uid) {
+                std::collections::hash_map::Entry::Occupied(e) => e.into_mut(),
+                std::collections::hash_map::Entry::Vacant(v) => v.insert(
+                    self.get_last(uid, Some(usize::MAX))?
+                        .into_iter()
+                        .map(|la| {
+                            let yaml = crate::maybe_decompress(&la.yaml_zstd);
+                            (la.ts, la.rv, sha256_hex(yaml.as_bytes()))
+                        })
+                        .collect(),
+                ),
+            };
+            if !have.insert((e.ts, e.rv.clone(), e.sha256.clone())) {
+                stats.duplicates += 1;
+                continue;
+            }
+            self.put_last(LastApplied {
+                uid,
+                rv: e.rv,
+                ts: e.ts,
+                yaml_zstd: crate::maybe_compress(&String::from_utf8_lossy(&yaml)),
+                key: e.key,
+            })?;
+            stats.imported += 1;
+        }
+        info!(
+            imported = stats.imported,
+            duplicates = stats.duplicates,
+            skipped = stats.skipped,
+            "persist: imported history"
+        );
+        Ok(stats)
+    }
+}
+
+/// Whether a snapshot is a `v1/Secret`, by its key or, for snapshots
+/// recorded without one, by the document's top-level `kind`.
+fn is_secret(key: Option<&ObjectKey>, yaml: &[u8]) -> bool {
+    match key {
+        Some(k) => k.gvk == "v1/Secret",
+        None => String::from_utf8_lossy(yaml)
+            .lines()
+            .any(|l| l.trim_end() == "kind: Secret"),
+    }
+}
+
+fn sha256_hex(bytes: &[u8]) -> String {
+    Sha256::digest(bytes)
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+/// Hyphenated UUID form, as shown everywhere else.
+fn fmt_uid(uid: &[u8; 16]) -> String {
+    let hex: String = uid.iter().map(|b| format!("{:02x}", b)).collect();
+    format!(
+        "{}-{}-{}-{}-{}",
+        &hex[..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..]
+    )
+}
+
+fn parse_uid(s: &str) -> Option<[u8; 16]> {
+    let hex: String = s.chars().filter(|c| *c != '-').collect();
+    if hex.len() != 32 {
+        return None;
+    }
+    let mut uid = [0u8; 16];
+    for (i, b) in uid.iter_mut().enumerate() {
+        *b = u8::from_str_radix(hex.get(2 * i..2 * i + 2)?, 16).ok()?;
+    }
+    Some(uid)
+}
+
+/// Minimal ustar writer: regular files with names under 100 bytes.
+struct TarWriter<W: Write> {
+    out: W,
+}
+
+impl<W: Write> TarWriter<W> {
+    fn new(out: W) -> Self {
+        Self { out }
+    }
+
+    fn append(&mut self, path: &str, mtime: i64, data: &[u8]) -> Result<()> {
+        if path.len() >= 100 {
+            return Err(anyhow!("archive path too long: {}", path));
+        }
+        let mut hdr = [0u8; BLOCK];
+        hdr[..path.len()].copy_from_slice(path.as_bytes());
+        octal(&mut hdr[100..108], 0o644);
+        octal(&mut hdr[108..116], 0);
+        octal(&mut hdr[116..124], 0);
+        octal(&mut hdr[124..136], data.len() as u64);
+        octal(&mut hdr[136..148], mtime.max(0) as u64);
+        hdr[156] = b'0';
+        hdr[257..263].copy_from_slice(b"ustar\0");
+        hdr[263..265].copy_from_slice(b"00");
+        hdr[148..156].fill(b' ');
+        let sum: u32 = hdr.iter().map(|b| *b as u32).sum();
+        octal(&mut hdr[148..155], sum as u64);
+        self.out.write_all(&hdr)?;
+        self.out.write_all(data)?;
+        let pad = (BLOCK - data.len() % BLOCK) % BLOCK;
+        self.out.write_all(&[0u8; BLOCK][..pad])?;
+        Ok(())
+    }
+
+    fn finish(mut self) -> Result<()> {
+        self.out.write_all(&[0u8; 2 * BLOCK])?;
+        self.out.flush()?;
+        Ok(())
+    }
+}
+
+/// Zero-padded octal with a trailing NUL, filling `field`.
+fn octal(field: &mut [u8], v: u64) {
+    let s = format!("{:0width$o}\0", v, width = field.len() - 1);
+    field.copy_from_slice(s.as_bytes());
+}
+
+fn parse_octal(field: &[u8]) -> Option<u64> {
+    let s = std::str::from_utf8(field).ok()?;
+    let s = s.trim_matches(|c: char| c == '\0' || c == ' ');
+    if s.is_empty() {
+        return Some(0);
+    }
+    u64::from_str_radix(s, 8).ok()
+}
+
+/// Regular files of a tar stream by path; other entry types are skipped.
+fn read_tar(mut input: impl Read) -> Result<HashMap<String, Vec<u8>>> {
+    let mut files = HashMap::new();
+    let mut hdr = [0u8; BLOCK];
+    loop {
+        input
+            .read_exact(&mut hdr)
+            .context("reading archive: unexpected end")?;
+        if hdr.iter().all(|b| *b == 0) {
+            return Ok(files);
+        }
+        let stored = parse_octal(&hdr[148..156]).ok_or_else(|| anyhow!("bad tar header"))?;
+        let sum: u64 = hdr
+            .iter()
+            .enumerate()
+            .map(|(i, b)| {
+                if (148..156).contains(&i) {
+                    32
+                } else {
+                    *b as u64
+                }
+            })
+            .sum();
+        if sum != stored {
+            return Err(anyhow!("tar header checksum mismatch"));
+        }
+        let field = |r: std::ops::Range<usize>| {
+            let f = &hdr[r];
+            let end = f.iter().position(|b| *b == 0).unwrap_or(f.len());
+            String::from_utf8_lossy(&f[..end]).to_string()
+        };
+        let (name, prefix) = (field(0..100), field(345..500));
+        let path = if prefix.is_empty() {
+            name
+        } else {
+            format!("{}/{}", prefix, name)
+        };
+        let size = parse_octal(&hdr[124..136]).ok_or_else(|| anyhow!("bad tar size"))?;
+        let regular = matches!(hdr[156], b'0' | 0);
+        // The header's size is untrusted: read what is there, up to it.
+        let mut data = Vec::new();
+        input
+            .by_ref()
+            .take(size)
+            .read_to_end(&mut data)
+            .with_context(|| format!("reading {} from archive", path))?;
+        if data.len() as u64 != size {
+            return Err(anyhow!("reading {} from archive: unexpected end", path));
+        }
+        let pad = (BLOCK - data.len() % BLOCK) % BLOCK;
+        input
+            .read_exact(&mut hdr[..pad])
+            .with_context(|| format!("reading {} from archive", path))?;
+        if regular {
+            files.insert(path, data);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::{retention, temp_dir};
+
+    fn store() -> (LogStore, std::path::PathBuf) {
+        let dir = temp_dir();
+        (LogStore::open_with(&dir, retention(0)).unwrap(), dir)
+    }
+
+    fn rec(uid: u8, ts: i64, gvk: &str, ns: &str) -> LastApplied {
+        LastApplied {
+            uid: [uid; 16],
+            rv: format!("rv-{}", ts),
+            ts,
+            yaml_zstd: crate::maybe_compress(&format!("kind: x\nts: {}\n", ts)),
+            key: Some(ObjectKey {
+                cluster: "dev".into(),
+                gvk: gvk.into(),
+                namespace: Some(ns.into()),
+                name: format!("o{}", uid),
+            }),
+        }
+    }
+
+    #[test]
+    fn export_filters_and_import_dedupes() {
+        let (src, src_dir) = store();
+        for ts in [10, 20, 30] {
+            src.put_last(rec(1, ts, "v1/ConfigMap", "shop")).unwrap();
+        }
+        src.put_last(rec(2, 15, "apps/v1/Deployment", "shop"))
+            .unwrap();
+        src.put_last(rec(3, 25, "v1/ConfigMap", "other")).unwrap();
+        src.put_last(rec(4, 25, "v1/Secret", "shop")).unwrap();
+
+        let filter = ExportFilter {
+            namespace: Some("shop".into()),
+            since: Some(15),
+            ..Default::default()
+        };
+        let mut archive = Vec::new();
+        let stats = src.export(&filter, &mut archive).unwrap();
+        assert_eq!((stats.objects, stats.records), (2, 3));
+        let files = read_tar(&archive[..]).unwrap();
+        let manifest: Manifest = serde_json::from_slice(&files[MANIFEST]).unwrap();
+        let ts: Vec<i64> = manifest.entries.iter().map(|e| e.ts).collect();
+        assert_eq!(ts, [20, 30, 15]);
+        assert_eq!(files[&manifest.entries[0].path], b"kind: x\nts: 20\n");
+
+        // The target already has one of the snapshots and a newer one.
+        let (dst, dst_dir) = store();
+        dst.put_last(rec(1, 20, "v1/ConfigMap", "shop")).unwrap();
+        dst.put_last(rec(1, 40, "v1/ConfigMap", "shop")).unwrap();
+        let stats = dst.import(&archive[..], false).unwrap();
+        assert_eq!((stats.imported, stats.duplicates), (2, 1));
+        let rvs: Vec<String> = dst
+            .get_last([1; 16], Some(10))
+            .unwrap()
+            .into_iter()
+            .map(|r| r.rv)
+            .collect();
+        assert_eq!(rvs, ["rv-40", "rv-30", "rv-20"]);
+        assert_eq!(dst.import(&archive[..], false).unwrap().imported, 0);
+
+        // A tampered file is rejected before anything is written.
+        let pos = archive.windows(5).position(|w| w == b"ts: 3").unwrap();
+        archive[pos + 4] = b'9';
+        assert!(dst.import(&archive[..], false).is_err());
+        let _ = std::fs::remove_dir_all(src_dir);
+        let _ = std::fs::remove_dir_all(dst_dir);
+    }
+
+    #[test]
+    fn import_keeps_secrets_out_of_plain_stores() {
+        let (src, src_dir) = store();
+        src.put_last(rec(1, 10, "v1/ConfigMap", "shop")).unwrap();
+        src.put_last(rec(2, 10, "v1/Secret", "shop")).unwrap();
+        src.put_last(LastApplied {
+            key: None,
+            yaml_zstd: crate::maybe_compress("apiVersion: v1\nkind: Secret\n"),
+            ..rec(3, 10, "v1/Secret", "shop")
+        })
+        .unwrap();
+        let filter = ExportFilter {
+            secrets: true,
+            ..Default::default()
+        };
+        let mut archive = Vec::new();
+        assert_eq!(src.export(&filter, &mut archive).unwrap().records, 3);
+
+        let (dst, dst_dir) = store();
+        let stats = dst.import(&archive[..], false).unwrap();
+        assert_eq!((stats.imported, stats.skipped), (1, 2));
+        assert!(dst.get_last([2; 16], None).unwrap().is_empty());
+        let stats = dst.import(&archive[..], true).unwrap();
+        assert_eq!((stats.imported, stats.duplicates, stats.skipped), (2, 1, 0));
+        let _ = std::fs::remove_dir_all(src_dir);
+        let _ = std::fs::remove_dir_all(dst_dir);
+    }
+
+    #[test]
+    fn read_tar_rejects_sizes_past_the_end() {
+        let mut archive = Vec::new();
+        let mut tar = TarWriter::new(&mut archive);
+        tar.append("a.yaml", 0, b"kind: x\n").unwrap();
+        tar.finish().unwrap();
+        // Claim a 1 GiB file; only a few blocks follow.
+        octal(&mut archive[124..136], 1 << 30);
+        archive[148..156].fill(b' ');
+        let sum: u32 = archive[..BLOCK].iter().map(|b| *b as u32).sum();
+        octal(&mut archive[148..155], sum as u64);
+        let err = read_tar(&archive[..]).unwrap_err().to_string();
+        assert!(err.contains("unexpected end"), "{}", err);
+    }
+}
@@ -0,0 +1,243 @@
+//! Optional encryption of stored snapshots.
+//!
+//! With a key configured (`ORKA_DB_KEY` or `ORKA_DB_KEY_FILE`), the YAML of
+//! each new record is sealed with XChaCha20-Poly1305 under a random nonce,
+//! authenticating the record's UID and timestamp as associated data. The
+//! sealed blob is `[b"\0ORKAENC"][key id[8]][nonce[24]][ciphertext+tag]`;
+//! UIDs, object keys, rvs and timestamps stay readable so the index works
+//! without the key. Plain records (written before a key was set) remain
+//! readable; [`crate::LogStore::rotate_key`] rewrites them all.
+
+use anyhow::{anyhow, Context, Result};
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng, Payload};
+use chacha20poly1305::{XChaCha20Poly1305, XNonce};
+use sha2::{Digest, Sha256};
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::LastApplied;
+
+const MAGIC: &[u8; 8] = b"\0ORKAENC";
+const ID_LEN: usize = 8;
+const NONCE_LEN: usize = 24;
+
+/// A 256-bit data key.
+#[derive(Clone)]
+pub struct Key {
+    id: [u8; ID_LEN],
+    cipher: XChaCha20Poly1305,
+}
+
+impl std::fmt::Debug for Key {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Key({})", self.id())
+    }
+}
+
+impl Key {
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let cipher = XChaCha20Poly1305::new_from_slice(bytes)
+            .map_err(|_| anyhow!("key must be 32 bytes, got {}", bytes.len()))?;
+        let digest = Sha256::new()
+            .chain_update(b"orka-db-key\0")
+            .chain_update(bytes)
+            .finalize();
+        let mut id = [0u8; ID_LEN];
+        id.copy_from_slice(&digest[..ID_LEN]);
+        Ok(Self { id, cipher })
+    }
+
+    /// A key written as 64 hex digits.
+    pub fn parse(text: &str) -> Result<Self> {
+        Self::from_bytes(&from_hex(text.trim()).ok_or_else(|| anyhow!("key is not valid hex"))?)
+    }
+
+    /// A keyfile holding either 64 hex digits or the 32 raw bytes.
+    pub fn load(path: &Path) -> Result<Self> {
+        let bytes =
+            std::fs::read(path).with_context(|| format!("reading key {}", path.display()))?;
+        match std::str::from_utf8(&bytes) {
+            Ok(text) if text.trim().len() == 64 => Self::parse(text),
+            _ => Self::from_bytes(&bytes),
+        }
+        .with_context(|| format!("loading key {}", path.display()))
+    }
+
+    /// A fresh random key as 64 hex digits, suitable for a keyfile.
+    pub fn generate() -> String {
+        to_hex(XChaCha20Poly1305::generate_key(&mut OsRng).as_slice())
+    }
+
+    /// Short fingerprint stored with each sealed record.
+    pub fn id(&self) -> String {
+        to_hex(&self.id)
+    }
+}
+
+/// The key new records are sealed with, plus earlier ones still accepted
+/// for reading.
+#[derive(Debug, Clone, Default)]
+pub struct Keyring {
+    current: Option<Arc<Key>>,
+    previous: Vec<Arc<Key>>,
+}
+
+impl Keyring {
+    pub fn new(current: Option<Key>) -> Self {
+        Self {
+            current: current.map(Arc::new),
+            previous: Vec::new(),
+        }
+    }
+
+    /// `ORKA_DB_KEY` (hex), else `ORKA_DB_KEY_FILE`; no key means records
+    /// are stored in plain text.
+    pub fn from_env() -> Result<Self> {
+        if let Ok(text) = std::env::var("ORKA_DB_KEY") {
+            return Ok(Self::new(Some(Key::parse(&text).context("ORKA_DB_KEY")?)));
+        }
+        match std::env::var_os("ORKA_DB_KEY_FILE") {
+            Some(path) => Ok(Self::new(Some(Key::load(Path::new(&path))?))),
+            None => Ok(Self::default()),
+        }
+    }
+
+    pub fn current(&self) -> Option<&Key> {
+        self.current.as_deref()
+    }
+
+    /// Make `new` the current key, keeping the old one for reading.
+    pub(crate) fn rotate(&mut self, new: Option<Key>) {
+        if let Some(old) = self.current.take() {
+            self.previous.push(old);
+        }
+        self.current = new.map(Arc::new);
+    }
+
+    /// Seal `la`'s YAML with the current key, if any.
+    pub(crate) fn seal(&self, la: &mut LastApplied) -> Result<()> {
+        let Some(key) = &self.current else {
+            return Ok(());
+        };
+        let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let aad = aad(la);
+        let sealed = key
+            .cipher
+            .encrypt(
+                &nonce,
+                Payload {
+                    msg: &la.yaml_zstd,
+                    aad: &aad,
+                },
+            )
+            .map_err(|_| anyhow!("encrypting record"))?;
+        let mut blob = Vec::with_capacity(MAGIC.len() + ID_LEN + NONCE_LEN + sealed.len());
+        blob.extend_from_slice(MAGIC);
+        blob.extend_from_slice(&key.id);
+        blob.extend_from_slice(nonce.as_slice());
+        blob.extend_from_slice(&sealed);
+        la.yaml_zstd = blob;
+        Ok(())
+    }
+
+    /// Replace a sealed YAML by its plain text; plain records pass through.
+    pub(crate) fn open(&self, la: &mut LastApplied) -> Result<()> {
+        let Some(rest) = la.yaml_zstd.strip_prefix(MAGIC.as_slice()) else {
+            return Ok(());
+        };
+        if rest.len() < ID_LEN + NONCE_LEN {
+            return Err(anyhow!("sealed record too short"));
+        }
+        let (id, rest) = rest.split_at(ID_LEN);
+        let (nonce, sealed) = rest.split_at(NONCE_LEN);
+        let key = self
+            .current
+            .iter()
+            .chain(&self.previous)
+            .find(|k| k.id == id)
+            .ok_or_else(|| {
+                anyhow!(
+                    "record is encrypted with key {}; set ORKA_DB_KEY or ORKA_DB_KEY_FILE to it",
+                    to_hex(id)
+                )
+            })?;
+        let plain = key
+            .cipher
+            .decrypt(
+                XNonce::from_slice(nonce),
+                Payload {
+                    msg: sealed,
+                    aad: &aad(la),
+                },
+            )
+            .map_err(|_| {
+                metrics::counter!("persist_decrypt_failed_total", 1u64);
+                anyhow!("record failed authentication (tampered or wrong key)")
+            })?;
+        la.yaml_zstd = plain;
+        Ok(())
+    }
+}
+
+fn aad(la: &LastApplied) -> [u8; 24] {
+    let mut aad = [0u8; 24];
+    aad[..16].copy_from_slice(&la.uid);
+    aad[16..].copy_from_slice(&la.ts.to_le_bytes());
+    aad
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn from_hex(s: &str) -> Option<Vec<u8>> {
+    (0..s.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(s.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn la(yaml: &str) -> LastApplied {
+        LastApplied {
+            uid: [3; 16],
+            rv: "1".into(),
+            ts: 42,
+            yaml_zstd: yaml.as_bytes().to_vec(),
+            key: None,
+        }
+    }
+
+    #[test]
+    fn seal_open_and_reject_tampering() {
+        let key = Key::parse(&Key::generate()).unwrap();
+        let ring = Keyring::new(Some(key.clone()));
+        let mut rec = la("data:\n  password: hunter2\n");
+        ring.seal(&mut rec).unwrap();
+        assert!(!rec.yaml_zstd.windows(7).any(|w| w == b"hunter2"));
+        let sealed = rec.clone();
+        ring.open(&mut rec).unwrap();
+        assert_eq!(rec.yaml_zstd, b"data:\n  password: hunter2\n");
+
+        // Moving a sealed blob to another record fails authentication.
+        let mut moved = LastApplied {
+            ts: 43,
+            ..sealed.clone()
+        };
+        assert!(ring.open(&mut moved).is_err());
+        // Without the key it cannot be read; plain records pass through.
+        let err = Keyring::default().open(&mut sealed.clone()).unwrap_err();
+        assert!(err.to_string().contains(&key.id()), "{}", err);
+        let mut plain = la("k: v\n");
+        Keyring::default().open(&mut plain).unwrap();
+        assert_eq!(plain.yaml_zstd, b"k: v\n");
+
+        // Rotated-out keys still decrypt.
+        let mut rotated = ring.clone();
+        rotated.rotate(Some(Key::parse(&Key::generate()).unwrap()));
+        rotated.open(&mut sealed.clone()).unwrap();
+    }
+}
@@ -0,0 +1,1772 @@
+//! Segmented last-applied store.
+//!
+//! A store is a directory:
+//! - `seg-00000001.log`, …: records are appended to the newest segment; a
+//!   new one is started once it passes [`Retention::segment_bytes`].
+//! - `index`: locations of all records and the object key of each UID, so
+//!   `open` only scans what was appended after it was written.
+//!
+//! Segment: header `[b"ORKASEG1"][epoch u64]`, then records
+//! `[payload_len u32][crc32 u32][payload]` with payload
+//! `[ts i64][uid[16]][rv_len u32][yaml_len u32][rv][yaml]` and optionally
+//! `[key_len u32][key]` (little endian). Records without a key predate it.
+//! - `writer.lock`: held shared while appending, so a process holding it
+//!   exclusively knows no record is half-written.
+//!
+//! A record that is cut short or fails its checksum ends a segment; in the
+//! newest segment that is a torn write, truncated on open or before the
+//! next append once no other process is appending.
+//!
+//! Retention (per-UID count, age, total size) hides expired records from
+//! reads at once; compaction rewrites sealed segments without them, in a
+//! background thread whenever a segment is sealed or via [`LogStore::compact`].
+//!
+//! Record YAML is sealed when a key is configured (see [`crate::crypt`]).
+//!
+//! Besides the per-UID index, UIDs are grouped by [`ObjectKey`] so the
+//! history of a name survives delete/recreate ([`LogStore::history`]).
+
+use anyhow::{anyhow, Context, Result};
+use metrics::{counter, histogram};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs::{File, OpenOptions};
+use std::io::{BufReader, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use tracing::{info, warn};
+
+use crate::{Key, Keyring, LastApplied, ObjectKey, Store};
+
+const SEG_MAGIC: &[u8; 8] = b"ORKASEG1";
+const IDX_MAGIC: &[u8; 8] = b"ORKAIDX2";
+const HEADER_LEN: u64 = 16;
+const REC_HEADER_LEN: u64 = 8;
+const PAYLOAD_FIXED: usize = 8 + 16 + 4 + 4;
+/// Upper bound for one payload; anything larger is treated as corruption.
+const MAX_PAYLOAD: u32 = 64 << 20;
+/// A compaction lock older than this is left over from a crashed process.
+const STALE_LOCK_SECS: u64 = 600;
+
+/// Which records a store keeps. Each UID's newest record is always kept so
+/// drift checks and rollbacks have a baseline.
+#[derive(Debug, Clone)]
+pub struct Retention {
+    /// Newest records kept per UID (0 = unlimited).
+    pub keep_per_uid: usize,
+    /// Records older than this many seconds are dropped.
+    pub max_age_secs: Option<i64>,
+    /// Oldest records are dropped until the store fits (compaction only).
+    pub max_total_bytes: Option<u64>,
+    /// Compact in a background thread whenever a segment is sealed.
+    pub background: bool,
+    /// A new segment is started once the newest would pass this size.
+    pub segment_bytes: u64,
+}
+
+impl Default for Retention {
+    fn default() -> Self {
+        Self {
+            keep_per_uid: 64,
+            max_age_secs: None,
+            max_total_bytes: Some(256 << 20),
+            background: true,
+            segment_bytes: 8 << 20,
+        }
+    }
+}
+
+impl Retention {
+    /// `ORKA_DB_KEEP_PER_UID`, `ORKA_DB_MAX_AGE_DAYS`, `ORKA_DB_MAX_BYTES`
+    /// (0 disables a limit), `ORKA_DB_COMPACT` and `ORKA_DB_SEGMENT_BYTES`
+    /// (at least 4096).
+    pub fn from_env() -> Self {
+        let num = |name: &str| std::env::var(name).ok().and_then(|s| s.parse::<u64>().ok());
+        let d = Self::default();
+        Self {
+            keep_per_uid: num("ORKA_DB_KEEP_PER_UID").map_or(d.keep_per_uid, |n| n as usize),
+            max_age_secs: match num("ORKA_DB_MAX_AGE_DAYS") {
+                Some(0) => None,
+                Some(days) => Some(days as i64 * 86_400),
+                None => d.max_age_secs,
+            },
+            max_total_bytes: match num("ORKA_DB_MAX_BYTES") {
+                Some(0) => None,
+                Some(n) => Some(n),
+                None => d.max_total_bytes,
+            },
+            background: std::env::var("ORKA_DB_COMPACT")
+                .map(|v| !matches!(v.to_ascii_lowercase().as_str(), "0" | "false" | "no"))
+                .unwrap_or(d.background),
+            segment_bytes: num("ORKA_DB_SEGMENT_BYTES").map_or(d.segment_bytes, |n| n.max(4096)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, serde::Serialize)]
+pub struct CompactStats {
+    pub segments_rewritten: usize,
+    pub segments_removed: usize,
+    pub records_dropped: usize,
+    pub bytes_before: u64,
+    pub bytes_after: u64,
+}
+
+#[derive(Debug, Clone, Default, serde::Serialize)]
+pub struct StoreStats {
+    pub segments: usize,
+    pub uids: usize,
+    pub records: usize,
+    pub bytes: u64,
+}
+
+#[derive(Debug, Clone, Default, serde::Serialize)]
+pub struct RotateStats {
+    pub segments: usize,
+    pub records: usize,
+    /// Id of the key records are now sealed with; `None` when decrypted.
+    pub key_id: Option<String>,
+}
+
+/// An object with recorded history, across its UID generations.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct ObjectHistory {
+    pub key: ObjectKey,
+    /// UIDs the object had, oldest first.
+    pub uids: Vec<[u8; 16]>,
+    pub records: usize,
+    pub last_ts: i64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Loc {
+    seg: u32,
+    off: u64,
+    len: u32,
+    ts: i64,
+}
+
+impl Loc {
+    fn size(&self) -> u64 {
+        REC_HEADER_LEN + self.len as u64
+    }
+
+    /// History order: by timestamp, then append position, so imported
+    /// older records do not count as newer than existing ones.
+    fn order(&self) -> (i64, u32, u64) {
+        (self.ts, self.seg, self.off)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Segment {
+    epoch: u64,
+    /// Bytes covered by the index (header included).
+    scanned: u64,
+}
+
+struct Inner {
+    dir: PathBuf,
+    segments: BTreeMap<u32, Segment>,
+    /// Per UID, in [`Loc::order`] (oldest first).
+    index: HashMap<[u8; 16], Vec<Loc>>,
+    /// Object key per UID, learned from any of its records, and back.
+    keys: HashMap<[u8; 16], ObjectKey>,
+    by_key: HashMap<ObjectKey, HashSet<[u8; 16]>>,
+    keyring: Keyring,
+    active: Option<(u32, File)>,
+    /// `writer.lock`.
+    writers: File,
+    segment_bytes: u64,
+    dirty: bool,
+}
+
+/// Segmented append-only store with a persisted index.
+pub struct LogStore {
+    inner: Arc<Mutex<Inner>>,
+    retention: Retention,
+    compacting: Arc<AtomicBool>,
+}
+
+fn seg_path(dir: &Path, id: u32) -> PathBuf {
+    dir.join(format!("seg-{:08}.log", id))
+}
+
+fn seg_id(name: &str) -> Option<u32> {
+    name.strip_prefix("seg-")?
+        .strip_suffix(".log")?
+        .parse()
+        .ok()
+}
+
+fn new_epoch() -> u64 {
+    let nanos = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos() as u64;
+    nanos ^ ((std::process::id() as u64) << 32)
+}
+
+fn read_epoch(path: &Path) -> Result<Option<u64>> {
+    let mut f = match File::open(path) {
+        Ok(f) => f,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+    let mut hdr = [0u8; HEADER_LEN as usize];
+    if f.read_exact(&mut hdr).is_err() || &hdr[..8] != SEG_MAGIC {
+        return Ok(None);
+    }
+    Ok(Some(u64::from_le_bytes(hdr[8..].try_into().unwrap())))
+}
+
+fn encode(la: &LastApplied) -> Vec<u8> {
+    let mut payload = Vec::with_capacity(PAYLOAD_FIXED + la.rv.len() + la.yaml_zstd.len());
+    payload.extend_from_slice(&la.ts.to_le_bytes());
+    payload.extend_from_slice(&la.uid);
+    payload.extend_from_slice(&(la.rv.len() as u32).to_le_bytes());
+    payload.extend_from_slice(&(la.yaml_zstd.len() as u32).to_le_bytes());
+    payload.extend_from_slice(la.rv.as_bytes());
+    payload.extend_from_slice(&la.yaml_zstd);
+    if let Some(key) = &la.key {
+        let key = encode_key(key);
+        payload.extend_from_slice(&(key.len() as u32).to_le_bytes());
+        payload.extend_from_slice(&key);
+    }
+    let mut rec = Vec::with_capacity(REC_HEADER_LEN as usize + payload.len());
+    rec.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+    rec.extend_from_slice(&crc32fast::hash(&payload).to_le_bytes());
+    rec.extend_from_slice(&payload);
+    rec
+}
+
+fn decode(payload: &[u8]) -> Result<LastApplied> {
+    if payload.len() < PAYLOAD_FIXED {
+        return Err(anyhow!("record too short"));
+    }
+    let ts = i64::from_le_bytes(payload[0..8].try_into().unwrap());
+    let uid: [u8; 16] = payload[8..24].try_into().unwrap();
+    let rv_len = u32::from_le_bytes(payload[24..28].try_into().unwrap()) as usize;
+    let yaml_len = u32::from_le_bytes(payload[28..32].try_into().unwrap()) as usize;
+    let rest = &payload[PAYLOAD_FIXED..];
+    let body = rv_len + yaml_len;
+    let key = match rest.len().checked_sub(body) {
+        Some(0) => None,
+        Some(extra) if extra >= 4 => {
+            let key_len = u32::from_le_bytes(rest[body..body + 4].try_into().unwrap()) as usize;
+            if extra != 4 + key_len {
+                return Err(anyhow!("record length mismatch"));
+            }
+            Some(decode_key(&rest[body + 4..]).ok_or_else(|| anyhow!("bad object key"))?)
+        }
+        _ => return Err(anyhow!("record length mismatch")),
+    };
+    Ok(LastApplied {
+        uid,
+        rv: String::from_utf8_lossy(&rest[..rv_len]).to_string(),
+        ts,
+        yaml_zstd: rest[rv_len..body].to_vec(),
+        key,
+    })
+}
+
+/// `cluster\0gvk\0namespace\0name`; cluster-scoped objects have an empty
+/// namespace.
+fn encode_key(key: &ObjectKey) -> Vec<u8> {
+    let ns = key.namespace.as_deref().unwrap_or("");
+    [key.cluster.as_str(), &key.gvk, ns, &key.name]
+        .join("\0")
+        .into_bytes()
+}
+
+fn decode_key(b: &[u8]) -> Option<ObjectKey> {
+    let s = std::str::from_utf8(b).ok()?;
+    let mut parts = s.split('\0');
+    let (cluster, gvk, ns, name) = (parts.next()?, parts.next()?, parts.next()?, parts.next()?);
+    if parts.next().is_some() || name.is_empty() {
+        return None;
+    }
+    Some(ObjectKey {
+        cluster: cluster.to_string(),
+        gvk: gvk.to_string(),
+        namespace: (!ns.is_empty()).then(|| ns.to_string()),
+        name: name.to_string(),
+    })
+}
+
+/// Read the record at the reader's position; `None` when it is incomplete
+/// or corrupt.
+fn read_record<R: Read>(r: &mut R) -> std::io::Result<Option<Vec<u8>>> {
+    let mut hdr = [0u8; REC_HEADER_LEN as usize];
+    let mut got = 0;
+    while got < hdr.len() {
+        match r.read(&mut hdr[got..])? {
+            0 => return Ok(None),
+            n => got += n,
+        }
+    }
+    let len = u32::from_le_bytes(hdr[..4].try_into().unwrap());
+    let crc = u32::from_le_bytes(hdr[4..].try_into().unwrap());
+    if len > MAX_PAYLOAD || (len as usize) < PAYLOAD_FIXED {
+        return Ok(None);
+    }
+    let mut payload = vec![0u8; len as usize];
+    match r.read_exact(&mut payload) {
+        Ok(()) => {}
+        Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(e),
+    }
+    Ok((crc32fast::hash(&payload) == crc).then_some(payload))
+}
+
+impl Inner {
+    fn insert(&mut self, uid: [u8; 16], loc: Loc) {
+        let locs = self.index.entry(uid).or_default();
+        let key = Loc::order;
+        match locs.last() {
+            Some(last) if key(last) > key(&loc) => {
+                let pos = locs.partition_point(|l| key(l) < key(&loc));
+                if locs.get(pos).map(key) != Some(key(&loc)) {
+                    locs.insert(pos, loc);
+                }
+            }
+            Some(last) if key(last) == key(&loc) => {}
+            _ => locs.push(loc),
+        }
+        self.dirty = true;
+    }
+
+    fn set_key(&mut self, uid: [u8; 16], key: ObjectKey) {
+        if self.keys.get(&uid) == Some(&key) {
+            return;
+        }
+        if let Some(old) = self.keys.insert(uid, key.clone()) {
+            self.unlink_key(uid, &old);
+        }
+        self.by_key.entry(key).or_default().insert(uid);
+        self.dirty = true;
+    }
+
+    fn unlink_key(&mut self, uid: [u8; 16], key: &ObjectKey) {
+        if let Some(uids) = self.by_key.get_mut(key) {
+            uids.remove(&uid);
+            if uids.is_empty() {
+                self.by_key.remove(key);
+            }
+        }
+    }
+
+    /// Forget keys of UIDs that no longer have records.
+    fn prune_keys(&mut self) {
+        let gone: Vec<([u8; 16], ObjectKey)> = self
+            .keys
+            .iter()
+            .filter(|(uid, _)| !self.index.contains_key(*uid))
+            .map(|(uid, key)| (*uid, key.clone()))
+            .collect();
+        for (uid, key) in gone {
+            self.keys.remove(&uid);
+            self.unlink_key(uid, &key);
+        }
+    }
+
+    fn drop_segment_locs(&mut self, id: u32) {
+        self.index.retain(|_, locs| {
+            locs.retain(|l| l.seg != id);
+            !locs.is_empty()
+        });
+        self.prune_keys();
+        self.dirty = true;
+    }
+
+    /// Index records of segment `id` from `from` up to `until` (or the end).
+    /// A torn tail is truncated when `truncate` is set.
+    fn scan(&mut self, id: u32, from: u64, until: Option<u64>, truncate: bool) -> Result<()> {
+        let path = seg_path(&self.dir, id);
+        let f = File::open(&path).with_context(|| format!("opening {}", path.display()))?;
+        let end = until.unwrap_or(f.metadata()?.len());
+        let mut r = BufReader::new(f);
+        r.seek(SeekFrom::Start(from))?;
+        let mut off = from;
+        let mut records = 0u64;
+        while off < end {
+            let Some(payload) = read_record(&mut r)? else {
+                break;
+            };
+            let Ok(la) = decode(&payload) else {
+                break;
+            };
+            let len = payload.len() as u32;
+            self.insert(
+                la.uid,
+                Loc {
+                    seg: id,
+                    off,
+                    len,
+                    ts: la.ts,
+                },
+            );
+            if let Some(key) = la.key {
+                self.set_key(la.uid, key);
+            }
+            off += REC_HEADER_LEN + len as u64;
+            records += 1;
+        }
+        if off < end && until.is_none() {
+            if truncate {
+                warn!(segment = %path.display(), at = off, dropped = end - off, "persist: truncating torn tail");
+                OpenOptions::new().write(true).open(&path)?.set_len(off)?;
+                counter!("persist_torn_tail_truncated_total", 1u64);
+            } else {
+                warn!(segment = %path.display(), at = off, "persist: unreadable record; ignoring rest of segment");
+            }
+        }
+        if let Some(seg) = self.segments.get_mut(&id) {
+            seg.scanned = seg.scanned.max(off);
+        }
+        counter!("persist_scanned_records_total", records);
+        Ok(())
+    }
+
+    /// Index the rest of segment `id`, truncating an unreadable tail only
+    /// while no other writer is mid-append; otherwise it is skipped.
+    fn scan_tail(&mut self, id: u32) -> Result<()> {
+        let scanned = self.segments[&id].scanned;
+        let exclusive = self.writers.try_lock().is_ok();
+        let res = self.scan(id, scanned, None, exclusive);
+        if exclusive {
+            self.writers.unlock()?;
+        }
+        res
+    }
+
+    /// Add `records` as a new segment that appears in one step: written to
+    /// a temp file, then linked in under the next free id.
+    fn add_segment(&mut self, records: Vec<LastApplied>) -> Result<()> {
+        let epoch = new_epoch();
+        let tmp = self.dir.join(format!("migrate-{:016x}.tmp", epoch));
+        let mut out = std::io::BufWriter::new(File::create(&tmp)?);
+        out.write_all(SEG_MAGIC)?;
+        out.write_all(&epoch.to_le_bytes())?;
+        for mut la in records {
+            self.keyring.seal(&mut la)?;
+            out.write_all(&encode(&la))?;
+        }
+        out.into_inner().map_err(|e| e.into_error())?.sync_all()?;
+        let linked = loop {
+            let id = self.segments.keys().next_back().map_or(1, |id| id + 1);
+            match std::fs::hard_link(&tmp, seg_path(&self.dir, id)) {
+                Ok(()) => break Ok(id),
+                // Another process started that segment; pick up its records.
+                Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => self.refresh()?,
+                Err(e) => break Err(e),
+            }
+        };
+        let _ = std::fs::remove_file(&tmp);
+        let id = linked?;
+        self.segments.insert(
+            id,
+            Segment {
+                epoch,
+                scanned: HEADER_LEN,
+            },
+        );
+        self.scan(id, HEADER_LEN, None, false)?;
+        self.save_index()
+    }
+
+    fn rescan(&mut self, id: u32, epoch: u64, truncate: bool) -> Result<()> {
+        self.drop_segment_locs(id);
+        self.segments.insert(
+            id,
+            Segment {
+                epoch,
+                scanned: HEADER_LEN,
+            },
+        );
+        self.scan(id, HEADER_LEN, None, truncate)
+    }
+
+    /// Pick up changes made by other processes: new segments, appended
+    /// records, compacted or removed segments.
+    fn refresh(&mut self) -> Result<()> {
+        let mut next = self.segments.keys().next_back().map_or(1, |id| id + 1);
+        while let Some(epoch) = read_epoch(&seg_path(&self.dir, next))? {
+            self.segments.insert(
+                next,
+                Segment {
+                    epoch,
+                    scanned: HEADER_LEN,
+                },
+            );
+            self.scan(next, HEADER_LEN, None, false)?;
+            next += 1;
+        }
+        let ids: Vec<u32> = self.segments.keys().copied().collect();
+        for id in ids {
+            let seg = self.segments[&id];
+            let len = match std::fs::metadata(seg_path(&self.dir, id)) {
+                Ok(m) => m.len(),
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                    self.segments.remove(&id);
+                    self.drop_segment_locs(id);
+                    continue;
+                }
+                Err(e) => return Err(e.into()),
+            };
+            if len < seg.scanned {
+                // Rewritten by a compaction elsewhere.
+                match read_epoch(&seg_path(&self.dir, id))? {
+                    Some(epoch) => self.rescan(id, epoch, false)?,
+                    None => {
+                        self.segments.remove(&id);
+                        self.drop_segment_locs(id);
+                    }
+                }
+            } else if len > seg.scanned {
+                self.scan(id, seg.scanned, None, false)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn new_segment(&mut self) -> Result<()> {
+        let id = self.segments.keys().next_back().map_or(1, |id| id + 1);
+        let path = seg_path(&self.dir, id);
+        let epoch = new_epoch();
+        let mut f = OpenOptions::new()
+            .create_new(true)
+            .append(true)
+            .read(true)
+            .open(&path)
+            .with_context(|| format!("creating {}", path.display()))?;
+        let mut hdr = Vec::with_capacity(HEADER_LEN as usize);
+        hdr.extend_from_slice(SEG_MAGIC);
+        hdr.extend_from_slice(&epoch.to_le_bytes());
+        f.write_all(&hdr)?;
+        f.flush()?;
+        self.segments.insert(
+            id,
+            Segment {
+                epoch,
+                scanned: HEADER_LEN,
+            },
+        );
+        self.active = Some((id, f));
+        Ok(())
+    }
+
+    /// Append handle for the newest segment, starting a new one when full.
+    /// Returns whether a segment was sealed.
+    fn ensure_active(&mut self, rec_len: u64) -> Result<bool> {
+        let newest = self.segments.keys().next_back().copied();
+        if self.active.as_ref().map(|(id, _)| *id) != newest {
+            self.active = match newest {
+                Some(id) => Some((
+                    id,
+                    OpenOptions::new()
+                        .append(true)
+                        .read(true)
+                        .open(seg_path(&self.dir, id))?,
+                )),
+                None => None,
+            };
+        }
+        match &self.active {
+            Some((_, f)) if f.metadata()?.len() + rec_len <= self.segment_bytes => Ok(false),
+            Some(_) => {
+                self.new_segment()?;
+                Ok(true)
+            }
+            None => {
+                self.new_segment()?;
+                Ok(false)
+            }
+        }
+    }
+
+    fn save_index(&mut self) -> Result<()> {
+        let mut buf = Vec::new();
+        buf.extend_from_slice(IDX_MAGIC);
+        buf.extend_from_slice(&(self.segments.len() as u32).to_le_bytes());
+        for (id, seg) in &self.segments {
+            buf.extend_from_slice(&id.to_le_bytes());
+            buf.extend_from_slice(&seg.epoch.to_le_bytes());
+            buf.extend_from_slice(&seg.scanned.to_le_bytes());
+        }
+        let count: usize = self.index.values().map(Vec::len).sum();
+        buf.extend_from_slice(&(count as u64).to_le_bytes());
+        for (uid, locs) in &self.index {
+            for l in locs {
+                buf.extend_from_slice(uid);
+                buf.extend_from_slice(&l.seg.to_le_bytes());
+                buf.extend_from_slice(&l.off.to_le_bytes());
+                buf.extend_from_slice(&l.len.to_le_bytes());
+                buf.extend_from_slice(&l.ts.to_le_bytes());
+            }
+        }
+        buf.extend_from_slice(&(self.keys.len() as u64).to_le_bytes());
+        for (uid, key) in &self.keys {
+            let key = encode_key(key);
+            buf.extend_from_slice(uid);
+            buf.extend_from_slice(&(key.len() as u32).to_le_bytes());
+            buf.extend_from_slice(&key);
+        }
+        buf.extend_from_slice(&crc32fast::hash(&buf).to_le_bytes());
+        let tmp = self.dir.join("index.tmp");
+        std::fs::write(&tmp, &buf)?;
+        std::fs::rename(&tmp, self.dir.join("index"))?;
+        self.dirty = false;
+        Ok(())
+    }
+
+    /// Segments, record locations and object keys from the index file, if
+    /// intact.
+    fn load_index(dir: &Path) -> Option<IndexFile> {
+        let buf = std::fs::read(dir.join("index")).ok()?;
+        let (body, crc) = buf.split_at(buf.len().checked_sub(4)?);
+        if !body.starts_with(IDX_MAGIC)
+            || crc32fast::hash(body) != u32::from_le_bytes(crc.try_into().ok()?)
+        {
+            return None;
+        }
+        let mut p = &body[8..];
+        let mut take = |n: usize| -> Option<&[u8]> {
+            if p.len() < n {
+                return None;
+            }
+            let (a, b) = p.split_at(n);
+            p = b;
+            Some(a)
+        };
+        let nseg = u32::from_le_bytes(take(4)?.try_into().ok()?);
+        let mut segments = BTreeMap::new();
+        for _ in 0..nseg {
+            let id = u32::from_le_bytes(take(4)?.try_into().ok()?);
+            let epoch = u64::from_le_bytes(take(8)?.try_into().ok()?);
+            let scanned = u64::from_le_bytes(take(8)?.try_into().ok()?);
+            segments.insert(id, Segment { epoch, scanned });
+        }
+        let count = u64::from_le_bytes(take(8)?.try_into().ok()?);
+        let mut locs = Vec::with_capacity(count.min(1 << 20) as usize);
+        for _ in 0..count {
+            let uid: [u8; 16] = take(16)?.try_into().ok()?;
+            let seg = u32::from_le_bytes(take(4)?.try_into().ok()?);
+            let off = u64::from_le_bytes(take(8)?.try_into().ok()?);
+            let len = u32::from_le_bytes(take(4)?.try_into().ok()?);
+            let ts = i64::from_le_bytes(take(8)?.try_into().ok()?);
+            locs.push((uid, Loc { seg, off, len, ts }));
+        }
+        let nkeys = u64::from_le_bytes(take(8)?.try_into().ok()?);
+        let mut keys = Vec::with_capacity(nkeys.min(1 << 20) as usize);
+        for _ in 0..nkeys {
+            let uid: [u8; 16] = take(16)?.try_into().ok()?;
+            let len = u32::from_le_bytes(take(4)?.try_into().ok()?) as usize;
+            keys.push((uid, decode_key(take(len)?)?));
+        }
+        Some(IndexFile {
+            segments,
+            locs,
+            keys,
+        })
+    }
+
+    /// Records hidden by retention: beyond the per-UID count or too old,
+    /// never a UID's newest.
+    fn expired(&self, retention: &Retention, now: i64) -> HashSet<(u32, u64)> {
+        self.index
+            .values()
+            .flat_map(|locs| live_mask(locs, retention, now).zip(locs))
+            .filter(|(live, _)| !live)
+            .map(|(_, l)| (l.seg, l.off))
+            .collect()
+    }
+
+    fn read_newest(
+        &self,
+        uid: [u8; 16],
+        cap: usize,
+        retention: &Retention,
+    ) -> Result<Vec<LastApplied>> {
+        let Some(all) = self.index.get(&uid) else {
+            return Ok(Vec::new());
+        };
+        let live: Vec<bool> = live_mask(all, retention, crate::now_ts()).collect();
+        let mut files = HashMap::new();
+        all.iter()
+            .zip(live)
+            .rev()
+            .filter(|(_, live)| *live)
+            .take(cap)
+            .map(|(l, _)| self.read_keyed(&mut files, uid, l))
+            .collect()
+    }
+
+    /// Newest records of all UIDs of `key`, interleaved in history order.
+    fn read_key(
+        &self,
+        key: &ObjectKey,
+        cap: usize,
+        retention: &Retention,
+    ) -> Result<Vec<LastApplied>> {
+        let Some(uids) = self.by_key.get(key) else {
+            return Ok(Vec::new());
+        };
+        let now = crate::now_ts();
+        let mut locs: Vec<([u8; 16], Loc)> = uids
+            .iter()
+            .filter_map(|uid| Some((uid, self.index.get(uid)?)))
+            .flat_map(|(uid, all)| {
+                live_mask(all, retention, now)
+                    .zip(all)
+                    .filter(|(live, _)| *live)
+                    .map(|(_, l)| (*uid, *l))
+            })
+            .collect();
+        locs.sort_by_key(|(_, l)| std::cmp::Reverse(l.order()));
+        let mut files = HashMap::new();
+        locs.iter()
+            .take(cap)
+            .map(|(uid, l)| self.read_keyed(&mut files, *uid, l))
+            .collect()
+    }
+
+    /// Read a record, filling in the UID's key when the record has none.
+    fn read_keyed(
+        &self,
+        files: &mut HashMap<u32, File>,
+        uid: [u8; 16],
+        loc: &Loc,
+    ) -> Result<LastApplied> {
+        let mut la = self.read_at(files, loc)?;
+        if la.key.is_none() {
+            la.key = self.keys.get(&uid).cloned();
+        }
+        Ok(la)
+    }
+
+    fn read_at(&self, files: &mut HashMap<u32, File>, loc: &Loc) -> Result<LastApplied> {
+        let f = match files.entry(loc.seg) {
+            std::collections::hash_map::Entry::Occupied(e) => e.into_mut(),
+            std::collections::hash_map::Entry::Vacant(e) => {
+                let path = seg_path(&self.dir, loc.seg);
+                let epoch = read_epoch(&path)?;
+                if epoch != self.segments.get(&loc.seg).map(|s| s.epoch) {
+                    return Err(anyhow!("segment {} changed while reading", loc.seg));
+                }
+                e.insert(File::open(path)?)
+            }
+        };
+        f.seek(SeekFrom::Start(loc.off))?;
+        let payload = read_record(f)?
+            .ok_or_else(|| anyhow!("corrupt record at segment {} offset {}", loc.seg, loc.off))?;
+        let mut la = decode(&payload)?;
+        self.keyring.open(&mut la)?;
+        Ok(la)
+    }
+}
+
+/// Contents of the `index` file.
+struct IndexFile {
+    segments: BTreeMap<u32, Segment>,
+    locs: Vec<([u8; 16], Loc)>,
+    keys: Vec<([u8; 16], ObjectKey)>,
+}
+
+/// Whether each of a UID's records (oldest first) survives retention.
+fn live_mask<'a>(
+    locs: &'a [Loc],
+    retention: &'a Retention,
+    now: i64,
+) -> impl Iterator<Item = bool> + 'a {
+    let cutoff = retention.max_age_secs.map(|age| now - age);
+    let n = locs.len();
+    locs.iter().enumerate().map(move |(i, l)| {
+        let from_newest = n - 1 - i;
+        let over_count = retention.keep_per_uid > 0 && from_newest >= retention.keep_per_uid;
+        let too_old = cutoff.is_some_and(|c| l.ts < c);
+        from_newest == 0 || !(over_count || too_old)
+    })
+}
+
+/// Cross-process compaction guard.
+struct CompactLock(PathBuf);
+
+impl CompactLock {
+    fn acquire(dir: &Path) -> Option<Self> {
+        let path = dir.join("compact.lock");
+        for _ in 0..2 {
+            match OpenOptions::new().write(true).create_new(true).open(&path) {
+                Ok(_) => return Some(Self(path)),
+                Err(_) => {
+                    let stale = std::fs::metadata(&path)
+                        .and_then(|m| m.modified())
+                        .ok()
+                        .and_then(|t| t.elapsed().ok())
+                        .is_some_and(|age| age.as_secs() > STALE_LOCK_SECS);
+                    if !stale {
+                        return None;
+                    }
+                    let _ = std::fs::remove_file(&path);
+                }
+            }
+        }
+        None
+    }
+}
+
+impl Drop for CompactLock {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.0);
+    }
+}
+
+impl LogStore {
+    /// Open the store at `ORKA_DB_PATH` (default `~/.orka/history`), moving
+    /// records from the old single-file log `~/.orka/lastapplied.log` into
+    /// it the first time.
+    pub fn open_default() -> Result<Self> {
+        match std::env::var("ORKA_DB_PATH") {
+            Ok(path) => Self::open(&path),
+            Err(_) => {
+                let store = Self::open(&default_store_path())?;
+                if let Some(legacy) = legacy_log_path().filter(|p| p.is_file()) {
+                    store.migrate_legacy(&legacy)?;
+                }
+                Ok(store)
+            }
+        }
+    }
+
+    /// Open (or create) the store directory at `path`. When `path` is (or
+    /// was, see [`LogStore::migrate_legacy`]) a file from the old
+    /// single-file format, the store lives in `<path>.d` and the file's
+    /// records are moved there.
+    pub fn open(path: &str) -> Result<Self> {
+        let path = Path::new(path);
+        let moved = |suffix: &str| PathBuf::from(format!("{}.{}", path.display(), suffix));
+        let dir = if path.is_file() || moved("d").is_dir() || moved("migrated").is_file() {
+            moved("d")
+        } else {
+            path.to_path_buf()
+        };
+        let store =
+            Self::open_with(&dir, Retention::from_env())?.with_keyring(Keyring::from_env()?);
+        if path.is_file() {
+            store.migrate_legacy(path)?;
+        }
+        Ok(store)
+    }
+
+    /// Seal new records with `keyring`'s current key and read sealed ones.
+    pub fn with_keyring(self, keyring: Keyring) -> Self {
+        self.inner.lock().unwrap().keyring = keyring;
+        self
+    }
+
+    /// Whether new records are encrypted.
+    pub fn encrypted(&self) -> bool {
+        self.inner.lock().unwrap().keyring.current().is_some()
+    }
+
+    pub fn open_with(dir: &Path, retention: Retention) -> Result<Self> {
+        let started = std::time::Instant::now();
+        std::fs::create_dir_all(dir).with_context(|| format!("creating {}", dir.display()))?;
+        let mut on_disk = BTreeMap::new();
+        for entry in std::fs::read_dir(dir)? {
+            let entry = entry?;
+            let name = entry.file_name().to_string_lossy().to_string();
+            if name.ends_with(".tmp") {
+                // Left behind by an interrupted compaction or index save.
+                let _ = std::fs::remove_file(entry.path());
+            } else if let Some(id) = seg_id(&name) {
+                on_disk.insert(id, entry.path());
+            }
+        }
+        let writers = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(dir.join("writer.lock"))
+            .with_context(|| format!("opening {}", dir.join("writer.lock").display()))?;
+        let mut inner = Inner {
+            dir: dir.to_path_buf(),
+            segments: BTreeMap::new(),
+            index: HashMap::new(),
+            keys: HashMap::new(),
+            by_key: HashMap::new(),
+            keyring: Keyring::default(),
+            active: None,
+            writers,
+            segment_bytes: retention.segment_bytes,
+            dirty: false,
+        };
+        let (known, locs, keys) = match Inner::load_index(dir) {
+            Some(idx) => (idx.segments, idx.locs, idx.keys),
+            None => Default::default(),
+        };
+        let newest = on_disk.keys().next_back().copied();
+        let mut trusted = HashSet::new();
+        for (id, path) in &on_disk {
+            let Some(epoch) = read_epoch(path)? else {
+                warn!(segment = %path.display(), "persist: segment without header; ignoring");
+                continue;
+            };
+            let len = std::fs::metadata(path)?.len();
+            match known.get(id) {
+                Some(seg) if seg.epoch == epoch && seg.scanned <= len => {
+                    inner.segments.insert(*id, *seg);
+                    trusted.insert(*id);
+                }
+                _ => {
+                    inner.segments.insert(
+                        *id,
+                        Segment {
+                            epoch,
+                            scanned: HEADER_LEN,
+                        },
+                    );
+                }
+            }
+        }
+        for (uid, loc) in locs {
+            if trusted.contains(&loc.seg) {
+                inner.index.entry(uid).or_default().push(loc);
+            }
+        }
+        for locs in inner.index.values_mut() {
+            locs.sort_by_key(Loc::order);
+        }
+        for (uid, key) in keys {
+            if inner.index.contains_key(&uid) {
+                inner.set_key(uid, key);
+            }
+        }
+        inner.dirty = false;
+        let ids: Vec<u32> = inner.segments.keys().copied().collect();
+        for id in ids {
+            if Some(id) == newest {
+                inner.scan_tail(id)?;
+            } else {
+                let scanned = inner.segments[&id].scanned;
+                inner.scan(id, scanned, None, false)?;
+            }
+        }
+        if inner.dirty || known.len() != inner.segments.len() {
+            inner.save_index()?;
+        }
+        histogram!("persist_open_ms", started.elapsed().as_secs_f64() * 1000.0);
+        Ok(Self {
+            inner: Arc::new(Mutex::new(inner)),
+            retention,
+            compacting: Arc::new(AtomicBool::new(false)),
+        })
+    }
+
+    /// Pick up what other processes wrote or compacted since the last read.
+    /// Reads refresh on their own; this is for long-lived handles that want
+    /// to see a failure instead of a stale view.
+    pub fn refresh(&self) -> Result<()> {
+        self.inner.lock().unwrap().refresh()
+    }
+
+    /// UIDs with at least one recorded entry, in no particular order.
+    pub fn uids(&self) -> Vec<[u8; 16]> {
+        let mut inner = self.inner.lock().unwrap();
+        if let Err(e) = inner.refresh() {
+            warn!(error = %e, "persist: refresh failed");
+        }
+        inner.index.keys().copied().collect()
+    }
+
+    /// Object key recorded for `uid`, if any of its records carries one.
+    pub fn key_of(&self, uid: [u8; 16]) -> Option<ObjectKey> {
+        let mut inner = self.inner.lock().unwrap();
+        if let Err(e) = inner.refresh() {
+            warn!(error = %e, "persist: refresh failed");
+        }
+        inner.keys.get(&uid).cloned()
+    }
+
+    /// Newest records of an object across all its UIDs (default 3), newest
+    /// first; includes generations that were deleted since.
+    pub fn history(&self, key: &ObjectKey, limit: Option<usize>) -> Result<Vec<LastApplied>> {
+        let started = std::time::Instant::now();
+        let cap = limit.unwrap_or(3);
+        let mut inner = self.inner.lock().unwrap();
+        inner.refresh()?;
+        let out = match inner.read_key(key, cap, &self.retention) {
+            Ok(out) => out,
+            Err(_) => {
+                inner.refresh()?;
+                inner.read_key(key, cap, &self.retention)?
+            }
+        };
+        histogram!("persist_get_ms", started.elapsed().as_secs_f64() * 1000.0);
+        Ok(out)
+    }
+
+    /// Every object with history, sorted by key. UIDs only recorded
+    /// without a key (written by older versions) are not included.
+    pub fn objects(&self) -> Vec<ObjectHistory> {
+        let mut inner = self.inner.lock().unwrap();
+        if let Err(e) = inner.refresh() {
+            warn!(error = %e, "persist: refresh failed");
+        }
+        let now = crate::now_ts();
+        let mut out: Vec<ObjectHistory> = inner
+            .by_key
+            .iter()
+            .map(|(key, uids)| {
+                let mut gens: Vec<(Loc, [u8; 16])> = uids
+                    .iter()
+                    .filter_map(|uid| Some((*inner.index.get(uid)?.first()?, *uid)))
+                    .collect();
+                gens.sort_by_key(|(l, _)| l.order());
+                let (mut records, mut last_ts) = (0, 0);
+                for uid in uids {
+                    let Some(locs) = inner.index.get(uid) else {
+                        continue;
+                    };
+                    records += live_mask(locs, &self.retention, now)
+                        .filter(|live| *live)
+                        .count();
+                    last_ts = last_ts.max(locs.last().map_or(0, |l| l.ts));
+                }
+                ObjectHistory {
+                    key: key.clone(),
+                    uids: gens.into_iter().map(|(_, uid)| uid).collect(),
+                    records,
+                    last_ts,
+                }
+            })
+            .collect();
+        out.sort_by(|a, b| a.key.cmp(&b.key));
+        out
+    }
+
+    pub fn stats(&self) -> StoreStats {
+        let inner = self.inner.lock().unwrap();
+        let bytes = inner
+            .segments
+            .keys()
+            .filter_map(|id| std::fs::metadata(seg_path(&inner.dir, *id)).ok())
+            .map(|m| m.len())
+            .sum();
+        StoreStats {
+            segments: inner.segments.len(),
+            uids: inner.index.len(),
+            records: inner.index.values().map(Vec::len).sum(),
+            bytes,
+        }
+    }
+
+    /// Move the records of an old single-file log into this store and
+    /// rename it to `<file>.migrated`. The records land in one new segment
+    /// at once; records the store already holds (same UID and timestamp,
+    /// left by a run interrupted before the rename) are skipped, so this can
+    /// be re-run safely.
+    pub fn migrate_legacy(&self, path: &Path) -> Result<usize> {
+        let mut r = BufReader::new(File::open(path)?);
+        let mut records = Vec::new();
+        loop {
+            let mut fixed = [0u8; PAYLOAD_FIXED];
+            if r.read_exact(&mut fixed).is_err() {
+                break;
+            }
+            let ts = i64::from_le_bytes(fixed[0..8].try_into().unwrap());
+            let uid: [u8; 16] = fixed[8..24].try_into().unwrap();
+            let rv_len = u32::from_le_bytes(fixed[24..28].try_into().unwrap()) as usize;
+            let yaml_len = u32::from_le_bytes(fixed[28..32].try_into().unwrap()) as usize;
+            let mut rest = vec![0u8; rv_len + yaml_len];
+            if r.read_exact(&mut rest).is_err() {
+                break;
+            }
+            records.push(LastApplied {
+                uid,
+                rv: String::from_utf8_lossy(&rest[..rv_len]).to_string(),
+                ts,
+                yaml_zstd: rest[rv_len..].to_vec(),
+                key: None,
+            });
+        }
+        let moved = {
+            let mut inner = self.inner.lock().unwrap();
+            inner.refresh()?;
+            let have: HashSet<([u8; 16], i64)> = inner
+                .index
+                .iter()
+                .flat_map(|(uid, locs)| locs.iter().map(move |l| (*uid, l.ts)))
+                .collect();
+            records.retain(|la| !have.contains(&(la.uid, la.ts)));
+            let moved = records.len();
+            if moved > 0 {
+                inner.add_segment(records)?;
+            }
+            moved
+        };
+        let done = PathBuf::from(format!("{}.migrated", path.display()));
+        std::fs::rename(path, &done)?;
+        info!(from = %path.display(), records = moved, "persist: migrated single-file log");
+        Ok(moved)
+    }
+
+    /// Rewrite every record sealed with `new`, or in plain text with
+    /// `None`, reading them with the current keys; `new` then becomes the
+    /// current key. Writes go to a fresh segment meanwhile. Processes using
+    /// the old key can no longer read the store afterwards.
+    pub fn rotate_key(&self, new: Option<Key>) -> Result<RotateStats> {
+        let dir = self.inner.lock().unwrap().dir.clone();
+        let _lock = CompactLock::acquire(&dir)
+            .ok_or_else(|| anyhow!("a compaction or key rotation is running; try again later"))?;
+        let mut inner = self.inner.lock().unwrap();
+        inner.refresh()?;
+        let mut target = inner.keyring.clone();
+        target.rotate(new);
+        inner.new_segment()?;
+        let sealed: Vec<u32> = inner.segments.keys().copied().collect();
+        let sealed = &sealed[..sealed.len() - 1];
+        let mut stats = RotateStats {
+            key_id: target.current().map(Key::id),
+            ..Default::default()
+        };
+        for &id in sealed {
+            let mut keep: Vec<([u8; 16], Loc)> = inner
+                .index
+                .iter()
+                .flat_map(|(uid, locs)| locs.iter().map(move |l| (*uid, *l)))
+                .filter(|(_, l)| l.seg == id)
+                .collect();
+            keep.sort_by_key(|(_, l)| l.off);
+            let tmp = dir.join(format!("seg-{:08}.log.tmp", id));
+            let mut out = std::io::BufWriter::new(File::create(&tmp)?);
+            let epoch = new_epoch();
+            out.write_all(SEG_MAGIC)?;
+            out.write_all(&epoch.to_le_bytes())?;
+            let mut files = HashMap::new();
+            for (uid, l) in &keep {
+                // Carries the object key along so it survives the rescan.
+                let mut la = inner.read_keyed(&mut files, *uid, l)?;
+                target.seal(&mut la)?;
+                out.write_all(&encode(&la))?;
+            }
+            out.into_inner().map_err(|e| e.into_error())?.sync_all()?;
+            drop(files);
+            std::fs::rename(&tmp, seg_path(&dir, id))?;
+            inner.rescan(id, epoch, false)?;
+            stats.segments += 1;
+            stats.records += keep.len();
+        }
+        inner.keyring = target;
+        inner.save_index()?;
+        counter!("persist_key_rotations_total", 1u64);
+        info!(segments = stats.segments, records = stats.records, key = ?stats.key_id, "persist: rotated key");
+        Ok(stats)
+    }
+
+    /// Rewrite sealed segments without records dropped by retention.
+    /// Returns `None` when another compaction is already running.
+    pub fn compact(&self) -> Result<Option<CompactStats>> {
+        compact(&self.inner, &self.retention, &self.compacting)
+    }
+
+    fn spawn_compaction(&self) {
+        if self.compacting.load(Ordering::Acquire) {
+            return;
+        }
+        let (inner, retention, flag) = (
+            self.inner.clone(),
+            self.retention.clone(),
+            self.compacting.clone(),
+        );
+        std::thread::spawn(move || match compact(&inner, &retention, &flag) {
+            Ok(Some(stats)) if stats.records_dropped > 0 => {
+                info!(
+                    dropped = stats.records_dropped,
+                    before = stats.bytes_before,
+                    after = stats.bytes_after,
+                    "persist: compacted"
+                );
+            }
+            Ok(_) => {}
+            Err(e) => warn!(error = %e, "persist: compaction failed"),
+        });
+    }
+}
+
+/// A sealed segment copied without its dropped records; `moved` maps old
+/// to new record offsets.
+struct Rewritten {
+    epoch: u64,
+    len: u64,
+    moved: HashMap<u64, u64>,
+}
+
+fn compact(
+    shared: &Mutex<Inner>,
+    retention: &Retention,
+    flag: &AtomicBool,
+) -> Result<Option<CompactStats>> {
+    if flag.swap(true, Ordering::AcqRel) {
+        return Ok(None);
+    }
+    struct Reset<'a>(&'a AtomicBool);
+    impl Drop for Reset<'_> {
+        fn drop(&mut self) {
+            self.0.store(false, Ordering::Release);
+        }
+    }
+    let _reset = Reset(flag);
+    let started = std::time::Instant::now();
+
+    let dir = shared.lock().unwrap().dir.clone();
+    let Some(_lock) = CompactLock::acquire(&dir) else {
+        return Ok(None);
+    };
+
+    // Plan under the lock: which records of which sealed segments survive.
+    let (plan, mut stats) = {
+        let mut inner = shared.lock().unwrap();
+        inner.refresh()?;
+        let mut dead = inner.expired(retention, crate::now_ts());
+        let stats = CompactStats {
+            bytes_before: inner
+                .segments
+                .keys()
+                .filter_map(|id| std::fs::metadata(seg_path(&inner.dir, *id)).ok())
+                .map(|m| m.len())
+                .sum(),
+            ..Default::default()
+        };
+        if let Some(max) = retention.max_total_bytes {
+            let mut live: Vec<(i64, u32, u64, u64)> = Vec::new();
+            let mut total = inner.segments.len() as u64 * HEADER_LEN;
+            for locs in inner.index.values() {
+                for (i, l) in locs.iter().enumerate() {
+                    if dead.contains(&(l.seg, l.off)) {
+                        continue;
+                    }
+                    total += l.size();
+                    if i + 1 < locs.len() {
+                        live.push((l.ts, l.seg, l.off, l.size()));
+                    }
+                }
+            }
+            live.sort();
+            for (_, seg, off, size) in live {
+                if total <= max {
+                    break;
+                }
+                dead.insert((seg, off));
+                total -= size;
+            }
+        }
+        let newest = inner.segments.keys().next_back().copied();
+        let mut plan: BTreeMap<u32, Vec<Loc>> = BTreeMap::new();
+        let mut touched = HashSet::new();
+        for locs in inner.index.values() {
+            for l in locs {
+                if Some(l.seg) == newest {
+                    continue;
+                }
+                if dead.contains(&(l.seg, l.off)) {
+                    touched.insert(l.seg);
+                } else {
+                    plan.entry(l.seg).or_default().push(*l);
+                }
+            }
+        }
+        // Segments with no indexed records at all are garbage too.
+        for id in inner.segments.keys() {
+            if Some(*id) != newest && !plan.contains_key(id) {
+                touched.insert(*id);
+            }
+        }
+        plan.retain(|id, _| touched.contains(id));
+        for id in &touched {
+            plan.entry(*id).or_default();
+        }
+        for keep in plan.values_mut() {
+            keep.sort_by_key(|l| l.off);
+        }
+        (plan, stats)
+    };
+    if plan.is_empty() {
+        return Ok(Some(stats));
+    }
+
+    // Copy survivors outside the lock; sealed segments only change here.
+    let mut rewritten: Vec<(u32, Option<Rewritten>)> = Vec::new();
+    for (id, keep) in &plan {
+        if keep.is_empty() {
+            rewritten.push((*id, None));
+            continue;
+        }
+        let mut src = File::open(seg_path(&dir, *id))?;
+        let tmp = dir.join(format!("seg-{:08}.log.tmp", id));
+        let mut out = std::io::BufWriter::new(File::create(&tmp)?);
+        let epoch = new_epoch();
+        out.write_all(SEG_MAGIC)?;
+        out.write_all(&epoch.to_le_bytes())?;
+        let mut moved = HashMap::new();
+        let mut pos = HEADER_LEN;
+        for l in keep {
+            let mut rec = vec![0u8; l.size() as usize];
+            src.seek(SeekFrom::Start(l.off))?;
+            src.read_exact(&mut rec)?;
+            out.write_all(&rec)?;
+            moved.insert(l.off, pos);
+            pos += l.size();
+        }
+        out.into_inner().map_err(|e| e.into_error())?.sync_all()?;
+        rewritten.push((
+            *id,
+            Some(Rewritten {
+                epoch,
+                len: pos,
+                moved,
+            }),
+        ));
+    }
+
+    // Swap in under the lock.
+    let mut inner = shared.lock().unwrap();
+    for (id, result) in rewritten {
+        let path = seg_path(&dir, id);
+        let before = inner
+            .index
+            .values()
+            .flatten()
+            .filter(|l| l.seg == id)
+            .count();
+        match result {
+            None => {
+                std::fs::remove_file(&path)?;
+                inner.segments.remove(&id);
+                inner.drop_segment_locs(id);
+                stats.segments_removed += 1;
+                stats.records_dropped += before;
+            }
+            Some(Rewritten { epoch, len, moved }) => {
+                std::fs::rename(dir.join(format!("seg-{:08}.log.tmp", id)), &path)?;
+                inner.segments.insert(
+                    id,
+                    Segment {
+                        epoch,
+                        scanned: len,
+                    },
+                );
+                inner.index.retain(|_, locs| {
+                    locs.retain_mut(|l| {
+                        if l.seg != id {
+                            return true;
+                        }
+                        match moved.get(&l.off) {
+                            Some(off) => {
+                                l.off = *off;
+                                true
+                            }
+                            None => false,
+                        }
+                    });
+                    !locs.is_empty()
+                });
+                inner.prune_keys();
+                stats.segments_rewritten += 1;
+                stats.records_dropped += before - moved.len();
+            }
+        }
+    }
+    stats.bytes_after = inner
+        .segments
+        .keys()
+        .filter_map(|id| std::fs::metadata(seg_path(&dir, *id)).ok())
+        .map(|m| m.len())
+        .sum();
+    inner.save_index()?;
+    counter!("persist_compactions_total", 1u64);
+    counter!(
+        "persist_compacted_records_total",
+        stats.records_dropped as u64
+    );
+    histogram!(
+        "persist_compact_ms",
+        started.elapsed().as_secs_f64() * 1000.0
+    );
+    Ok(Some(stats))
+}
+
+impl Drop for LogStore {
+    fn drop(&mut self) {
+        if let Ok(mut inner) = self.inner.lock() {
+            if inner.dirty {
+                if let Err(e) = inner.save_index() {
+                    warn!(error = %e, "persist: saving index failed");
+                }
+            }
+        }
+    }
+}
+
+impl Store for LogStore {
+    fn put_last(&self, la: LastApplied) -> Result<()> {
+        let started = std::time::Instant::now();
+        let mut la = la;
+        let sealed = {
+            let mut inner = self.inner.lock().unwrap();
+            inner.keyring.seal(&mut la)?;
+            let rec = encode(&la);
+            inner.refresh()?;
+            let sealed = inner.ensure_active(rec.len() as u64)?;
+            let (id, f) = inner.active.as_ref().expect("active segment");
+            let id = *id;
+            if f.metadata()?.len() > inner.segments[&id].scanned {
+                // Unreadable after the refresh: a torn write, unless another
+                // process is still appending it.
+                inner.scan_tail(id)?;
+            }
+            inner.writers.lock_shared()?;
+            let (_, f) = inner.active.as_mut().expect("active segment");
+            let written = f
+                .write_all(&rec)
+                .and_then(|()| f.flush())
+                .and_then(|()| f.stream_position());
+            inner.writers.unlock()?;
+            let end = written?;
+            let off = end - rec.len() as u64;
+            // Another process may have appended in between.
+            let scanned = inner.segments[&id].scanned;
+            if off > scanned {
+                inner.scan(id, scanned, Some(off), false)?;
+            }
+            let len = (rec.len() as u64 - REC_HEADER_LEN) as u32;
+            inner.insert(
+                la.uid,
+                Loc {
+                    seg: id,
+                    off,
+                    len,
+                    ts: la.ts,
+                },
+            );
+            if let Some(seg) = inner.segments.get_mut(&id) {
+                seg.scanned = seg.scanned.max(end);
+            }
+            if let Some(key) = la.key {
+                inner.set_key(la.uid, key);
+            }
+            if sealed {
+                inner.save_index()?;
+            }
+            sealed
+        };
+        if sealed && self.retention.background {
+            self.spawn_compaction();
+        }
+        histogram!("persist_put_ms", started.elapsed().as_secs_f64() * 1000.0);
+        counter!("persist_put_total", 1u64);
+        Ok(())
+    }
+
+    fn get_last(&self, uid: [u8; 16], limit: Option<usize>) -> Result<Vec<LastApplied>> {
+        let started = std::time::Instant::now();
+        let cap = limit.unwrap_or(3);
+        let mut inner = self.inner.lock().unwrap();
+        inner.refresh()?;
+        let out = match inner.read_newest(uid, cap, &self.retention) {
+            Ok(out) => out,
+            // Compacted by another process since the refresh.
+            Err(_) => {
+                inner.refresh()?;
+                inner.read_newest(uid, cap, &self.retention)?
+            }
+        };
+        histogram!("persist_get_ms", started.elapsed().as_secs_f64() * 1000.0);
+        Ok(out)
+    }
+}
+
+fn default_store_path() -> String {
+    match orka_dir() {
+        Some(mut p) => {
+            p.push("history");
+            p.to_string_lossy().to_string()
+        }
+        None => "orka-history".to_string(),
+    }
+}
+
+fn legacy_log_path() -> Option<PathBuf> {
+    orka_dir().map(|p| p.join("lastapplied.log"))
+}
+
+fn orka_dir() -> Option<PathBuf> {
+    let mut p = PathBuf::from(std::env::var_os("HOME")?);
+    p.push(".orka");
+    let _ = std::fs::create_dir_all(&p);
+    Some(p)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::{retention, temp_dir};
+
+    fn la(uid: u8, i: i64) -> LastApplied {
+        LastApplied {
+            uid: [uid; 16],
+            rv: format!("rv-{}", i),
+            ts: i,
+            yaml_zstd: format!("k: v{}\n", i).into_bytes(),
+            key: None,
+        }
+    }
+
+    #[test]
+    fn reopen_uses_index_and_truncates_torn_tail() {
+        let dir = temp_dir();
+        {
+            let s = LogStore::open_with(&dir, retention(0)).unwrap();
+            for i in 0..4 {
+                s.put_last(la(1, i)).unwrap();
+            }
+        }
+        assert!(dir.join("index").is_file());
+        // A torn write after the index was saved.
+        let seg = seg_path(&dir, 1);
+        let good = std::fs::metadata(&seg).unwrap().len();
+        let mut rec = encode(&la(1, 9));
+        rec.truncate(rec.len() - 3);
+        OpenOptions::new()
+            .append(true)
+            .open(&seg)
+            .unwrap()
+            .write_all(&rec)
+            .unwrap();
+
+        let s = LogStore::open_with(&dir, retention(0)).unwrap();
+        assert_eq!(std::fs::metadata(&seg).unwrap().len(), good);
+        let rows = s.get_last([1; 16], Some(10)).unwrap();
+        let rvs: Vec<&str> = rows.iter().map(|r| r.rv.as_str()).collect();
+        assert_eq!(rvs, ["rv-3", "rv-2", "rv-1", "rv-0"]);
+        s.put_last(la(1, 5)).unwrap();
+        assert_eq!(s.get_last([1; 16], Some(1)).unwrap()[0].rv, "rv-5");
+
+        // A corrupted index is rebuilt from the segments.
+        drop(s);
+        std::fs::write(dir.join("index"), b"garbage").unwrap();
+        let s = LogStore::open_with(&dir, retention(0)).unwrap();
+        assert_eq!(s.get_last([1; 16], Some(10)).unwrap().len(), 5);
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn torn_tail_is_kept_while_another_writer_appends() {
+        let dir = temp_dir();
+        {
+            let s = LogStore::open_with(&dir, retention(0)).unwrap();
+            s.put_last(la(1, 0)).unwrap();
+        }
+        let seg = seg_path(&dir, 1);
+        let mut rec = encode(&la(1, 1));
+        rec.truncate(rec.len() - 3);
+        OpenOptions::new()
+            .append(true)
+            .open(&seg)
+            .unwrap()
+            .write_all(&rec)
+            .unwrap();
+        let len = std::fs::metadata(&seg).unwrap().len();
+
+        // Another process is mid-append: the partial record stays.
+        let other = File::options()
+            .write(true)
+            .open(dir.join("writer.lock"))
+            .unwrap();
+        other.lock_shared().unwrap();
+        let s = LogStore::open_with(&dir, retention(0)).unwrap();
+        assert_eq!(std::fs::metadata(&seg).unwrap().len(), len);
+        assert_eq!(s.get_last([1; 16], Some(10)).unwrap().len(), 1);
+        drop(s);
+
+        // Once it is gone, the tail is torn and cut off.
+        other.unlock().unwrap();
+        let s = LogStore::open_with(&dir, retention(0)).unwrap();
+        assert!(std::fs::metadata(&seg).unwrap().len() < len);
+        s.put_last(la(1, 2)).unwrap();
+        let s = LogStore::open_with(&dir, retention(0)).unwrap();
+        assert_eq!(s.get_last([1; 16], Some(10)).unwrap().len(), 2);
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn retention_and_compaction_keep_newest() {
+        let dir = temp_dir();
+        let small = Retention {
+            segment_bytes: 4096,
+            ..retention(2)
+        };
+        let s = LogStore::open_with(&dir, small).unwrap();
+        for i in 0..200 {
+            s.put_last(la((i % 2) as u8, i)).unwrap();
+        }
+        // Retention applies to reads before any compaction.
+        let rows = s.get_last([0; 16], Some(10)).unwrap();
+        let rvs: Vec<&str> = rows.iter().map(|r| r.rv.as_str()).collect();
+        assert_eq!(rvs, ["rv-198", "rv-196"]);
+        let before = s.stats();
+        assert!(before.segments > 2, "{:?}", before);
+
+        let stats = s.compact().unwrap().unwrap();
+        assert!(stats.records_dropped > 150, "{:?}", stats);
+        assert!(stats.bytes_after < stats.bytes_before);
+        let after = s.stats();
+        assert!(after.segments < before.segments, "{:?}", after);
+        assert_eq!(s.get_last([1; 16], Some(10)).unwrap()[0].rv, "rv-199");
+        drop(s);
+
+        let s = LogStore::open_with(&dir, retention(2)).unwrap();
+        assert_eq!(s.stats().records, after.records);
+        assert_eq!(s.get_last([0; 16], Some(10)).unwrap().len(), 2);
+        let mut uids = s.uids();
+        uids.sort();
+        assert_eq!(uids, vec![[0; 16], [1; 16]]);
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn history_follows_key_across_uids() {
+        let dir = temp_dir();
+        let key = |name: &str| ObjectKey {
+            cluster: "kind-dev".into(),
+            gvk: "v1/ConfigMap".into(),
+            namespace: Some("shop".into()),
+            name: name.into(),
+        };
+        let keyed = |uid, i, name: &str| LastApplied {
+            key: Some(key(name)),
+            ..la(uid, i)
+        };
+        {
+            let s = LogStore::open_with(&dir, retention(0)).unwrap();
+            // Recorded before keys existed; the key is learned later.
+            s.put_last(la(1, 0)).unwrap();
+            s.put_last(keyed(1, 1, "cfg")).unwrap();
+            s.put_last(keyed(3, 2, "other")).unwrap();
+            // Deleted and recreated under a new UID.
+            s.put_last(keyed(2, 3, "cfg")).unwrap();
+            s.put_last(keyed(2, 4, "cfg")).unwrap();
+            let rows = s.history(&key("cfg"), Some(10)).unwrap();
+            let got: Vec<(u8, &str)> = rows.iter().map(|r| (r.uid[0], r.rv.as_str())).collect();
+            assert_eq!(got, [(2, "rv-4"), (2, "rv-3"), (1, "rv-1"), (1, "rv-0")]);
+            assert!(rows.iter().all(|r| r.key == Some(key("cfg"))));
+        }
+        for corrupt in [false, true] {
+            if corrupt {
+                std::fs::write(dir.join("index"), b"garbage").unwrap();
+            }
+            let s = LogStore::open_with(&dir, retention(0)).unwrap();
+            assert_eq!(s.key_of([1; 16]), Some(key("cfg")));
+            assert_eq!(s.history(&key("cfg"), Some(2)).unwrap()[1].rv, "rv-3");
+            let objects = s.objects();
+            let names: Vec<&str> = objects.iter().map(|o| o.key.name.as_str()).collect();
+            assert_eq!(names, ["cfg", "other"]);
+            assert_eq!(objects[0].uids, vec![[1; 16], [2; 16]]);
+            assert_eq!((objects[0].records, objects[0].last_ts), (4, 4));
+        }
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn rotate_key_reseals_all_records() {
+        let dir = temp_dir();
+        let contains = |needle: &[u8]| {
+            std::fs::read_dir(&dir).unwrap().any(|e| {
+                let bytes = std::fs::read(e.unwrap().path()).unwrap();
+                bytes.windows(needle.len()).any(|w| w == needle)
+            })
+        };
+        let (a, b) = (
+            Key::parse(&Key::generate()).unwrap(),
+            Key::parse(&Key::generate()).unwrap(),
+        );
+        let s = LogStore::open_with(&dir, retention(0)).unwrap();
+        s.put_last(la(1, 0)).unwrap();
+        let stats = s.rotate_key(Some(a.clone())).unwrap();
+        assert_eq!((stats.records, stats.key_id), (1, Some(a.id())));
+        s.put_last(la(1, 1)).unwrap();
+        assert!(s.encrypted());
+        assert!(!contains(b"k: v0") && !contains(b"k: v1"));
+        drop(s);
+
+        let open = |key: Option<Key>| {
+            LogStore::open_with(&dir, retention(0))
+                .unwrap()
+                .with_keyring(Keyring::new(key))
+        };
+        assert!(open(None).get_last([1; 16], None).is_err());
+        let s = open(Some(a));
+        let yaml: Vec<String> = s
+            .get_last([1; 16], None)
+            .unwrap()
+            .iter()
+            .map(|r| crate::maybe_decompress(&r.yaml_zstd))
+            .collect();
+        assert_eq!(yaml, ["k: v1\n", "k: v0\n"]);
+        s.rotate_key(Some(b.clone())).unwrap();
+        drop(s);
+        assert_eq!(open(Some(b)).get_last([1; 16], None).unwrap().len(), 2);
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    /// An old single-file log holding `records` of UID 4.
+    fn write_legacy(path: &Path, records: i64) {
+        let mut old = Vec::new();
+        for i in 0..records {
+            let l = la(4, i);
+            old.extend_from_slice(&l.ts.to_le_bytes());
+            old.extend_from_slice(&l.uid);
+            old.extend_from_slice(&(l.rv.len() as u32).to_le_bytes());
+            old.extend_from_slice(&(l.yaml_zstd.len() as u32).to_le_bytes());
+            old.extend_from_slice(l.rv.as_bytes());
+            old.extend_from_slice(&l.yaml_zstd);
+        }
+        std::fs::write(path, &old).unwrap();
+    }
+
+    #[test]
+    fn migrates_single_file_log() {
+        let dir = temp_dir();
+        std::fs::create_dir_all(&dir).unwrap();
+        let legacy = dir.join("lastapplied.log");
+        write_legacy(&legacy, 3);
+
+        let s = LogStore::open(legacy.to_str().unwrap()).unwrap();
+        assert_eq!(s.get_last([4; 16], Some(5)).unwrap()[0].rv, "rv-2");
+        assert!(!legacy.exists());
+        assert!(dir.join("lastapplied.log.migrated").is_file());
+
+        // Interrupted before the rename: running again adds nothing.
+        write_legacy(&legacy, 3);
+        assert_eq!(s.migrate_legacy(&legacy).unwrap(), 0);
+        assert_eq!(s.get_last([4; 16], Some(5)).unwrap().len(), 3);
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn reopening_a_migrated_path_keeps_its_history() {
+        let dir = temp_dir();
+        std::fs::create_dir_all(&dir).unwrap();
+        let legacy = dir.join("lastapplied.log");
+        write_legacy(&legacy, 3);
+        let path = legacy.to_str().unwrap();
+
+        let s = LogStore::open(path).unwrap();
+        s.put_last(la(4, 3)).unwrap();
+        drop(s);
+        let s = LogStore::open(path).unwrap();
+        assert_eq!(s.get_last([4; 16], Some(5)).unwrap().len(), 4);
+        assert!(!legacy.exists());
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
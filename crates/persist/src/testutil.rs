@@ -0,0 +1,26 @@
+//! Fixture helpers shared by this crate's unit tests.
+
+use std::path::PathBuf;
+
+use crate::Retention;
+
+/// A fresh store directory path under the system temp dir.
+pub(crate) fn temp_dir() -> PathBuf {
+    let nanos = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_nanos();
+    std::env::temp_dir().join(format!("orka-seg-{}-{}", std::process::id(), nanos))
+}
+
+/// Retention keeping `keep_per_uid` snapshots (0 = all), without the
+/// background compactor.
+pub(crate) fn retention(keep_per_uid: usize) -> Retention {
+    Retention {
+        keep_per_uid,
+        max_age_secs: None,
+        max_total_bytes: None,
+        background: false,
+        segment_bytes: 8 << 20,
+    }
+}
@@ -34,6 +34,8 @@ pub struct CrdSchema {
     pub flags: SchemaFlags,
 }
 
+const CREATION_TIMESTAMP: &str = "metadata.creationTimestamp";
+
 fn normalize_json_path(jp: &str) -> Option<String> {
     // Accept only simple paths like .spec.foo.bar[0]
     if jp.contains('?') || jp.contains('*') {
@@ -151,6 +153,15 @@ pub async fn fetch_crd_schema(gvk_key: &str) -> Result<Option<CrdSchema>> {
         Some(v) => v,
         None => return Err(anyhow!("CRD not found for {}", gvk_key)),
     };
+    let schema = schema_from_crd(&v, version);
+    // Let Table listings map server-rendered printer columns to the same ids.
+    orka_kubehub::set_table_columns(gvk_key, schema.table_columns());
+    Ok(Some(schema))
+}
+
+/// Build the schema for one CRD object (as JSON), preferring the storage
+/// version and falling back to `version` when the CRD lists none.
+fn schema_from_crd(v: &serde_json::Value, version: &str) -> CrdSchema {
     let versions = v
         .get("spec")
         .and_then(|s| s.get("versions"))
@@ -235,14 +246,16 @@ pub async fn fetch_crd_schema(gvk_key: &str) -> Result<Option<CrdSchema>> {
     // Projected paths: prefer printer columns; else derive from OpenAPI schema
     let mut projected_paths: Vec<PathSpec> = Vec::new();
     if !printer_cols.is_empty() {
-        for (i, c) in printer_cols.iter().enumerate() {
+        // Ids stay the printer column index. Age is skipped: LiteObj already
+        // carries creation_ts, and Tables only render it as a relative age.
+        for (i, c) in printer_cols.iter().enumerate().take(6) {
+            if c.json_path == CREATION_TIMESTAMP {
+                continue;
+            }
             projected_paths.push(PathSpec {
                 id: i as u32,
                 json_path: c.json_path.clone(),
             });
-            if projected_paths.len() >= 6 {
-                break;
-            }
         }
     } else {
         // Try to locate openAPIV3Schema for the chosen version
@@ -272,12 +285,12 @@ pub async fn fetch_crd_schema(gvk_key: &str) -> Result<Option<CrdSchema>> {
         }
     }
 
-    Ok(Some(CrdSchema {
+    CrdSchema {
         served_version,
         printer_cols,
         projected_paths,
         flags: SchemaFlags::default(),
-    }))
+    }
 }
 
 /// Simple projector built from a `CrdSchema` projected paths.
@@ -354,33 +367,68 @@ impl CrdSchema {
     pub fn projector(&self) -> SchemaProjector {
         SchemaProjector::new(self.projected_paths.clone())
     }
+
+    /// Printer column names paired with the projected id they feed, for
+    /// decoding `as=Table` lists. Empty when the ids come from the OpenAPI
+    /// schema instead.
+    pub fn table_columns(&self) -> Vec<(String, u32)> {
+        if self.printer_cols.is_empty() {
+            return Vec::new();
+        }
+        self.projected_paths
+            .iter()
+            .filter_map(|p| {
+                let col = self.printer_cols.get(p.id as usize)?;
+                Some((col.name.clone(), p.id))
+            })
+            .collect()
+    }
 }
 
 // Feature-gated JSON Schema validation utilities
+#[cfg(feature = "jsonschema-validate")]
+pub mod openapi;
+#[cfg(feature = "jsonschema-validate")]
+pub mod store;
+#[cfg(feature = "jsonschema-validate")]
+pub mod yamlpos;
+
 #[cfg(feature = "jsonschema-validate")]
 pub mod validate {
     use super::*;
     use anyhow::{Context, Result};
+    use jsonschema::error::ValidationErrorKind;
     use jsonschema::{Draft, JSONSchema};
+    use std::collections::HashMap;
+    use std::sync::{Mutex, OnceLock};
 
     #[derive(Debug, Clone, Serialize, Deserialize)]
     pub struct ValidationIssue {
         pub path: String,
         pub error: String,
         pub hint: Option<String>,
+        /// 1-based position in the validated YAML, when it could be located.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub line: Option<usize>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub column: Option<usize>,
     }
 
-    async fn fetch_openapi_schema(gvk_key: &str) -> Result<Option<serde_json::Value>> {
+    fn split_gvk(gvk_key: &str) -> Result<(&str, &str, &str)> {
+        let parts: Vec<_> = gvk_key.split('/').collect();
+        match parts.as_slice() {
+            [version, kind] => Ok(("", *version, *kind)),
+            [group, version, kind] => Ok((*group, *version, *kind)),
+            _ => Err(anyhow::anyhow!("invalid gvk key: {}", gvk_key)),
+        }
+    }
+
+    async fn fetch_crd_openapi_schema(gvk_key: &str) -> Result<Option<serde_json::Value>> {
         use k8s_openapi::apiextensions_apiserver::pkg::apis::apiextensions::v1 as apiextv1;
         use kube::{api::ListParams, Api};
 
         let client = orka_kubehub::get_kube_client().await?;
-        let parts: Vec<_> = gvk_key.split('/').collect();
-        let (group, version, kind) = match parts.as_slice() {
-            [version, kind] => ("", *version, *kind),
-            [group, version, kind] => (*group, *version, *kind),
-            _ => return Err(anyhow::anyhow!("invalid gvk key: {}", gvk_key)),
-        };
+        let (group, version, kind) = split_gvk(gvk_key)?;
         if group.is_empty() {
             return Ok(None);
         }
@@ -461,52 +509,148 @@ pub mod validate {
         Ok(schema_opt)
     }
 
-    /// Validate a YAML document against the CRD's `openAPIV3Schema` for the given GVK.
-    /// Returns a list of human-friendly issues; empty on success.
+    /// Schemas compiled so far, by GVK key. `JSONSchema` 0.17 borrows its
+    /// schema for `'static`, so each one is leaked once and reused.
+    fn schema_cache() -> &'static Mutex<HashMap<String, &'static serde_json::Value>> {
+        static CACHE: OnceLock<Mutex<HashMap<String, &'static serde_json::Value>>> =
+            OnceLock::new();
+        CACHE.get_or_init(|| Mutex::new(HashMap::new()))
+    }
+
+    fn offline_only() -> bool {
+        std::env::var("ORKA_SCHEMA_OFFLINE_ONLY")
+            .map(|v| v == "1" || v.eq_ignore_ascii_case("true") || v.eq_ignore_ascii_case("yes"))
+            .unwrap_or(false)
+    }
+
+    /// The offline store, loaded once from `ORKA_SCHEMA_PATH` (and the cached
+    /// cluster documents when offline-only).
+    fn offline_store() -> Result<&'static crate::store::SchemaStore> {
+        static STORE: OnceLock<std::result::Result<crate::store::SchemaStore, String>> =
+            OnceLock::new();
+        STORE
+            .get_or_init(|| {
+                crate::store::SchemaStore::from_env(offline_only()).map_err(|e| format!("{:#}", e))
+            })
+            .as_ref()
+            .map_err(|e| anyhow::anyhow!("loading offline schemas: {}", e))
+    }
+
+    /// Schema for a GVK: the offline store first, then (unless
+    /// `ORKA_SCHEMA_OFFLINE_ONLY`) the cluster's OpenAPI v3 document for
+    /// built-ins and CRDs alike, then the CRD's own `openAPIV3Schema` for
+    /// older servers.
+    async fn schema_for_gvk(gvk_key: &str) -> Result<Option<&'static serde_json::Value>> {
+        let (group, version, kind) = split_gvk(gvk_key)?;
+        if let Some(s) = offline_store()?.get(group, version, kind) {
+            return Ok(Some(s));
+        }
+        if offline_only() {
+            return Ok(None);
+        }
+        if let Some(s) = schema_cache().lock().unwrap().get(gvk_key) {
+            return Ok(Some(*s));
+        }
+        let schema = match crate::openapi::fetch_schema(group, version, kind).await {
+            Ok(Some(s)) => Some(s),
+            Ok(None) => fetch_crd_openapi_schema(gvk_key).await?,
+            Err(e) => {
+                tracing::debug!(error = %e, gvk = gvk_key, "openapi v3 unavailable");
+                fetch_crd_openapi_schema(gvk_key).await?
+            }
+        };
+        Ok(schema.map(|s| {
+            let s: &'static serde_json::Value = Box::leak(Box::new(s));
+            schema_cache()
+                .lock()
+                .unwrap()
+                .insert(gvk_key.to_string(), s);
+            s
+        }))
+    }
+
+    /// Validate a YAML document against the schema the cluster serves for the
+    /// given GVK. Returns a list of human-friendly issues; empty on success.
     pub async fn validate_yaml_for_gvk(gvk_key: &str, yaml: &str) -> Result<Vec<ValidationIssue>> {
-        let schema = match fetch_openapi_schema(gvk_key).await? {
+        let schema = match schema_for_gvk(gvk_key).await? {
             Some(s) => s,
             None => {
                 return Ok(vec![ValidationIssue {
                     path: "".into(),
-                    error: "no CRD schema available for builtin kind".into(),
+                    error: format!("no schema available for {}", gvk_key),
                     hint: None,
+                    line: None,
+                    column: None,
                 }])
             }
         };
-        let json: serde_json::Value = match serde_yaml::from_str::<serde_yaml::Value>(yaml) {
+        validate_with_schema(schema, yaml)
+    }
+
+    /// Validate a YAML document against a JSON Schema, locating each issue in
+    /// the YAML source.
+    pub fn validate_with_schema(
+        schema: &'static serde_json::Value,
+        yaml: &str,
+    ) -> Result<Vec<ValidationIssue>> {
+        let mut json: serde_json::Value = match serde_yaml::from_str::<serde_yaml::Value>(yaml) {
             Ok(v) => serde_json::to_value(v).context("converting YAML to JSON")?,
             Err(e) => {
+                let (line, column) = e
+                    .location()
+                    .map(|l| (Some(l.line()), Some(l.column())))
+                    .unwrap_or((None, None));
                 return Ok(vec![ValidationIssue {
                     path: "".into(),
                     error: format!("YAML parse error: {}", e),
                     hint: Some("check indentation and syntax".into()),
-                }])
+                    line,
+                    column,
+                }]);
             }
         };
-        // JSONSchema 0.17 requires a 'static schema reference; leak for now (acceptable for CLI usage).
-        let schema_static: &'static serde_json::Value = Box::leak(Box::new(schema));
+        crate::openapi::strip_nulls(&mut json);
         let compiled = JSONSchema::options()
             .with_draft(Draft::Draft7)
-            .compile(schema_static)
-            .context("compiling CRD JSON Schema")?;
+            .compile(schema)
+            .context("compiling JSON Schema")?;
+        let positions = crate::yamlpos::Positions::index(yaml);
         let mut issues: Vec<ValidationIssue> = Vec::new();
         let result = compiled.validate(&json);
         if let Err(errors) = result {
             for err in errors {
                 let path = err.instance_path.to_string();
                 let error = err.to_string();
+                // Point unknown fields at their key rather than the parent object.
+                let pos = match &err.kind {
+                    ValidationErrorKind::AdditionalProperties { unexpected } => unexpected
+                        .first()
+                        .and_then(|k| {
+                            let key = k.replace('~', "~0").replace('/', "~1");
+                            positions.key(&format!("{}/{}", path, key))
+                        })
+                        .or_else(|| positions.nearest(&path)),
+                    _ => positions.nearest(&path),
+                };
                 // Keep hints minimal to avoid depending on specific jsonschema internals
                 let hint = if error.contains("required property") {
                     Some("missing required field".into())
                 } else if error.contains("type:") || error.contains("expected type") {
                     Some("mismatched type".into())
-                } else if error.contains("enum") {
+                } else if error.contains("enum") || error.contains("is not one of") {
                     Some("value not in allowed set".into())
+                } else if matches!(err.kind, ValidationErrorKind::AdditionalProperties { .. }) {
+                    Some("unknown field".into())
                 } else {
                     None
                 };
-                issues.push(ValidationIssue { path, error, hint });
+                issues.push(ValidationIssue {
+                    path,
+                    error,
+                    hint,
+                    line: pos.map(|p| p.0),
+                    column: pos.map(|p| p.1),
+                });
             }
         }
         Ok(issues)
@@ -629,4 +773,67 @@ mod tests {
         assert!(out.contains(&(2, "3".to_string())));
         assert!(out.contains(&(3, "false".to_string())));
     }
+
+    #[test]
+    fn table_and_json_paths_project_the_same_columns() {
+        let crd = serde_json::json!({
+            "spec": {
+                "group": "test.orka.dev",
+                "names": {"kind": "Sprocket"},
+                "versions": [{
+                    "name": "v1",
+                    "served": true,
+                    "storage": true,
+                    "additionalPrinterColumns": [
+                        {"name": "Ready", "type": "boolean", "jsonPath": ".status.ready"},
+                        {"name": "Replicas", "type": "integer", "jsonPath": ".spec.replicas"},
+                        {"name": "Age", "type": "date", "jsonPath": ".metadata.creationTimestamp"}
+                    ]
+                }]
+            }
+        });
+        let schema = schema_from_crd(&crd, "v1");
+        orka_kubehub::set_table_columns("test.orka.dev/v1/Sprocket", schema.table_columns());
+
+        let metadata = serde_json::json!({
+            "name": "s1",
+            "namespace": "default",
+            "uid": "0b0e1c3a-5a4e-4c1e-9d3a-1f2e3d4c5b6a",
+            "creationTimestamp": "2024-01-01T00:00:00Z"
+        });
+        let object = serde_json::json!({
+            "apiVersion": "test.orka.dev/v1",
+            "kind": "Sprocket",
+            "metadata": metadata,
+            "spec": {"replicas": 3},
+            "status": {"ready": true}
+        });
+        let table = serde_json::json!({
+            "kind": "Table",
+            "metadata": {},
+            "columnDefinitions": [
+                {"name": "Name", "type": "string", "format": "name"},
+                {"name": "Ready", "type": "boolean"},
+                {"name": "Replicas", "type": "integer"},
+                {"name": "Age", "type": "date"}
+            ],
+            "rows": [{
+                "cells": ["s1", true, 3, "5m"],
+                "object": {"kind": "PartialObjectMetadata", "metadata": metadata}
+            }]
+        });
+        let gvk = kube::core::GroupVersionKind::gvk("test.orka.dev", "v1", "Sprocket");
+        let (items, _) =
+            orka_kubehub::decode_table_page(table.to_string().as_bytes(), &gvk).unwrap();
+
+        let mut from_table = items[0].projected.to_vec();
+        let mut from_json = schema.projector().project(&object).to_vec();
+        from_table.sort();
+        from_json.sort();
+        assert_eq!(
+            from_table,
+            vec![(0, "true".to_string()), (1, "3".to_string())]
+        );
+        assert_eq!(from_table, from_json);
+    }
 }
@@ -0,0 +1,438 @@
+//! JSON Schemas for any served kind from the cluster's `/openapi/v3`.
+//!
+//! The root document lists one document per group-version with a content
+//! hash in its URL; those are cached on disk under the server's git version
+//! and reused until the hash changes (new CRD versions, upgrades). A kind's
+//! schema is its component plus every component it references, rewritten
+//! into a self-contained Draft 7 schema: `$ref`s point at `definitions`,
+//! int-or-string and quantity fields accept numbers and strings, and objects
+//! with declared properties reject unknown fields unless they preserve them.
+
+use std::collections::{BTreeMap, VecDeque};
+use std::path::PathBuf;
+
+use anyhow::{anyhow, Context, Result};
+use serde_json::{json, Map, Value as Json};
+use tracing::debug;
+
+const REF_PREFIX: &str = "#/components/schemas/";
+
+/// Components that accept either a number or a string on the wire.
+const NUMBER_OR_STRING: &[&str] = &[
+    "io.k8s.apimachinery.pkg.api.resource.Quantity",
+    "io.k8s.apimachinery.pkg.util.intstr.IntOrString",
+];
+
+pub fn cache_dir() -> PathBuf {
+    if let Ok(p) = std::env::var("ORKA_OPENAPI_CACHE_PATH") {
+        return PathBuf::from(p);
+    }
+    let mut base = std::env::var("HOME")
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| PathBuf::from("."));
+    base.push(".orka/cache/openapi");
+    base
+}
+
+/// `api/v1` for the core group, `apis/<group>/<version>` otherwise.
+pub fn group_version_path(group: &str, version: &str) -> String {
+    if group.is_empty() {
+        format!("api/{}", version)
+    } else {
+        format!("apis/{}/{}", group, version)
+    }
+}
+
+fn sanitize(s: &str) -> String {
+    s.chars()
+        .map(|c| match c {
+            'a'..='z' | 'A'..='Z' | '0'..='9' | '.' | '-' | '_' => c,
+            _ => '_',
+        })
+        .collect()
+}
+
+async fn get_json(client: &kube::Client, url: &str) -> Result<Json> {
+    let req = http::Request::get(url)
+        .header(http::header::ACCEPT, "application/json")
+        .body(Vec::new())?;
+    let text = client
+        .request_text(req)
+        .await
+        .with_context(|| format!("GET {}", url))?;
+    serde_json::from_str(&text).with_context(|| format!("parsing {}", url))
+}
+
+/// Fetch the OpenAPI v3 document for a group-version, from the disk cache
+/// when its hash is unchanged. `Ok(None)` when the server does not publish it.
+pub async fn fetch_group_version(group: &str, version: &str) -> Result<Option<Json>> {
+    let client = orka_kubehub::get_kube_client().await?;
+    let server_version = client
+        .apiserver_version()
+        .await
+        .map(|v| v.git_version)
+        .unwrap_or_else(|_| "unknown".into());
+    let root = get_json(&client, "/openapi/v3").await?;
+    let gv = group_version_path(group, version);
+    let Some(url) = root
+        .pointer(&format!(
+            "/paths/{}/serverRelativeURL",
+            gv.replace('/', "~1")
+        ))
+        .and_then(|u| u.as_str())
+    else {
+        return Ok(None);
+    };
+    let hash = url.split_once("hash=").map(|(_, h)| h.to_string());
+    let file = hash.as_ref().map(|h| {
+        cache_dir().join(sanitize(&server_version)).join(format!(
+            "{}-{}.json",
+            sanitize(&gv),
+            sanitize(h)
+        ))
+    });
+    if let Some(f) = &file {
+        if let Ok(bytes) = std::fs::read(f) {
+            if let Ok(doc) = serde_json::from_slice(&bytes) {
+                debug!(path = %f.display(), "openapi: cache hit");
+                return Ok(Some(doc));
+            }
+        }
+    }
+    let doc = get_json(&client, url).await?;
+    if let Some(f) = &file {
+        if let Err(e) = save(f, &doc) {
+            debug!(error = %e, "openapi: cache write failed");
+        }
+    }
+    Ok(Some(doc))
+}
+
+fn save(path: &std::path::Path, doc: &Json) -> Result<()> {
+    let dir = path
+        .parent()
+        .ok_or_else(|| anyhow!("cache path without parent"))?;
+    std::fs::create_dir_all(dir)?;
+    let tmp = path.with_extension("json.tmp");
+    std::fs::write(&tmp, serde_json::to_vec(doc)?)?;
+    std::fs::rename(&tmp, path)?;
+    Ok(())
+}
+
+/// Schema for `group/version/kind` from the cluster, if it publishes one.
+pub async fn fetch_schema(group: &str, version: &str, kind: &str) -> Result<Option<Json>> {
+    Ok(fetch_group_version(group, version)
+        .await?
+        .and_then(|doc| schema_for_kind(&doc, group, version, kind)))
+}
+
+/// Every group/version/kind a group-version document defines.
+pub fn kinds(doc: &Json) -> Vec<(String, String, String)> {
+    let Some(schemas) = doc
+        .pointer("/components/schemas")
+        .and_then(|s| s.as_object())
+    else {
+        return Vec::new();
+    };
+    let mut out: Vec<(String, String, String)> = schemas
+        .values()
+        .filter_map(|s| s.get("x-kubernetes-group-version-kind")?.as_array())
+        .flatten()
+        .filter_map(|g| {
+            Some((
+                g.get("group")
+                    .and_then(|v| v.as_str())
+                    .unwrap_or("")
+                    .to_string(),
+                g.get("version")?.as_str()?.to_string(),
+                g.get("kind")?.as_str()?.to_string(),
+            ))
+        })
+        .collect();
+    out.sort();
+    out.dedup();
+    out
+}
+
+/// Write every group-version document the cluster serves to `dir` as
+/// `<group-version>.json`, for offline validation. Returns the file count.
+pub async fn dump(dir: &std::path::Path) -> Result<usize> {
+    let client = orka_kubehub::get_kube_client().await?;
+    let root = get_json(&client, "/openapi/v3").await?;
+    let paths = root
+        .get("paths")
+        .and_then(|p| p.as_object())
+        .ok_or_else(|| anyhow!("/openapi/v3 lists no paths"))?;
+    let mut n = 0;
+    for (gv, entry) in paths {
+        if !(gv.starts_with("api/") || gv.starts_with("apis/")) {
+            continue;
+        }
+        let Some(url) = entry.get("serverRelativeURL").and_then(|u| u.as_str()) else {
+            continue;
+        };
+        let doc = get_json(&client, url).await?;
+        save(&dir.join(format!("{}.json", sanitize(gv))), &doc)?;
+        n += 1;
+    }
+    Ok(n)
+}
+
+/// Name of the component tagged with `x-kubernetes-group-version-kind` for
+/// the given kind.
+fn component_for_kind<'a>(
+    schemas: &'a Map<String, Json>,
+    group: &str,
+    version: &str,
+    kind: &str,
+) -> Option<&'a String> {
+    schemas.iter().find_map(|(name, s)| {
+        let gvks = s.get("x-kubernetes-group-version-kind")?.as_array()?;
+        gvks.iter()
+            .any(|g| {
+                g.get("group").and_then(|v| v.as_str()).unwrap_or("") == group
+                    && g.get("version").and_then(|v| v.as_str()) == Some(version)
+                    && g.get("kind").and_then(|v| v.as_str()) == Some(kind)
+            })
+            .then_some(name)
+    })
+}
+
+/// Build a self-contained JSON Schema for a kind from a group-version
+/// OpenAPI v3 document.
+pub fn schema_for_kind(doc: &Json, group: &str, version: &str, kind: &str) -> Option<Json> {
+    let schemas = doc.pointer("/components/schemas")?.as_object()?;
+    let root = component_for_kind(schemas, group, version, kind)?;
+    let mut definitions = BTreeMap::new();
+    let mut queue = VecDeque::from([root.clone()]);
+    while let Some(name) = queue.pop_front() {
+        if definitions.contains_key(&name) {
+            continue;
+        }
+        let Some(s) = schemas.get(&name) else {
+            continue;
+        };
+        collect_refs(s, &mut queue);
+        definitions.insert(name.clone(), convert(&name, s));
+    }
+    let mut out = definitions.get(root).cloned()?;
+    if let Some(obj) = out.as_object_mut() {
+        obj.insert(
+            "definitions".into(),
+            Json::Object(definitions.into_iter().collect()),
+        );
+    }
+    Some(out)
+}
+
+fn collect_refs(v: &Json, out: &mut VecDeque<String>) {
+    match v {
+        Json::Object(m) => {
+            if let Some(r) = m.get("$ref").and_then(|r| r.as_str()) {
+                if let Some(name) = r.strip_prefix(REF_PREFIX) {
+                    out.push_back(name.to_string());
+                }
+            }
+            m.values().for_each(|c| collect_refs(c, out));
+        }
+        Json::Array(a) => a.iter().for_each(|c| collect_refs(c, out)),
+        _ => {}
+    }
+}
+
+fn number_or_string() -> Json {
+    json!({"anyOf": [{"type": "number"}, {"type": "string"}]})
+}
+
+fn convert(name: &str, s: &Json) -> Json {
+    if NUMBER_OR_STRING.contains(&name) {
+        return number_or_string();
+    }
+    convert_node(s)
+}
+
+pub(crate) fn convert_node(s: &Json) -> Json {
+    let Json::Object(m) = s else {
+        return s.clone();
+    };
+    if m.get("x-kubernetes-int-or-string")
+        .and_then(|v| v.as_bool())
+        == Some(true)
+        || m.get("format").and_then(|v| v.as_str()) == Some("int-or-string")
+    {
+        return number_or_string();
+    }
+    let mut out = Map::new();
+    for (k, v) in m {
+        let v = match k.as_str() {
+            "$ref" => match v.as_str().and_then(|r| r.strip_prefix(REF_PREFIX)) {
+                Some(name) => Json::String(format!("#/definitions/{}", name)),
+                None => v.clone(),
+            },
+            "properties" | "patternProperties" => match v {
+                Json::Object(props) => Json::Object(
+                    props
+                        .iter()
+                        .map(|(pk, pv)| (pk.clone(), convert_node(pv)))
+                        .collect(),
+                ),
+                other => other.clone(),
+            },
+            "items" | "additionalProperties" | "not" => convert_node(v),
+            "allOf" | "anyOf" | "oneOf" => match v {
+                Json::Array(a) => Json::Array(a.iter().map(convert_node).collect()),
+                other => other.clone(),
+            },
+            // Informational only; keeps the compiled schema small.
+            "description" | "x-kubernetes-group-version-kind" => continue,
+            _ => v.clone(),
+        };
+        out.insert(k.clone(), v);
+    }
+    if out.remove("nullable").and_then(|v| v.as_bool()) == Some(true) {
+        if let Some(Json::String(t)) = out.get("type").cloned() {
+            out.insert("type".into(), json!([t, "null"]));
+        }
+    }
+    let preserves = out
+        .get("x-kubernetes-preserve-unknown-fields")
+        .and_then(|v| v.as_bool())
+        == Some(true);
+    if out.contains_key("properties") && !out.contains_key("additionalProperties") && !preserves {
+        out.insert("additionalProperties".into(), Json::Bool(false));
+    }
+    Json::Object(out)
+}
+
+/// Drop `null` map values: the apiserver treats them as unset, so they should
+/// not trip type checks (e.g. `creationTimestamp: null` in exported YAML).
+pub fn strip_nulls(v: &mut Json) {
+    match v {
+        Json::Object(m) => {
+            m.retain(|_, c| !c.is_null());
+            m.values_mut().for_each(strip_nulls);
+        }
+        Json::Array(a) => a.iter_mut().for_each(strip_nulls),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A trimmed `api/v1` document with the shapes that matter.
+    fn core_v1_doc() -> Json {
+        json!({
+            "openapi": "3.0.0",
+            "components": {"schemas": {
+                "io.k8s.api.core.v1.Pod": {
+                    "type": "object",
+                    "properties": {
+                        "apiVersion": {"type": "string"},
+                        "kind": {"type": "string"},
+                        "metadata": {"allOf": [{"$ref": "#/components/schemas/io.k8s.apimachinery.pkg.apis.meta.v1.ObjectMeta"}], "default": {}},
+                        "spec": {"allOf": [{"$ref": "#/components/schemas/io.k8s.api.core.v1.PodSpec"}], "default": {}},
+                    },
+                    "x-kubernetes-group-version-kind": [{"group": "", "kind": "Pod", "version": "v1"}],
+                },
+                "io.k8s.api.core.v1.PodSpec": {
+                    "type": "object",
+                    "required": ["containers"],
+                    "properties": {
+                        "containers": {"type": "array", "items": {"allOf": [{"$ref": "#/components/schemas/io.k8s.api.core.v1.Container"}], "default": {}}},
+                    },
+                },
+                "io.k8s.api.core.v1.Container": {
+                    "type": "object",
+                    "required": ["name"],
+                    "properties": {
+                        "name": {"type": "string", "description": "Name of the container."},
+                        "image": {"type": "string"},
+                        "imagePullPolicy": {"type": "string", "enum": ["Always", "IfNotPresent", "Never"]},
+                        "ports": {"type": "array", "items": {"type": "object", "properties": {
+                            "containerPort": {"type": "integer", "format": "int32"},
+                        }}},
+                        "resources": {"type": "object", "properties": {
+                            "limits": {"type": "object", "additionalProperties": {"allOf": [{"$ref": "#/components/schemas/io.k8s.apimachinery.pkg.api.resource.Quantity"}]}},
+                        }},
+                    },
+                },
+                "io.k8s.apimachinery.pkg.apis.meta.v1.ObjectMeta": {
+                    "type": "object",
+                    "properties": {
+                        "name": {"type": "string"},
+                        "labels": {"type": "object", "additionalProperties": {"type": "string", "default": ""}},
+                        "creationTimestamp": {"allOf": [{"$ref": "#/components/schemas/io.k8s.apimachinery.pkg.apis.meta.v1.Time"}]},
+                    },
+                },
+                "io.k8s.apimachinery.pkg.apis.meta.v1.Time": {"type": "string", "format": "date-time"},
+                "io.k8s.apimachinery.pkg.api.resource.Quantity": {"type": "string"},
+                "io.k8s.api.core.v1.Service": {"type": "object"},
+            }},
+        })
+    }
+
+    #[test]
+    fn builds_self_contained_schema_for_kind() {
+        let s = schema_for_kind(&core_v1_doc(), "", "v1", "Pod").unwrap();
+        let defs = s["definitions"].as_object().unwrap();
+        let mut names: Vec<&str> = defs.keys().map(|k| k.as_str()).collect();
+        names.sort();
+        assert_eq!(
+            names,
+            vec![
+                "io.k8s.api.core.v1.Container",
+                "io.k8s.api.core.v1.Pod",
+                "io.k8s.api.core.v1.PodSpec",
+                "io.k8s.apimachinery.pkg.api.resource.Quantity",
+                "io.k8s.apimachinery.pkg.apis.meta.v1.ObjectMeta",
+                "io.k8s.apimachinery.pkg.apis.meta.v1.Time",
+            ],
+            "unreferenced Service left out"
+        );
+        assert_eq!(
+            s["properties"]["spec"]["allOf"][0]["$ref"],
+            "#/definitions/io.k8s.api.core.v1.PodSpec"
+        );
+        assert_eq!(s["additionalProperties"], false);
+        assert_eq!(
+            defs["io.k8s.apimachinery.pkg.api.resource.Quantity"],
+            number_or_string()
+        );
+        assert!(defs["io.k8s.api.core.v1.Container"]["properties"]["name"]
+            .get("description")
+            .is_none());
+        assert!(schema_for_kind(&core_v1_doc(), "apps", "v1", "Pod").is_none());
+        assert_eq!(group_version_path("", "v1"), "api/v1");
+        assert_eq!(group_version_path("apps", "v1"), "apis/apps/v1");
+    }
+
+    #[test]
+    fn catches_enum_typos_and_unknown_fields_with_positions() {
+        let schema = schema_for_kind(&core_v1_doc(), "", "v1", "Pod").unwrap();
+        let schema: &'static Json = Box::leak(Box::new(schema));
+        let yaml = "apiVersion: v1\nkind: Pod\nmetadata:\n  name: web\n  creationTimestamp: null\nspec:\n  containers:\n  - name: app\n    image: nginx\n    imagePullPolicy: always\n    resources:\n      limits: {cpu: 500m, memory: 1}\n    imagePullPolice: Always\n";
+        let issues = crate::validate::validate_with_schema(schema, yaml).unwrap();
+        let found: Vec<(&str, Option<usize>, Option<usize>)> = issues
+            .iter()
+            .map(|i| (i.path.as_str(), i.line, i.column))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                ("/spec/containers/0/imagePullPolicy", Some(10), Some(22)),
+                ("/spec/containers/0", Some(13), Some(5)),
+            ],
+            "{:?}",
+            issues
+        );
+        assert_eq!(issues[0].hint.as_deref(), Some("value not in allowed set"));
+        assert_eq!(issues[1].hint.as_deref(), Some("unknown field"));
+
+        let ok = "apiVersion: v1\nkind: Pod\nspec:\n  containers:\n  - name: app\n    ports:\n    - containerPort: 80\n";
+        assert!(crate::validate::validate_with_schema(schema, ok)
+            .unwrap()
+            .is_empty());
+    }
+}
@@ -0,0 +1,373 @@
+//! Offline schema store for validation without cluster access.
+//!
+//! Filled from any mix of:
+//! - CRD manifests (`.yaml`/`.yml`, multi-document, or `.json`): one schema
+//!   per version that carries an `openAPIV3Schema`;
+//! - OpenAPI v3 group-version documents (`.json` with `components`), as
+//!   written to the `/openapi/v3` cache or dumped with `orkactl schema --dump`;
+//! - standalone JSON Schemas tagged with `x-kubernetes-group-version-kind`.
+//!   No set for built-in kinds ships with orka; the supported one is
+//!   `yannh/kubernetes-json-schema` (`<version>-standalone-strict` or
+//!   `<version>-standalone`), downloaded separately.
+//!
+//! `ORKA_SCHEMA_PATH` lists the sources (path-list separated); with
+//! `ORKA_SCHEMA_K8S_VERSION` set, a directory's `<version>`,
+//! `<version>-standalone-strict` or `<version>-standalone` subdirectory is
+//! used when present. When offline-only, the cached cluster documents for
+//! that version (or the most recent one) are loaded underneath.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use serde::Deserialize as _;
+use serde_json::{json, Value as Json};
+
+use crate::openapi;
+
+#[derive(Debug, Default)]
+pub struct SchemaStore {
+    schemas: HashMap<String, Json>,
+}
+
+/// Kinds are matched case-insensitively: schema sets named by file only
+/// know the lower-cased kind.
+fn store_key(group: &str, version: &str, kind: &str) -> String {
+    format!("{}/{}/{}", group, version, kind).to_ascii_lowercase()
+}
+
+impl SchemaStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.schemas.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.schemas.is_empty()
+    }
+
+    /// Add or replace the schema for a kind.
+    pub fn insert(&mut self, group: &str, version: &str, kind: &str, schema: Json) {
+        self.schemas.insert(store_key(group, version, kind), schema);
+    }
+
+    pub fn get(&self, group: &str, version: &str, kind: &str) -> Option<&Json> {
+        self.schemas.get(&store_key(group, version, kind))
+    }
+
+    /// Add every CRD in a (multi-document) YAML string; other kinds are
+    /// ignored. Returns the number of schemas added.
+    pub fn add_crds_yaml(&mut self, yaml: &str) -> Result<usize> {
+        let mut n = 0;
+        for doc in serde_yaml::Deserializer::from_str(yaml) {
+            let v = serde_yaml::Value::deserialize(doc)?;
+            let v: Json = serde_json::to_value(v)?;
+            n += self.add_crd_or_list(&v);
+        }
+        Ok(n)
+    }
+
+    fn add_crd_or_list(&mut self, v: &Json) -> usize {
+        match v.get("kind").and_then(|k| k.as_str()) {
+            Some("CustomResourceDefinition") => {
+                let schemas = crd_schemas(v);
+                let n = schemas.len();
+                for (g, ver, k, s) in schemas {
+                    self.insert(&g, &ver, &k, s);
+                }
+                n
+            }
+            Some(k) if k.ends_with("List") => v
+                .get("items")
+                .and_then(|i| i.as_array())
+                .map(|items| items.iter().map(|i| self.add_crd_or_list(i)).sum())
+                .unwrap_or(0),
+            _ => 0,
+        }
+    }
+
+    /// Add every kind an OpenAPI v3 group-version document defines.
+    pub fn add_openapi_doc(&mut self, doc: &Json) -> usize {
+        let mut n = 0;
+        for (g, v, k) in openapi::kinds(doc) {
+            if let Some(s) = openapi::schema_for_kind(doc, &g, &v, &k) {
+                self.insert(&g, &v, &k, s);
+                n += 1;
+            }
+        }
+        n
+    }
+
+    /// Add a JSON file: an OpenAPI document, a standalone kind schema or a CRD.
+    pub fn add_json(&mut self, v: &Json) -> usize {
+        if v.get("components").is_some() {
+            return self.add_openapi_doc(v);
+        }
+        if let Some(gvks) = v
+            .get("x-kubernetes-group-version-kind")
+            .and_then(|g| g.as_array())
+        {
+            let mut n = 0;
+            for gvk in gvks {
+                let field = |f: &str| gvk.get(f).and_then(|s| s.as_str()).unwrap_or("");
+                if !field("version").is_empty() && !field("kind").is_empty() {
+                    self.insert(field("group"), field("version"), field("kind"), v.clone());
+                    n += 1;
+                }
+            }
+            return n;
+        }
+        self.add_crd_or_list(v)
+    }
+
+    /// Add a file or, recursively, a directory of schema sources.
+    pub fn add_path(&mut self, path: &Path) -> Result<usize> {
+        if path.is_dir() {
+            let dir = versioned_subdir(path).unwrap_or_else(|| path.to_path_buf());
+            let mut entries: Vec<(std::time::SystemTime, PathBuf)> = std::fs::read_dir(&dir)
+                .with_context(|| format!("reading {}", dir.display()))?
+                .filter_map(|e| e.ok())
+                .map(|e| {
+                    let mtime = e
+                        .metadata()
+                        .and_then(|m| m.modified())
+                        .unwrap_or(std::time::UNIX_EPOCH);
+                    (mtime, e.path())
+                })
+                .collect();
+            // Oldest first, so the newest of several cached documents for a
+            // group-version wins.
+            entries.sort();
+            let mut n = 0;
+            for (_, p) in entries {
+                if p.is_dir() || has_ext(&p, &["yaml", "yml", "json"]) {
+                    n += self.add_path(&p)?;
+                }
+            }
+            return Ok(n);
+        }
+        let text =
+            std::fs::read_to_string(path).with_context(|| format!("reading {}", path.display()))?;
+        if has_ext(path, &["json"]) {
+            let v: Json = serde_json::from_str(&text)
+                .with_context(|| format!("parsing {}", path.display()))?;
+            Ok(self.add_json(&v))
+        } else {
+            self.add_crds_yaml(&text)
+                .with_context(|| format!("parsing {}", path.display()))
+        }
+    }
+
+    /// Build the store from `ORKA_SCHEMA_PATH`, plus the cached cluster
+    /// documents when `offline_only`.
+    pub fn from_env(offline_only: bool) -> Result<Self> {
+        let mut store = Self::new();
+        if offline_only {
+            if let Some(dir) = cached_cluster_dir(&openapi::cache_dir()) {
+                store.add_path(&dir)?;
+            }
+        }
+        if let Some(paths) = std::env::var_os("ORKA_SCHEMA_PATH") {
+            for p in std::env::split_paths(&paths) {
+                if !p.as_os_str().is_empty() {
+                    store.add_path(&p)?;
+                }
+            }
+        }
+        Ok(store)
+    }
+}
+
+fn has_ext(p: &Path, exts: &[&str]) -> bool {
+    p.extension()
+        .and_then(|e| e.to_str())
+        .map(|e| exts.iter().any(|x| e.eq_ignore_ascii_case(x)))
+        .unwrap_or(false)
+}
+
+fn k8s_version() -> Option<String> {
+    std::env::var("ORKA_SCHEMA_K8S_VERSION")
+        .ok()
+        .filter(|v| !v.is_empty())
+}
+
+fn versioned_subdir(dir: &Path) -> Option<PathBuf> {
+    let v = k8s_version()?;
+    let v = if v.starts_with('v') {
+        v
+    } else {
+        format!("v{}", v)
+    };
+    [
+        v.clone(),
+        format!("{}-standalone-strict", v),
+        format!("{}-standalone", v),
+    ]
+    .into_iter()
+    .map(|d| dir.join(d))
+    .find(|d| d.is_dir())
+}
+
+/// The `/openapi/v3` cache directory of the configured server version
+/// (matched by prefix, so `v1.29` finds `v1.29.4-gke.1`), else the most
+/// recently written one.
+fn cached_cluster_dir(root: &Path) -> Option<PathBuf> {
+    let mut dirs: Vec<(std::time::SystemTime, PathBuf)> = std::fs::read_dir(root)
+        .ok()?
+        .filter_map(|e| e.ok())
+        .filter(|e| e.path().is_dir())
+        .map(|e| {
+            let mtime = e
+                .metadata()
+                .and_then(|m| m.modified())
+                .unwrap_or(std::time::UNIX_EPOCH);
+            (mtime, e.path())
+        })
+        .collect();
+    dirs.sort();
+    if let Some(v) = k8s_version() {
+        let v = if v.starts_with('v') {
+            v
+        } else {
+            format!("v{}", v)
+        };
+        return dirs.into_iter().rev().map(|(_, d)| d).find(|d| {
+            d.file_name()
+                .and_then(|n| n.to_str())
+                .map(|n| n.starts_with(&v))
+                .unwrap_or(false)
+        });
+    }
+    dirs.pop().map(|(_, d)| d)
+}
+
+/// Schemas of a CRD, one per version with an `openAPIV3Schema` (falling back
+/// to the legacy `spec.validation`), converted like the served OpenAPI ones.
+pub fn crd_schemas(crd: &Json) -> Vec<(String, String, String, Json)> {
+    let Some(spec) = crd.get("spec") else {
+        return Vec::new();
+    };
+    let group = spec.get("group").and_then(|g| g.as_str()).unwrap_or("");
+    let Some(kind) = spec.pointer("/names/kind").and_then(|k| k.as_str()) else {
+        return Vec::new();
+    };
+    let legacy = spec.pointer("/validation/openAPIV3Schema");
+    let mut versions: Vec<(String, Option<&Json>)> = spec
+        .get("versions")
+        .and_then(|v| v.as_array())
+        .map(|vs| {
+            vs.iter()
+                .filter_map(|v| {
+                    let name = v.get("name")?.as_str()?.to_string();
+                    Some((name, v.pointer("/schema/openAPIV3Schema")))
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    if versions.is_empty() {
+        if let Some(v) = spec.get("version").and_then(|v| v.as_str()) {
+            versions.push((v.to_string(), None));
+        }
+    }
+    versions
+        .into_iter()
+        .filter_map(|(version, schema)| {
+            let mut schema = schema.or(legacy)?.clone();
+            // Every object carries these even when the CRD leaves them out.
+            if let Some(props) = schema.get_mut("properties").and_then(|p| p.as_object_mut()) {
+                props
+                    .entry("apiVersion")
+                    .or_insert_with(|| json!({"type": "string"}));
+                props
+                    .entry("kind")
+                    .or_insert_with(|| json!({"type": "string"}));
+                props
+                    .entry("metadata")
+                    .or_insert_with(|| json!({"type": "object"}));
+            }
+            Some((
+                group.to_string(),
+                version,
+                kind.to_string(),
+                openapi::convert_node(&schema),
+            ))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CRD: &str = r#"
+apiVersion: apiextensions.k8s.io/v1
+kind: CustomResourceDefinition
+metadata:
+  name: widgets.example.com
+spec:
+  group: example.com
+  names: {kind: Widget, plural: widgets}
+  scope: Namespaced
+  versions:
+  - name: v1
+    served: true
+    storage: true
+    schema:
+      openAPIV3Schema:
+        type: object
+        properties:
+          spec:
+            type: object
+            properties:
+              size: {type: string, enum: [small, large]}
+              port: {x-kubernetes-int-or-string: true}
+              extra: {type: object, x-kubernetes-preserve-unknown-fields: true}
+---
+apiVersion: v1
+kind: ConfigMap
+metadata: {name: ignored}
+"#;
+
+    #[test]
+    fn loads_crd_files_and_validates_offline() {
+        let mut store = SchemaStore::new();
+        assert_eq!(store.add_crds_yaml(CRD).unwrap(), 1);
+        assert!(store.get("example.com", "v1", "widget").is_some());
+        let schema: &'static Json = Box::leak(Box::new(
+            store.get("example.com", "v1", "Widget").unwrap().clone(),
+        ));
+
+        let ok = "apiVersion: example.com/v1\nkind: Widget\nmetadata:\n  name: w\nspec:\n  size: small\n  port: http\n  extra: {anything: 1}\n";
+        assert!(crate::validate::validate_with_schema(schema, ok)
+            .unwrap()
+            .is_empty());
+
+        let bad = "apiVersion: example.com/v1\nkind: Widget\nmetadata:\n  name: w\nspec:\n  size: medium\n  colour: red\n";
+        let issues = crate::validate::validate_with_schema(schema, bad).unwrap();
+        let paths: Vec<_> = issues.iter().map(|i| (i.path.as_str(), i.line)).collect();
+        assert_eq!(paths, vec![("/spec/size", Some(6)), ("/spec", Some(7))]);
+    }
+
+    #[test]
+    fn loads_directories_of_mixed_sources() {
+        let dir = std::env::temp_dir().join(format!("orka-schema-store-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("nested")).unwrap();
+        std::fs::write(dir.join("nested/widgets.yaml"), CRD).unwrap();
+        std::fs::write(
+            dir.join("configmap-v1.json"),
+            r#"{"type":"object","properties":{"data":{"type":"object"}},
+                "x-kubernetes-group-version-kind":[{"group":"","kind":"ConfigMap","version":"v1"}]}"#,
+        )
+        .unwrap();
+        std::fs::write(dir.join("README.md"), "not a schema").unwrap();
+        let mut store = SchemaStore::new();
+        let n = store.add_path(&dir).unwrap();
+        std::fs::remove_dir_all(&dir).ok();
+        assert_eq!(n, 2);
+        assert!(store.get("", "v1", "ConfigMap").is_some());
+        assert!(store.get("example.com", "v1", "Widget").is_some());
+    }
+}
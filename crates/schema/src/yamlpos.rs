@@ -0,0 +1,214 @@
+//! YAML source positions for JSON pointers.
+//!
+//! Validation reports instance paths as JSON pointers (`/spec/replicas`);
+//! this index maps them back to 1-based line and column in the first YAML
+//! document, for both a node's value and, inside mappings, its key.
+
+use std::collections::HashMap;
+
+use yaml_rust::parser::{Event, MarkedEventReceiver, Parser};
+use yaml_rust::scanner::Marker;
+
+/// Line and column, both 1-based.
+pub type Pos = (usize, usize);
+
+#[derive(Debug, Default)]
+pub struct Positions {
+    values: HashMap<String, Pos>,
+    keys: HashMap<String, Pos>,
+}
+
+impl Positions {
+    /// Index the first document of `yaml`; unparseable input yields an
+    /// empty index.
+    pub fn index(yaml: &str) -> Self {
+        let mut rx = Receiver::default();
+        let mut parser = Parser::new(yaml.chars());
+        let _ = parser.load(&mut rx, false);
+        rx.out
+    }
+
+    /// Where the value at `pointer` starts.
+    pub fn value(&self, pointer: &str) -> Option<Pos> {
+        self.values.get(pointer).copied()
+    }
+
+    /// Where the mapping key naming `pointer` starts, falling back to its value.
+    pub fn key(&self, pointer: &str) -> Option<Pos> {
+        self.keys
+            .get(pointer)
+            .copied()
+            .or_else(|| self.value(pointer))
+    }
+
+    /// The value at `pointer`, or the key of its closest indexed ancestor.
+    pub fn nearest(&self, pointer: &str) -> Option<Pos> {
+        if let Some(pos) = self.value(pointer) {
+            return Some(pos);
+        }
+        let mut p = pointer;
+        loop {
+            if let Some(pos) = self.key(p) {
+                return Some(pos);
+            }
+            match p.rfind('/') {
+                Some(i) => p = &p[..i],
+                None => return None,
+            }
+        }
+    }
+}
+
+enum Frame {
+    /// `start` is where the mapping began; its value position is taken from
+    /// the first key instead when there is one, as block mappings are marked
+    /// at their first `:` rather than the key before it.
+    Map {
+        path: String,
+        key: Option<String>,
+        start: Pos,
+    },
+    Seq {
+        path: String,
+        idx: usize,
+    },
+}
+
+#[derive(Default)]
+struct Receiver {
+    stack: Vec<Frame>,
+    out: Positions,
+    done: bool,
+}
+
+fn escape(seg: &str) -> String {
+    seg.replace('~', "~0").replace('/', "~1")
+}
+
+fn pos(m: &Marker) -> Pos {
+    (m.line(), m.col() + 1)
+}
+
+impl Receiver {
+    /// Path of the node starting now, or `None` when it is a mapping key.
+    fn begin_node(&mut self, scalar: Option<&str>, mark: Marker, is_map: bool) -> Option<String> {
+        let path = match self.stack.last_mut() {
+            None => String::new(),
+            Some(Frame::Map { path, key, .. }) => match key {
+                None => {
+                    if let Some(k) = scalar {
+                        let child = format!("{}/{}", path, escape(k));
+                        self.out.values.entry(path.clone()).or_insert(pos(&mark));
+                        self.out.keys.insert(child, pos(&mark));
+                        *key = Some(k.to_string());
+                    } else {
+                        // Complex keys are not addressable by JSON pointer.
+                        *key = Some(String::new());
+                    }
+                    return None;
+                }
+                Some(k) => format!("{}/{}", path, escape(k)),
+            },
+            Some(Frame::Seq { path, idx }) => format!("{}/{}", path, idx),
+        };
+        if !is_map {
+            self.out.values.entry(path.clone()).or_insert(pos(&mark));
+        }
+        Some(path)
+    }
+
+    fn end_node(&mut self) {
+        match self.stack.last_mut() {
+            Some(Frame::Map { key, .. }) => *key = None,
+            Some(Frame::Seq { idx, .. }) => *idx += 1,
+            None => self.done = true,
+        }
+    }
+}
+
+impl MarkedEventReceiver for Receiver {
+    fn on_event(&mut self, ev: Event, mark: Marker) {
+        if self.done {
+            return;
+        }
+        match ev {
+            Event::Scalar(..) | Event::Alias(_) => {
+                let scalar = match &ev {
+                    Event::Scalar(v, ..) => Some(v.as_str()),
+                    _ => None,
+                };
+                let is_value = self.begin_node(scalar, mark, false).is_some();
+                if is_value {
+                    self.end_node();
+                }
+            }
+            Event::MappingStart(_) => {
+                let start = pos(&mark);
+                // A mapping used as a key is swallowed as an unnamed frame.
+                let path = self
+                    .begin_node(None, mark, true)
+                    .unwrap_or_else(|| String::from("\u{0}"));
+                self.stack.push(Frame::Map {
+                    path,
+                    key: None,
+                    start,
+                })
+            }
+            Event::SequenceStart(_) => match self.begin_node(None, mark, false) {
+                Some(path) => self.stack.push(Frame::Seq { path, idx: 0 }),
+                None => self.stack.push(Frame::Seq {
+                    path: String::from("\u{0}"),
+                    idx: 0,
+                }),
+            },
+            Event::MappingEnd | Event::SequenceEnd => {
+                let frame = self.stack.pop();
+                if let Some(Frame::Map { path, start, .. }) = &frame {
+                    self.out.values.entry(path.clone()).or_insert(*start);
+                }
+                let was_key = matches!(
+                    frame,
+                    Some(Frame::Map { ref path, .. } | Frame::Seq { ref path, .. }) if path == "\u{0}"
+                );
+                if !was_key {
+                    self.end_node();
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn maps_pointers_to_lines_and_columns() {
+        let yaml = "apiVersion: v1\nkind: Pod\nmetadata:\n  name: web\nspec:\n  containers:\n  - name: app\n    image: nginx\n    imagePullPolicy: always\n    ports:\n      - containerPort: 80\n";
+        let p = Positions::index(yaml);
+        assert_eq!(p.value("/kind"), Some((2, 7)));
+        assert_eq!(p.key("/metadata/name"), Some((4, 3)));
+        assert_eq!(p.value("/spec/containers/0/imagePullPolicy"), Some((9, 22)));
+        assert_eq!(p.key("/spec/containers/0/imagePullPolicy"), Some((9, 5)));
+        assert_eq!(
+            p.value("/spec/containers/0/ports/0/containerPort"),
+            Some((11, 24))
+        );
+        assert_eq!(p.value("/spec/containers/0"), Some((7, 5)));
+        // Unknown leaf: nearest indexed ancestor.
+        assert_eq!(p.nearest("/spec/containers/0/resources"), Some((7, 5)));
+        assert_eq!(p.nearest("/spec/containers/0/image"), Some((8, 12)));
+        assert_eq!(p.nearest("/metadata/name/x"), Some((4, 3)));
+    }
+
+    #[test]
+    fn flow_style_and_escaped_keys() {
+        let p = Positions::index("metadata:\n  labels: {app.kubernetes.io/name: web, x: [1, 2]}\n");
+        assert_eq!(
+            p.value("/metadata/labels/app.kubernetes.io~1name"),
+            Some((2, 36))
+        );
+        assert_eq!(p.value("/metadata/labels/x/1"), Some((2, 48)));
+    }
+}